@@ -0,0 +1,95 @@
+//! Check that a linked ELF file matches the layout it was linked with
+//!
+//! The linker places input sections the script doesn't mention as orphans
+//! wherever its heuristics choose, and does not complain when a section
+//! ends up outside of the region the script meant. An audit compares every
+//! allocated section of the ELF file with the [`Layout`](../layout/struct.Layout.html)
+//! and reports each one the linker placed differently.
+
+use crate::elf::Elf;
+use crate::layout::Layout;
+use std::fmt;
+
+/// A section of the ELF file which does not match the layout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Finding {
+    /// The layout does not describe the section (section, address, region)
+    Orphan(String, u64, Option<String>),
+    /// The section runs from the wrong region (section, address, expected region)
+    WrongRegion(String, u64, String),
+    /// The section is loaded from the wrong region (section, load address, expected region)
+    WrongLoadRegion(String, u64, String),
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Finding::Orphan(name, address, Some(region)) => write!(
+                f,
+                "{} at {:#010X} in {} is an orphan section",
+                name, address, region
+            ),
+            Finding::Orphan(name, address, None) => write!(
+                f,
+                "{} at {:#010X} is an orphan section outside of every region",
+                name, address
+            ),
+            Finding::WrongRegion(name, address, region) => write!(
+                f,
+                "{} at {:#010X} is not in region {}",
+                name, address, region
+            ),
+            Finding::WrongLoadRegion(name, address, region) => write!(
+                f,
+                "{} loaded at {:#010X} is not in region {}",
+                name, address, region
+            ),
+        }
+    }
+}
+
+/// Compare the allocated sections of `elf` with `layout`
+///
+/// Empty sections are ignored, the linker may leave them at any address.
+pub fn audit(layout: &Layout, elf: &Elf) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for section in elf.sections().iter().filter(|s| s.is_alloc() && s.size > 0) {
+        let planned = match layout.section(&section.name) {
+            Some(planned) => planned,
+            None => {
+                findings.push(Finding::Orphan(
+                    section.name.clone(),
+                    section.address,
+                    layout
+                        .region_containing(section.address)
+                        .map(|r| r.name.clone()),
+                ));
+                continue;
+            }
+        };
+        let fits = |region: &str, address: u64| {
+            layout
+                .region(region)
+                .map(|r| r.contains(address) && address + section.size <= r.end())
+                .unwrap_or(false)
+        };
+        if !fits(&planned.vma, section.address) {
+            findings.push(Finding::WrongRegion(
+                section.name.clone(),
+                section.address,
+                planned.vma.clone(),
+            ));
+        }
+        if let (Some(lma), true) = (planned.lma.as_ref(), section.has_contents()) {
+            let load = elf.load_address(section);
+            if !fits(lma, load) {
+                findings.push(Finding::WrongLoadRegion(
+                    section.name.clone(),
+                    load,
+                    lma.clone(),
+                ));
+            }
+        }
+    }
+    findings
+}
@@ -0,0 +1,59 @@
+//! Check a linked ELF file against its layout
+//!
+//! Every allocated section must be described by the `layout.json` written
+//! alongside the generated linker script and must run from, and load from,
+//! the regions it describes. The tool exits with an error otherwise, so
+//! that it may fail CI when the linker placed something unexpectedly.
+//!
+//! `--compat rtic` or `--compat embassy` also checks the layout against
+//! what the framework expects of the linker script.
+//!
+//! ```text
+//! imxrt-audit [--compat rtic|embassy] <firmware.elf> <layout.json>
+//! ```
+
+use imxrt_rt_gen::audit;
+use imxrt_rt_gen::compat::{self, Framework};
+use imxrt_rt_gen::elf::Elf;
+use imxrt_rt_gen::layout::Layout;
+use std::env;
+use std::process;
+
+const USAGE: &str = "usage: imxrt-audit [--compat rtic|embassy] <firmware.elf> <layout.json>";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut framework: Option<Framework> = None;
+    let mut files = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--compat" => framework = Some(args.next().unwrap_or_else(|| usage()).parse()?),
+            _ => files.push(arg),
+        }
+    }
+    if files.len() != 2 {
+        usage();
+    }
+    let elf = Elf::open(&files[0])?;
+    let layout = Layout::open(&files[1])?;
+    let mut failed = false;
+    for finding in audit::audit(&layout, &elf).iter() {
+        eprintln!("error: {}", finding);
+        failed = true;
+    }
+    if let Some(framework) = framework {
+        for issue in compat::check(&layout, framework).iter() {
+            eprintln!("error: {}", issue);
+            failed = true;
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+    Ok(())
+}
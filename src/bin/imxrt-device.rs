@@ -0,0 +1,32 @@
+//! Generate `device.x` from an SVD file or a PAC's interrupt enum
+//!
+//! The input is read as SVD when it looks like XML, otherwise as the
+//! `Interrupt` enum of a PAC, one `NAME = NUMBER,` variant per line.
+//!
+//! ```text
+//! imxrt-device <chip.svd | interrupts.rs> <device.x>
+//! ```
+
+use imxrt_rt_gen::device;
+use std::env;
+use std::fs;
+use std::process;
+
+const USAGE: &str = "usage: imxrt-device <chip.svd | interrupts.rs> <device.x>";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let files: Vec<String> = env::args().skip(1).collect();
+    if files.len() != 2 {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+    let input = fs::read_to_string(&files[0])?;
+    let interrupts = if input.trim_start().starts_with('<') {
+        device::from_svd(&input)?
+    } else {
+        device::from_pac(&input)?
+    };
+    fs::write(&files[1], device::render(&interrupts))?;
+    println!("{}: {} interrupts", files[1], interrupts.len());
+    Ok(())
+}
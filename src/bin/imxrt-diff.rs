@@ -0,0 +1,66 @@
+//! Report the differences between two builds
+//!
+//! Both inputs are either exported `layout.json` files or linked ELF
+//! files. Each `--stable` symbol must keep its address, the tool exits
+//! with an error if one moved or disappeared.
+//!
+//! ```text
+//! imxrt-diff [--stable SYMBOL]... <old> <new>
+//! ```
+
+use imxrt_rt_gen::diff;
+use imxrt_rt_gen::elf::Elf;
+use imxrt_rt_gen::layout::Layout;
+use std::env;
+use std::fs;
+use std::process;
+
+const USAGE: &str = "usage: imxrt-diff [--stable SYMBOL]... <old> <new>";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut stable = Vec::new();
+    let mut files = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--stable" => match args.next() {
+                Some(symbol) => stable.push(symbol),
+                None => {
+                    eprintln!("{}", USAGE);
+                    process::exit(2);
+                }
+            },
+            _ => files.push(arg),
+        }
+    }
+    if files.len() != 2 {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+
+    let old = fs::read(&files[0])?;
+    let new = fs::read(&files[1])?;
+    let changes = if old.starts_with(b"\x7fELF") {
+        diff::elfs(&Elf::parse(old)?, &Elf::parse(new)?)
+    } else {
+        diff::layouts(
+            &Layout::from_json(&String::from_utf8(old)?)?,
+            &Layout::from_json(&String::from_utf8(new)?)?,
+        )
+    };
+    for change in changes.iter() {
+        println!("{}", change);
+    }
+
+    let mut broken = false;
+    for symbol in stable.iter() {
+        if let Some(change) = changes.iter().find(|c| c.breaks_symbol(symbol)) {
+            eprintln!("error: stable {}", change);
+            broken = true;
+        }
+    }
+    if broken {
+        process::exit(1);
+    }
+    Ok(())
+}
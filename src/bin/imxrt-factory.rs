@@ -0,0 +1,78 @@
+//! Assemble a full-flash factory image from prebuilt binaries
+//!
+//! Each `--part REGION=FILE` is stored at the origin of the region, for
+//! example the bootloader, the application slot and a filesystem image.
+//! The image starts at the origin of `--base-region` and gaps are filled
+//! with `--fill`, the erased flash value by default. A manifest of the
+//! parts is written next to the image as `<factory.bin>.json`.
+//!
+//! ```text
+//! imxrt-factory --base-region FLASH --part REGION=FILE... [--fill BYTE] <layout.json> <factory.bin>
+//! ```
+
+use imxrt_rt_gen::factory::{self, Part};
+use imxrt_rt_gen::image;
+use imxrt_rt_gen::layout::Layout;
+use imxrt_rt_gen::size::parse_size;
+use std::env;
+use std::fs;
+use std::process;
+
+const USAGE: &str = "usage: imxrt-factory --base-region NAME --part REGION=FILE... [--fill BYTE] <layout.json> <factory.bin>";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut base = None;
+    let mut fill = image::ERASED;
+    let mut parts = Vec::new();
+    let mut files = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--base-region" => base = Some(value()),
+            "--fill" => {
+                fill = parse_size(&value())
+                    .filter(|fill| *fill <= 0xFF)
+                    .unwrap_or_else(|| usage()) as u8
+            }
+            "--part" => {
+                let part = value();
+                let mut split = part.splitn(2, '=');
+                match (split.next(), split.next()) {
+                    (Some(region), Some(file)) => parts.push(Part {
+                        name: String::from(file),
+                        region: String::from(region),
+                        data: fs::read(file)?,
+                    }),
+                    _ => usage(),
+                }
+            }
+            _ => files.push(arg),
+        }
+    }
+    let base = base.unwrap_or_else(|| usage());
+    if files.len() != 2 || parts.is_empty() {
+        usage();
+    }
+
+    let layout = Layout::open(&files[0])?;
+    let (image, manifest) = factory::assemble(&layout, &base, &parts, fill)?;
+    image.save(&files[1])?;
+    fs::write(format!("{}.json", files[1]), manifest.to_json())?;
+    for part in manifest.parts.iter() {
+        println!(
+            "{:#010X} {:>10} {} ({})",
+            part.address, part.length, part.name, part.region
+        );
+    }
+    println!(
+        "{}: {} bytes at {:#010X}",
+        files[1], manifest.length, manifest.base
+    );
+    Ok(())
+}
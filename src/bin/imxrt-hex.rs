@@ -0,0 +1,51 @@
+//! Export a linked ELF file as Intel HEX or Motorola S-records
+//!
+//! Records use the load address of each segment, so sections copied to
+//! RAM at reset are programmed at their flash address.
+//!
+//! ```text
+//! imxrt-hex [--srec] <firmware.elf> <firmware.hex>
+//! imxrt-hex --teensy <layout.json> <firmware.elf> <firmware.hex>
+//! ```
+//!
+//! `--teensy` writes a file for the Teensy 4 loader instead, checking the
+//! flash region starts at 0x6000_0000 with an FCB.
+
+use imxrt_rt_gen::elf::Elf;
+use imxrt_rt_gen::hex;
+use imxrt_rt_gen::layout::Layout;
+use std::env;
+use std::fs;
+use std::process;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut srec = false;
+    let mut teensy = None;
+    let mut files = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--srec" => srec = true,
+            "--teensy" => teensy = args.next(),
+            _ => files.push(arg),
+        }
+    }
+    if files.len() != 2 {
+        eprintln!(
+            "usage: imxrt-hex [--srec | --teensy <layout.json>] <firmware.elf> <firmware.hex>"
+        );
+        process::exit(2);
+    }
+
+    let elf = Elf::open(&files[0])?;
+    let segments = hex::load_segments(&elf);
+    let output = if let Some(layout) = teensy {
+        hex::teensy(&elf, &Layout::open(layout)?)?
+    } else if srec {
+        hex::srec(&segments, &files[0], Some(elf.entry()))
+    } else {
+        hex::ihex(&segments, Some(elf.entry()))
+    };
+    fs::write(&files[1], output)?;
+    Ok(())
+}
@@ -0,0 +1,56 @@
+//! Select the hottest functions of a profile for ITCM
+//!
+//! The profile has one `samples symbol` pair per line, for example from
+//! PC sampling, and functions are sized from a previous build. The report
+//! lists the selected functions with their cumulative size, and `--list`
+//! writes their symbols, one per line, for the build script to pass to
+//! `LinkerScript::hot_text`.
+//!
+//! ```text
+//! imxrt-hot --budget SIZE [--list <hot.txt>] <profile.txt> <firmware.elf>
+//! ```
+
+use imxrt_rt_gen::elf::Elf;
+use imxrt_rt_gen::hot::{self, Profile};
+use imxrt_rt_gen::size::parse_size;
+use std::env;
+use std::fs;
+use std::process;
+
+const USAGE: &str =
+    "usage: imxrt-hot --budget SIZE [--list <hot.txt>] <profile.txt> <firmware.elf>";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut budget = None;
+    let mut list = None;
+    let mut files = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--budget" => budget = Some(parse_size(&value()).unwrap_or_else(|| usage())),
+            "--list" => list = Some(value()),
+            _ => files.push(arg),
+        }
+    }
+    let budget = budget.unwrap_or_else(|| usage());
+    if files.len() != 2 {
+        usage();
+    }
+
+    let profile: Profile = fs::read_to_string(&files[0])?.parse()?;
+    let elf = Elf::open(&files[1])?;
+    let set = hot::select(&profile, &elf, budget);
+    print!("{}", set);
+    if let Some(list) = list {
+        let mut symbols = set.symbols().join("\n");
+        symbols.push('\n');
+        fs::write(list, symbols)?;
+    }
+    Ok(())
+}
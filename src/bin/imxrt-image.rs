@@ -0,0 +1,97 @@
+//! Convert a linked ELF file into a flat bootable image
+//!
+//! The image covers the boot region, or `--region`, from its origin so
+//! the FCB, IVT, and application land at the offsets the layout gave
+//! them. Gaps are filled with `--fill`, the erased flash value by default.
+//! Sections the layout stores compressed are replaced with their LZ4
+//! blocks.
+//!
+//! ```text
+//! imxrt-image [--region NAME] [--fill BYTE] <firmware.elf> <layout.json> <firmware.bin>
+//! ```
+//!
+//! With `--sign` the image is prepared for HAB signing: it is padded to
+//! `--sign-align`, `--csf-size` bytes are reserved for the CSF after it,
+//! the IVT (`--ivt`, by default the layout's `ivt` section or 0x1000 into
+//! the image) is pointed at the CSF, and a manifest with the addresses
+//! the signing tool needs is written to `<firmware.bin>.json`.
+
+use imxrt_rt_gen::compress;
+use imxrt_rt_gen::elf::Elf;
+use imxrt_rt_gen::image::{self, Image};
+use imxrt_rt_gen::layout::Layout;
+use imxrt_rt_gen::sign::{self, SigningOptions};
+use imxrt_rt_gen::size::parse_size;
+use std::env;
+use std::process;
+
+const USAGE: &str = "usage: imxrt-image [--region NAME] [--fill BYTE] [--sign [--ivt ADDRESS] [--csf-size SIZE] [--sign-align SIZE]] <firmware.elf> <layout.json> <firmware.bin>";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn size_arg(arg: Option<String>) -> u64 {
+    arg.as_deref()
+        .and_then(parse_size)
+        .unwrap_or_else(|| usage())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut region = None;
+    let mut fill = image::ERASED;
+    let mut signing = false;
+    let mut ivt = None;
+    let mut options = SigningOptions::default();
+    let mut files = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--region" => region = Some(args.next().unwrap_or_else(|| usage())),
+            "--fill" => {
+                let value = args.next().unwrap_or_else(|| usage());
+                fill = parse_size(&value)
+                    .filter(|fill| *fill <= 0xFF)
+                    .unwrap_or_else(|| usage()) as u8;
+            }
+            "--sign" => signing = true,
+            "--ivt" => ivt = Some(size_arg(args.next())),
+            "--csf-size" => options.csf_size = size_arg(args.next()),
+            "--sign-align" => options.align = size_arg(args.next()),
+            _ => files.push(arg),
+        }
+    }
+    if files.len() != 3 {
+        usage();
+    }
+
+    let elf = Elf::open(&files[0])?;
+    let layout = Layout::open(&files[1])?;
+    let mut image = Image::from_elf(&elf, &layout, region.as_deref(), fill)?;
+    let saved = compress::compress(&mut image, &elf, &layout)?;
+    if saved > 0 {
+        println!("{} bytes saved by compression", saved);
+    }
+    if signing {
+        let ivt = ivt
+            .or_else(|| layout.fixed_address("ivt").map(|(address, _)| address))
+            .unwrap_or(image.base + 0x1000);
+        let manifest = sign::prepare(&mut image, ivt, options)?;
+        std::fs::write(format!("{}.json", files[2]), manifest.to_json())?;
+        println!("{}", manifest.csf_blocks(&files[2]));
+        println!(
+            "CSF at {:#010X}, {} bytes reserved",
+            manifest.csf, manifest.csf_size
+        );
+    }
+    image.save(&files[2])?;
+    println!(
+        "{}: {} bytes from {:#010X} to {:#010X}",
+        files[2],
+        image.data.len(),
+        image.base,
+        image.end()
+    );
+    Ok(())
+}
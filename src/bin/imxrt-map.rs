@@ -0,0 +1,52 @@
+//! Render the memory map of a layout as SVG or HTML
+//!
+//! With `--elf` the blocks are sized from the linked ELF file, otherwise
+//! the sections the layout places in each region are shown in order.
+//!
+//! ```text
+//! imxrt-map [--elf firmware.elf] [--html] <layout.json> > map.svg
+//! ```
+
+use imxrt_rt_gen::elf::Elf;
+use imxrt_rt_gen::layout::Layout;
+use imxrt_rt_gen::{map, size};
+use std::env;
+use std::process;
+
+const USAGE: &str = "usage: imxrt-map [--elf firmware.elf] [--html] <layout.json>";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut elf = None;
+    let mut html = false;
+    let mut files = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--html" => html = true,
+            "--elf" => match args.next() {
+                Some(path) => elf = Some(path),
+                None => {
+                    eprintln!("{}", USAGE);
+                    process::exit(2);
+                }
+            },
+            _ => files.push(arg),
+        }
+    }
+    if files.len() != 1 {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+
+    let layout = Layout::open(&files[0])?;
+    let report = match elf {
+        Some(path) => Some(size::report(&layout, &Elf::open(path)?)),
+        None => None,
+    };
+    if html {
+        print!("{}", map::html(&layout, report.as_ref()));
+    } else {
+        print!("{}", map::svg(&layout, report.as_ref()));
+    }
+    Ok(())
+}
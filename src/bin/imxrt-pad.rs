@@ -0,0 +1,96 @@
+//! Pad a flat image to erase block boundaries and split it into slots
+//!
+//! The image is padded with `--fill` (the erased flash value by default)
+//! to a multiple of `--block` bytes, after removing trailing fill bytes
+//! with `--trim`. With `--layout`, each `--slot NAME` region is extracted
+//! from the image, which starts at the origin of `--base-region`, padded
+//! the same way and written to `<firmware.bin>.<NAME>.bin`.
+//!
+//! ```text
+//! imxrt-pad [--block SIZE] [--fill BYTE] [--trim] <firmware.bin>
+//! imxrt-pad --layout <layout.json> --base-region NAME --slot NAME... [--block SIZE] <firmware.bin>
+//! ```
+
+use imxrt_rt_gen::image::{self, Image};
+use imxrt_rt_gen::layout::Layout;
+use imxrt_rt_gen::size::parse_size;
+use std::env;
+use std::fs;
+use std::process;
+
+const USAGE: &str = "usage: imxrt-pad [--block SIZE] [--fill BYTE] [--trim] [--layout <layout.json> --base-region NAME --slot NAME...] <firmware.bin>";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut block = 4096;
+    let mut fill = image::ERASED;
+    let mut trim = false;
+    let mut layout = None;
+    let mut base_region = None;
+    let mut slots = Vec::new();
+    let mut files = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--block" => block = parse_size(&value()).unwrap_or_else(|| usage()),
+            "--fill" => {
+                fill = parse_size(&value())
+                    .filter(|fill| *fill <= 0xFF)
+                    .unwrap_or_else(|| usage()) as u8
+            }
+            "--trim" => trim = true,
+            "--layout" => layout = Some(value()),
+            "--base-region" => base_region = Some(value()),
+            "--slot" => slots.push(value()),
+            _ => files.push(arg),
+        }
+    }
+    if files.len() != 1 {
+        usage();
+    }
+
+    let data = fs::read(&files[0])?;
+    if let Some(layout) = layout {
+        let layout = Layout::open(layout)?;
+        let base = base_region
+            .as_deref()
+            .and_then(|name| layout.region(name))
+            .unwrap_or_else(|| usage());
+        let full = Image {
+            base: base.origin,
+            data,
+        };
+        for slot in slots.iter() {
+            let region = layout
+                .region(slot)
+                .ok_or_else(|| format!("unknown region {:?}", slot))?;
+            let mut image = full.slice(region)?;
+            if trim {
+                image.trim(fill);
+            }
+            image.pad_to(block, fill);
+            let path = format!("{}.{}.bin", files[0], slot);
+            image.save(&path)?;
+            println!(
+                "{}: {} bytes at {:#010X}",
+                path,
+                image.data.len(),
+                image.base
+            );
+        }
+    } else {
+        let mut image = Image { base: 0, data };
+        if trim {
+            image.trim(fill);
+        }
+        image.pad_to(block, fill);
+        image.save(&files[0])?;
+        println!("{}: {} bytes", files[0], image.data.len());
+    }
+    Ok(())
+}
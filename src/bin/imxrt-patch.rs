@@ -0,0 +1,67 @@
+//! Patch prebuilt boot blobs into a flat image
+//!
+//! Each `--section NAME=FILE` copies the file into the fixed size section
+//! the layout reserved for it, for example an FCB generated by
+//! imxrt-boot-gen or a vendor tool. The image is expected to start at the
+//! origin of the section's region, as written by `imxrt-image`.
+//!
+//! ```text
+//! imxrt-patch --section fcb=fcb.bin [--section NAME=FILE]... <firmware.bin> <layout.json>
+//! ```
+
+use imxrt_rt_gen::image::Image;
+use imxrt_rt_gen::layout::Layout;
+use std::env;
+use std::fs;
+use std::process;
+
+const USAGE: &str =
+    "usage: imxrt-patch --section NAME=FILE [--section NAME=FILE]... <firmware.bin> <layout.json>";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut patches = Vec::new();
+    let mut files = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--section" => {
+                let patch = args.next().unwrap_or_else(|| usage());
+                let mut parts = patch.splitn(2, '=');
+                match (parts.next(), parts.next()) {
+                    (Some(section), Some(file)) => {
+                        patches.push((String::from(section), String::from(file)))
+                    }
+                    _ => usage(),
+                }
+            }
+            _ => files.push(arg),
+        }
+    }
+    if files.len() != 2 || patches.is_empty() {
+        usage();
+    }
+
+    let layout = Layout::open(&files[1])?;
+    let (section, _) = &patches[0];
+    let base = layout
+        .section(section)
+        .and_then(|s| layout.region(&s.vma))
+        .map(|r| r.origin)
+        .ok_or_else(|| format!("unknown section {:?}", section))?;
+    let mut image = Image {
+        base,
+        data: fs::read(&files[0])?,
+    };
+    for (section, file) in patches.iter() {
+        let blob = fs::read(file)?;
+        image.patch(&layout, section, &blob)?;
+        println!("{}: {} bytes into {}", file, blob.len(), section);
+    }
+    image.save(&files[0])?;
+    Ok(())
+}
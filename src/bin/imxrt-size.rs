@@ -0,0 +1,65 @@
+//! Print a size breakdown of a linked ELF file
+//!
+//! Sections are grouped by the region they were placed in according to
+//! the `layout.json` written alongside the generated linker script.
+//!
+//! ```text
+//! imxrt-size [--crates] [--budget crate:REGION=size]... <firmware.elf> <layout.json>
+//! ```
+//!
+//! `--crates` attributes symbols to the crates they came from. Each
+//! `--budget` limits the bytes a crate may use in a region, for example
+//! `--budget smoltcp:ITCM=16K`, and the tool exits with an error if any
+//! budget is exceeded.
+
+use imxrt_rt_gen::elf::Elf;
+use imxrt_rt_gen::layout::Layout;
+use imxrt_rt_gen::size::{self, Budget};
+use std::env;
+use std::process;
+
+const USAGE: &str =
+    "usage: imxrt-size [--crates] [--budget crate:REGION=size]... <firmware.elf> <layout.json>";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut show_crates = false;
+    let mut budgets: Vec<Budget> = Vec::new();
+    let mut files = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--crates" => show_crates = true,
+            "--budget" => match args.next() {
+                Some(budget) => budgets.push(budget.parse()?),
+                None => {
+                    eprintln!("{}", USAGE);
+                    process::exit(2);
+                }
+            },
+            _ => files.push(arg),
+        }
+    }
+    if files.len() != 2 {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+    let elf = Elf::open(&files[0])?;
+    let layout = Layout::open(&files[1])?;
+    print!("{}", size::report(&layout, &elf));
+
+    if show_crates || !budgets.is_empty() {
+        let crates = size::crates(&layout, &elf);
+        if show_crates {
+            println!();
+            print!("{}", crates);
+        }
+        let violations = crates.check(&budgets);
+        for violation in violations.iter() {
+            eprintln!("error: {}", violation);
+        }
+        if !violations.is_empty() {
+            process::exit(1);
+        }
+    }
+    Ok(())
+}
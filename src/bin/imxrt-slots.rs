@@ -0,0 +1,103 @@
+//! Extract per-slot update payloads for A/B layouts
+//!
+//! Slots are regions of the `layout.json` written alongside the generated
+//! linker script. Payloads are taken either from a full-flash image
+//! starting at the origin of `--base-region`, or from one ELF file linked
+//! for each slot with `--elf SLOT=FILE`. Each payload is stripped of
+//! trailing fill bytes, padded to a multiple of `--align` and written to
+//! `<SLOT>.bin` in the `--out` directory.
+//!
+//! ```text
+//! imxrt-slots [--align SIZE] [--fill BYTE] [--out DIR] --image <full.bin> --base-region NAME --slot NAME... <layout.json>
+//! imxrt-slots [--align SIZE] [--fill BYTE] [--out DIR] --elf SLOT=FILE... <layout.json>
+//! ```
+
+use imxrt_rt_gen::elf::Elf;
+use imxrt_rt_gen::image::{self, Image};
+use imxrt_rt_gen::layout::Layout;
+use imxrt_rt_gen::size::parse_size;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+const USAGE: &str = "usage: imxrt-slots [--align SIZE] [--fill BYTE] [--out DIR] (--image <full.bin> --base-region NAME --slot NAME... | --elf SLOT=FILE...) <layout.json>";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut align = 4096;
+    let mut fill = image::ERASED;
+    let mut out = PathBuf::from(".");
+    let mut full = None;
+    let mut base = None;
+    let mut slots = Vec::new();
+    let mut elfs = Vec::new();
+    let mut files = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--align" => align = parse_size(&value()).unwrap_or_else(|| usage()),
+            "--fill" => {
+                fill = parse_size(&value())
+                    .filter(|fill| *fill <= 0xFF)
+                    .unwrap_or_else(|| usage()) as u8
+            }
+            "--out" => out = PathBuf::from(value()),
+            "--image" => full = Some(value()),
+            "--base-region" => base = Some(value()),
+            "--slot" => slots.push(value()),
+            "--elf" => {
+                let elf = value();
+                let mut split = elf.splitn(2, '=');
+                match (split.next(), split.next()) {
+                    (Some(slot), Some(file)) => elfs.push((String::from(slot), String::from(file))),
+                    _ => usage(),
+                }
+            }
+            _ => files.push(arg),
+        }
+    }
+    if files.len() != 1 || full.is_some() != elfs.is_empty() {
+        usage();
+    }
+
+    let layout = Layout::open(&files[0])?;
+    let slot = |name: &str| {
+        layout
+            .region(name)
+            .ok_or_else(|| format!("unknown region {:?}", name))
+    };
+    let mut payloads = Vec::new();
+    if let Some(full) = full {
+        let base = slot(&base.unwrap_or_else(|| usage()))?;
+        let image = Image {
+            base: base.origin,
+            data: fs::read(full)?,
+        };
+        for name in slots.iter() {
+            payloads.push((name, image.payload(slot(name)?, align, fill)?));
+        }
+    } else {
+        for (name, file) in elfs.iter() {
+            let elf = Elf::open(file)?;
+            let image = Image::from_elf(&elf, &layout, Some(name), fill)?;
+            payloads.push((name, image.payload(slot(name)?, align, fill)?));
+        }
+    }
+    for (name, payload) in payloads.iter() {
+        let path = out.join(format!("{}.bin", name));
+        payload.save(&path)?;
+        println!(
+            "{}: {} bytes at {:#010X}",
+            path.display(),
+            payload.data.len(),
+            payload.base
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,84 @@
+//! Suggest a stack size from the stack frames measured by the compiler
+//!
+//! The ELF file must be built with `RUSTFLAGS="-Z emit-stack-sizes"`.
+//! Without a call graph the worst case is estimated from the `--depth`
+//! largest frames, with one (`--calls`, one `caller -> callee` edge per
+//! line) the deepest path from `--root` is used instead.
+//!
+//! ```text
+//! imxrt-stack [--depth N] [--margin PERCENT] [--calls FILE] [--root FUNCTION] <firmware.elf>
+//! ```
+
+use imxrt_rt_gen::elf::Elf;
+use imxrt_rt_gen::stack::{self, CallGraph, StackSizes};
+use std::env;
+use std::fs;
+use std::process;
+
+const USAGE: &str = "usage: imxrt-stack [--depth N] [--margin PERCENT] [--calls FILE] [--root FUNCTION] <firmware.elf>";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut depth = 8;
+    let mut margin = 25;
+    let mut calls = None;
+    let mut root = String::from("main");
+    let mut files = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next().unwrap_or_else(|| {
+                eprintln!("{}", USAGE);
+                process::exit(2);
+            })
+        };
+        match arg.as_str() {
+            "--depth" => depth = value().parse()?,
+            "--margin" => margin = value().parse()?,
+            "--calls" => calls = Some(value()),
+            "--root" => root = value(),
+            _ => files.push(arg),
+        }
+    }
+    if files.len() != 1 {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+
+    let elf = Elf::open(&files[0])?;
+    let sizes = StackSizes::from_elf(&elf)?;
+    for frame in sizes.frames.iter().take(10) {
+        println!("{:>8} {}", frame.size, frame.name);
+    }
+    let usage = match calls {
+        Some(path) => {
+            let graph: CallGraph = fs::read_to_string(path)?.parse()?;
+            match sizes.worst_case(&graph, &root) {
+                Some(usage) => {
+                    println!("worst case from {}: {} bytes", root, usage);
+                    usage
+                }
+                None => {
+                    eprintln!(
+                        "error: recursion reachable from {}, stack use is unbounded",
+                        root
+                    );
+                    process::exit(1);
+                }
+            }
+        }
+        None => {
+            let usage = sizes.estimate(depth);
+            println!(
+                "estimate from the {} largest frames: {} bytes",
+                depth, usage
+            );
+            usage
+        }
+    };
+    println!(
+        "suggested stack size with {}% margin: {} bytes",
+        margin,
+        stack::suggest(usage, margin)
+    );
+    Ok(())
+}
@@ -0,0 +1,56 @@
+//! Convert a linked ELF file into a UF2 file
+//!
+//! The flat image of the boot region, or `--region`, is split into UF2
+//! blocks starting at the region origin. `--family` overrides the UF2
+//! family ID, which defaults to the i.MX RT10xx ID.
+//!
+//! ```text
+//! imxrt-uf2 [--region NAME] [--family ID] <firmware.elf> <layout.json> <firmware.uf2>
+//! ```
+
+use imxrt_rt_gen::elf::Elf;
+use imxrt_rt_gen::image::{self, Image};
+use imxrt_rt_gen::layout::Layout;
+use imxrt_rt_gen::size::parse_size;
+use imxrt_rt_gen::uf2;
+use std::env;
+use std::fs;
+use std::process;
+
+const USAGE: &str =
+    "usage: imxrt-uf2 [--region NAME] [--family ID] <firmware.elf> <layout.json> <firmware.uf2>";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut region = None;
+    let mut family = uf2::MIMXRT10XX_FAMILY_ID;
+    let mut files = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--region" => region = Some(args.next().unwrap_or_else(|| usage())),
+            "--family" => {
+                family = args
+                    .next()
+                    .as_deref()
+                    .and_then(parse_size)
+                    .filter(|id| *id <= u64::from(u32::MAX))
+                    .unwrap_or_else(|| usage()) as u32
+            }
+            _ => files.push(arg),
+        }
+    }
+    if files.len() != 3 {
+        usage();
+    }
+
+    let elf = Elf::open(&files[0])?;
+    let layout = Layout::open(&files[1])?;
+    let image = Image::from_elf(&elf, &layout, region.as_deref(), image::ERASED)?;
+    fs::write(&files[2], uf2::from_image(&image, family))?;
+    Ok(())
+}
@@ -1,23 +1,597 @@
-//! Test tool to render a LinkerScript to `stdout`
+//! Generate, check and inspect linker scripts described by JSON configs
 //!
-//! This might not represent a linker script that can be used on a
-//! device! But, it may help with visually inspecting the output.
+//! See the [`config`](../imxrt_rt_gen/config/index.html) module for the
+//! config format. A config whose name ends in `.toml` is read as TOML,
+//! any other as JSON.
+//!
+//! ```text
+//! render generate [--watch] [--out DIR] [--all] [--device FILE] <config.json>
+//! render check [--watch] <config.json>
+//! render explain [--watch] <config.json>
+//! render diff <old> <new>
+//! render size <firmware.elf> <config>
+//! render presets [<preset>]
+//! render init [--out DIR] <preset>
+//! render compare <config> <link.x>
+//! render convert [--rust] <link.x>
+//! ```
+//!
+//! `generate` writes `link.x`, `reset.rs`, `regions.rs` and `layout.json`
+//! into the `--out` directory, for builds which don't run a `build.rs`.
+//! Lints are printed as warnings, or fail the command when the config's
+//! validation is strict. A config given without a command is generated.
+//! `--all` also writes the C header `memory_map.h`, `device.x` from the
+//! SVD file or PAC interrupt enum given with `--device`, and
+//! `manifest.json` listing every file written.
+//!
+//! `check` runs the same validation without writing any files, for a
+//! pre-commit hook.
+//!
+//! `explain` prints a table of the regions and sections the config
+//! resolves to, with the addresses known before linking and the config
+//! entry which added each one.
+//!
+//! `diff` reports the regions and sections which differ between two
+//! configs, and `size` the usage of each region by a linked ELF file.
+//! Either takes a `layout.json` written by a previous build in place of
+//! a config.
+//!
+//! `compare` reads the regions and section placement of a hand-written
+//! linker script, following its includes such as `memory.x`, and reports
+//! how the config's script differs from it, for migrating a project.
+//!
+//! `convert` reads a hand-written `memory.x` or `link.x` the same way and
+//! prints a config placing its regions and sections, or with `--rust` the
+//! `LinkerScript` calls of a build script, as a starting point for a
+//! migration. Sections of no known kind are listed as skipped.
+//!
+//! `presets` lists the presets a config may start from, or describes the
+//! regions and sections of one of them. `--list-chips` and `--describe`
+//! are the same.
+//!
+//! `init` starts a crate on a preset, writing a config which only names
+//! the preset and a `build.rs` generating the linker script from it into
+//! the `--out` directory. Existing files are left as they are, with what
+//! would have been written printed instead.
+//!
+//! With `--watch` the command runs again each time the config file
+//! changes, printing errors rather than exiting on them, until it is
+//! interrupted.
+//!
+//! Options a command doesn't take fail with the usage rather than being
+//! ignored, as does `--device` without `--all`. `--help` prints the usage
+//! whatever else is given.
+//!
+//! The command line is parsed by hand rather than with a crate such as
+//! clap, so the crate, which BSPs take as a build dependency, keeps no
+//! dependencies of its own.
+
+use imxrt_rt_gen::config::{self, Config};
+use imxrt_rt_gen::elf::Elf;
+use imxrt_rt_gen::layout::Layout;
+use imxrt_rt_gen::{device, diff, preset, script, size};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+const USAGE: &str =
+    "usage: render generate [--watch] [--out DIR] [--all] [--device FILE] <config.json>
+       render check [--watch] <config.json>
+       render explain [--watch] <config.json>
+       render diff <old> <new>
+       render size <firmware.elf> <config>
+       render presets [<preset>]
+       render init [--out DIR] <preset>
+       render compare <config> <link.x>
+       render convert [--rust] <link.x>";
+
+/// The commands, which a config given without one can't be named
+const COMMANDS: [&str; 9] = [
+    "generate", "check", "explain", "diff", "size", "presets", "init", "compare", "convert",
+];
+
+/// How often `--watch` looks at the config file
+const POLL: Duration = Duration::from_millis(500);
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+/// Read a layout from a `layout.json` file, or resolve one from a config
+fn layout(file: &str) -> Result<Layout, Box<dyn std::error::Error>> {
+    match Layout::open(file) {
+        Ok(layout) => Ok(layout),
+        Err(_) => Ok(Config::open(file)?.linker_script()?.layout()),
+    }
+}
+
+/// Print the presets, or describe the one called `name`
+fn presets(name: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    match name {
+        None => {
+            for (name, summary) in preset::PRESETS.iter() {
+                println!("{:<16} {}", name, summary);
+            }
+        }
+        Some(name) => match preset::describe(name) {
+            Some(description) => print!("{}", description?),
+            None => return Err(format!("unknown preset {:?}, see render presets", name).into()),
+        },
+    }
+    Ok(())
+}
+
+/// Write a starter config and build script for a preset into `out`
+fn init(name: &str, out: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let starter = config::starter(name)
+        .ok_or_else(|| format!("unknown preset {:?}, see render presets", name))?;
+    let files = [
+        (config::STARTER_FILE, starter),
+        ("build.rs", config::build_rs(config::STARTER_FILE)),
+    ];
+    for (file, contents) in files.iter() {
+        let path = out.join(file);
+        if path.exists() {
+            println!("{} exists, add this to it:\n{}", path.display(), contents);
+        } else {
+            fs::write(&path, contents)?;
+            eprintln!("wrote {}", path.display());
+        }
+    }
+    Ok(())
+}
 
-use imxrt_rt_gen::*;
-use std::io;
+/// What `generate` writes
+#[derive(Debug, Default, PartialEq)]
+struct Artifacts {
+    all: bool,
+    /// SVD file or PAC interrupt enum for `device.x`
+    device: Option<String>,
+}
+
+/// A command line, parsed
+#[derive(Debug, PartialEq)]
+enum Invocation {
+    Help,
+    Presets(Option<String>),
+    Init(String, PathBuf),
+    Diff(String, String),
+    Compare(String, String),
+    Convert(String, bool),
+    Size(String, String),
+    /// `generate`, `check` or `explain` on a config, once or watching it
+    Config {
+        command: String,
+        file: String,
+        out: PathBuf,
+        artifacts: Artifacts,
+        watch: bool,
+    },
+}
+
+/// The options each command takes, the others are rejected
+fn options(command: &str) -> &'static [&'static str] {
+    match command {
+        "generate" => &["--watch", "--out", "--all", "--device"],
+        "check" | "explain" => &["--watch"],
+        "init" => &["--out"],
+        "convert" => &["--rust"],
+        _ => &[],
+    }
+}
+
+/// Parse the arguments following the program name
+///
+/// Unknown options, options the command doesn't take and `--device`
+/// without `--all` are errors rather than ignored.
+fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Invocation, String> {
+    let mut out = PathBuf::from(".");
+    let mut watch = false;
+    let mut rust = false;
+    let mut artifacts = Artifacts::default();
+    let mut presets = None;
+    let mut given = Vec::new();
+    let mut files = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--help" || arg == "-h" {
+            return Ok(Invocation::Help);
+        }
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--out" => out = PathBuf::from(value()?),
+            "--watch" => watch = true,
+            "--rust" => rust = true,
+            "--all" => artifacts.all = true,
+            "--device" => artifacts.device = Some(value()?),
+            "--list-chips" => presets = Some(("--list-chips", None)),
+            "--describe" => presets = Some(("--describe", Some(value()?))),
+            _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+            _ => {
+                files.push(arg);
+                continue;
+            }
+        }
+        given.push(arg);
+    }
+
+    if let Some((option, name)) = presets {
+        if given.len() > 1 || !files.is_empty() {
+            return Err(format!("{} takes no other arguments", option));
+        }
+        return Ok(Invocation::Presets(name));
+    }
+    let files: Vec<&str> = files.iter().map(String::as_str).collect();
+    let command = match files.as_slice() {
+        [file] if !COMMANDS.contains(file) => "generate",
+        [command, ..] => command,
+        [] => return Err(String::from("expected a command and its files")),
+    };
+    if let Some(option) = given
+        .iter()
+        .find(|arg| !options(command).contains(&arg.as_str()))
+    {
+        return Err(format!("{} doesn't take {}", command, option));
+    }
+    if artifacts.device.is_some() && !artifacts.all {
+        return Err(String::from("--device only applies with --all"));
+    }
+    let owned = |arg: &str| String::from(arg);
+    let invocation = match files.as_slice() {
+        ["presets"] => Invocation::Presets(None),
+        ["presets", name] => Invocation::Presets(Some(owned(name))),
+        ["init", name] => Invocation::Init(owned(name), out),
+        ["diff", old, new] => Invocation::Diff(owned(old), owned(new)),
+        ["compare", config, link_x] => Invocation::Compare(owned(config), owned(link_x)),
+        ["convert", link_x] => Invocation::Convert(owned(link_x), rust),
+        ["size", elf, config] => Invocation::Size(owned(elf), owned(config)),
+        [command @ ("generate" | "check" | "explain"), file] => Invocation::Config {
+            command: owned(command),
+            file: owned(file),
+            out,
+            artifacts,
+            watch,
+        },
+        [file] if !COMMANDS.contains(file) => Invocation::Config {
+            command: owned("generate"),
+            file: owned(file),
+            out,
+            artifacts,
+            watch,
+        },
+        _ => return Err(format!("wrong files for {}", command)),
+    };
+    Ok(invocation)
+}
+
+/// Read the interrupts for `device.x`, from SVD when the file looks like
+/// XML or else from a PAC's interrupt enum
+fn interrupts(file: &str) -> Result<Vec<device::Interrupt>, Box<dyn std::error::Error>> {
+    let input = fs::read_to_string(file)?;
+    if input.trim_start().starts_with('<') {
+        Ok(device::from_svd(&input)?)
+    } else {
+        Ok(device::from_pac(&input)?)
+    }
+}
+
+/// Run a command on the config in `file`
+fn run(
+    command: &str,
+    file: &str,
+    out: &Path,
+    artifacts: &Artifacts,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::open(file)?;
+    let warnings = match command {
+        "generate" if artifacts.all => {
+            let interrupts = match &artifacts.device {
+                Some(device) => interrupts(device)?,
+                None => Vec::new(),
+            };
+            fs::create_dir_all(out)?;
+            config.linker_script()?.generate_all(out, &interrupts)?
+        }
+        "generate" => {
+            fs::create_dir_all(out)?;
+            config.linker_script()?.generate_to(out)?
+        }
+        "check" => config.linker_script()?.check()?,
+        "explain" => {
+            print!("{}", config.explain()?);
+            return Ok(());
+        }
+        _ => usage(),
+    };
+    for warning in warnings {
+        eprintln!("warning: {}", warning);
+    }
+    Ok(())
+}
+
+/// Run a command each time the config in `file` changes
+fn watch(command: &str, file: &str, out: &Path, artifacts: &Artifacts) -> ! {
+    let modified = || {
+        fs::metadata(file)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    };
+    let mut last = None;
+    loop {
+        let current = modified();
+        if current.is_some() && current != last {
+            last = current;
+            match run(command, file, out, artifacts) {
+                Ok(()) => eprintln!("{} {}: ok", command, file),
+                Err(err) => eprintln!("{} {}: error: {}", command, file, err),
+            }
+        }
+        thread::sleep(POLL);
+    }
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut ls = LinkerScript::<u32>::new();
-    let flash = ls.region(FLASH, 0x0, 512).unwrap();
-    let ram = ls.region(RAM, 0x20000000, 128).unwrap();
-    ls.stack(ram.clone()).unwrap();
-    ls.heap(ram.clone()).unwrap();
-    ls.boot_config(512, "fcb", flash.clone()).unwrap();
-    ls.vector_table(ram.clone(), Some(flash.clone())).unwrap();
-    ls.text(flash.clone(), None).unwrap();
-    ls.data(false, ram.clone(), Some(flash.clone())).unwrap();
-    ls.rodata(false, flash.clone(), None).unwrap();
-    ls.bss(false, ram.clone(), Some(flash.clone())).unwrap();
-    ls.write(&mut io::stdout().lock())
-        .map_err(|err| Box::new(err) as _)
+    let invocation = match parse(env::args().skip(1)) {
+        Ok(invocation) => invocation,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            usage()
+        }
+    };
+    match invocation {
+        Invocation::Help => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Invocation::Presets(name) => presets(name.as_deref()),
+        Invocation::Init(name, out) => init(&name, &out),
+        Invocation::Diff(old, new) => {
+            for change in diff::layouts(&layout(&old)?, &layout(&new)?) {
+                println!("{}", change);
+            }
+            Ok(())
+        }
+        Invocation::Compare(config, link_x) => {
+            for change in diff::layouts(&script::open(&link_x)?, &layout(&config)?) {
+                println!("{}", change);
+            }
+            Ok(())
+        }
+        Invocation::Convert(link_x, rust) => {
+            let (config, skipped) = Config::from_layout(&script::open(&link_x)?);
+            if rust {
+                print!("{}", config.to_builder());
+            } else {
+                print!("{}", config.to_json());
+            }
+            for section in skipped {
+                eprintln!(
+                    "skipped .{}, it is not a section kind the generator places",
+                    section
+                );
+            }
+            Ok(())
+        }
+        Invocation::Size(elf, config) => {
+            print!("{}", size::report(&layout(&config)?, &Elf::open(&elf)?));
+            Ok(())
+        }
+        Invocation::Config {
+            command,
+            file,
+            out,
+            artifacts,
+            watch: true,
+        } => self::watch(&command, &file, &out, &artifacts),
+        Invocation::Config {
+            command,
+            file,
+            out,
+            artifacts,
+            watch: false,
+        } => run(&command, &file, &out, &artifacts),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Result<Invocation, String> {
+        parse(line.split_whitespace().map(String::from))
+    }
+
+    fn config(command: &str, file: &str) -> Invocation {
+        Invocation::Config {
+            command: String::from(command),
+            file: String::from(file),
+            out: PathBuf::from("."),
+            artifacts: Artifacts::default(),
+            watch: false,
+        }
+    }
+
+    #[test]
+    fn config_commands() {
+        assert_eq!(
+            args("generate imxrt.json"),
+            Ok(config("generate", "imxrt.json"))
+        );
+        assert_eq!(args("imxrt.json"), Ok(config("generate", "imxrt.json")));
+        assert_eq!(args("check imxrt.json"), Ok(config("check", "imxrt.json")));
+        assert_eq!(
+            args("explain imxrt.json"),
+            Ok(config("explain", "imxrt.json"))
+        );
+        assert_eq!(
+            args("generate --watch --out target/ld --all --device imxrt.svd imxrt.json"),
+            Ok(Invocation::Config {
+                command: String::from("generate"),
+                file: String::from("imxrt.json"),
+                out: PathBuf::from("target/ld"),
+                artifacts: Artifacts {
+                    all: true,
+                    device: Some(String::from("imxrt.svd")),
+                },
+                watch: true,
+            })
+        );
+        match args("check --watch imxrt.json") {
+            Ok(Invocation::Config { watch, .. }) => assert!(watch),
+            result => panic!("Expected a watched check, but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn other_commands() {
+        assert_eq!(args("--help"), Ok(Invocation::Help));
+        assert_eq!(args("generate -h imxrt.json"), Ok(Invocation::Help));
+        assert_eq!(args("presets"), Ok(Invocation::Presets(None)));
+        assert_eq!(
+            args("--describe lm3s6965evb"),
+            Ok(Invocation::Presets(Some(String::from("lm3s6965evb"))))
+        );
+        assert_eq!(
+            args("init --out app lm3s6965evb"),
+            Ok(Invocation::Init(
+                String::from("lm3s6965evb"),
+                PathBuf::from("app")
+            ))
+        );
+        assert_eq!(
+            args("diff old.json new.json"),
+            Ok(Invocation::Diff(
+                String::from("old.json"),
+                String::from("new.json")
+            ))
+        );
+        assert_eq!(
+            args("convert --rust link.x"),
+            Ok(Invocation::Convert(String::from("link.x"), true))
+        );
+    }
+
+    #[test]
+    fn converted_build_script_compiles() {
+        let config = Config::from_json(
+            r#"{
+                "preset": "lm3s6965evb",
+                "family": "imxrt1176",
+                "second_core_mailbox": "0x20000000",
+                "regions": [
+                    { "name": "OCRAM", "origin": "0x20200000", "size": "64K" },
+                    { "name": "OCRAM_NC", "alias_of": "OCRAM", "origin": "0x20280000" }
+                ],
+                "sections": [
+                    { "kind": "heap", "name": "dma_heap", "vma": "OCRAM", "min_size": "4K", "views": ["OCRAM_NC"] },
+                    { "kind": "custom_section", "name": "buffers", "vma": "RAM", "fill": 0 },
+                    { "kind": "custom_section", "name": "frames", "vma": "OCRAM", "priority": 2, "no_cross_refs_from": ["text", "tdata"] },
+                    { "kind": "thread_local", "vma": "RAM", "lma": "FLASH", "priority": 1 },
+                    { "kind": "bss", "prefix": true, "vma": "OCRAM", "second_core": true }
+                ]
+            }"#,
+        )
+        .unwrap();
+        config.linker_script().unwrap();
+        let builder = config.to_builder();
+        assert!(builder.contains("let flash = ls.region_id(\"FLASH\")?;\n"));
+        assert!(builder.contains("let custom_section_2 = ls.custom_section(\"frames\""));
+        assert!(builder.contains("for id in thread_local.iter() {\n    ls.priority(id, 1)?;\n}\n"));
+        assert!(builder.contains("ls.second_core_init(&[bss.clone()], 0x20000000)?;\n"));
+
+        let dir = env::temp_dir().join("imxrt-rt-gen-converted");
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("build.rs");
+        fs::write(
+            &source,
+            format!(
+                "use imxrt_rt_gen::*;\n\nfn main() -> Result<(), LinkerError> {{\n{}ls.check()?;\nOk(())\n}}\n",
+                builder
+            ),
+        )
+        .unwrap();
+        // The test runs from the deps directory, next to which Cargo copies
+        // the crate's library
+        let deps = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+        let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+        let output = process::Command::new(rustc)
+            .args([
+                "--edition",
+                "2018",
+                "--crate-type",
+                "bin",
+                "--emit",
+                "metadata",
+            ])
+            .arg("--extern")
+            .arg(format!(
+                "imxrt_rt_gen={}",
+                deps.with_file_name("libimxrt_rt_gen.rlib").display()
+            ))
+            .arg("-L")
+            .arg(format!("dependency={}", deps.display()))
+            .arg("--out-dir")
+            .arg(&dir)
+            .arg(&source)
+            .output()
+            .unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(
+            output.status.success(),
+            "{}\n{}",
+            builder,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    #[test]
+    fn rejects_bad_command_lines() {
+        assert!(args("").is_err());
+        assert!(args("generate").is_err());
+        assert!(args("diff old.json").is_err());
+        assert!(args("generate --out").is_err());
+        assert!(args("generate --verbose imxrt.json").is_err());
+    }
+
+    #[test]
+    fn rejects_options_of_other_commands() {
+        let rejected = |line: &str, error: &str| match args(line) {
+            Err(err) => assert_eq!(err, error),
+            result => panic!("Expected {:?} to fail, but got {:?}", line, result),
+        };
+        rejected("check --out target imxrt.json", "check doesn't take --out");
+        rejected("explain --all imxrt.json", "explain doesn't take --all");
+        rejected(
+            "diff --watch old.json new.json",
+            "diff doesn't take --watch",
+        );
+        rejected("convert --out target link.x", "convert doesn't take --out");
+        rejected("generate --rust imxrt.json", "generate doesn't take --rust");
+        rejected("imxrt.json --rust", "generate doesn't take --rust");
+        rejected("init --watch lm3s6965evb", "init doesn't take --watch");
+        rejected(
+            "generate --device imxrt.svd imxrt.json",
+            "--device only applies with --all",
+        );
+        rejected(
+            "--list-chips --out target",
+            "--list-chips takes no other arguments",
+        );
+        rejected(
+            "--describe lm3s6965evb init",
+            "--describe takes no other arguments",
+        );
+        rejected(
+            "--all --list-chips",
+            "--list-chips takes no other arguments",
+        );
+        rejected("size firmware.elf", "wrong files for size");
+        assert!(args("check --watch imxrt.json").is_ok());
+        assert!(args("imxrt.json --out target --all").is_ok());
+        assert_eq!(args("--list-chips"), Ok(Invocation::Presets(None)));
+    }
 }
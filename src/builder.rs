@@ -0,0 +1,204 @@
+//! A builder which checks the required sections at compile time
+//!
+//! [`LinkerScript`](../struct.LinkerScript.html) reports a missing
+//! required section when it is written, from the build script. The
+//! [`LinkerScriptBuilder`] places the required sections in a fixed order
+//! instead, the stack, vector table, text, rodata, data and then bss, each
+//! step changing the builder's state, and only offers
+//! [`generate`](struct.LinkerScriptBuilder.html#method.generate) once the
+//! bss is placed. Forgetting a section is then a compile error.
+//!
+//! Regions and optional sections are added through
+//! [`script`](struct.LinkerScriptBuilder.html#method.script) at any step.
+//!
+//! ```no_run
+//! use imxrt_rt_gen::builder::LinkerScriptBuilder;
+//! use imxrt_rt_gen::{FLASH, RAM};
+//!
+//! let mut builder = LinkerScriptBuilder::<u32>::new();
+//! let flash = builder.region(FLASH, 0x6000_0000, 0x20_0000)?;
+//! let ram = builder.region(RAM, 0x2020_0000, 0x8_0000)?;
+//! builder
+//!     .stack(ram.clone())?
+//!     .vector_table(flash.clone(), None)?
+//!     .text(flash.clone(), None)?
+//!     .rodata(false, flash.clone(), None)?
+//!     .data(false, ram.clone(), Some(flash))?
+//!     .bss(false, ram, None)?
+//!     .generate()?;
+//! # Ok::<(), imxrt_rt_gen::LinkerError>(())
+//! ```
+
+use crate::{lint, LinkerScript, RegionID, Result, Word};
+use std::io::Write;
+use std::marker::PhantomData;
+use std::path::Path;
+
+/// No required section placed yet
+#[derive(Debug, Clone, Copy)]
+pub struct Empty;
+
+/// The stack is placed
+#[derive(Debug, Clone, Copy)]
+pub struct WithStack;
+
+/// The vector table is placed
+#[derive(Debug, Clone, Copy)]
+pub struct WithVectorTable;
+
+/// The text section is placed
+#[derive(Debug, Clone, Copy)]
+pub struct WithText;
+
+/// The rodata section is placed
+#[derive(Debug, Clone, Copy)]
+pub struct WithROData;
+
+/// The data section is placed
+#[derive(Debug, Clone, Copy)]
+pub struct WithData;
+
+/// Every required section is placed, the script may be generated
+#[derive(Debug, Clone, Copy)]
+pub struct Complete;
+
+/// A linker script with the required sections placed up to `State`
+#[derive(Debug, Clone)]
+pub struct LinkerScriptBuilder<W: Word, State = Empty> {
+    script: LinkerScript<W>,
+    state: PhantomData<State>,
+}
+
+impl<W: Word> Default for LinkerScriptBuilder<W, Empty> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: Word> LinkerScriptBuilder<W, Empty> {
+    /// A builder with no regions or sections
+    pub fn new() -> Self {
+        Self::from_script(LinkerScript::new())
+    }
+
+    /// Required stack location, see
+    /// [`LinkerScript::stack`](../struct.LinkerScript.html#method.stack)
+    pub fn stack(mut self, vma: RegionID) -> Result<LinkerScriptBuilder<W, WithStack>> {
+        self.script.stack(vma)?;
+        Ok(self.into_state())
+    }
+}
+
+impl<W: Word> LinkerScriptBuilder<W, WithStack> {
+    /// Required vector table, see
+    /// [`LinkerScript::vector_table`](../struct.LinkerScript.html#method.vector_table)
+    pub fn vector_table(
+        mut self,
+        vma: RegionID,
+        lma: Option<RegionID>,
+    ) -> Result<LinkerScriptBuilder<W, WithVectorTable>> {
+        self.script.vector_table(vma, lma)?;
+        Ok(self.into_state())
+    }
+}
+
+impl<W: Word> LinkerScriptBuilder<W, WithVectorTable> {
+    /// Required text section
+    pub fn text(
+        mut self,
+        vma: RegionID,
+        lma: Option<RegionID>,
+    ) -> Result<LinkerScriptBuilder<W, WithText>> {
+        self.script.text(vma, lma)?;
+        Ok(self.into_state())
+    }
+}
+
+impl<W: Word> LinkerScriptBuilder<W, WithText> {
+    /// Required rodata section
+    pub fn rodata(
+        mut self,
+        prefix: bool,
+        vma: RegionID,
+        lma: Option<RegionID>,
+    ) -> Result<LinkerScriptBuilder<W, WithROData>> {
+        self.script.rodata(prefix, vma, lma)?;
+        Ok(self.into_state())
+    }
+}
+
+impl<W: Word> LinkerScriptBuilder<W, WithROData> {
+    /// Required data section
+    pub fn data(
+        mut self,
+        prefix: bool,
+        vma: RegionID,
+        lma: Option<RegionID>,
+    ) -> Result<LinkerScriptBuilder<W, WithData>> {
+        self.script.data(prefix, vma, lma)?;
+        Ok(self.into_state())
+    }
+}
+
+impl<W: Word> LinkerScriptBuilder<W, WithData> {
+    /// Required bss section, the last one
+    pub fn bss(
+        mut self,
+        prefix: bool,
+        vma: RegionID,
+        lma: Option<RegionID>,
+    ) -> Result<LinkerScriptBuilder<W, Complete>> {
+        self.script.bss(prefix, vma, lma)?;
+        Ok(self.into_state())
+    }
+}
+
+impl<W: Word> LinkerScriptBuilder<W, Complete> {
+    /// The script with every required section placed
+    pub fn build(self) -> LinkerScript<W> {
+        self.script
+    }
+
+    /// See [`LinkerScript::generate`](../struct.LinkerScript.html#method.generate)
+    pub fn generate(self) -> Result<Vec<lint::Warning>> {
+        self.script.generate()
+    }
+
+    /// See [`LinkerScript::generate_to`](../struct.LinkerScript.html#method.generate_to)
+    pub fn generate_to<P: AsRef<Path>>(self, dir: P) -> Result<Vec<lint::Warning>> {
+        self.script.generate_to(dir)
+    }
+
+    /// See [`LinkerScript::write`](../struct.LinkerScript.html#method.write)
+    pub fn write<Wr: Write>(self, link_x: &mut Wr) -> Result<Vec<lint::Warning>> {
+        self.script.write(link_x)
+    }
+}
+
+impl<W: Word, State> LinkerScriptBuilder<W, State> {
+    /// Add a memory region, see
+    /// [`LinkerScript::region`](../struct.LinkerScript.html#method.region)
+    pub fn region(&mut self, name: &str, origin: W, size: W) -> Result<RegionID> {
+        self.script.region(name, origin, size)
+    }
+
+    /// The script being built, to add regions, optional sections and
+    /// settings
+    ///
+    /// Placing a required section through the script fails the matching
+    /// step of the builder as a duplicate section.
+    pub fn script(&mut self) -> &mut LinkerScript<W> {
+        &mut self.script
+    }
+
+    fn from_script(script: LinkerScript<W>) -> Self {
+        LinkerScriptBuilder {
+            script,
+            state: PhantomData,
+        }
+    }
+
+    fn into_state<Next>(self) -> LinkerScriptBuilder<W, Next> {
+        LinkerScriptBuilder::from_script(self.script)
+    }
+}
@@ -0,0 +1,289 @@
+//! i.MX RT chip families and where their boot ROMs look for boot data
+//!
+//! Booting from FlexSPI NOR flash, the boot ROM reads the FlexSPI
+//! configuration block (FCB) at an offset from the start of flash which
+//! differs across families, then the image vector table (IVT) at 0x1000.
+//! An FCB at the wrong offset leaves a part which does not boot.
+//!
+//! Parts with a second FlexSPI controller may boot from either, as the
+//! fuses select, each mapping flash at its own address.
+//!
+//! Flash may be encrypted, decrypted on the fly by the family's engine
+//! within a few aligned address ranges. The boot ROM reads the FCB and
+//! key blob before the engine is set up, so they stay in plaintext.
+//!
+//! While booting, the boot ROM keeps its data in on-chip RAM. An image the
+//! ROM loads into RAM must not overlap that workspace.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// An i.MX RT chip family
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Family {
+    Imxrt1010,
+    Imxrt1015,
+    Imxrt1020,
+    Imxrt1040,
+    Imxrt1050,
+    Imxrt1060,
+    Imxrt1064,
+    Imxrt1170,
+}
+
+impl Family {
+    /// Address the boot ROM reads FlexSPI NOR flash from, through the
+    /// controller the family boots from unless the fuses select another
+    pub fn flash_base(self) -> u64 {
+        self.flexspi_base(self.default_flexspi())
+            .expect("every family boots from its default FlexSPI")
+    }
+
+    /// The FlexSPI controller the boot ROM reads flash through with the
+    /// fuses left blank, FlexSPI2 for the RT1064's internal flash
+    pub fn default_flexspi(self) -> FlexSpi {
+        match self {
+            Family::Imxrt1064 => FlexSpi::FlexSpi2,
+            _ => FlexSpi::FlexSpi1,
+        }
+    }
+
+    /// Memory the boot ROM uses while booting
+    pub fn rom_reservations(self) -> Vec<Reservation> {
+        let reservation = |name: &str, origin, size| Reservation {
+            name: String::from(name),
+            origin,
+            size,
+        };
+        match self {
+            Family::Imxrt1170 => vec![reservation("ROM_WORKSPACE", 0x2024_0000, 0x1_0000)],
+            _ => vec![reservation("ROM_WORKSPACE", 0x2020_0000, 0x8000)],
+        }
+    }
+
+    /// Engine decrypting flash on the fly, `None` when the family has none
+    pub fn encryption_engine(self) -> Option<Engine> {
+        match self {
+            Family::Imxrt1020
+            | Family::Imxrt1040
+            | Family::Imxrt1050
+            | Family::Imxrt1060
+            | Family::Imxrt1064 => Some(Engine::Bee),
+            Family::Imxrt1170 => Some(Engine::Otfad),
+            Family::Imxrt1010 | Family::Imxrt1015 => None,
+        }
+    }
+
+    /// Address `flexspi` maps flash to, `None` when the family can't boot
+    /// from that controller
+    pub fn flexspi_base(self, flexspi: FlexSpi) -> Option<u64> {
+        match (self, flexspi) {
+            (Family::Imxrt1170, FlexSpi::FlexSpi1) => Some(0x3000_0000),
+            (Family::Imxrt1170, FlexSpi::FlexSpi2) => Some(0x6000_0000),
+            (Family::Imxrt1060 | Family::Imxrt1064, FlexSpi::FlexSpi2) => Some(0x7000_0000),
+            (_, FlexSpi::FlexSpi1) => Some(0x6000_0000),
+            (_, FlexSpi::FlexSpi2) => None,
+        }
+    }
+
+    /// Address of the transfer control descriptor of eDMA channel 0, the
+    /// others following it 32 bytes apart
+    pub fn edma_tcd_base(self) -> u64 {
+        match self {
+            Family::Imxrt1170 => 0x4007_1000,
+            _ => 0x400E_9000,
+        }
+    }
+
+    /// Number of eDMA channels
+    pub fn edma_channels(self) -> u64 {
+        match self {
+            Family::Imxrt1010 => 16,
+            _ => 32,
+        }
+    }
+
+    /// Offset of the FCB from the start of flash
+    pub fn fcb_offset(self) -> u64 {
+        match self {
+            Family::Imxrt1010 | Family::Imxrt1170 => 0x400,
+            _ => 0,
+        }
+    }
+
+    /// Offset of the IVT from the start of flash
+    pub fn ivt_offset(self) -> u64 {
+        0x1000
+    }
+}
+
+impl FromStr for Family {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().trim_start_matches("imxrt") {
+            "1010" | "1011" => Ok(Family::Imxrt1010),
+            "1015" => Ok(Family::Imxrt1015),
+            "1020" | "1021" => Ok(Family::Imxrt1020),
+            "1040" | "1042" => Ok(Family::Imxrt1040),
+            "1050" | "1051" | "1052" => Ok(Family::Imxrt1050),
+            "1060" | "1061" | "1062" => Ok(Family::Imxrt1060),
+            "1064" => Ok(Family::Imxrt1064),
+            "1170" | "1171" | "1172" | "1173" | "1175" | "1176" => Ok(Family::Imxrt1170),
+            _ => Err(format!("unknown i.MX RT family {:?}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Family {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Family::Imxrt1010 => "imxrt1010",
+            Family::Imxrt1015 => "imxrt1015",
+            Family::Imxrt1020 => "imxrt1020",
+            Family::Imxrt1040 => "imxrt1040",
+            Family::Imxrt1050 => "imxrt1050",
+            Family::Imxrt1060 => "imxrt1060",
+            Family::Imxrt1064 => "imxrt1064",
+            Family::Imxrt1170 => "imxrt1170",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Memory the boot ROM uses while booting, such as its workspace or a
+/// fuse shadow area
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reservation {
+    pub name: String,
+    pub origin: u64,
+    pub size: u64,
+}
+
+impl Reservation {
+    /// First address past the reservation
+    pub fn end(&self) -> u64 {
+        self.origin + self.size
+    }
+}
+
+/// An engine decrypting flash on the fly
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    /// Bus encryption engine
+    Bee,
+    /// On-the-fly AES decryption
+    Otfad,
+}
+
+impl Engine {
+    /// Number of address ranges the engine decrypts
+    pub fn max_regions(self) -> usize {
+        match self {
+            Engine::Bee => 2,
+            Engine::Otfad => 4,
+        }
+    }
+
+    /// Alignment of the start and size of each address range
+    pub fn alignment(self) -> u64 {
+        0x400
+    }
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Engine::Bee => f.write_str("BEE"),
+            Engine::Otfad => f.write_str("OTFAD"),
+        }
+    }
+}
+
+/// Boot config sections the boot ROM reads before decrypting flash
+pub const PLAINTEXT_SECTIONS: [&str; 2] = ["fcb", "keyblob"];
+
+/// A FlexSPI controller the boot ROM reads flash through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlexSpi {
+    FlexSpi1,
+    FlexSpi2,
+}
+
+impl FromStr for FlexSpi {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "flexspi1" | "flexspi" => Ok(FlexSpi::FlexSpi1),
+            "flexspi2" => Ok(FlexSpi::FlexSpi2),
+            _ => Err(format!(
+                "unknown FlexSPI {:?}, expected flexspi1 or flexspi2",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for FlexSpi {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FlexSpi::FlexSpi1 => f.write_str("flexspi1"),
+            FlexSpi::FlexSpi2 => f.write_str("flexspi2"),
+        }
+    }
+}
+
+/// How the boot ROM starts the image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BootMode {
+    /// Execute in place from FlexSPI NOR flash
+    Xip,
+    /// The boot ROM copies the image into RAM and runs it from there
+    RamLoad,
+    /// The boot ROM receives the image over USB or UART serial download
+    /// into RAM and runs it from there, see
+    /// [`LinkerScript::serial_download_ivt`](../struct.LinkerScript.html#method.serial_download_ivt)
+    SerialDownload,
+}
+
+/// Offset of the IVT from the start of an image which isn't booted from
+/// FlexSPI NOR flash
+pub const SERIAL_DOWNLOAD_IVT_OFFSET: u64 = 0x400;
+
+/// Offset of the vector table from the start of a serial download image,
+/// after the IVT, boot data and DCD
+pub const SERIAL_DOWNLOAD_IMAGE_OFFSET: u64 = 0x1000;
+
+/// Offset of the DCD from the start of a serial download image, after the
+/// IVT and boot data
+pub const SERIAL_DOWNLOAD_DCD_OFFSET: u64 = 0x430;
+
+/// Largest device configuration data (DCD) the boot ROM runs
+pub const DCD_MAX_SIZE: u64 = 1768;
+
+impl FromStr for BootMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "xip" => Ok(BootMode::Xip),
+            "ram-load" => Ok(BootMode::RamLoad),
+            "serial-download" => Ok(BootMode::SerialDownload),
+            _ => Err(format!(
+                "unknown boot mode {:?}, expected xip, ram-load or serial-download",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for BootMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BootMode::Xip => f.write_str("xip"),
+            BootMode::RamLoad => f.write_str("ram-load"),
+            BootMode::SerialDownload => f.write_str("serial-download"),
+        }
+    }
+}
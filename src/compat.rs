@@ -0,0 +1,118 @@
+//! Compatibility checks for RTIC and embassy applications
+//!
+//! Both frameworks are built on cortex-m-rt and assume parts of its stock
+//! linker script: a vector table the interrupt handlers they bind by name
+//! are dispatched from, and statics which are neither overlapped nor
+//! initialized twice. RTIC additionally places resources in `.uninit.*`
+//! input sections, collected by an `.uninit` output section which is not
+//! initialized at reset.
+//! Checking a [`Layout`](../layout/struct.Layout.html) reports where the
+//! model differs from those assumptions before the application is linked.
+
+use crate::layout::{Layout, LayoutSize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Largest vector table of the i.MX RT family, 16 exceptions and up to
+/// 240 interrupts, rounded up to the VTOR alignment it requires
+pub const VECTOR_TABLE_ALIGN: u64 = 1024;
+
+/// A framework making assumptions about the layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framework {
+    /// Real-Time Interrupt-driven Concurrency
+    Rtic,
+    /// The embassy async executor
+    Embassy,
+}
+
+impl FromStr for Framework {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rtic" => Ok(Framework::Rtic),
+            "embassy" => Ok(Framework::Embassy),
+            _ => Err(format!(
+                "unknown framework {:?}, expected rtic or embassy",
+                s
+            )),
+        }
+    }
+}
+
+/// A difference between the layout and what a framework expects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    /// There is no `.uninit` section, `.uninit.*` input sections would be orphans
+    MissingUninit,
+    /// The uninit section has a load region and would be initialized at reset
+    LoadedUninit(String),
+    /// The vector table is not at a VTOR aligned address (address)
+    UnalignedVectorTable(u64),
+    /// A stack and a heap share a region (region)
+    StackHeapOverlap(String),
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Issue::MissingUninit => write!(
+                f,
+                "no .uninit section, resources placed in .uninit.* would be orphans"
+            ),
+            Issue::LoadedUninit(region) => write!(
+                f,
+                "the .uninit section is loaded from {} and would be initialized at reset",
+                region
+            ),
+            Issue::UnalignedVectorTable(address) => write!(
+                f,
+                "the vector table at {:#010X} is not aligned to {} bytes for VTOR",
+                address, VECTOR_TABLE_ALIGN
+            ),
+            Issue::StackHeapOverlap(region) => write!(
+                f,
+                "the stack and a heap both take the remaining space of {}",
+                region
+            ),
+        }
+    }
+}
+
+/// Check `layout` against the expectations of `framework`
+pub fn check(layout: &Layout, framework: Framework) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    // RTIC places resources in .uninit.* and initializes them itself
+    if framework == Framework::Rtic {
+        match layout.sections.iter().find(|s| s.name == "uninit") {
+            None => issues.push(Issue::MissingUninit),
+            Some(section) => {
+                if let Some(lma) = &section.lma {
+                    issues.push(Issue::LoadedUninit(lma.clone()));
+                }
+            }
+        }
+    }
+    // Interrupts are bound by name and dispatched through VTOR whatever
+    // the number of priority bits
+    if let Some(address) = layout.address("vector_table") {
+        if address % VECTOR_TABLE_ALIGN != 0 {
+            issues.push(Issue::UnalignedVectorTable(address));
+        }
+    }
+    for stack in layout
+        .sections
+        .iter()
+        .filter(|s| s.size == LayoutSize::Stack)
+    {
+        if layout
+            .sections
+            .iter()
+            .any(|s| s.size == LayoutSize::Heap && s.vma == stack.vma)
+        {
+            issues.push(Issue::StackHeapOverlap(stack.vma.clone()));
+        }
+    }
+    issues
+}
@@ -0,0 +1,221 @@
+//! LZ4 compression of the sections stored compressed in flash
+//!
+//! Sections marked with [`compress`](../struct.LinkerScript.html#method.compress)
+//! are linked uncompressed, last in their load region. After linking,
+//! [`compress`] replaces their load images at the end of the flat image
+//! with LZ4 blocks, one per section, which the generated reset decodes
+//! one after another from the load address of the first.
+//!
+//! Only the block format is produced, without the frame header or
+//! checksums, since the reset knows the size of each section from its
+//! run addresses.
+
+use crate::elf::Elf;
+use crate::image::Image;
+use crate::layout::Layout;
+use crate::{LinkerError, Result};
+
+/// Shortest match the block format encodes
+const MIN_MATCH: usize = 4;
+
+/// Bytes at the end of a block which must be literals
+const LAST_LITERALS: usize = 5;
+
+/// The last match must start at least this many bytes before the end
+const MATCH_LIMIT: usize = 12;
+
+/// Largest distance back to a match
+const MAX_OFFSET: usize = 0xFFFF;
+
+const HASH_BITS: u32 = 12;
+
+fn invalid(reason: String) -> LinkerError {
+    LinkerError::InvalidImage(reason)
+}
+
+fn read_u32(input: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([input[at], input[at + 1], input[at + 2], input[at + 3]])
+}
+
+/// Append the rest of a length which didn't fit in its token nibble
+fn push_length(out: &mut Vec<u8>, mut length: usize) {
+    while length >= 255 {
+        out.push(255);
+        length -= 255;
+    }
+    out.push(length as u8);
+}
+
+/// Append a sequence of `literals`, followed by a match of `length` bytes
+/// `offset` bytes back unless it is the last
+fn push_sequence(out: &mut Vec<u8>, literals: &[u8], matched: Option<(usize, usize)>) {
+    let extra = matched.map_or(0, |(_, length)| length - MIN_MATCH);
+    out.push(((literals.len().min(15) << 4) | extra.min(15)) as u8);
+    if literals.len() >= 15 {
+        push_length(out, literals.len() - 15);
+    }
+    out.extend_from_slice(literals);
+    if let Some((offset, _)) = matched {
+        out.extend_from_slice(&(offset as u16).to_le_bytes());
+        if extra >= 15 {
+            push_length(out, extra - 15);
+        }
+    }
+}
+
+/// Compress `input` into an LZ4 block
+///
+/// An empty input gives an empty block, which the reset's decoder reads
+/// nothing from.
+pub fn lz4(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    if input.is_empty() {
+        return out;
+    }
+    let mut table = vec![usize::MAX; 1 << HASH_BITS];
+    let mut anchor = 0;
+    let mut pos = 0;
+    if input.len() > MATCH_LIMIT {
+        let limit = input.len() - MATCH_LIMIT;
+        let match_end = input.len() - LAST_LITERALS;
+        while pos < limit {
+            let sequence = read_u32(input, pos);
+            let hash = (sequence.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize;
+            let candidate = table[hash];
+            table[hash] = pos;
+            if candidate == usize::MAX
+                || pos - candidate > MAX_OFFSET
+                || read_u32(input, candidate) != sequence
+            {
+                pos += 1;
+                continue;
+            }
+            let mut length = MIN_MATCH;
+            while pos + length < match_end && input[candidate + length] == input[pos + length] {
+                length += 1;
+            }
+            push_sequence(
+                &mut out,
+                &input[anchor..pos],
+                Some((pos - candidate, length)),
+            );
+            pos += length;
+            anchor = pos;
+        }
+    }
+    push_sequence(&mut out, &input[anchor..], None);
+    out
+}
+
+fn truncated() -> LinkerError {
+    invalid(String::from("truncated LZ4 block"))
+}
+
+fn next_byte(input: &[u8], src: &mut usize) -> Result<usize> {
+    let byte = *input.get(*src).ok_or_else(truncated)?;
+    *src += 1;
+    Ok(byte as usize)
+}
+
+/// Read the rest of a length whose token nibble is `length`
+fn next_length(input: &[u8], src: &mut usize, mut length: usize) -> Result<usize> {
+    if length == 15 {
+        loop {
+            let byte = next_byte(input, src)?;
+            length += byte;
+            if byte != 255 {
+                break;
+            }
+        }
+    }
+    Ok(length)
+}
+
+/// Decompress an LZ4 block of `size` bytes, as the reset does
+pub fn unlz4(input: &[u8], size: usize) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(size);
+    let mut src = 0;
+    while out.len() < size {
+        let token = next_byte(input, &mut src)?;
+        let literals = next_length(input, &mut src, token >> 4)?;
+        out.extend_from_slice(input.get(src..src + literals).ok_or_else(truncated)?);
+        src += literals;
+        if out.len() >= size {
+            break;
+        }
+        let offset = next_byte(input, &mut src)? | next_byte(input, &mut src)? << 8;
+        let length = next_length(input, &mut src, token & 0xF)? + MIN_MATCH;
+        if offset == 0 || offset > out.len() {
+            return Err(invalid(format!(
+                "LZ4 match offset {} is out of range",
+                offset
+            )));
+        }
+        for _ in 0..length {
+            out.push(out[out.len() - offset]);
+        }
+    }
+    if out.len() != size {
+        return Err(invalid(format!(
+            "LZ4 block decodes to {} bytes rather than {}",
+            out.len(),
+            size
+        )));
+    }
+    Ok(out)
+}
+
+/// Replace the load images of the layout's compressed sections in `image`
+/// with their LZ4 blocks, returning the number of bytes saved
+///
+/// The compressed sections must be stored one after the other at the end
+/// of the image, as the linker places them. The compressed image must
+/// still fit in the region it starts in.
+pub fn compress(image: &mut Image, elf: &Elf, layout: &Layout) -> Result<u64> {
+    let mut sections = Vec::new();
+    for section in layout.sections.iter().filter(|section| section.compressed) {
+        let name = format!(".{}", section.output);
+        let found = elf.section(&name).ok_or_else(|| {
+            invalid(format!(
+                "compressed section {} is not in the ELF file",
+                name
+            ))
+        })?;
+        sections.push((elf.load_address(found), elf.section_data(found), name));
+    }
+    sections.sort_by_key(|(load, _, _)| *load);
+    let (first, end) = match (sections.first(), sections.last()) {
+        (Some((first, _, _)), Some((last, data, _))) => (*first, last + data.len() as u64),
+        _ => return Ok(0),
+    };
+    for pair in sections.windows(2) {
+        let ((load, data, previous), (next, _, name)) = (&pair[0], &pair[1]);
+        if load + data.len() as u64 != *next {
+            return Err(invalid(format!(
+                "compressed section {} does not follow {}",
+                name, previous
+            )));
+        }
+    }
+    if first < image.base || image.end() > end {
+        return Err(invalid(format!(
+            "compressed sections at {:#010X}..{:#010X} are not at the end of the image",
+            first, end
+        )));
+    }
+
+    image.data.truncate((first - image.base) as usize);
+    for (_, data, _) in sections.iter() {
+        image.data.extend_from_slice(&lz4(data));
+    }
+    if let Some(region) = layout.region_containing(image.base) {
+        if image.end() > region.end() {
+            return Err(invalid(format!(
+                "compressed image ends at {:#010X}, past region {}",
+                image.end(),
+                region.name
+            )));
+        }
+    }
+    Ok(end.saturating_sub(image.end()))
+}
@@ -0,0 +1,1430 @@
+//! Describe a linker script in a JSON or TOML file rather than a build
+//! script
+//!
+//! A config lets builds without a `build.rs`, such as Make, CMake or Bazel,
+//! generate the same files through the `render` tool. It names the regions
+//! and the sections placed in them, optionally starting from a
+//! [preset](../preset/index.html):
+//!
+//! ```json
+//! {
+//!     "preset": "lm3s6965evb",
+//!     "family": "imxrt1062",
+//!     "boot_mode": "xip",
+//!     "flexspi": "flexspi2",
+//!     "validation": "strict",
+//!     "regions": [
+//!         { "name": "ITCM", "origin": "0x0", "size": "128K" },
+//!         { "name": "OCRAM_NC", "alias_of": "OCRAM", "origin": "0x20280000" }
+//!     ],
+//!     "sections": [
+//!         { "kind": "text", "vma": "ITCM", "lma": "FLASH" },
+//!         { "kind": "heap", "vma": "OCRAM", "min_size": "4K" }
+//!     ]
+//! }
+//! ```
+//!
+//! The same config in TOML lists the regions and sections as arrays of
+//! tables:
+//!
+//! ```toml
+//! preset = "lm3s6965evb"
+//! family = "imxrt1062"
+//!
+//! [[regions]]
+//! name = "ITCM"
+//! origin = 0x0
+//! size = "128K"
+//!
+//! [[sections]]
+//! kind = "text"
+//! vma = "ITCM"
+//! lma = "FLASH"
+//! ```
+//!
+//! Addresses and sizes are integers or strings as accepted by
+//! [`parse_size`](../size/fn.parse_size.html). Each section's `kind` names
+//! the [`LinkerScript`](../struct.LinkerScript.html) method adding it, and
+//! takes that method's arguments as members: `prefix`, `size`, `name`,
+//! `input`, `functions` and `files`. A loaded section with
+//! `"compressed": true` is stored LZ4 compressed, and one with
+//! `"dma_init": true` copied by eDMA. `load_at` loads a section from an
+//! exact address, and `priority` places a section earlier or later in its
+//! region. A section's `fill` byte fills its gaps, `0xFF` to match erased
+//! flash, and `keep` wraps its input sections in KEEP or, with `false`,
+//! stops doing so for the vector table and boot config, and
+//! `"noload": true` gives a section the NOLOAD type. Sections with
+//! `"second_core": true` are zeroed by the second core, through the mailbox
+//! at `second_core_mailbox`. A region's `kind`, one of `flash`, `ram` or
+//! `data`, sets its MEMORY attributes, one with `"reserved": true` is only
+//! declared, and its `reserve_start` bytes are skipped. A section's
+//! `no_cross_refs_from` lists the output sections which must not reference
+//! it, and its `views` the aliases its symbols are also exported in. A
+//! section's `spill` names the region its input sections spill into when
+//! they don't fit. `constants` maps symbol names to the build time values
+//! they hold. `stack_alignment` aligns the ends of the stack to more than
+//! the 8 bytes the AAPCS requires, `stack_guard_gap` leaves a gap for an
+//! MPU region below it, and `"stack_protector": true` defines the guard of
+//! C code built with `-fstack-protector`, seeded with `stack_canary` when
+//! it is given. A member the format doesn't
+//! define fails the config, rather than a misspelling being ignored.
+
+use crate::json::{self, Value};
+use crate::layout::{Layout, LayoutSize};
+use crate::size::parse_size;
+use crate::toml;
+use crate::{
+    chip, lint, preset, LinkerError, LinkerScript, RegionID, RegionKind, Result, SectionID,
+};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::Write;
+
+fn invalid(reason: String) -> LinkerError {
+    LinkerError::InvalidConfig(reason)
+}
+
+/// Members of a config
+const CONFIG_KEYS: &[&str] = &[
+    "preset",
+    "family",
+    "boot_mode",
+    "flexspi",
+    "validation",
+    "pack_loads",
+    "aeabi_memcpy",
+    "dcache_before_init",
+    "second_core_mailbox",
+    "tiny",
+    "reset_stub",
+    "program_headers",
+    "stack_alignment",
+    "stack_guard_gap",
+    "stack_protector",
+    "stack_canary",
+    "constants",
+    "regions",
+    "sections",
+];
+
+/// Members of a region
+const REGION_KEYS: &[&str] = &[
+    "name",
+    "origin",
+    "size",
+    "alias_of",
+    "dma",
+    "encrypted",
+    "kind",
+    "reserved",
+    "reserve_start",
+];
+
+/// Members of a section
+const SECTION_KEYS: &[&str] = &[
+    "kind",
+    "vma",
+    "lma",
+    "prefix",
+    "size",
+    "name",
+    "input",
+    "functions",
+    "min_size",
+    "compressed",
+    "dma_init",
+    "second_core",
+    "no_cross_refs_from",
+    "views",
+    "spill",
+    "files",
+    "load_at",
+    "priority",
+    "fill",
+    "keep",
+    "noload",
+];
+
+/// Fail on a member of `value` not in `known`, most likely a misspelling
+/// which would otherwise be silently ignored
+fn known_keys(value: &Value, known: &[&str], what: &str) -> Result<()> {
+    match value {
+        Value::Object(members) => match members
+            .iter()
+            .find(|(key, _)| !known.contains(&key.as_str()))
+        {
+            Some((key, _)) => Err(invalid(format!("unknown field {:?} in {}", key, what))),
+            None => Ok(()),
+        },
+        _ => Err(invalid(format!("{} must be an object", what))),
+    }
+}
+
+/// A region of a config
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionConfig {
+    pub name: String,
+    pub origin: u64,
+    /// Size of the region, `None` for an alias
+    pub size: Option<u64>,
+    /// The region this one is a view of
+    pub alias_of: Option<String>,
+    pub dma: bool,
+    /// Decrypted on the fly by the family's engine
+    pub encrypted: bool,
+    /// What the region's memory allows
+    pub kind: Option<RegionKind>,
+    /// Only declared, sections may not be placed in it
+    pub reserved: bool,
+    /// Bytes at its start sections aren't placed in
+    pub reserve_start: Option<u64>,
+}
+
+/// A section of a config, added by the `LinkerScript` method `kind`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionConfig {
+    pub kind: String,
+    pub vma: String,
+    pub lma: Option<String>,
+    pub prefix: bool,
+    pub size: Option<u64>,
+    pub name: Option<String>,
+    pub input: Option<String>,
+    pub functions: Vec<String>,
+    pub min_size: Option<u64>,
+    /// Stored LZ4 compressed in its load region
+    pub compressed: bool,
+    /// Copied by eDMA at reset
+    pub dma_init: bool,
+    /// Zeroed by the second core
+    pub second_core: bool,
+    /// Output names of the sections which must not reference this one
+    pub no_cross_refs_from: Vec<String>,
+    /// Aliases of the run region the section's symbols are exported in
+    pub views: Vec<String>,
+    /// Region the input sections which don't fit spill into
+    pub spill: Option<String>,
+    /// Patterns of the files a text partition takes the code of
+    pub files: Vec<String>,
+    /// Exact load address in the load region
+    pub load_at: Option<u64>,
+    /// Placement priority rather than the kind's default
+    pub priority: Option<i32>,
+    /// Byte filling the section's gaps
+    pub fill: Option<u8>,
+    /// Input sections are kept, rather than the kind's default
+    pub keep: Option<bool>,
+    /// Output section of type NOLOAD
+    pub noload: bool,
+}
+
+/// A linker script described by a JSON file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    pub preset: Option<String>,
+    pub family: Option<chip::Family>,
+    pub boot_mode: Option<chip::BootMode>,
+    pub flexspi: Option<chip::FlexSpi>,
+    /// Pack the loaded sections for one copy loop
+    pub pack_loads: bool,
+    /// Copy and zero at reset with the ARM EABI memory functions
+    pub aeabi_memcpy: bool,
+    /// The data cache is enabled before the reset's `init`
+    pub dcache_before_init: bool,
+    /// Mailbox of the sections the second core zeroes
+    pub second_core_mailbox: Option<u64>,
+    /// Only the features of the smallest startup
+    pub tiny: bool,
+    /// The reset module has the position independent stub
+    pub reset_stub: bool,
+    /// The linker script declares its program headers
+    pub program_headers: bool,
+    /// Alignment of the stack's ends, 8 bytes when unset
+    pub stack_alignment: Option<u64>,
+    /// Bytes left between the statics and the stack
+    pub stack_guard_gap: Option<u64>,
+    /// The reset module defines the stack protector's guard
+    pub stack_protector: bool,
+    /// Canary the guard is seeded with rather than the default
+    pub stack_canary: Option<u64>,
+    /// Symbols defined with build time values, in order
+    pub constants: Vec<(String, u64)>,
+    pub validation: lint::Validation,
+    pub regions: Vec<RegionConfig>,
+    pub sections: Vec<SectionConfig>,
+}
+
+impl Config {
+    /// Read a config from a JSON file, or a TOML one when its extension is
+    /// `.toml`
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Config> {
+        let input = std::fs::read_to_string(&path)?;
+        match path.as_ref().extension() {
+            Some(ext) if ext == "toml" => Config::from_toml(&input),
+            _ => Config::from_json(&input),
+        }
+    }
+
+    /// Parse a config from JSON
+    pub fn from_json(input: &str) -> Result<Config> {
+        Config::from_value(&json::parse(input).map_err(invalid)?)
+    }
+
+    /// Parse a config from TOML, with the members of the JSON format as
+    /// keys and `[[regions]]` and `[[sections]]` as arrays of tables
+    pub fn from_toml(input: &str) -> Result<Config> {
+        Config::from_value(&toml::parse(input).map_err(invalid)?)
+    }
+
+    fn from_value(doc: &Value) -> Result<Config> {
+        let string = |value: &Value, key: &str| -> Result<Option<String>> {
+            match value.get(key) {
+                None | Some(Value::Null) => Ok(None),
+                Some(Value::Str(s)) => Ok(Some(s.clone())),
+                Some(_) => Err(invalid(format!("field {:?} must be a string", key))),
+            }
+        };
+        let required = |value: &Value, key: &str| -> Result<String> {
+            string(value, key)?.ok_or_else(|| invalid(format!("missing field {:?}", key)))
+        };
+        let number = |value: &Value, key: &str| -> Result<Option<u64>> {
+            match value.get(key) {
+                None | Some(Value::Null) => Ok(None),
+                Some(Value::Str(s)) => parse_size(s)
+                    .map(Some)
+                    .ok_or_else(|| invalid(format!("field {:?} is not a size, {:?}", key, s))),
+                Some(value) => value
+                    .as_u64()
+                    .map(Some)
+                    .ok_or_else(|| invalid(format!("field {:?} must be a size", key))),
+            }
+        };
+        let flag = |value: &Value, key: &str| value.get(key) == Some(&Value::Bool(true));
+        let strings = |value: &Value, key: &str| -> Result<Vec<String>> {
+            match value.get(key) {
+                None => Ok(Vec::new()),
+                Some(strings) => strings
+                    .as_array()
+                    .and_then(|strings| {
+                        strings
+                            .iter()
+                            .map(|string| string.as_str().map(String::from))
+                            .collect()
+                    })
+                    .ok_or_else(|| invalid(format!("field {:?} must be strings", key))),
+            }
+        };
+        let members = |key: &str| -> Result<&[Value]> {
+            match doc.get(key) {
+                None => Ok(&[]),
+                Some(members) => members
+                    .as_array()
+                    .ok_or_else(|| invalid(format!("field {:?} must be an array", key))),
+            }
+        };
+        known_keys(doc, CONFIG_KEYS, "the config")?;
+
+        let mut config = Config {
+            preset: string(doc, "preset")?,
+            family: string(doc, "family")?
+                .map(|family| family.parse().map_err(invalid))
+                .transpose()?,
+            boot_mode: string(doc, "boot_mode")?
+                .map(|mode| mode.parse().map_err(invalid))
+                .transpose()?,
+            flexspi: string(doc, "flexspi")?
+                .map(|flexspi| flexspi.parse().map_err(invalid))
+                .transpose()?,
+            pack_loads: flag(doc, "pack_loads"),
+            aeabi_memcpy: flag(doc, "aeabi_memcpy"),
+            dcache_before_init: flag(doc, "dcache_before_init"),
+            second_core_mailbox: number(doc, "second_core_mailbox")?,
+            tiny: flag(doc, "tiny"),
+            reset_stub: flag(doc, "reset_stub"),
+            program_headers: flag(doc, "program_headers"),
+            stack_alignment: number(doc, "stack_alignment")?,
+            stack_guard_gap: number(doc, "stack_guard_gap")?,
+            stack_protector: flag(doc, "stack_protector"),
+            stack_canary: number(doc, "stack_canary")?,
+            validation: string(doc, "validation")?
+                .map(|validation| validation.parse().map_err(invalid))
+                .transpose()?
+                .unwrap_or_default(),
+            ..Config::default()
+        };
+        match doc.get("constants") {
+            None => {}
+            Some(constants @ Value::Object(members)) => {
+                for (name, _) in members.iter() {
+                    let value = number(constants, name)?
+                        .ok_or_else(|| invalid(format!("constant {:?} needs a value", name)))?;
+                    config.constants.push((name.clone(), value));
+                }
+            }
+            Some(_) => {
+                return Err(invalid(String::from(
+                    "field \"constants\" must map names to values",
+                )))
+            }
+        }
+        for (idx, region) in members("regions")?.iter().enumerate() {
+            known_keys(region, REGION_KEYS, &format!("regions[{}]", idx))?;
+            let name = required(region, "name")?;
+            let alias_of = string(region, "alias_of")?;
+            let size = number(region, "size")?;
+            if alias_of.is_none() && size.is_none() {
+                return Err(invalid(format!("region {:?} needs a size", name)));
+            }
+            config.regions.push(RegionConfig {
+                origin: number(region, "origin")?
+                    .ok_or_else(|| invalid(format!("region {:?} needs an origin", name)))?,
+                name,
+                size,
+                alias_of,
+                dma: flag(region, "dma"),
+                encrypted: flag(region, "encrypted"),
+                kind: string(region, "kind")?
+                    .map(|kind| kind.parse().map_err(invalid))
+                    .transpose()?,
+                reserved: flag(region, "reserved"),
+                reserve_start: number(region, "reserve_start")?,
+            });
+        }
+        for (idx, section) in members("sections")?.iter().enumerate() {
+            known_keys(section, SECTION_KEYS, &format!("sections[{}]", idx))?;
+            config.sections.push(SectionConfig {
+                kind: required(section, "kind")?,
+                vma: required(section, "vma")?,
+                lma: string(section, "lma")?,
+                prefix: flag(section, "prefix"),
+                size: number(section, "size")?,
+                name: string(section, "name")?,
+                input: string(section, "input")?,
+                functions: strings(section, "functions")?,
+                min_size: number(section, "min_size")?,
+                compressed: flag(section, "compressed"),
+                dma_init: flag(section, "dma_init"),
+                second_core: flag(section, "second_core"),
+                no_cross_refs_from: strings(section, "no_cross_refs_from")?,
+                views: strings(section, "views")?,
+                spill: string(section, "spill")?,
+                files: strings(section, "files")?,
+                load_at: number(section, "load_at")?,
+                priority: match section.get("priority") {
+                    None | Some(Value::Null) => None,
+                    Some(priority) => Some(priority.as_i64().ok_or_else(|| {
+                        invalid(String::from("field \"priority\" must be an integer"))
+                    })? as i32),
+                },
+                fill: number(section, "fill")?
+                    .map(|fill| {
+                        u8::try_from(fill)
+                            .map_err(|_| invalid(format!("fill {:#X} is not a byte", fill)))
+                    })
+                    .transpose()?,
+                keep: match section.get("keep") {
+                    None | Some(Value::Null) => None,
+                    Some(Value::Bool(keep)) => Some(*keep),
+                    Some(_) => return Err(invalid(String::from("field \"keep\" must be a bool"))),
+                },
+                noload: flag(section, "noload"),
+            });
+        }
+        Ok(config)
+    }
+
+    /// A config placing the regions and sections of a layout, such as one
+    /// read from a hand-written linker script by the
+    /// [`script`](../script/index.html) module
+    ///
+    /// Sections are matched to a kind by their names, and heaps by their
+    /// size. Those which match none are left out and their names returned.
+    /// A stack is added in the region of `.bss` when the layout has none,
+    /// since hand-written scripts usually place the stack by a symbol
+    /// instead.
+    pub fn from_layout(layout: &Layout) -> (Config, Vec<String>) {
+        const KINDS: [&str; 8] = [
+            "vector_table",
+            "text",
+            "rodata",
+            "data",
+            "bss",
+            "uninit",
+            "stack",
+            "heap",
+        ];
+        const PREFIXED: [&str; 3] = ["rodata", "data", "bss"];
+        let mut config = Config::default();
+        let mut skipped = Vec::new();
+        for region in layout.regions.iter() {
+            config.regions.push(RegionConfig {
+                name: region.name.clone(),
+                origin: region.origin,
+                size: Some(region.size),
+                alias_of: None,
+                dma: false,
+                encrypted: false,
+                kind: None,
+                reserved: false,
+                reserve_start: None,
+            });
+        }
+        for section in layout.sections.iter() {
+            let prefixed = section
+                .output
+                .strip_prefix(&format!("{}.", section.vma))
+                .filter(|kind| PREFIXED.contains(kind));
+            let kind = match prefixed {
+                Some(kind) => kind,
+                None if KINDS.contains(&section.output.as_str()) => section.output.as_str(),
+                None if section.size == LayoutSize::Heap => "heap",
+                None => {
+                    skipped.push(section.output.clone());
+                    continue;
+                }
+            };
+            config.sections.push(SectionConfig {
+                kind: String::from(kind),
+                vma: section.vma.clone(),
+                lma: section.lma.clone(),
+                prefix: prefixed.is_some(),
+                size: None,
+                name: Some(section.output.clone())
+                    .filter(|output| kind == "heap" && output != "heap"),
+                input: None,
+                functions: Vec::new(),
+                min_size: None,
+                compressed: section.compressed,
+                dma_init: false,
+                second_core: false,
+                no_cross_refs_from: Vec::new(),
+                views: Vec::new(),
+                spill: None,
+                files: Vec::new(),
+                load_at: None,
+                priority: None,
+                fill: None,
+                keep: None,
+                noload: false,
+            });
+        }
+        let has = |kind: &str| config.sections.iter().any(|section| section.kind == kind);
+        if !has("stack") {
+            let bss = config.sections.iter().find(|section| section.kind == "bss");
+            if let Some(vma) = bss.map(|bss| bss.vma.clone()) {
+                config.sections.push(SectionConfig {
+                    kind: String::from("stack"),
+                    vma,
+                    lma: None,
+                    prefix: false,
+                    size: None,
+                    name: None,
+                    input: None,
+                    functions: Vec::new(),
+                    min_size: None,
+                    compressed: false,
+                    dma_init: false,
+                    second_core: false,
+                    no_cross_refs_from: Vec::new(),
+                    views: Vec::new(),
+                    spill: None,
+                    files: Vec::new(),
+                    load_at: None,
+                    priority: None,
+                    fill: None,
+                    keep: None,
+                    noload: false,
+                });
+            }
+        }
+        (config, skipped)
+    }
+
+    /// Render the config as JSON, readable by [`from_json`](#method.from_json)
+    pub fn to_json(&self) -> String {
+        let hex = |value: u64| Value::from(format!("{:#X}", value));
+        let mut doc = Vec::new();
+        let mut member = |key: &str, value: Option<String>| {
+            if let Some(value) = value {
+                doc.push((String::from(key), Value::from(value)));
+            }
+        };
+        member("preset", self.preset.clone());
+        member("family", self.family.map(|family| family.to_string()));
+        member("boot_mode", self.boot_mode.map(|mode| mode.to_string()));
+        member("flexspi", self.flexspi.map(|flexspi| flexspi.to_string()));
+        member(
+            "validation",
+            Some(String::from(match self.validation {
+                lint::Validation::Permissive => "permissive",
+                lint::Validation::Strict => "strict",
+            })),
+        );
+        if self.pack_loads {
+            doc.push((String::from("pack_loads"), true.into()));
+        }
+        if self.aeabi_memcpy {
+            doc.push((String::from("aeabi_memcpy"), true.into()));
+        }
+        if self.dcache_before_init {
+            doc.push((String::from("dcache_before_init"), true.into()));
+        }
+        if let Some(mailbox) = self.second_core_mailbox {
+            doc.push((String::from("second_core_mailbox"), hex(mailbox)));
+        }
+        if self.tiny {
+            doc.push((String::from("tiny"), true.into()));
+        }
+        if self.reset_stub {
+            doc.push((String::from("reset_stub"), true.into()));
+        }
+        if self.program_headers {
+            doc.push((String::from("program_headers"), true.into()));
+        }
+        if let Some(align) = self.stack_alignment {
+            doc.push((String::from("stack_alignment"), hex(align)));
+        }
+        if let Some(gap) = self.stack_guard_gap {
+            doc.push((String::from("stack_guard_gap"), hex(gap)));
+        }
+        if self.stack_protector {
+            doc.push((String::from("stack_protector"), true.into()));
+        }
+        if let Some(canary) = self.stack_canary {
+            doc.push((String::from("stack_canary"), hex(canary)));
+        }
+        if !self.constants.is_empty() {
+            let constants = self
+                .constants
+                .iter()
+                .map(|(name, value)| (name.clone(), hex(*value)))
+                .collect();
+            doc.push((String::from("constants"), Value::Object(constants)));
+        }
+        let regions = self
+            .regions
+            .iter()
+            .map(|region| {
+                let mut members = vec![
+                    (String::from("name"), region.name.as_str().into()),
+                    (String::from("origin"), hex(region.origin)),
+                ];
+                if let Some(size) = region.size {
+                    members.push((String::from("size"), hex(size)));
+                }
+                if let Some(of) = &region.alias_of {
+                    members.push((String::from("alias_of"), of.as_str().into()));
+                }
+                if region.dma {
+                    members.push((String::from("dma"), true.into()));
+                }
+                if region.encrypted {
+                    members.push((String::from("encrypted"), true.into()));
+                }
+                if let Some(kind) = region.kind {
+                    members.push((String::from("kind"), kind.to_string().as_str().into()));
+                }
+                if region.reserved {
+                    members.push((String::from("reserved"), true.into()));
+                }
+                if let Some(bytes) = region.reserve_start {
+                    members.push((String::from("reserve_start"), hex(bytes)));
+                }
+                Value::Object(members)
+            })
+            .collect();
+        let sections = self
+            .sections
+            .iter()
+            .map(|section| {
+                let mut members = vec![
+                    (String::from("kind"), section.kind.as_str().into()),
+                    (String::from("vma"), section.vma.as_str().into()),
+                ];
+                let optional = [
+                    ("lma", section.lma.clone().map(Value::from)),
+                    ("prefix", Some(true.into()).filter(|_| section.prefix)),
+                    ("size", section.size.map(hex)),
+                    ("name", section.name.clone().map(Value::from)),
+                    ("input", section.input.clone().map(Value::from)),
+                    (
+                        "functions",
+                        Some(Value::Array(
+                            section
+                                .functions
+                                .iter()
+                                .map(|f| f.as_str().into())
+                                .collect(),
+                        ))
+                        .filter(|_| !section.functions.is_empty()),
+                    ),
+                    ("min_size", section.min_size.map(hex)),
+                    (
+                        "compressed",
+                        Some(true.into()).filter(|_| section.compressed),
+                    ),
+                    ("dma_init", Some(true.into()).filter(|_| section.dma_init)),
+                    (
+                        "second_core",
+                        Some(true.into()).filter(|_| section.second_core),
+                    ),
+                    (
+                        "no_cross_refs_from",
+                        Some(Value::Array(
+                            section
+                                .no_cross_refs_from
+                                .iter()
+                                .map(|from| from.as_str().into())
+                                .collect(),
+                        ))
+                        .filter(|_| !section.no_cross_refs_from.is_empty()),
+                    ),
+                    (
+                        "views",
+                        Some(Value::Array(
+                            section
+                                .views
+                                .iter()
+                                .map(|view| view.as_str().into())
+                                .collect(),
+                        ))
+                        .filter(|_| !section.views.is_empty()),
+                    ),
+                    ("spill", section.spill.clone().map(Value::from)),
+                    (
+                        "files",
+                        Some(Value::Array(
+                            section
+                                .files
+                                .iter()
+                                .map(|file| file.as_str().into())
+                                .collect(),
+                        ))
+                        .filter(|_| !section.files.is_empty()),
+                    ),
+                    ("load_at", section.load_at.map(hex)),
+                    ("priority", section.priority.map(Value::from)),
+                    ("fill", section.fill.map(|fill| hex(fill.into()))),
+                    ("keep", section.keep.map(Value::from)),
+                    ("noload", Some(true.into()).filter(|_| section.noload)),
+                ];
+                for (key, value) in optional.iter() {
+                    if let Some(value) = value {
+                        members.push((String::from(*key), value.clone()));
+                    }
+                }
+                Value::Object(members)
+            })
+            .collect();
+        doc.push((String::from("regions"), Value::Array(regions)));
+        doc.push((String::from("sections"), Value::Array(sections)));
+        format!("{}\n", Value::Object(doc))
+    }
+
+    /// Render the config as the `LinkerScript` calls of a build script
+    ///
+    /// The calls are statements of a function returning
+    /// `Result<_, LinkerError>`, with the crate's items in scope. Regions the config places
+    /// sections in without adding them, such as those of its preset, are
+    /// looked up by [`region_id`](../struct.LinkerScript.html#method.region_id).
+    pub fn to_builder(&self) -> String {
+        let var = |region: &str| -> String {
+            region
+                .chars()
+                .map(|c| {
+                    if c.is_alphanumeric() {
+                        c.to_ascii_lowercase()
+                    } else {
+                        '_'
+                    }
+                })
+                .collect()
+        };
+        let id = |region: &str| format!("{}.clone()", var(region));
+        let lma = |section: &SectionConfig| match &section.lma {
+            Some(lma) => format!("Some({})", id(lma)),
+            None => String::from("None"),
+        };
+        // Kinds whose method adds several sections, returning their IDs
+        let several = |section: &SectionConfig| {
+            matches!(
+                section.kind.as_str(),
+                "boot_gen" | "unwind_tables" | "thread_local" | "coverage"
+            )
+        };
+        let mut out = String::new();
+        match &self.preset {
+            Some(name) => writeln!(out, "let mut ls = preset::{}()?;", name).unwrap(),
+            None => writeln!(out, "let mut ls = LinkerScript::<u32>::new();").unwrap(),
+        }
+        if let Some(family) = self.family {
+            writeln!(out, "ls.family(chip::Family::{:?});", family).unwrap();
+        }
+        if let Some(mode) = self.boot_mode {
+            writeln!(out, "ls.boot_mode(chip::BootMode::{:?});", mode).unwrap();
+        }
+        if let Some(flexspi) = self.flexspi {
+            writeln!(out, "ls.boot_flexspi(chip::FlexSpi::{:?});", flexspi).unwrap();
+        }
+        if self.pack_loads {
+            writeln!(out, "ls.pack_loads(true);").unwrap();
+        }
+        if self.aeabi_memcpy {
+            writeln!(out, "ls.aeabi_memcpy(true);").unwrap();
+        }
+        if self.dcache_before_init {
+            writeln!(out, "ls.dcache_before_init(true);").unwrap();
+        }
+        if self.tiny {
+            writeln!(out, "ls.tiny(true);").unwrap();
+        }
+        if self.reset_stub {
+            writeln!(out, "ls.reset_stub(true);").unwrap();
+        }
+        if self.program_headers {
+            writeln!(out, "ls.program_headers(true);").unwrap();
+        }
+        if let Some(align) = self.stack_alignment {
+            writeln!(out, "ls.stack_alignment({})?;", align).unwrap();
+        }
+        if let Some(gap) = self.stack_guard_gap {
+            writeln!(out, "ls.stack_guard_gap({:#X})?;", gap).unwrap();
+        }
+        match (self.stack_protector, self.stack_canary) {
+            (true, Some(canary)) => writeln!(out, "ls.stack_canary({:#X});", canary).unwrap(),
+            (true, None) => writeln!(out, "ls.stack_protector(true);").unwrap(),
+            (false, _) => {}
+        }
+        for (name, value) in self.constants.iter() {
+            writeln!(out, "ls.define_symbol({:?}, {:#X})?;", name, value).unwrap();
+        }
+        if self.validation != lint::Validation::Permissive {
+            writeln!(
+                out,
+                "ls.validation(lint::Validation::{:?});",
+                self.validation
+            )
+            .unwrap();
+        }
+        let referenced = self
+            .regions
+            .iter()
+            .filter_map(|region| region.alias_of.as_deref())
+            .chain(self.sections.iter().flat_map(|section| {
+                std::iter::once(section.vma.as_str())
+                    .chain(section.lma.as_deref())
+                    .chain(section.views.iter().map(String::as_str))
+                    .chain(section.spill.as_deref())
+            }));
+        let mut looked_up: Vec<&str> = Vec::new();
+        for name in referenced {
+            if !looked_up.contains(&name) && !self.regions.iter().any(|r| r.name == name) {
+                writeln!(out, "let {} = ls.region_id({:?})?;", var(name), name).unwrap();
+                looked_up.push(name);
+            }
+        }
+        for region in self.regions.iter() {
+            match &region.alias_of {
+                Some(of) => writeln!(
+                    out,
+                    "let {} = ls.alias({:?}, &{}, {:#010X})?;",
+                    var(&region.name),
+                    region.name,
+                    var(of),
+                    region.origin
+                ),
+                None => writeln!(
+                    out,
+                    "let {} = ls.{}({:?}, {:#010X}, {:#X})?;",
+                    var(&region.name),
+                    if region.reserved {
+                        "reserve_region"
+                    } else {
+                        "region"
+                    },
+                    region.name,
+                    region.origin,
+                    region.size.unwrap_or_default()
+                ),
+            }
+            .unwrap();
+            if region.dma {
+                writeln!(out, "ls.dma_capable(&{})?;", var(&region.name)).unwrap();
+            }
+            if region.encrypted {
+                writeln!(out, "ls.encrypted(&{})?;", var(&region.name)).unwrap();
+            }
+            if let Some(kind) = region.kind {
+                writeln!(
+                    out,
+                    "ls.region_kind(&{}, RegionKind::{:?})?;",
+                    var(&region.name),
+                    kind
+                )
+                .unwrap();
+            }
+            if let Some(bytes) = region.reserve_start {
+                writeln!(
+                    out,
+                    "ls.reserve_start(&{}, {:#X})?;",
+                    var(&region.name),
+                    bytes
+                )
+                .unwrap();
+            }
+        }
+        // Sections with settings or referenced by others are bound to a
+        // variable, named by their kind and numbered when another section
+        // or a region took the name
+        let bound = |section: &SectionConfig| {
+            section.min_size.is_some()
+                || section.compressed
+                || section.dma_init
+                || section.second_core
+                || !section.no_cross_refs_from.is_empty()
+                || !section.views.is_empty()
+                || section.spill.is_some()
+                || section.load_at.is_some()
+                || section.priority.is_some()
+                || section.fill.is_some()
+                || section.keep.is_some()
+                || section.noload
+                || self
+                    .sections
+                    .iter()
+                    .any(|other| other.no_cross_refs_from.contains(&section.output()))
+        };
+        let mut taken: Vec<String> = self
+            .regions
+            .iter()
+            .map(|region| var(&region.name))
+            .chain(looked_up.iter().map(|name| var(name)))
+            .chain(std::iter::once(String::from("ls")))
+            .collect();
+        let names: Vec<String> = self
+            .sections
+            .iter()
+            .map(|section| {
+                let mut name = section.kind.clone();
+                let mut count = 1;
+                if bound(section) {
+                    while taken.contains(&name) {
+                        count += 1;
+                        name = format!("{}_{}", section.kind, count);
+                    }
+                    taken.push(name.clone());
+                }
+                name
+            })
+            .collect();
+        for (section, name) in self.sections.iter().zip(names.iter()) {
+            let vma = id(&section.vma);
+            let size = format!("{:#X}", section.size.unwrap_or_default());
+            let arguments = match section.kind.as_str() {
+                "heap" => match &section.name {
+                    Some(name) => format!("{:?}, {}", name, vma),
+                    None => vma,
+                },
+                "stack"
+                | "uninit"
+                | "unwind_tables"
+                | "dynamic_relocations"
+                | "boot_gen"
+                | "build_id"
+                | "version_string" => vma,
+                "serial_download_ivt" => match section.size {
+                    Some(size) => format!("{}, Some({:#X})", vma, size),
+                    None => format!("{}, None", vma),
+                },
+                "stack_below_statics" => format!("{}, {}", size, vma),
+                "process_stack" => format!("{}, {}", vma, size),
+                "boot_config" => format!(
+                    "{}, {:?}, {}",
+                    size,
+                    section.name.as_deref().unwrap_or_default(),
+                    vma
+                ),
+                "data" | "rodata" | "bss" | "text_ram" => {
+                    format!("{}, {}, {}", section.prefix, vma, lma(section))
+                }
+                "retain" => format!(
+                    "{:?}, {}, {}",
+                    section.input.as_deref().unwrap_or_default(),
+                    vma,
+                    lma(section)
+                ),
+                "custom_section" => format!(
+                    "{:?}, {}, {}, {}, {}",
+                    section.name.as_deref().unwrap_or_default(),
+                    section.prefix,
+                    vma,
+                    lma(section),
+                    match section.size {
+                        Some(size) => format!("Some({:#X})", size),
+                        None => String::from("None"),
+                    }
+                ),
+                "hot_text" => format!("&{:?}, {}, {}", section.functions, vma, lma(section)),
+                "text_partition" => format!(
+                    "&{:?}, {}, {}, {}",
+                    section.files,
+                    match section.size {
+                        Some(size) => format!("Some({:#X})", size),
+                        None => String::from("None"),
+                    },
+                    vma,
+                    lma(section)
+                ),
+                "thread_local" | "coverage" => format!(
+                    "{}, {}",
+                    id(section.lma.as_deref().unwrap_or_default()),
+                    vma
+                ),
+                _ => format!("{}, {}", vma, lma(section)),
+            };
+            let method = match (section.kind.as_str(), &section.name) {
+                ("heap", Some(_)) => "named_heap",
+                (kind, _) => kind,
+            };
+            if !bound(section) {
+                writeln!(out, "ls.{}({})?;", method, arguments).unwrap();
+                continue;
+            }
+            writeln!(out, "let {} = ls.{}({})?;", name, method, arguments).unwrap();
+            // The settings apply to each section of a kind adding several
+            let (indent, target) = if several(section) {
+                writeln!(out, "for id in {}.iter() {{", name).unwrap();
+                ("    ", String::from("id"))
+            } else {
+                ("", format!("&{}", name))
+            };
+            if let Some(min_size) = section.min_size {
+                writeln!(out, "{}ls.min_size({}, {:#X})?;", indent, target, min_size).unwrap();
+            }
+            if section.compressed {
+                writeln!(out, "{}ls.compress({})?;", indent, target).unwrap();
+            }
+            if section.dma_init {
+                writeln!(out, "{}ls.dma_init({})?;", indent, target).unwrap();
+            }
+            for view in section.views.iter() {
+                writeln!(out, "{}ls.alias_view({}, &{})?;", indent, target, var(view)).unwrap();
+            }
+            if let Some(spill) = &section.spill {
+                writeln!(out, "{}ls.spill({}, {})?;", indent, target, id(spill)).unwrap();
+            }
+            if let Some(address) = section.load_at {
+                writeln!(out, "{}ls.load_at({}, {:#010X})?;", indent, target, address).unwrap();
+            }
+            if let Some(priority) = section.priority {
+                writeln!(out, "{}ls.priority({}, {})?;", indent, target, priority).unwrap();
+            }
+            if let Some(fill) = section.fill {
+                writeln!(out, "{}ls.fill({}, {:#04X})?;", indent, target, fill).unwrap();
+            }
+            if let Some(keep) = section.keep {
+                writeln!(out, "{}ls.keep({}, {})?;", indent, target, keep).unwrap();
+            }
+            if section.noload {
+                writeln!(out, "{}ls.noload({}, true)?;", indent, target).unwrap();
+            }
+            if several(section) {
+                writeln!(out, "}}").unwrap();
+            }
+        }
+        let handed: Vec<(&SectionConfig, &String)> = self
+            .sections
+            .iter()
+            .zip(names.iter())
+            .filter(|(section, _)| section.second_core)
+            .collect();
+        if let (false, Some(mailbox)) = (handed.is_empty(), self.second_core_mailbox) {
+            let ids = if handed.iter().any(|(section, _)| several(section)) {
+                writeln!(out, "let mut handed = Vec::new();").unwrap();
+                for (section, name) in handed.iter() {
+                    if several(section) {
+                        writeln!(out, "handed.extend({}.iter().cloned());", name).unwrap();
+                    } else {
+                        writeln!(out, "handed.push({}.clone());", name).unwrap();
+                    }
+                }
+                String::from("handed")
+            } else {
+                let names: Vec<String> = handed
+                    .iter()
+                    .map(|(_, name)| format!("{}.clone()", name))
+                    .collect();
+                format!("[{}]", names.join(", "))
+            };
+            writeln!(out, "ls.second_core_init(&{}, {:#X})?;", ids, mailbox).unwrap();
+        }
+        for (section, name) in self.sections.iter().zip(names.iter()) {
+            if section.no_cross_refs_from.is_empty() {
+                continue;
+            }
+            let from: Vec<String> = section
+                .no_cross_refs_from
+                .iter()
+                .map(|output| {
+                    match self
+                        .sections
+                        .iter()
+                        .zip(names.iter())
+                        .find(|(other, _)| &other.output() == output && !several(other))
+                    {
+                        Some((_, name)) => format!("{}.clone()", name),
+                        None => format!("ls.section_id({:?})?", output),
+                    }
+                })
+                .collect();
+            if several(section) {
+                writeln!(out, "for id in {}.iter() {{", name).unwrap();
+                writeln!(out, "    ls.no_cross_refs(id, &[{}])?;", from.join(", ")).unwrap();
+                writeln!(out, "}}").unwrap();
+            } else {
+                writeln!(out, "ls.no_cross_refs(&{}, &[{}])?;", name, from.join(", ")).unwrap();
+            }
+        }
+        out
+    }
+
+    /// Build the linker script the config describes
+    pub fn linker_script(&self) -> Result<LinkerScript<u32>> {
+        self.build().map(|(ls, _)| ls)
+    }
+
+    /// Build the linker script, with the config entry which produced each
+    /// region and section, keyed by region name and output section name
+    fn build(&self) -> Result<(LinkerScript<u32>, HashMap<String, String>)> {
+        let mut ls = match &self.preset {
+            Some(name) => preset::named(name)
+                .ok_or_else(|| invalid(format!("unknown preset {:?}", name)))??,
+            None => LinkerScript::new(),
+        };
+        if let Some(family) = self.family {
+            ls.family(family);
+        }
+        if let Some(mode) = self.boot_mode {
+            ls.boot_mode(mode);
+        }
+        if let Some(flexspi) = self.flexspi {
+            ls.boot_flexspi(flexspi);
+        }
+        ls.pack_loads(self.pack_loads);
+        ls.aeabi_memcpy(self.aeabi_memcpy);
+        ls.dcache_before_init(self.dcache_before_init);
+        ls.tiny(self.tiny);
+        ls.reset_stub(self.reset_stub);
+        ls.program_headers(self.program_headers);
+        match (self.stack_protector, self.stack_canary) {
+            (true, Some(canary)) => ls.stack_canary(word("stack_canary", canary)?),
+            (false, Some(_)) => {
+                return Err(invalid(String::from(
+                    "a stack_canary is given without the stack_protector",
+                )))
+            }
+            (protect, None) => ls.stack_protector(protect),
+        }
+        if let Some(align) = self.stack_alignment {
+            ls.stack_alignment(word("stack_alignment", align)?)?;
+        }
+        if let Some(gap) = self.stack_guard_gap {
+            ls.stack_guard_gap(word("stack_guard_gap", gap)?)?;
+        }
+        for (name, value) in self.constants.iter() {
+            ls.define_symbol(name, word(name, *value)?)?;
+        }
+        ls.validation(self.validation);
+        let mut sources = HashMap::new();
+        if let Some(name) = &self.preset {
+            let layout = ls.layout();
+            let names = layout.regions.iter().map(|region| region.name.clone());
+            let outputs = layout.sections.iter().map(|section| section.output.clone());
+            for name_or_output in names.chain(outputs) {
+                sources.insert(name_or_output, format!("preset {}", name));
+            }
+        }
+        for (index, region) in self.regions.iter().enumerate() {
+            let origin = word(&region.name, region.origin)?;
+            let id = match &region.alias_of {
+                Some(of) => ls.alias(&region.name, &RegionID(of.clone()), origin)?,
+                None => {
+                    let size = word(&region.name, region.size.unwrap_or_default())?;
+                    if region.reserved {
+                        ls.reserve_region(&region.name, origin, size)?
+                    } else {
+                        ls.region(&region.name, origin, size)?
+                    }
+                }
+            };
+            if region.encrypted {
+                ls.encrypted(&id)?;
+            }
+            if region.dma {
+                ls.dma_capable(&id)?;
+            }
+            if let Some(kind) = region.kind {
+                ls.region_kind(&id, kind)?;
+            }
+            if let Some(bytes) = region.reserve_start {
+                ls.reserve_start(&id, word(&region.name, bytes)?)?;
+            }
+            sources.insert(id.0, format!("regions[{}]", index));
+        }
+        let mut handed = Vec::new();
+        let mut no_cross_refs = Vec::new();
+        for (index, section) in self.sections.iter().enumerate() {
+            for id in section.add(&mut ls)? {
+                if let Some(min_size) = section.min_size {
+                    ls.min_size(&id, word(&section.kind, min_size)?)?;
+                }
+                if section.compressed {
+                    ls.compress(&id)?;
+                }
+                if section.dma_init {
+                    ls.dma_init(&id)?;
+                }
+                for view in section.views.iter() {
+                    ls.alias_view(&id, &RegionID(view.clone()))?;
+                }
+                if let Some(address) = section.load_at {
+                    ls.load_at(&id, word(&section.kind, address)?)?;
+                }
+                if let Some(priority) = section.priority {
+                    ls.priority(&id, priority)?;
+                }
+                if let Some(fill) = section.fill {
+                    ls.fill(&id, fill)?;
+                }
+                if let Some(keep) = section.keep {
+                    ls.keep(&id, keep)?;
+                }
+                if section.noload {
+                    ls.noload(&id, true)?;
+                }
+                if let Some(spill) = &section.spill {
+                    let spilled = ls.spill(&id, RegionID(spill.clone()))?;
+                    sources.insert(spilled.0, format!("sections[{}] spill", index));
+                }
+                if section.second_core {
+                    handed.push(id.clone());
+                }
+                if !section.no_cross_refs_from.is_empty() {
+                    let from: Vec<SectionID> = section
+                        .no_cross_refs_from
+                        .iter()
+                        .cloned()
+                        .map(SectionID)
+                        .collect();
+                    no_cross_refs.push((id.clone(), from));
+                }
+                sources.insert(id.0, format!("sections[{}] {}", index, section.kind));
+            }
+        }
+        match self.second_core_mailbox {
+            Some(mailbox) => ls.second_core_init(&handed, word("second_core_mailbox", mailbox)?)?,
+            None if !handed.is_empty() => {
+                return Err(invalid(String::from(
+                    "sections are handed to the second core without a second_core_mailbox",
+                )))
+            }
+            None => {}
+        }
+        for (to, from) in no_cross_refs.iter() {
+            ls.no_cross_refs(to, from)?;
+        }
+        Ok((ls, sources))
+    }
+
+    /// Describe the resolved layout as a table
+    ///
+    /// Every region is listed with its origin, size and end, then every
+    /// section in placement order with the regions it runs from and is
+    /// loaded from. A section's address and size are shown when they are
+    /// known before linking, which is when only fixed size sections precede
+    /// it. The last column is the config entry, or preset, that added the
+    /// region or section.
+    pub fn explain(&self) -> Result<String> {
+        let (ls, sources) = self.build()?;
+        let layout = ls.layout();
+        let source = |name: &str| sources.get(name).map(String::as_str).unwrap_or("-");
+        let mut out = String::new();
+        writeln!(
+            out,
+            "{:<16} {:<10} {:>10} {:<10} source",
+            "region", "origin", "size", "end"
+        )
+        .unwrap();
+        for region in layout.regions.iter() {
+            writeln!(
+                out,
+                "{:<16} {:#010X} {:>10} {:#010X} {}",
+                region.name,
+                region.origin,
+                region.size,
+                region.end(),
+                source(&region.name)
+            )
+            .unwrap();
+        }
+        writeln!(out).unwrap();
+        writeln!(
+            out,
+            "{:<16} {:<10} {:<10} {:<10} {:>10} source",
+            "section", "region", "load", "address", "size"
+        )
+        .unwrap();
+        for section in layout.sections.iter() {
+            let fixed = layout.fixed_address(&section.output);
+            let address = fixed.map_or_else(
+                || String::from("-"),
+                |(address, _)| format!("{:#010X}", address),
+            );
+            let size = match section.size {
+                LayoutSize::Fixed(size) | LayoutSize::FixedStack(size) => size.to_string(),
+                LayoutSize::Linker => String::from("linked"),
+                LayoutSize::Stack | LayoutSize::Heap => String::from("rest"),
+            };
+            writeln!(
+                out,
+                "{:<16} {:<10} {:<10} {:<10} {:>10} {}",
+                format!(".{}", section.output),
+                section.vma,
+                section.lma.as_deref().unwrap_or("-"),
+                address,
+                size,
+                source(&section.output)
+            )
+            .unwrap();
+        }
+        Ok(out)
+    }
+}
+
+/// Name of the config file `render init` writes
+pub const STARTER_FILE: &str = "imxrt-rt.json";
+
+/// A config starting from the preset called `name`, with empty lists of
+/// regions and sections to add to it
+pub fn starter(name: &str) -> Option<String> {
+    preset::PRESETS.iter().find(|(preset, _)| *preset == name)?;
+    Some(format!(
+        r#"{{
+    "preset": "{}",
+    "validation": "permissive",
+    "regions": [],
+    "sections": []
+}}
+"#,
+        name
+    ))
+}
+
+/// A `build.rs` generating the linker script from the config at `path`,
+/// relative to the crate, into `OUT_DIR` and adding it to the link search
+/// path
+pub fn build_rs(path: &str) -> String {
+    format!(
+        r#"use imxrt_rt_gen::config::Config;
+use std::env;
+use std::path::PathBuf;
+
+fn main() {{
+    let out = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    let config = Config::open("{path}").unwrap();
+    for warning in config.linker_script().unwrap().generate_to(&out).unwrap() {{
+        println!("cargo:warning={{}}", warning);
+    }}
+    println!("cargo:rustc-link-search={{}}", out.display());
+    println!("cargo:rerun-if-changed={path}");
+}}
+"#,
+        path = path
+    )
+}
+
+/// Narrow an address or size to the 32 bit words of a config's script
+fn word(name: &str, value: u64) -> Result<u32> {
+    if value > u64::from(u32::MAX) {
+        return Err(invalid(format!(
+            "{} has {:#X}, larger than 32 bits",
+            name, value
+        )));
+    }
+    Ok(value as u32)
+}
+
+impl SectionConfig {
+    /// Name of the output section `add` places
+    fn output(&self) -> String {
+        match (self.kind.as_str(), &self.name) {
+            ("boot_config", Some(name)) | ("heap", Some(name)) => name.clone(),
+            ("custom_section", Some(name)) if self.prefix => format!("{}.{}", self.vma, name),
+            ("custom_section", Some(name)) => name.clone(),
+            ("text_partition", _) => format!("{}.text", self.vma),
+            (kind, _) if self.prefix => format!("{}.{}", self.vma, kind),
+            (kind, _) => String::from(kind),
+        }
+    }
+
+    /// Add the section to `ls` with the method named by `kind`
+    fn add(&self, ls: &mut LinkerScript<u32>) -> Result<Vec<SectionID>> {
+        let vma = RegionID(self.vma.clone());
+        let lma = self.lma.clone().map(RegionID);
+        let missing = |field: &str| invalid(format!("{} section needs a {:?}", self.kind, field));
+        let size =
+            || -> Result<u32> { word(&self.kind, self.size.ok_or_else(|| missing("size"))?) };
+        let id = match self.kind.as_str() {
+            "stack" => ls.stack(vma)?,
+            "stack_below_statics" => ls.stack_below_statics(size()?, vma)?,
+            "process_stack" => ls.process_stack(vma, size()?)?,
+            "heap" => match &self.name {
+                Some(name) => ls.named_heap(name, vma)?,
+                None => ls.heap(vma)?,
+            },
+            "uninit" => ls.uninit(vma)?,
+            "boot_config" => {
+                let name = self.name.as_ref().ok_or_else(|| missing("name"))?;
+                ls.boot_config(size()?, name, vma)?
+            }
+            "boot_gen" => return ls.boot_gen(vma),
+            "serial_download_ivt" => {
+                let dcd = self.size.map(|size| word(&self.kind, size)).transpose()?;
+                ls.serial_download_ivt(vma, dcd)?
+            }
+            "build_id" => ls.build_id(vma)?,
+            "version_string" => ls.version_string(vma)?,
+            "vector_table" => ls.vector_table(vma, lma)?,
+            "text" => ls.text(vma, lma)?,
+            "data" => ls.data(self.prefix, vma, lma)?,
+            "rodata" => ls.rodata(self.prefix, vma, lma)?,
+            "bss" => ls.bss(self.prefix, vma, lma)?,
+            "unwind_tables" => return ls.unwind_tables(vma),
+            "position_independent" => ls.position_independent(vma, lma)?,
+            "thread_local" => return ls.thread_local(lma.ok_or_else(|| missing("lma"))?, vma),
+            "coverage" => return ls.coverage(lma.ok_or_else(|| missing("lma"))?, vma),
+            "dynamic_relocations" => ls.dynamic_relocations(vma)?,
+            "retain" => {
+                let input = self.input.as_ref().ok_or_else(|| missing("input"))?;
+                ls.retain(input, vma, lma)?
+            }
+            "custom_section" => {
+                let name = self.name.as_ref().ok_or_else(|| missing("name"))?;
+                let size = self.size.map(|size| word(&self.kind, size)).transpose()?;
+                ls.custom_section(name, self.prefix, vma, lma, size)?
+            }
+            "text_ram" => ls.text_ram(self.prefix, vma, lma)?,
+            "hot_text" => {
+                let functions: Vec<&str> = self.functions.iter().map(String::as_str).collect();
+                ls.hot_text(&functions, vma, lma)?
+            }
+            "text_partition" => {
+                let files: Vec<&str> = self.files.iter().map(String::as_str).collect();
+                let budget = self.size.map(|size| word(&self.kind, size)).transpose()?;
+                ls.text_partition(&files, budget, vma, lma)?
+            }
+            other => return Err(invalid(format!("unknown section kind {:?}", other))),
+        };
+        Ok(vec![id])
+    }
+}
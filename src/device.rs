@@ -0,0 +1,136 @@
+//! `device.x` interrupt lists
+//!
+//! The generated linker script includes `device.x`, which provides a
+//! default handler for every interrupt of the chip. PACs generated by
+//! svd2rust ship one, but a layout built without a PAC, or for a chip the
+//! PAC doesn't match, needs one generated from the same source: the SVD
+//! file, or the `Interrupt` enum of the PAC.
+
+use crate::{LinkerError, Result};
+use std::fmt::Write;
+
+/// A device interrupt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interrupt {
+    /// Handler name
+    pub name: String,
+    /// Interrupt number
+    pub value: u32,
+}
+
+fn invalid(reason: String) -> LinkerError {
+    LinkerError::InvalidDevice(reason)
+}
+
+fn number(value: &str) -> Option<u32> {
+    let value = value.trim();
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Text of the first `<tag>` element in `xml`
+fn element<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&close)?;
+    Some(xml[start..end].trim())
+}
+
+/// Sort by number and drop repeated names
+fn normalize(mut interrupts: Vec<Interrupt>) -> Vec<Interrupt> {
+    interrupts.sort_by(|a, b| a.value.cmp(&b.value).then(a.name.cmp(&b.name)));
+    let mut seen = Vec::new();
+    interrupts.retain(|interrupt| {
+        if seen.contains(&interrupt.name) {
+            false
+        } else {
+            seen.push(interrupt.name.clone());
+            true
+        }
+    });
+    interrupts
+}
+
+/// Collect the `<interrupt>` elements of an SVD file
+///
+/// Peripherals sharing an interrupt list it more than once, each name is
+/// kept once.
+pub fn from_svd(svd: &str) -> Result<Vec<Interrupt>> {
+    let mut interrupts = Vec::new();
+    let mut rest = svd;
+    while let Some(start) = rest.find("<interrupt>") {
+        let end = rest[start..]
+            .find("</interrupt>")
+            .map(|end| start + end)
+            .ok_or_else(|| invalid(String::from("unterminated <interrupt> element")))?;
+        let body = &rest[start..end];
+        let name = element(body, "name")
+            .ok_or_else(|| invalid(String::from("interrupt without a name")))?;
+        let value = element(body, "value")
+            .and_then(number)
+            .ok_or_else(|| invalid(format!("interrupt {} without a valid value", name)))?;
+        interrupts.push(Interrupt {
+            name: String::from(name),
+            value,
+        });
+        rest = &rest[end..];
+    }
+    if interrupts.is_empty() {
+        return Err(invalid(String::from("no interrupts in the SVD file")));
+    }
+    Ok(normalize(interrupts))
+}
+
+/// Collect the variants of a PAC's `Interrupt` enum
+///
+/// The input is the enum as written in the PAC source, or any list of
+/// `NAME = NUMBER,` lines. Attributes and comments are ignored.
+pub fn from_pac(source: &str) -> Result<Vec<Interrupt>> {
+    let mut interrupts = Vec::new();
+    for line in source.lines().map(str::trim) {
+        let line = line.trim_end_matches(',');
+        let mut parts = line.splitn(2, '=');
+        let (name, value) = match (parts.next(), parts.next()) {
+            (Some(name), Some(value)) => (name.trim(), value),
+            _ => continue,
+        };
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            continue;
+        }
+        let value =
+            number(value).ok_or_else(|| invalid(format!("interrupt {} has no number", name)))?;
+        interrupts.push(Interrupt {
+            name: String::from(name),
+            value,
+        });
+    }
+    if interrupts.is_empty() {
+        return Err(invalid(String::from("no interrupts in the enum")));
+    }
+    Ok(normalize(interrupts))
+}
+
+/// Render `device.x`, providing `DefaultHandler` for every interrupt
+pub fn render(interrupts: &[Interrupt]) -> String {
+    let mut out = String::new();
+    for interrupt in interrupts.iter() {
+        let _ = writeln!(
+            out,
+            "PROVIDE({} = DefaultHandler); /* {} */",
+            interrupt.name, interrupt.value
+        );
+    }
+    out
+}
+
+/// Place `device.x` in the current working directory, next to the
+/// `link.x` including it
+pub fn generate(interrupts: &[Interrupt]) -> Result<()> {
+    crate::write_file("device.x", render(interrupts).as_bytes())
+}
@@ -0,0 +1,243 @@
+//! Differences between two builds
+//!
+//! Two exported layouts are compared by their regions and section
+//! placement, two linked ELF files by the address and size of their
+//! allocated sections and global symbols. Comparing ELF files catches
+//! things a layout can't, such as a symbol that must stay at a stable
+//! address for a bootloader API moving because a section before it grew.
+
+use crate::elf::Elf;
+use crate::layout::Layout;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A single difference between an old and a new build
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// A region only present in the new build
+    RegionAdded(String),
+    /// A region only present in the old build
+    RegionRemoved(String),
+    /// A region whose origin or size changed, (name, old origin, old size, new origin, new size)
+    RegionChanged(String, u64, u64, u64, u64),
+    /// A section only present in the new build
+    SectionAdded(String),
+    /// A section only present in the old build
+    SectionRemoved(String),
+    /// A section placed in a different region, (name, old placement, new placement)
+    SectionPlaced(String, String, String),
+    /// A section placed at a different address, (name, old address, new address)
+    SectionMoved(String, u64, u64),
+    /// A section whose size changed, (name, old size, new size)
+    SectionResized(String, u64, u64),
+    /// A global symbol only present in the new build
+    SymbolAdded(String),
+    /// A global symbol only present in the old build
+    SymbolRemoved(String),
+    /// A global symbol at a different address, (name, old address, new address)
+    SymbolMoved(String, u64, u64),
+}
+
+impl Change {
+    /// Name of the region, section, or symbol changed
+    pub fn name(&self) -> &str {
+        match self {
+            Change::RegionAdded(name)
+            | Change::RegionRemoved(name)
+            | Change::RegionChanged(name, ..)
+            | Change::SectionAdded(name)
+            | Change::SectionRemoved(name)
+            | Change::SectionPlaced(name, ..)
+            | Change::SectionMoved(name, ..)
+            | Change::SectionResized(name, ..)
+            | Change::SymbolAdded(name)
+            | Change::SymbolRemoved(name)
+            | Change::SymbolMoved(name, ..) => name,
+        }
+    }
+
+    /// True if the change makes a symbol unavailable at its old address
+    pub fn breaks_symbol(&self, symbol: &str) -> bool {
+        match self {
+            Change::SymbolRemoved(name) | Change::SymbolMoved(name, ..) => name == symbol,
+            _ => false,
+        }
+    }
+}
+
+fn signed_delta(old: u64, new: u64) -> String {
+    if new >= old {
+        format!("+{}", new - old)
+    } else {
+        format!("-{}", old - new)
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::RegionAdded(name) => write!(f, "region {} added", name),
+            Change::RegionRemoved(name) => write!(f, "region {} removed", name),
+            Change::RegionChanged(name, old_origin, old_size, new_origin, new_size) => write!(
+                f,
+                "region {} changed from {:#010X}+{:#X} to {:#010X}+{:#X}",
+                name, old_origin, old_size, new_origin, new_size
+            ),
+            Change::SectionAdded(name) => write!(f, "section {} added", name),
+            Change::SectionRemoved(name) => write!(f, "section {} removed", name),
+            Change::SectionPlaced(name, old, new) => {
+                write!(f, "section {} placed in {} instead of {}", name, new, old)
+            }
+            Change::SectionMoved(name, old, new) => write!(
+                f,
+                "section {} moved from {:#010X} to {:#010X}",
+                name, old, new
+            ),
+            Change::SectionResized(name, old, new) => write!(
+                f,
+                "section {} resized from {} to {} bytes ({})",
+                name,
+                old,
+                new,
+                signed_delta(*old, *new)
+            ),
+            Change::SymbolAdded(name) => write!(f, "symbol {} added", name),
+            Change::SymbolRemoved(name) => write!(f, "symbol {} removed", name),
+            Change::SymbolMoved(name, old, new) => write!(
+                f,
+                "symbol {} moved from {:#010X} to {:#010X}",
+                name, old, new
+            ),
+        }
+    }
+}
+
+/// Compare the keyed entries of an old and a new build
+fn compare<T, F>(
+    old: BTreeMap<String, T>,
+    new: BTreeMap<String, T>,
+    added: fn(String) -> Change,
+    removed: fn(String) -> Change,
+    mut changed: F,
+) -> Vec<Change>
+where
+    F: FnMut(&String, &T, &T, &mut Vec<Change>),
+{
+    let mut changes = Vec::new();
+    for (name, old_value) in old.iter() {
+        match new.get(name) {
+            Some(new_value) => changed(name, old_value, new_value, &mut changes),
+            None => changes.push(removed(name.clone())),
+        }
+    }
+    for name in new.keys().filter(|name| !old.contains_key(*name)) {
+        changes.push(added(name.clone()));
+    }
+    changes
+}
+
+/// Differences between two layouts
+pub fn layouts(old: &Layout, new: &Layout) -> Vec<Change> {
+    let regions = |layout: &Layout| {
+        layout
+            .regions
+            .iter()
+            .map(|r| (r.name.clone(), (r.origin, r.size)))
+            .collect()
+    };
+    let mut changes = compare(
+        regions(old),
+        regions(new),
+        Change::RegionAdded,
+        Change::RegionRemoved,
+        |name, old, new, changes| {
+            if old != new {
+                changes.push(Change::RegionChanged(
+                    name.clone(),
+                    old.0,
+                    old.1,
+                    new.0,
+                    new.1,
+                ));
+            }
+        },
+    );
+
+    let placement = |layout: &Layout| {
+        layout
+            .sections
+            .iter()
+            .map(|s| {
+                let placed = match &s.lma {
+                    Some(lma) => format!("{} AT> {}", s.vma, lma),
+                    None => s.vma.clone(),
+                };
+                (format!(".{}", s.output), placed)
+            })
+            .collect()
+    };
+    changes.extend(compare(
+        placement(old),
+        placement(new),
+        Change::SectionAdded,
+        Change::SectionRemoved,
+        |name, old, new, changes| {
+            if old != new {
+                changes.push(Change::SectionPlaced(
+                    name.clone(),
+                    old.clone(),
+                    new.clone(),
+                ));
+            }
+        },
+    ));
+    changes
+}
+
+/// Differences between two linked ELF files
+///
+/// Allocated sections are compared by address and size, global symbols
+/// by address.
+pub fn elfs(old: &Elf, new: &Elf) -> Vec<Change> {
+    let sections = |elf: &Elf| {
+        elf.sections()
+            .iter()
+            .filter(|s| s.is_alloc())
+            .map(|s| (s.name.clone(), (s.address, s.size)))
+            .collect()
+    };
+    let mut changes = compare(
+        sections(old),
+        sections(new),
+        Change::SectionAdded,
+        Change::SectionRemoved,
+        |name, old, new, changes| {
+            if old.0 != new.0 {
+                changes.push(Change::SectionMoved(name.clone(), old.0, new.0));
+            }
+            if old.1 != new.1 {
+                changes.push(Change::SectionResized(name.clone(), old.1, new.1));
+            }
+        },
+    );
+
+    let symbols = |elf: &Elf| {
+        elf.symbols()
+            .iter()
+            .filter(|s| s.global && !s.name.is_empty())
+            .map(|s| (s.name.clone(), s.value))
+            .collect()
+    };
+    changes.extend(compare(
+        symbols(old),
+        symbols(new),
+        Change::SymbolAdded,
+        Change::SymbolRemoved,
+        |name, old, new, changes| {
+            if old != new {
+                changes.push(Change::SymbolMoved(name.clone(), *old, *new));
+            }
+        },
+    ));
+    changes
+}
@@ -0,0 +1,123 @@
+//! Dual image boot on the RT1170
+//!
+//! The RT1170 boot ROM may boot a secondary image from an offset into
+//! FlexSPI NOR flash, selected by fuses, for example when the primary
+//! image fails authentication. The secondary image is the same firmware
+//! linked to run from the offset. The ROM reads the FCB from the start of
+//! flash only, so the secondary image keeps the space of the boot config
+//! sections but their addresses aren't checked against the family.
+//!
+//! The offset is fused in 1 MB steps, and [`DualImage`] records the value
+//! the provisioning flow burns.
+
+use crate::json::Value;
+use crate::{chip, lint, write_file, LinkerError, LinkerScript, RegionID, Result, Word};
+use std::path::Path;
+
+/// Step of the secondary image offset the fuse field counts
+pub const OFFSET_STEP: u64 = 0x10_0000;
+
+/// Largest value of the secondary image offset fuse field
+pub const MAX_FUSE: u64 = 15;
+
+fn invalid(reason: String) -> LinkerError {
+    LinkerError::InvalidDualImage(reason)
+}
+
+/// Where the secondary image is, and how to fuse the part to boot it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DualImage {
+    /// Region holding the primary image
+    pub region: String,
+    /// Address of the primary image
+    pub primary: u64,
+    /// Address of the secondary image
+    pub secondary: u64,
+    /// Offset of the secondary image from the primary
+    pub offset: u64,
+    /// Value of the secondary image offset fuse field
+    pub fuse: u64,
+}
+
+impl DualImage {
+    /// Render as JSON for the provisioning flow
+    pub fn to_json(&self) -> String {
+        let hex = |value: u64| Value::from(format!("{:#X}", value));
+        let doc = Value::Object(vec![
+            (String::from("region"), self.region.as_str().into()),
+            (String::from("primary"), hex(self.primary)),
+            (String::from("secondary"), hex(self.secondary)),
+            (String::from("offset"), hex(self.offset)),
+            (String::from("fuse"), self.fuse.into()),
+        ]);
+        format!("{}\n", doc)
+    }
+}
+
+/// The secondary image of `ls`, its `flash` region moved `offset` bytes up
+///
+/// The family must be set and support dual image boot, and the offset must
+/// be a fusable step no smaller than the region, so the images don't
+/// overlap.
+pub fn secondary<W: Word>(
+    ls: &LinkerScript<W>,
+    flash: &RegionID,
+    offset: W,
+) -> Result<(LinkerScript<W>, DualImage)> {
+    match ls.family {
+        Some(chip::Family::Imxrt1170) => {}
+        Some(family) => return Err(invalid(format!("the {} can't boot a second image", family))),
+        None => return Err(invalid(String::from("set the chip family, an imxrt1170"))),
+    }
+    let region = ls
+        .regions
+        .get(&flash.0)
+        .ok_or_else(|| LinkerError::UnknownRegion(flash.clone()))?;
+    let offset = offset.into();
+    let fuse = offset / OFFSET_STEP;
+    if !offset.is_multiple_of(OFFSET_STEP) || fuse == 0 || fuse > MAX_FUSE {
+        return Err(invalid(format!(
+            "the offset {:#X} must be 1 to {} steps of {:#X}",
+            offset, MAX_FUSE, OFFSET_STEP
+        )));
+    }
+    if offset < region.size.into() {
+        return Err(invalid(format!(
+            "the offset {:#X} is inside the {:#X} bytes of {}",
+            offset, region.size, region.name
+        )));
+    }
+    let primary = region.origin.into();
+    let origin = W::try_from(primary + offset)
+        .map_err(|_| invalid(format!("{:#X} is past the address space", primary + offset)))?;
+    let mut secondary = ls.relocated(flash, origin)?;
+    secondary.family = None;
+    let image = DualImage {
+        region: region.name.clone(),
+        primary,
+        secondary: primary + offset,
+        offset,
+        fuse,
+    };
+    Ok((secondary, image))
+}
+
+/// Generate the primary image into `dir`, the secondary image into its
+/// `secondary` directory, and `dual_image.json` describing the fuse
+///
+/// Returns the lints of the primary and secondary images.
+pub fn generate_to<W: Word, P: AsRef<Path>>(
+    ls: LinkerScript<W>,
+    flash: &RegionID,
+    offset: W,
+    dir: P,
+) -> Result<(Vec<lint::Warning>, Vec<lint::Warning>)> {
+    let dir = dir.as_ref();
+    let (secondary, image) = secondary(&ls, flash, offset)?;
+    let secondary_dir = dir.join("secondary");
+    std::fs::create_dir_all(&secondary_dir)?;
+    let primary_warnings = ls.generate_to(dir)?;
+    let secondary_warnings = secondary.generate_to(&secondary_dir)?;
+    write_file(dir.join("dual_image.json"), image.to_json().as_bytes())?;
+    Ok((primary_warnings, secondary_warnings))
+}
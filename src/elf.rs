@@ -0,0 +1,608 @@
+//! Minimal little-endian ELF reader used by the post-build tools
+//!
+//! Only the parts of the file needed to reason about where the linker
+//! placed things are read: section headers, program headers, and the
+//! symbol table. Both 32 and 64 bit class files are supported.
+
+use crate::{LinkerError, Result};
+use std::fs;
+use std::path::Path;
+
+/// Section header type for program data
+pub const SHT_PROGBITS: u32 = 1;
+
+/// Section header type for the symbol table
+pub const SHT_SYMTAB: u32 = 2;
+
+/// Section header type for sections occupying no file space (bss)
+pub const SHT_NOBITS: u32 = 8;
+
+/// Section flag marking a section as occupying memory at runtime
+pub const SHF_ALLOC: u64 = 0x2;
+
+/// Section flag marking a section as executable
+pub const SHF_EXECINSTR: u64 = 0x4;
+
+/// Symbol type for source file names
+pub const STT_FILE: u8 = 4;
+
+/// Program header type for loadable segments
+pub const PT_LOAD: u32 = 1;
+
+/// A section header
+#[derive(Debug, Clone)]
+pub struct ElfSection {
+    /// Section name, including the leading '.'
+    pub name: String,
+    /// Section type (`SHT_*`)
+    pub kind: u32,
+    /// Section flags (`SHF_*`)
+    pub flags: u64,
+    /// Runtime (virtual) address
+    pub address: u64,
+    /// Offset of the section contents in the file
+    pub offset: u64,
+    /// Size of the section in memory
+    pub size: u64,
+}
+
+impl ElfSection {
+    /// True if the section occupies memory at runtime
+    pub fn is_alloc(&self) -> bool {
+        self.flags & SHF_ALLOC != 0
+    }
+
+    /// True if the section has contents in the file, that is
+    /// it must be loaded rather than zeroed.
+    pub fn has_contents(&self) -> bool {
+        self.kind != SHT_NOBITS
+    }
+}
+
+/// A program header
+#[derive(Debug, Clone)]
+pub struct ElfSegment {
+    /// Segment type (`PT_*`)
+    pub kind: u32,
+    /// Offset of the segment contents in the file
+    pub offset: u64,
+    /// Runtime (virtual) address
+    pub vaddr: u64,
+    /// Load (physical) address
+    pub paddr: u64,
+    /// Number of bytes in the file
+    pub filesz: u64,
+    /// Number of bytes in memory
+    pub memsz: u64,
+}
+
+/// A symbol table entry
+#[derive(Debug, Clone)]
+pub struct ElfSymbol {
+    /// Symbol name as found in the file, possibly mangled
+    pub name: String,
+    /// Symbol value, usually an address
+    pub value: u64,
+    /// Symbol size in bytes
+    pub size: u64,
+    /// Index of the section the symbol is defined in
+    pub section: u16,
+    /// Symbol type (`STT_*`)
+    pub kind: u8,
+    /// True for global and weak symbols, false for local symbols
+    pub global: bool,
+}
+
+/// A parsed ELF file
+#[derive(Debug)]
+pub struct Elf {
+    data: Vec<u8>,
+    is64: bool,
+    entry: u64,
+    sections: Vec<ElfSection>,
+    segments: Vec<ElfSegment>,
+    symbols: Vec<ElfSymbol>,
+}
+
+fn invalid(reason: &str) -> LinkerError {
+    LinkerError::InvalidElf(String::from(reason))
+}
+
+/// Little-endian field reader which is aware of the file class
+struct Reader<'a> {
+    data: &'a [u8],
+    is64: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&self, offset: u64, len: u64) -> Result<&'a [u8]> {
+        let start = offset as usize;
+        let end = start
+            .checked_add(len as usize)
+            .ok_or_else(|| invalid("offset overflow"))?;
+        self.data
+            .get(start..end)
+            .ok_or_else(|| invalid("truncated file"))
+    }
+
+    fn u16(&self, offset: u64) -> Result<u16> {
+        let b = self.bytes(offset, 2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&self, offset: u64) -> Result<u32> {
+        let b = self.bytes(offset, 4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&self, offset: u64) -> Result<u64> {
+        let b = self.bytes(offset, 8)?;
+        let mut raw = [0u8; 8];
+        raw.copy_from_slice(b);
+        Ok(u64::from_le_bytes(raw))
+    }
+
+    /// Read an address sized field, 4 or 8 bytes depending on the class
+    fn word(&self, offset: u64) -> Result<u64> {
+        if self.is64 {
+            self.u64(offset)
+        } else {
+            self.u32(offset).map(u64::from)
+        }
+    }
+
+    fn string(&self, offset: u64) -> Result<String> {
+        let start = offset as usize;
+        let tail = self
+            .data
+            .get(start..)
+            .ok_or_else(|| invalid("string offset out of range"))?;
+        let len = tail.iter().position(|b| *b == 0).unwrap_or(tail.len());
+        Ok(String::from_utf8_lossy(&tail[..len]).into_owned())
+    }
+}
+
+impl Elf {
+    /// Read and parse the ELF file at `path`
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Elf> {
+        Elf::parse(fs::read(path)?)
+    }
+
+    /// Parse an in memory ELF file
+    pub fn parse(data: Vec<u8>) -> Result<Elf> {
+        if data.len() < 16 || &data[0..4] != b"\x7fELF" {
+            return Err(invalid("missing ELF magic"));
+        }
+        let is64 = match data[4] {
+            1 => false,
+            2 => true,
+            _ => return Err(invalid("unknown ELF class")),
+        };
+        if data[5] != 1 {
+            return Err(invalid("only little-endian files are supported"));
+        }
+        let rd = Reader { data: &data, is64 };
+
+        let (entry, phoff, shoff, rest) = if is64 {
+            (rd.u64(24)?, rd.u64(32)?, rd.u64(40)?, 52)
+        } else {
+            (
+                u64::from(rd.u32(24)?),
+                u64::from(rd.u32(28)?),
+                u64::from(rd.u32(32)?),
+                40,
+            )
+        };
+        let phentsize = u64::from(rd.u16(rest + 2)?);
+        let phnum = u64::from(rd.u16(rest + 4)?);
+        let shentsize = u64::from(rd.u16(rest + 6)?);
+        let shnum = u64::from(rd.u16(rest + 8)?);
+        let shstrndx = u64::from(rd.u16(rest + 10)?);
+
+        let mut segments = Vec::new();
+        for idx in 0..phnum {
+            let base = phoff + idx * phentsize;
+            let segment = if is64 {
+                ElfSegment {
+                    kind: rd.u32(base)?,
+                    offset: rd.u64(base + 8)?,
+                    vaddr: rd.u64(base + 16)?,
+                    paddr: rd.u64(base + 24)?,
+                    filesz: rd.u64(base + 32)?,
+                    memsz: rd.u64(base + 40)?,
+                }
+            } else {
+                ElfSegment {
+                    kind: rd.u32(base)?,
+                    offset: rd.word(base + 4)?,
+                    vaddr: rd.word(base + 8)?,
+                    paddr: rd.word(base + 12)?,
+                    filesz: rd.word(base + 16)?,
+                    memsz: rd.word(base + 20)?,
+                }
+            };
+            segments.push(segment);
+        }
+
+        // Raw headers first, names are resolved once the string table is known
+        let mut raw = Vec::new();
+        for idx in 0..shnum {
+            let base = shoff + idx * shentsize;
+            let w = if is64 { 8 } else { 4 };
+            let name = rd.u32(base)?;
+            let kind = rd.u32(base + 4)?;
+            let flags = rd.word(base + 8)?;
+            let address = rd.word(base + 8 + w)?;
+            let offset = rd.word(base + 8 + 2 * w)?;
+            let size = rd.word(base + 8 + 3 * w)?;
+            let link = rd.u32(base + 8 + 4 * w)?;
+            let entsize = rd.word(base + 16 + 5 * w)?;
+            raw.push((name, kind, flags, address, offset, size, link, entsize));
+        }
+        let shstr_offset = raw
+            .get(shstrndx as usize)
+            .map(|hdr| hdr.4)
+            .ok_or_else(|| invalid("missing section name table"))?;
+
+        let mut sections = Vec::new();
+        for hdr in raw.iter() {
+            sections.push(ElfSection {
+                name: rd.string(shstr_offset + u64::from(hdr.0))?,
+                kind: hdr.1,
+                flags: hdr.2,
+                address: hdr.3,
+                offset: hdr.4,
+                size: hdr.5,
+            });
+        }
+
+        let mut symbols = Vec::new();
+        for hdr in raw.iter().filter(|hdr| hdr.1 == SHT_SYMTAB) {
+            let strtab = raw
+                .get(hdr.6 as usize)
+                .map(|s| s.4)
+                .ok_or_else(|| invalid("missing symbol string table"))?;
+            let entsize = if hdr.7 == 0 {
+                if is64 {
+                    24
+                } else {
+                    16
+                }
+            } else {
+                hdr.7
+            };
+            for idx in 0..hdr.5 / entsize {
+                let base = hdr.4 + idx * entsize;
+                let name = rd.u32(base)?;
+                let (value, size, info, section) = if is64 {
+                    (
+                        rd.u64(base + 8)?,
+                        rd.u64(base + 16)?,
+                        rd.bytes(base + 4, 1)?[0],
+                        rd.u16(base + 6)?,
+                    )
+                } else {
+                    (
+                        u64::from(rd.u32(base + 4)?),
+                        u64::from(rd.u32(base + 8)?),
+                        rd.bytes(base + 12, 1)?[0],
+                        rd.u16(base + 14)?,
+                    )
+                };
+                symbols.push(ElfSymbol {
+                    name: rd.string(strtab + u64::from(name))?,
+                    value,
+                    size,
+                    section,
+                    kind: info & 0xf,
+                    global: info >> 4 != 0,
+                });
+            }
+        }
+
+        Ok(Elf {
+            data,
+            is64,
+            entry,
+            sections,
+            segments,
+            symbols,
+        })
+    }
+
+    /// True for 64 bit class files
+    pub fn is64(&self) -> bool {
+        self.is64
+    }
+
+    /// Entry point address
+    pub fn entry(&self) -> u64 {
+        self.entry
+    }
+
+    /// All section headers, in file order
+    pub fn sections(&self) -> &[ElfSection] {
+        &self.sections
+    }
+
+    /// All program headers, in file order
+    pub fn segments(&self) -> &[ElfSegment] {
+        &self.segments
+    }
+
+    /// All symbols from the symbol table
+    pub fn symbols(&self) -> &[ElfSymbol] {
+        &self.symbols
+    }
+
+    /// Find a section by its name, including the leading '.'
+    pub fn section(&self, name: &str) -> Option<&ElfSection> {
+        self.sections.iter().find(|s| s.name == name)
+    }
+
+    /// Find a symbol by its (mangled) name
+    pub fn symbol(&self, name: &str) -> Option<&ElfSymbol> {
+        self.symbols.iter().find(|s| s.name == name)
+    }
+
+    /// Contents of a section, empty for sections without file contents
+    pub fn section_data(&self, section: &ElfSection) -> &[u8] {
+        if !section.has_contents() {
+            return &[];
+        }
+        let start = section.offset as usize;
+        let end = start.saturating_add(section.size as usize);
+        self.data.get(start..end).unwrap_or(&[])
+    }
+
+    /// Contents of a segment as stored in the file
+    pub fn segment_data(&self, segment: &ElfSegment) -> &[u8] {
+        let start = segment.offset as usize;
+        let end = start.saturating_add(segment.filesz as usize);
+        self.data.get(start..end).unwrap_or(&[])
+    }
+
+    /// Load address of an allocatable section
+    ///
+    /// This is the address the section is stored at in the image, which
+    /// differs from its runtime address when the section is copied at
+    /// reset (for example `.data` loaded from flash).
+    pub fn load_address(&self, section: &ElfSection) -> u64 {
+        self.segments
+            .iter()
+            .filter(|seg| seg.kind == PT_LOAD)
+            .find(|seg| {
+                section.has_contents()
+                    && section.offset >= seg.offset
+                    && section.offset + section.size <= seg.offset + seg.filesz
+                    && section.address >= seg.vaddr
+                    && section.address + section.size <= seg.vaddr + seg.memsz
+            })
+            .map(|seg| seg.paddr + (section.address - seg.vaddr))
+            .unwrap_or(section.address)
+    }
+}
+
+/// Builder for small ELF32 files used by the unit tests
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::{PT_LOAD, SHF_ALLOC, SHT_NOBITS, SHT_PROGBITS, SHT_SYMTAB};
+
+    struct TestSection {
+        name: String,
+        kind: u32,
+        flags: u64,
+        address: u64,
+        load: u64,
+        data: Vec<u8>,
+        size: u64,
+    }
+
+    #[derive(Default)]
+    pub(crate) struct ElfBuilder {
+        sections: Vec<TestSection>,
+        symbols: Vec<(String, u64, u64, u8, u16)>,
+    }
+
+    impl ElfBuilder {
+        /// Add a section with contents loaded at `load` and running at `address`,
+        /// returns the section index
+        pub(crate) fn progbits(&mut self, name: &str, address: u64, load: u64, data: &[u8]) -> u16 {
+            self.sections.push(TestSection {
+                name: String::from(name),
+                kind: SHT_PROGBITS,
+                flags: SHF_ALLOC,
+                address,
+                load,
+                data: data.to_vec(),
+                size: data.len() as u64,
+            });
+            self.sections.len() as u16
+        }
+
+        /// Add a zero initialized section, returns the section index
+        pub(crate) fn nobits(&mut self, name: &str, address: u64, size: u64) -> u16 {
+            self.sections.push(TestSection {
+                name: String::from(name),
+                kind: SHT_NOBITS,
+                flags: SHF_ALLOC,
+                address,
+                load: address,
+                data: Vec::new(),
+                size,
+            });
+            self.sections.len() as u16
+        }
+
+        /// Add a global function symbol
+        pub(crate) fn symbol(&mut self, name: &str, value: u64, size: u64, section: u16) {
+            self.symbols
+                .push((String::from(name), value, size, 0x12, section));
+        }
+
+        /// Add a local function symbol
+        pub(crate) fn local(&mut self, name: &str, value: u64, size: u64, section: u16) {
+            self.symbols
+                .push((String::from(name), value, size, 0x02, section));
+        }
+
+        /// Add a source file symbol, which applies to the local symbols after it
+        pub(crate) fn file(&mut self, name: &str) {
+            self.symbols
+                .push((String::from(name), 0, 0, super::STT_FILE, 0xfff1));
+        }
+
+        pub(crate) fn build(&self) -> Vec<u8> {
+            let loads: Vec<&TestSection> = self
+                .sections
+                .iter()
+                .filter(|s| s.kind == SHT_PROGBITS)
+                .collect();
+            let mut out = vec![0u8; 52 + 32 * loads.len()];
+            let mut offsets = Vec::new();
+            for section in self.sections.iter() {
+                offsets.push(out.len() as u64);
+                out.extend_from_slice(&section.data);
+            }
+
+            let mut shstrtab = vec![0u8];
+            let mut names = Vec::new();
+            for name in self
+                .sections
+                .iter()
+                .map(|s| s.name.as_str())
+                .chain([".shstrtab", ".symtab", ".strtab"].iter().cloned())
+            {
+                names.push(shstrtab.len() as u32);
+                shstrtab.extend_from_slice(name.as_bytes());
+                shstrtab.push(0);
+            }
+            let mut strtab = vec![0u8];
+            let mut symtab = vec![0u8; 16];
+            for (name, value, size, info, section) in self.symbols.iter() {
+                symtab.extend_from_slice(&(strtab.len() as u32).to_le_bytes());
+                symtab.extend_from_slice(&(*value as u32).to_le_bytes());
+                symtab.extend_from_slice(&(*size as u32).to_le_bytes());
+                symtab.extend_from_slice(&[*info, 0]);
+                symtab.extend_from_slice(&section.to_le_bytes());
+                strtab.extend_from_slice(name.as_bytes());
+                strtab.push(0);
+            }
+            let shstr_off = out.len() as u32;
+            out.extend_from_slice(&shstrtab);
+            let symtab_off = out.len() as u32;
+            out.extend_from_slice(&symtab);
+            let strtab_off = out.len() as u32;
+            out.extend_from_slice(&strtab);
+            let shoff = out.len() as u32;
+
+            let count = self.sections.len();
+            let shdr = |out: &mut Vec<u8>, fields: [u32; 10]| {
+                for field in fields.iter() {
+                    out.extend_from_slice(&field.to_le_bytes());
+                }
+            };
+            shdr(&mut out, [0; 10]);
+            for (idx, section) in self.sections.iter().enumerate() {
+                shdr(
+                    &mut out,
+                    [
+                        names[idx],
+                        section.kind,
+                        section.flags as u32,
+                        section.address as u32,
+                        offsets[idx] as u32,
+                        section.size as u32,
+                        0,
+                        0,
+                        4,
+                        0,
+                    ],
+                );
+            }
+            shdr(
+                &mut out,
+                [
+                    names[count],
+                    3,
+                    0,
+                    0,
+                    shstr_off,
+                    shstrtab.len() as u32,
+                    0,
+                    0,
+                    1,
+                    0,
+                ],
+            );
+            shdr(
+                &mut out,
+                [
+                    names[count + 1],
+                    SHT_SYMTAB,
+                    0,
+                    0,
+                    symtab_off,
+                    symtab.len() as u32,
+                    count as u32 + 3,
+                    1,
+                    4,
+                    16,
+                ],
+            );
+            shdr(
+                &mut out,
+                [
+                    names[count + 2],
+                    3,
+                    0,
+                    0,
+                    strtab_off,
+                    strtab.len() as u32,
+                    0,
+                    0,
+                    1,
+                    0,
+                ],
+            );
+
+            out[0..4].copy_from_slice(b"\x7fELF");
+            out[4] = 1;
+            out[5] = 1;
+            out[6] = 1;
+            out[16..18].copy_from_slice(&2u16.to_le_bytes());
+            out[18..20].copy_from_slice(&40u16.to_le_bytes());
+            out[20..24].copy_from_slice(&1u32.to_le_bytes());
+            out[28..32].copy_from_slice(&52u32.to_le_bytes());
+            out[32..36].copy_from_slice(&shoff.to_le_bytes());
+            out[40..42].copy_from_slice(&52u16.to_le_bytes());
+            out[42..44].copy_from_slice(&32u16.to_le_bytes());
+            out[44..46].copy_from_slice(&(loads.len() as u16).to_le_bytes());
+            out[46..48].copy_from_slice(&40u16.to_le_bytes());
+            out[48..50].copy_from_slice(&(count as u16 + 4).to_le_bytes());
+            out[50..52].copy_from_slice(&(count as u16 + 1).to_le_bytes());
+
+            let mut phdr = 52;
+            for (idx, section) in self.sections.iter().enumerate() {
+                if section.kind != SHT_PROGBITS {
+                    continue;
+                }
+                let fields = [
+                    PT_LOAD,
+                    offsets[idx] as u32,
+                    section.address as u32,
+                    section.load as u32,
+                    section.size as u32,
+                    section.size as u32,
+                    5,
+                    4,
+                ];
+                for field in fields.iter() {
+                    out[phdr..phdr + 4].copy_from_slice(&field.to_le_bytes());
+                    phdr += 4;
+                }
+            }
+            out
+        }
+    }
+}
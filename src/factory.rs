@@ -0,0 +1,145 @@
+//! Full-flash factory images
+//!
+//! A factory image programs a blank part in one go: the bootloader, the
+//! application and any other prebuilt binaries, such as a filesystem
+//! image, each stored at the origin of the region the layout reserved for
+//! it. Gaps between the parts are filled, and a manifest records where
+//! every part ended up.
+
+use crate::image::Image;
+use crate::json::Value;
+use crate::layout::Layout;
+use crate::{LinkerError, Result};
+
+/// A binary to place in a factory image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part {
+    /// Name of the part in the manifest, for example its file name
+    pub name: String,
+    /// Region the part is stored at the origin of
+    pub region: String,
+    /// Contents of the part
+    pub data: Vec<u8>,
+}
+
+/// Where a part was placed in a factory image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlacedPart {
+    /// Name of the part
+    pub name: String,
+    /// Region holding the part
+    pub region: String,
+    /// Address of the first byte of the part
+    pub address: u64,
+    /// Offset of the part from the start of the image
+    pub offset: u64,
+    /// Length of the part in bytes
+    pub length: u64,
+}
+
+/// Description of an assembled factory image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FactoryManifest {
+    /// Address of the first byte of the image
+    pub base: u64,
+    /// Length of the image in bytes
+    pub length: u64,
+    /// Value used to fill gaps between parts
+    pub fill: u8,
+    /// Parts in address order
+    pub parts: Vec<PlacedPart>,
+}
+
+fn invalid(reason: String) -> LinkerError {
+    LinkerError::InvalidImage(reason)
+}
+
+/// Compose `parts` into one image of the region `base`
+///
+/// Every part's region must be within `base` and the part must fit its
+/// region. Parts may not overlap.
+pub fn assemble(
+    layout: &Layout,
+    base: &str,
+    parts: &[Part],
+    fill: u8,
+) -> Result<(Image, FactoryManifest)> {
+    let base = layout
+        .region(base)
+        .ok_or_else(|| invalid(format!("unknown region {:?}", base)))?;
+    let mut placed: Vec<PlacedPart> = Vec::new();
+    for part in parts.iter() {
+        let region = layout
+            .region(&part.region)
+            .ok_or_else(|| invalid(format!("unknown region {:?}", part.region)))?;
+        if region.origin < base.origin || region.end() > base.end() {
+            return Err(invalid(format!(
+                "region {} is not within region {}",
+                region.name, base.name
+            )));
+        }
+        let length = part.data.len() as u64;
+        if length > region.size {
+            return Err(invalid(format!(
+                "{} is {} bytes, which does not fit in the {} bytes of region {}",
+                part.name, length, region.size, region.name
+            )));
+        }
+        let address = region.origin;
+        if let Some(other) = placed
+            .iter()
+            .find(|p| address < p.address + p.length && p.address < address + length)
+        {
+            return Err(invalid(format!("{} overlaps {}", part.name, other.name)));
+        }
+        placed.push(PlacedPart {
+            name: part.name.clone(),
+            region: region.name.clone(),
+            address,
+            offset: address - base.origin,
+            length,
+        });
+    }
+
+    let mut image = Image {
+        base: base.origin,
+        data: Vec::new(),
+    };
+    for (part, place) in parts.iter().zip(placed.iter()) {
+        image.write(place.address, &part.data, fill);
+    }
+    placed.sort_by_key(|p| p.address);
+    let manifest = FactoryManifest {
+        base: image.base,
+        length: image.data.len() as u64,
+        fill,
+        parts: placed,
+    };
+    Ok((image, manifest))
+}
+
+impl FactoryManifest {
+    /// Render the manifest as JSON
+    pub fn to_json(&self) -> String {
+        let parts = self
+            .parts
+            .iter()
+            .map(|part| {
+                Value::Object(vec![
+                    (String::from("name"), part.name.as_str().into()),
+                    (String::from("region"), part.region.as_str().into()),
+                    (String::from("address"), part.address.into()),
+                    (String::from("offset"), part.offset.into()),
+                    (String::from("length"), part.length.into()),
+                ])
+            })
+            .collect();
+        let doc = Value::Object(vec![
+            (String::from("base"), self.base.into()),
+            (String::from("length"), self.length.into()),
+            (String::from("fill"), u64::from(self.fill).into()),
+            (String::from("parts"), Value::Array(parts)),
+        ]);
+        format!("{}\n", doc)
+    }
+}
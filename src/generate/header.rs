@@ -0,0 +1,39 @@
+use crate::{LinkerScript, Region, Word};
+use std::io::{Error, Write};
+
+/// Region name as used in C macro names
+fn macro_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Generate a C header of the region origins and sizes
+///
+/// Every region gets `<REGION>_ORIGIN` and `<REGION>_SIZE` macros, for C
+/// code and assembly linked into the same image.
+pub fn render<W: Word, Wr: Write>(ls: &LinkerScript<W>, out: &mut Wr) -> Result<(), Error> {
+    writeln!(
+        out,
+        "/* Generated by imxrt-rt-gen from the regions of the linker script */"
+    )?;
+    writeln!(out, "#ifndef IMXRT_RT_GEN_MEMORY_MAP_H")?;
+    writeln!(out, "#define IMXRT_RT_GEN_MEMORY_MAP_H")?;
+    writeln!(out)?;
+    let mut regions: Vec<&Region<W>> = ls.regions.values().collect();
+    regions.sort_by(|a, b| a.name.cmp(&b.name));
+    for region in regions {
+        let name = macro_name(&region.name);
+        writeln!(out, "#define {}_ORIGIN {:#X}u", name, region.origin)?;
+        writeln!(out, "#define {}_SIZE {:#X}u", name, region.size)?;
+    }
+    writeln!(out)?;
+    writeln!(out, "#endif")?;
+    Ok(())
+}
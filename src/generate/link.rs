@@ -1,118 +1,612 @@
-use crate::{LinkerScript, Section, SectionSize, Word};
+use crate::{LinkerScript, Region, Section, SectionSize, Word, FLASH, RAM};
 use std::io::{Error, Write};
 
+/// The program header of a section, `NONE` for sections without contents
+fn segment<W: Word>(section: &Section<W>) -> String {
+    if !section.has_contents() {
+        return String::from("NONE");
+    }
+    match &section.lma {
+        Some(lma) => format!("load_{}_from_{}", section.vma.0, lma.0),
+        None => format!("load_{}", section.vma.0),
+    }
+}
+
+/// render the end of an output section, with its regions, program header
+/// and fill
+fn render_footer<W: Word, Wr: Write>(
+    ls: &LinkerScript<W>,
+    out: &mut Wr,
+    section: &Section<W>,
+    regions: &str,
+) -> Result<(), Error> {
+    write!(out, "\t}} {}", regions)?;
+    if ls.program_headers {
+        write!(out, " :{}", segment(section))?;
+    }
+    if let Some(byte) = section.fill {
+        write!(out, " =0x{:02X}{:02X}{:02X}{:02X}", byte, byte, byte, byte)?;
+    }
+    writeln!(out)
+}
+
+/// render the PHDRS command, a load segment for each pair of regions
+/// holding contents in placement order
+fn render_phdrs<W: Word, Wr: Write>(ls: &LinkerScript<W>, out: &mut Wr) -> Result<(), Error> {
+    let mut sorted_sections: Vec<&Section<W>> = ls.sections.values().collect();
+    sorted_sections.sort_by(|a, b| ls.placement_order(a, b));
+    let mut segments: Vec<String> = Vec::new();
+    for section in sorted_sections {
+        let name = segment(section);
+        if section.has_contents() && !segments.contains(&name) {
+            segments.push(name);
+        }
+    }
+    writeln!(out, "PHDRS {{")?;
+    for name in segments {
+        writeln!(out, "\t{} PT_LOAD;", name)?;
+    }
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// render the first line of an output section, with its type and an
+/// explicit load address
+///
+/// A section in an aliased region is placed at an explicit address, after
+/// the sections already placed in any view of the same memory.
+fn render_header<W: Word, Wr: Write>(
+    ls: &LinkerScript<W>,
+    out: &mut Wr,
+    section: &Section<W>,
+) -> Result<(), Error> {
+    write!(out, "\t.{}", section.output_name())?;
+    if ls.is_aliased(&section.vma.0) {
+        write!(
+            out,
+            " (__{}_origin + __{}_used)",
+            section.vma.0,
+            ls.physical(&section.vma.0)
+        )?;
+    }
+    if section.noload {
+        write!(out, " (NOLOAD)")?;
+    }
+    write!(out, " :")?;
+    match (&section.lma, section.load_at) {
+        (Some(_), Some(address)) => write!(out, " AT({:#X})", address)?,
+        (Some(lma), None) if ls.is_aliased(&lma.0) => write!(
+            out,
+            " AT(__{}_origin + __{}_used)",
+            lma.0,
+            ls.physical(&lma.0)
+        )?,
+        _ => {}
+    }
+    writeln!(out)
+}
+
+/// render the accounting of a section's bytes in a region, aliases of a
+/// region share its count
+fn render_used<W: Word, Wr: Write>(
+    ls: &LinkerScript<W>,
+    out: &mut Wr,
+    region: &str,
+    name: &str,
+) -> Result<(), Error> {
+    let physical = ls.physical(region);
+    writeln!(
+        out,
+        "\t__{}_used = __{}_used + SIZEOF(.{});",
+        physical, physical, name
+    )
+}
+
+/// render the input section patterns of a section, one per file pattern
+/// and wrapped in KEEP when the section is kept
+fn render_inputs<W: Word, Wr: Write>(out: &mut Wr, section: &Section<W>) -> Result<(), Error> {
+    let name = section.output_name();
+    let inputs = if section.inputs.is_empty() {
+        format!(".{} .{}.*", name, name)
+    } else {
+        section
+            .inputs
+            .iter()
+            .map(|input| format!("{} {}.*", input, input))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let all = [String::from("*")];
+    let files = if section.files.is_empty() {
+        &all[..]
+    } else {
+        &section.files[..]
+    };
+    for file in files {
+        if section.keep {
+            writeln!(out, "\t\tKEEP({}({}));", file, inputs)?;
+        } else {
+            writeln!(out, "\t\t{}({});", file, inputs)?;
+        }
+    }
+    Ok(())
+}
+
 /// render a linker sized section
 fn render_linker_section<W: Word, Wr: Write>(
+    ls: &LinkerScript<W>,
     out: &mut Wr,
     section: &Section<W>,
 ) -> Result<(), Error> {
-    writeln!(out, "\t.{} :", section.name)?;
+    let name = section.output_name();
+    let symbol = section.symbol_name();
+    render_header(ls, out, section)?;
     writeln!(out, "\t{{")?;
     writeln!(out, "\t\t. = ALIGN({});", std::mem::align_of::<W>())?;
-    writeln!(out, "\t\t__start_{} = .;", section.name)?;
+    writeln!(out, "\t\t__start_{} = .;", symbol)?;
     if let Some(linker_preamble) = &section.linker_preamble {
         writeln!(out, "\t\t{}", linker_preamble)?;
     }
-    writeln!(out, "\t\t*(.{} .{}.*);", section.name, section.name)?;
+    render_inputs(out, section)?;
     writeln!(out, "\t\t. = ALIGN({});", std::mem::align_of::<W>())?;
-    writeln!(out, "\t\t__end_{} = .;", section.name)?;
+    writeln!(out, "\t\t__end_{} = .;", symbol)?;
+    if section.keep && !section.inputs.is_empty() {
+        writeln!(out, "\t\t__stop_{} = .;", symbol)?;
+    }
     if let Some(lma) = &section.lma {
-        writeln!(out, "\t}} > {} AT> {}", section.vma.0, lma.0)?;
-        writeln!(
-            out,
-            "\t__load_{} = LOADADDR(.{});",
-            section.name, section.name
-        )?;
-        writeln!(
-            out,
-            "\t__{}_used = __{}_used + SIZEOF(.{});",
-            section.vma.0, section.vma.0, section.name
-        )?;
-        writeln!(
-            out,
-            "\t__{}_used = __{}_used + SIZEOF(.{});",
-            lma.0, lma.0, section.name
-        )?;
+        if section.load_at.is_some() || ls.is_aliased(&lma.0) {
+            render_footer(ls, out, section, &format!("> {}", section.vma.0))?;
+        } else {
+            render_footer(
+                ls,
+                out,
+                section,
+                &format!("> {} AT> {}", section.vma.0, lma.0),
+            )?;
+        }
+        writeln!(out, "\t__load_{} = LOADADDR(.{});", symbol, name)?;
+        if ls.position_independent {
+            writeln!(
+                out,
+                "\t__load_offset_{} = LOADADDR(.{}) - ORIGIN({});",
+                symbol, name, lma.0
+            )?;
+        }
+        render_used(ls, out, &section.vma.0, &name)?;
+        if section.load_at.is_none() {
+            render_used(ls, out, &lma.0, &name)?;
+        }
     } else {
-        writeln!(out, "\t}} > {}", section.vma.0)?;
-        writeln!(
-            out,
-            "\t__{}_used = __{}_used + SIZEOF(.{});",
-            section.vma.0, section.vma.0, section.name
-        )?;
+        render_footer(ls, out, section, &format!("> {}", section.vma.0))?;
+        render_used(ls, out, &section.vma.0, &name)?;
     }
-    writeln!(out, "")?;
+    writeln!(out)?;
     Ok(())
 }
 
 /// render a heap section
 fn render_heap_section<W: Word, Wr: Write>(
+    ls: &LinkerScript<W>,
     out: &mut Wr,
     section: &Section<W>,
 ) -> Result<(), Error> {
-    writeln!(out, "\t.{} :", section.name)?;
+    let symbol = section.symbol_name();
+    render_header(ls, out, section)?;
     writeln!(out, "\t{{")?;
     writeln!(
         out,
         "\t\t. = __{}_origin + __{}_used;",
-        section.vma.0, section.vma.0
+        section.vma.0,
+        ls.physical(&section.vma.0)
     )?;
     writeln!(out, "\t\t. = ALIGN({});", std::mem::align_of::<W>())?;
-    writeln!(out, "\t\t__start_{} = .;", section.name)?;
-    writeln!(
-        out,
-        "\t\t. = __{}_origin + __{}_size;",
-        section.vma.0, section.vma.0
-    )?;
-    writeln!(out, "\t\t__end_{} = .;", section.name)?;
-    writeln!(out, "\t}} > {}", section.vma.0)?;
-    writeln!(out, "")?;
+    writeln!(out, "\t\t__start_{} = .;", symbol)?;
+    render_fill_end(ls, out, section)?;
+    writeln!(out, "\t\t__end_{} = .;", symbol)?;
+    render_footer(ls, out, section, &format!("> {}", section.vma.0))?;
+    writeln!(out)?;
     Ok(())
 }
 
-/// render a heap section
+/// render a stack section, above the guard gap when there is one
 fn render_stack_section<W: Word, Wr: Write>(
+    ls: &LinkerScript<W>,
     out: &mut Wr,
     section: &Section<W>,
 ) -> Result<(), Error> {
-    writeln!(out, "\t.{} :", section.name)?;
+    let symbol = section.symbol_name();
+    render_header(ls, out, section)?;
     writeln!(out, "\t{{")?;
     writeln!(
         out,
         "\t\t. = __{}_origin + __{}_used;",
-        section.vma.0, section.vma.0
-    )?;
-    writeln!(out, "\t\t. = ALIGN({});", std::mem::align_of::<W>())?;
-    writeln!(out, "\t\t__end_{} = .;", section.name)?;
-    writeln!(
-        out,
-        "\t\t. = __{}_origin + __{}_size;",
-        section.vma.0, section.vma.0
+        section.vma.0,
+        ls.physical(&section.vma.0)
     )?;
-    writeln!(out, "\t\t__start_{} = .;", section.name)?;
-    writeln!(out, "\t}} > {}", section.vma.0)?;
-    writeln!(out, "")?;
+    if let Some(gap) = ls.guard_gap {
+        writeln!(out, "\t\t. = ALIGN({});", gap)?;
+        writeln!(out, "\t\t__start_{}_guard = .;", symbol)?;
+        writeln!(out, "\t\t. = . + {};", gap)?;
+        writeln!(out, "\t\t__end_{}_guard = .;", symbol)?;
+    }
+    writeln!(out, "\t\t. = ALIGN({});", ls.stack_align)?;
+    writeln!(out, "\t\t__end_{} = .;", symbol)?;
+    let top = render_fill_end(ls, out, section)?;
+    render_stack_top(ls, out, section, top)?;
+    render_footer(ls, out, section, &format!("> {}", section.vma.0))?;
+    writeln!(out)?;
     Ok(())
 }
 
-/// render a heap section
+/// render the end of the space a heap or stack fills, its region's end
+/// or its lowest exclusion, returning that address
+fn render_fill_end<W: Word, Wr: Write>(
+    ls: &LinkerScript<W>,
+    out: &mut Wr,
+    section: &Section<W>,
+) -> Result<u64, Error> {
+    match ls.excluded(&section.vma.0).first() {
+        Some(excluded) => {
+            writeln!(out, "\t\t. = {:#X};", excluded.origin)?;
+            Ok(excluded.origin)
+        }
+        None => {
+            writeln!(
+                out,
+                "\t\t. = __{}_origin + __{}_size;",
+                section.vma.0, section.vma.0
+            )?;
+            let region = &ls.regions[&section.vma.0];
+            Ok(region.origin.into() + region.size.into())
+        }
+    }
+}
+
+/// render the top of a stack, rounded down to the stack alignment when
+/// `top`, an address or a size from an aligned address, isn't aligned
+fn render_stack_top<W: Word, Wr: Write>(
+    ls: &LinkerScript<W>,
+    out: &mut Wr,
+    section: &Section<W>,
+    top: u64,
+) -> Result<(), Error> {
+    let symbol = section.symbol_name();
+    if top.is_multiple_of(ls.stack_align) {
+        writeln!(out, "\t\t__start_{} = .;", symbol)?;
+    } else {
+        writeln!(
+            out,
+            "\t\t__start_{} = ALIGN(. - {}, {});",
+            symbol,
+            ls.stack_align - 1,
+            ls.stack_align
+        )?;
+    }
+    Ok(())
+}
+
+/// render a fixed size stack section, placed by priority
+fn render_fixed_stack_section<W: Word, Wr: Write>(
+    ls: &LinkerScript<W>,
+    out: &mut Wr,
+    section: &Section<W>,
+    size: W,
+) -> Result<(), Error> {
+    let name = section.output_name();
+    let symbol = section.symbol_name();
+    render_header(ls, out, section)?;
+    writeln!(out, "\t{{")?;
+    writeln!(out, "\t\t. = ALIGN({});", ls.stack_align)?;
+    writeln!(out, "\t\t__end_{} = .;", symbol)?;
+    writeln!(out, "\t\t. += {};", size)?;
+    render_stack_top(ls, out, section, size.into())?;
+    render_footer(ls, out, section, &format!("> {}", section.vma.0))?;
+    render_used(ls, out, &section.vma.0, &name)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// render the minimum size assertion of a region filling section, a heap
+/// spans from its start symbol to its end symbol and a stack the other way
+fn render_min_size<W: Word, Wr: Write>(out: &mut Wr, section: &Section<W>) -> Result<(), Error> {
+    let (low, high) = match section.size {
+        SectionSize::Heap => ("start", "end"),
+        SectionSize::Stack | SectionSize::FixedStack(_) => ("end", "start"),
+        SectionSize::Linker | SectionSize::Fixed(_) => return Ok(()),
+    };
+    if let Some(min_size) = section.min_size {
+        let symbol = section.symbol_name();
+        writeln!(
+            out,
+            "ASSERT(__{}_{} - __{}_{} >= {}, \"Section .{} is smaller than {} bytes\");",
+            high,
+            symbol,
+            low,
+            symbol,
+            min_size,
+            section.output_name(),
+            min_size
+        )?;
+    }
+    Ok(())
+}
+
+/// render the section's symbols as seen through each of its views
+fn render_views<W: Word, Wr: Write>(out: &mut Wr, section: &Section<W>) -> Result<(), Error> {
+    let symbol = section.symbol_name();
+    for view in section.views.iter() {
+        for edge in ["start", "end"].iter() {
+            writeln!(
+                out,
+                "\t__{}_{}_{} = __{}_{} - __{}_origin + __{}_origin;",
+                edge, symbol, view.0, edge, symbol, section.vma.0, view.0
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// render the size of a section and the section taking its spill, after
+/// the latter
+fn render_spill_size<W: Word, Wr: Write>(out: &mut Wr, section: &Section<W>) -> Result<(), Error> {
+    if let Some(first) = &section.spill_of {
+        let first = first.replace('.', "_");
+        let spill = section.symbol_name();
+        writeln!(
+            out,
+            "\t__size_{} = __end_{} - __start_{} + __end_{} - __start_{};",
+            first, first, first, spill, spill
+        )?;
+    }
+    Ok(())
+}
+
+/// render a fixed size section, its preamble padded to the size
 fn render_fixed_section<W: Word, Wr: Write>(
+    ls: &LinkerScript<W>,
     out: &mut Wr,
     section: &Section<W>,
     size: W,
 ) -> Result<(), Error> {
-    writeln!(out, "\t.{} :", section.name)?;
+    let name = section.output_name();
+    let symbol = section.symbol_name();
+    render_header(ls, out, section)?;
     writeln!(out, "\t{{")?;
-    writeln!(out, "\t\t__start_{} = .;", section.name)?;
-    writeln!(out, "\t\t. += {}", size)?;
-    writeln!(out, "\t\t__end_{} = .;", section.name)?;
-    writeln!(out, "\t}} > {}", section.vma.0)?;
+    writeln!(out, "\t\t__start_{} = .;", symbol)?;
+    match &section.linker_preamble {
+        Some(linker_preamble) => writeln!(out, "\t\t{}", linker_preamble)?,
+        None => render_inputs(out, section)?,
+    }
+    writeln!(out, "\t\t. = __start_{} + {};", symbol, size)?;
+    writeln!(out, "\t\t__end_{} = .;", symbol)?;
+    render_footer(ls, out, section, &format!("> {}", section.vma.0))?;
+    render_used(ls, out, &section.vma.0, &name)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// render the MEMORY command with the regions matching `filter`
+fn render_memory<W: Word, Wr: Write, F: Fn(&Region<W>) -> bool>(
+    ls: &LinkerScript<W>,
+    out: &mut Wr,
+    filter: F,
+) -> Result<(), Error> {
+    writeln!(out, "MEMORY {{")?;
+    for region in ls.regions.values().filter(|region| filter(region)) {
+        let attributes = match region.kind {
+            Some(kind) => format!(" ({})", kind.attributes()),
+            None => String::new(),
+        };
+        let (origin, size): (u64, u64) = (region.origin.into(), region.size.into());
+        writeln!(
+            out,
+            "\t{}{} : ORIGIN = {:#X}, LENGTH = {:#X}",
+            region.name,
+            attributes,
+            origin + region.skip,
+            size - region.skip
+        )?;
+    }
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// render the region symbols and the sections matching `filter` in
+/// priority order
+fn render_sections<W: Word, Wr: Write, F: Fn(&Section<W>) -> bool>(
+    ls: &LinkerScript<W>,
+    out: &mut Wr,
+    filter: F,
+) -> Result<(), Error> {
+    for region in ls.regions.values() {
+        writeln!(out, "\t__{}_origin = {};", region.name, region.origin)?;
+        writeln!(out, "\t__{}_size = {};", region.name, region.size)?;
+        if region.alias_of.is_none() {
+            writeln!(out, "\t__{}_used = {};", region.name, region.skip)?;
+        }
+    }
+    let mut sorted_sections: Vec<Section<W>> = ls
+        .sections
+        .values()
+        .filter(|section| filter(section))
+        .cloned()
+        .collect();
+    sorted_sections.sort_by(|a, b| ls.placement_order(a, b));
+    for section in sorted_sections.iter() {
+        match section.size {
+            SectionSize::Linker => render_linker_section(ls, out, section)?,
+            SectionSize::Heap => render_heap_section(ls, out, section)?,
+            SectionSize::Stack => render_stack_section(ls, out, section)?,
+            SectionSize::FixedStack(size) => render_fixed_stack_section(ls, out, section, size)?,
+            SectionSize::Fixed(size) => render_fixed_section(ls, out, section, size)?,
+        }
+        render_views(out, section)?;
+        render_spill_size(out, section)?;
+    }
+    for (_, excluded) in ls.exclusions.iter() {
+        writeln!(out, "\t__start_{} = {:#X};", excluded.name, excluded.origin)?;
+        writeln!(out, "\t__end_{} = {:#X};", excluded.name, excluded.end())?;
+    }
+    Ok(())
+}
+
+/// render the checks of the placement of the sections matching `filter`
+///
+/// These follow SECTIONS, as lld only parses an ASSERT in SECTIONS when an
+/// output section comes after it.
+fn render_asserts<W: Word, Wr: Write, F: Fn(&Section<W>) -> bool>(
+    ls: &LinkerScript<W>,
+    out: &mut Wr,
+    filter: F,
+) -> Result<(), Error> {
+    let mut sorted_sections: Vec<&Section<W>> = ls
+        .sections
+        .values()
+        .filter(|section| filter(section))
+        .collect();
+    sorted_sections.sort_by(|a, b| ls.placement_order(a, b));
+    for section in sorted_sections.iter() {
+        let name = section.output_name();
+        if let (Some(lma), Some(_)) = (&section.lma, section.load_at) {
+            writeln!(
+                out,
+                "ASSERT(LOADADDR(.{}) + SIZEOF(.{}) <= __{}_origin + __{}_size, \"Section .{} is not loaded within {}\");",
+                name, name, lma.0, lma.0, name, lma.0
+            )?;
+        }
+        if let Some(max_size) = section.max_size {
+            writeln!(
+                out,
+                "ASSERT(SIZEOF(.{}) <= {}, \"Section .{} is larger than {} bytes\");",
+                name, max_size, name, max_size
+            )?;
+        }
+        render_min_size(out, section)?;
+    }
+    if ls.pack_loads {
+        let loaded: Vec<&Section<W>> = sorted_sections
+            .iter()
+            .copied()
+            .filter(|section| section.lma.is_some())
+            .collect();
+        for pair in loaded.windows(2) {
+            let (previous, next) = (pair[0], pair[1]);
+            if previous.lma != next.lma {
+                continue;
+            }
+            writeln!(
+                out,
+                "ASSERT(__load_{} == __load_{} + SIZEOF(.{}), \"Packed section .{} does not follow .{}\");",
+                next.symbol_name(),
+                previous.symbol_name(),
+                previous.output_name(),
+                next.output_name(),
+                previous.output_name()
+            )?;
+        }
+    }
+    let mut aliased: Vec<&Region<W>> = ls
+        .regions
+        .values()
+        .filter(|region| region.alias_of.is_none() && ls.is_aliased(&region.name))
+        .collect();
+    aliased.sort_by(|a, b| a.name.cmp(&b.name));
+    for region in aliased {
+        writeln!(
+            out,
+            "ASSERT(__{}_used <= __{}_size, \"Sections in {} and its aliases do not fit\");",
+            region.name, region.name, region.name
+        )?;
+    }
+    for (region, excluded) in ls.exclusions.iter() {
+        writeln!(
+            out,
+            "ASSERT(__{}_origin + __{}_used <= {:#X}, \"Sections in {} overlap {}\");",
+            region.0,
+            ls.physical(&region.0),
+            excluded.origin,
+            region.0,
+            excluded.name
+        )?;
+    }
+    for (region, reservation) in ls.reserved() {
+        let physical = ls.physical(&region.name);
+        writeln!(
+            out,
+            "ASSERT(__{}_origin + __{}_used <= {:#X}, \"The image in {} overlaps the boot ROM's {}\");",
+            region.name, physical, reservation.origin, region.name, reservation.name
+        )?;
+    }
+    Ok(())
+}
+
+/// render the symbols unwinders use to find the ARM exception index, or
+/// discard the unwind tables when they are not placed
+fn render_unwind<W: Word, Wr: Write>(ls: &LinkerScript<W>, out: &mut Wr) -> Result<(), Error> {
+    if ls.sections.contains_key("ARM.exidx") {
+        writeln!(out, "\tPROVIDE(__exidx_start = __start_ARM_exidx);")?;
+        writeln!(out, "\tPROVIDE(__exidx_end = __end_ARM_exidx);")?;
+    } else {
+        writeln!(out, "\t/DISCARD/ :")?;
+        writeln!(out, "\t{{")?;
+        writeln!(
+            out,
+            "\t\t*(.ARM.extab .ARM.extab.* .ARM.exidx .ARM.exidx.* .eh_frame_hdr .eh_frame);"
+        )?;
+        writeln!(out, "\t}}")?;
+    }
+    Ok(())
+}
+
+/// render the symbols C runtimes use to find the TLS image, when thread
+/// local storage is placed
+fn render_tls<W: Word, Wr: Write>(ls: &LinkerScript<W>, out: &mut Wr) -> Result<(), Error> {
+    if !ls.sections.contains_key("tls") {
+        return Ok(());
+    }
+    for section in ["tdata", "tbss"].iter() {
+        writeln!(out, "\tPROVIDE(__{}_start = __start_{});", section, section)?;
+        writeln!(out, "\tPROVIDE(__{}_end = __end_{});", section, section)?;
+    }
+    writeln!(out, "\t__tls_size = __end_tls - __start_tls;")?;
+    writeln!(out, "\t__tls_block = __start_tls;")?;
+    Ok(())
+}
+
+/// render a NOCROSSREFS_TO command for each section the others listed with
+/// it must not reference
+fn render_no_cross_refs<W: Word, Wr: Write>(
+    ls: &LinkerScript<W>,
+    out: &mut Wr,
+) -> Result<(), Error> {
+    for (to, from) in ls.no_cross_refs.iter() {
+        let from: Vec<String> = from.iter().map(|id| format!(".{}", id.0)).collect();
+        writeln!(out, "NOCROSSREFS_TO(.{} {});", to.0, from.join(" "))?;
+    }
+    Ok(())
+}
+
+/// Regions defined by the memory.x included from cortex-m-rt's link.x
+const STOCK_REGIONS: [&str; 2] = [FLASH, RAM];
+
+/// Generate a supplement to cortex-m-rt's link.x from a LinkerScript
+///
+/// Only the regions cortex-m-rt doesn't know about, and the sections which
+/// are prefixed or run from those regions, are rendered. The sections are
+/// inserted after cortex-m-rt's `.bss` section.
+pub fn render_insert<W: Word, Wr: Write>(ls: &LinkerScript<W>, out: &mut Wr) -> Result<(), Error> {
     writeln!(
         out,
-        "\t__{}_used = __{}_used + SIZEOF(.{});",
-        section.vma.0, section.vma.0, section.name
+        "/* Supplement to cortex-m-rt's link.x, use with -Tlink.x -Tinsert.x */"
     )?;
-    writeln!(out, "")?;
+    let extra = |region: &str| !STOCK_REGIONS.contains(&region);
+    render_memory(ls, out, |region| extra(&region.name))?;
+
+    writeln!(out, "SECTIONS {{")?;
+    render_sections(ls, out, |section| section.prefix || extra(&section.vma.0))?;
+    writeln!(out, "}} INSERT AFTER .bss;")?;
+    render_asserts(ls, out, |section| section.prefix || extra(&section.vma.0))?;
+    render_no_cross_refs(ls, out)?;
     Ok(())
 }
 
@@ -152,34 +646,22 @@ EXTERN(__INTERRUPTS); /* `static` variable similar to `__EXCEPTIONS` */
 "
     )?;
 
-    writeln!(out, "MEMORY {{")?;
-    for region in ls.regions.values() {
-        writeln!(
-            out,
-            "\t{} : ORIGIN = {:#X}, LENGTH = {:#X}",
-            region.name, region.origin, region.size
-        )?;
+    render_memory(ls, out, |_| true)?;
+    for (name, address) in ls.symbols.iter().chain(ls.constants.iter()) {
+        writeln!(out, "{} = {:#X};", name, address)?;
     }
-    writeln!(out, "}}")?;
 
-    writeln!(out, "SECTIONS {{")?;
-    for region in ls.regions.values() {
-        writeln!(out, "\t__{}_origin = {};", region.name, region.origin)?;
-        writeln!(out, "\t__{}_size = {};", region.name, region.size)?;
-        writeln!(out, "\t__{}_used = 0;", region.name)?;
-    }
-    let mut sorted_sections: Vec<Section<W>> = ls.sections.values().cloned().collect();
-    sorted_sections.sort_by(|a, b| a.priority.partial_cmp(&b.priority).unwrap());
-    for section in sorted_sections.iter() {
-        match section.size {
-            SectionSize::Linker => render_linker_section(out, section)?,
-            SectionSize::Heap => render_heap_section(out, section)?,
-            SectionSize::Stack => render_stack_section(out, section)?,
-            SectionSize::Fixed(size) => render_fixed_section(out, section, size)?,
-        }
+    if ls.program_headers {
+        render_phdrs(ls, out)?;
     }
 
+    writeln!(out, "SECTIONS {{")?;
+    render_sections(ls, out, |_| true)?;
+    render_unwind(ls, out)?;
+    render_tls(ls, out)?;
     writeln!(out, "}}")?;
+    render_asserts(ls, out, |_| true)?;
+    render_no_cross_refs(ls, out)?;
 
     //TODO assign a symbol describing the size of each region
     //and section. The section sizes are needed for double linking
@@ -1,2 +1,6 @@
+pub(crate) mod header;
 pub(crate) mod link;
+pub(crate) mod placement;
+pub(crate) mod predicates;
 pub(crate) mod reset;
+pub(crate) mod version;
@@ -0,0 +1,115 @@
+use super::predicates::ident;
+use crate::{LinkerScript, Section, Word};
+use std::io::{Error, Write};
+
+/// Data cache line of the Cortex-M7, which DMA buffers must not share
+const CACHE_LINE: usize = 32;
+
+/// render the section name constant and placement macro of one prefixed
+/// section
+fn render_section<W: Word, Wr: Write>(
+    ls: &LinkerScript<W>,
+    out: &mut Wr,
+    section: &Section<W>,
+) -> Result<(), Error> {
+    let output = section.output_name();
+    let name = ident(&output);
+    let upper = name.to_uppercase();
+    let lower = name.to_lowercase();
+    let regions = match &section.lma {
+        Some(lma) => format!("{} loaded from {}", section.vma.0, lma.0),
+        None => section.vma.0.clone(),
+    };
+    writeln!(out, "/// Input section of .{}, {}", output, regions)?;
+    writeln!(out, "pub const {}_SECTION: &str = \".{}\";", upper, output)?;
+    writeln!(out)?;
+    writeln!(out, "/// Place items in .{}, {}", output, regions)?;
+    if section.name == "bss" {
+        writeln!(out, "///")?;
+        writeln!(
+            out,
+            "/// The reset zeroes the section, so statics must be zero initialized."
+        )?;
+    }
+    let dma = ls.regions[&section.vma.0].dma && section.is_writable();
+    if dma {
+        writeln!(out, "///")?;
+        writeln!(
+            out,
+            "/// {} is DMA capable, so statics must be aligned to the {} byte data",
+            section.vma.0, CACHE_LINE
+        )?;
+        writeln!(
+            out,
+            "/// cache line, and share no line with other statics, which is checked."
+        )?;
+    }
+    writeln!(out, "#[macro_export]")?;
+    writeln!(out, "macro_rules! {} {{", lower)?;
+    if dma {
+        for mutability in ["", "mut "].iter() {
+            writeln!(
+                out,
+                "    ($(#[$attr:meta])* $vis:vis static {}$name:ident: $ty:ty = $value:expr;) => {{",
+                mutability
+            )?;
+            writeln!(
+                out,
+                "        const _: () = assert!(core::mem::align_of::<$ty>() >= {}, \"DMA buffers must be cache line aligned\");",
+                CACHE_LINE
+            )?;
+            writeln!(out, "        #[link_section = \".{}\"]", output)?;
+            writeln!(
+                out,
+                "        $(#[$attr])* $vis static {}$name: $ty = $value;",
+                mutability
+            )?;
+            writeln!(out, "    }};")?;
+        }
+    }
+    writeln!(out, "    ($($item:item)*) => {{")?;
+    writeln!(out, "        $(#[link_section = \".{}\"] $item)*", output)?;
+    writeln!(out, "    }};")?;
+    writeln!(out, "}}")?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Generate a module naming the input sections of the prefixed sections
+///
+/// Every prefixed section gets a `<SECTION>_SECTION` constant of the name
+/// `#[link_section]` takes and a `<section>!` macro placing the items
+/// given to it there, so the application can't name a section the linker
+/// script doesn't place. Statics placed in writable sections of DMA
+/// capable regions must be aligned to the data cache line, which the
+/// macros assert at compile time.
+pub fn render<W: Word, Wr: Write>(ls: &LinkerScript<W>, out: &mut Wr) -> Result<(), Error> {
+    writeln!(
+        out,
+        "// Generated by imxrt-rt-gen from the prefixed sections of the linker script"
+    )?;
+    writeln!(out)?;
+    let mut sections: Vec<&Section<W>> = ls
+        .sections
+        .values()
+        .filter(|section| section.prefix)
+        .collect();
+    sections.sort_by(|a, b| ls.placement_order(a, b));
+    if sections
+        .iter()
+        .any(|section| ls.regions[&section.vma.0].dma && section.is_writable())
+    {
+        writeln!(
+            out,
+            "/// A value aligned to the {} byte data cache line, for DMA buffers",
+            CACHE_LINE
+        )?;
+        writeln!(out, "#[repr(C, align({}))]", CACHE_LINE)?;
+        writeln!(out, "pub struct CacheAligned<T>(pub T);")?;
+        writeln!(out)?;
+    }
+    for section in sections {
+        render_section(ls, out, section)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,304 @@
+use crate::{LinkerScript, Region, Section, Word};
+use std::io::{Error, Write};
+
+/// Region name as used in Rust identifiers
+pub(super) fn ident(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Name of the constant for the linker symbol `symbol`, without the
+/// leading underscores in upper case
+pub(crate) fn constant(symbol: &str) -> String {
+    symbol.trim_start_matches('_').to_uppercase()
+}
+
+/// Names of the constants defined for `region`
+pub(crate) fn region_constants<W: Word>(region: &Region<W>) -> [String; 2] {
+    let upper = ident(&region.name).to_uppercase();
+    [format!("{}_ORIGIN", upper), format!("{}_SIZE", upper)]
+}
+
+/// render the constants and predicates of one region
+fn render_region<W: Word, Wr: Write>(out: &mut Wr, region: &Region<W>) -> Result<(), Error> {
+    let upper = ident(&region.name).to_uppercase();
+    let lower = ident(&region.name).to_lowercase();
+    let [origin, size] = region_constants(region);
+    writeln!(out, "/// First address of {}", region.name)?;
+    writeln!(out, "pub const {}: usize = {:#X};", origin, region.origin)?;
+    writeln!(out, "/// Size of {} in bytes", region.name)?;
+    writeln!(out, "pub const {}: usize = {:#X};", size, region.size)?;
+    writeln!(out)?;
+    writeln!(out, "/// True if `addr` is in {}", region.name)?;
+    writeln!(out, "#[inline]")?;
+    writeln!(out, "pub fn in_{}(addr: usize) -> bool {{", lower)?;
+    writeln!(
+        out,
+        "    addr.wrapping_sub({}_ORIGIN) < {}_SIZE",
+        upper, upper
+    )?;
+    writeln!(out, "}}")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "/// True if all `len` bytes from `addr` are in {}",
+        region.name
+    )?;
+    writeln!(out, "#[inline]")?;
+    writeln!(
+        out,
+        "pub fn within_{}(addr: usize, len: usize) -> bool {{",
+        lower
+    )?;
+    writeln!(
+        out,
+        "    in_{}(addr) && len <= {}_SIZE - (addr - {}_ORIGIN)",
+        lower, upper, upper
+    )?;
+    writeln!(out, "}}")?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Generate a module of address predicates from a LinkerScript
+///
+/// Every region gets `<REGION>_ORIGIN` and `<REGION>_SIZE` constants and
+/// `in_<region>` and `within_<region>` functions, and `is_dma_capable`
+/// checks a buffer against the regions marked as DMA capable. Symbols
+/// defined with build time values are constants. Sections with
+/// alias views get `<section>_in_<view>`, every view of aliased memory
+/// `to_<view>` translating addresses in the others, and a placed build
+/// ID note `build_id`. With encrypted regions,
+/// `ENCRYPTED_RANGES` lists what the engine decrypts.
+pub fn render<W: Word, Wr: Write>(ls: &LinkerScript<W>, out: &mut Wr) -> Result<(), Error> {
+    writeln!(
+        out,
+        "// Generated by imxrt-rt-gen from the regions of the linker script"
+    )?;
+    writeln!(out)?;
+    let mut regions: Vec<&Region<W>> = ls.regions.values().collect();
+    regions.sort_by(|a, b| a.name.cmp(&b.name));
+    for region in regions.iter() {
+        render_region(out, region)?;
+    }
+
+    let dma: Vec<&&Region<W>> = regions.iter().filter(|region| region.dma).collect();
+    let names: Vec<&str> = dma.iter().map(|region| region.name.as_str()).collect();
+    writeln!(
+        out,
+        "/// True if all `len` elements at `ptr` are in a region DMA may access: {}",
+        if names.is_empty() {
+            String::from("none")
+        } else {
+            names.join(", ")
+        }
+    )?;
+    writeln!(out, "#[inline]")?;
+    writeln!(
+        out,
+        "pub fn is_dma_capable<T>(ptr: *const T, len: usize) -> bool {{"
+    )?;
+    if dma.is_empty() {
+        writeln!(out, "    let _ = (ptr, len);")?;
+        writeln!(out, "    false")?;
+    } else {
+        writeln!(out, "    let addr = ptr as usize;")?;
+        writeln!(
+            out,
+            "    let bytes = len.saturating_mul(core::mem::size_of::<T>());"
+        )?;
+        let checks: Vec<String> = dma
+            .iter()
+            .map(|region| format!("within_{}(addr, bytes)", ident(&region.name).to_lowercase()))
+            .collect();
+        writeln!(out, "    {}", checks.join(" || "))?;
+    }
+    writeln!(out, "}}")?;
+    render_constants(ls, out)?;
+    render_views(ls, out)?;
+    render_translations(ls, out)?;
+    render_build_id(ls, out)?;
+    render_encrypted(ls, out)
+}
+
+/// render the translation into each view of aliased memory of the
+/// addresses in its other views
+fn render_translations<W: Word, Wr: Write>(
+    ls: &LinkerScript<W>,
+    out: &mut Wr,
+) -> Result<(), Error> {
+    let mut regions: Vec<&Region<W>> = ls
+        .regions
+        .values()
+        .filter(|region| ls.is_aliased(&region.name))
+        .collect();
+    regions.sort_by(|a, b| a.name.cmp(&b.name));
+    for region in regions.iter() {
+        let physical = ls.physical(&region.name);
+        let to = ident(&region.name).to_uppercase();
+        writeln!(out)?;
+        writeln!(
+            out,
+            "/// `addr` in any view of the memory of {}, seen through {}, or `None`",
+            physical, region.name
+        )?;
+        writeln!(out, "/// outside them")?;
+        writeln!(out, "#[inline]")?;
+        writeln!(
+            out,
+            "pub fn to_{}(addr: usize) -> Option<usize> {{",
+            to.to_lowercase()
+        )?;
+        for view in regions
+            .iter()
+            .filter(|view| ls.physical(&view.name) == physical)
+        {
+            let from = ident(&view.name).to_uppercase();
+            writeln!(out, "    if in_{}(addr) {{", from.to_lowercase())?;
+            if from == to {
+                writeln!(out, "        return Some(addr);")?;
+            } else {
+                writeln!(
+                    out,
+                    "        return Some(addr - {}_ORIGIN + {}_ORIGIN);",
+                    from, to
+                )?;
+            }
+            writeln!(out, "    }}")?;
+        }
+        writeln!(out, "    None")?;
+        writeln!(out, "}}")?;
+    }
+    Ok(())
+}
+
+/// render the accessor of the build ID note, only when it is placed
+fn render_build_id<W: Word, Wr: Write>(ls: &LinkerScript<W>, out: &mut Wr) -> Result<(), Error> {
+    if !ls.sections.contains_key("build_id") {
+        return Ok(());
+    }
+    writeln!(out)?;
+    writeln!(
+        out,
+        "/// The build ID of the image, empty when linked without `--build-id`"
+    )?;
+    writeln!(out, "pub fn build_id() -> &'static [u8] {{")?;
+    writeln!(out, "    extern \"C\" {{")?;
+    writeln!(out, "        static __start_build_id: [u32; 3];")?;
+    writeln!(out, "    }}")?;
+    writeln!(out, "    unsafe {{")?;
+    writeln!(
+        out,
+        "        let note = &*core::ptr::addr_of!(__start_build_id);"
+    )?;
+    writeln!(out, "        let [name_size, id_size, _] = *note;")?;
+    writeln!(out, "        let name = (name_size as usize + 3) & !3;")?;
+    writeln!(
+        out,
+        "        let id = (note.as_ptr() as *const u8).add(12 + name);"
+    )?;
+    writeln!(
+        out,
+        "        core::slice::from_raw_parts(id, id_size as usize)"
+    )?;
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// render the build time values defined as symbols
+fn render_constants<W: Word, Wr: Write>(ls: &LinkerScript<W>, out: &mut Wr) -> Result<(), Error> {
+    for (name, value) in ls.constants.iter() {
+        writeln!(out)?;
+        writeln!(out, "/// Value of the linker symbol `{}`", name)?;
+        writeln!(out, "pub const {}: usize = {:#X};", constant(name), value)?;
+    }
+    Ok(())
+}
+
+/// render the translation of pointers in a section to each of its views
+fn render_views<W: Word, Wr: Write>(ls: &LinkerScript<W>, out: &mut Wr) -> Result<(), Error> {
+    let mut sections: Vec<&Section<W>> = ls
+        .sections
+        .values()
+        .filter(|section| !section.views.is_empty())
+        .collect();
+    sections.sort_by_key(|section| section.output_name());
+    for section in sections {
+        let vma = ident(&section.vma.0);
+        let symbol = section.symbol_name();
+        for view in section.views.iter() {
+            let view = ident(&view.0);
+            writeln!(out)?;
+            writeln!(
+                out,
+                "/// `ptr` in .{}, seen through {}, or `None` outside .{}",
+                section.output_name(),
+                view,
+                section.output_name()
+            )?;
+            writeln!(out, "#[inline]")?;
+            writeln!(
+                out,
+                "pub fn {}_in_{}<T>(ptr: *const T) -> Option<*mut T> {{",
+                ident(&section.output_name()).to_lowercase(),
+                view.to_lowercase()
+            )?;
+            writeln!(out, "    extern \"C\" {{")?;
+            writeln!(out, "        static __start_{}: u8;", symbol)?;
+            writeln!(out, "        static __end_{}: u8;", symbol)?;
+            writeln!(out, "    }}")?;
+            writeln!(out, "    let addr = ptr as usize;")?;
+            writeln!(out, "    let (start, end) = unsafe {{")?;
+            writeln!(out, "        (")?;
+            writeln!(
+                out,
+                "            core::ptr::addr_of!(__start_{}) as usize,",
+                symbol
+            )?;
+            writeln!(
+                out,
+                "            core::ptr::addr_of!(__end_{}) as usize,",
+                symbol
+            )?;
+            writeln!(out, "        )")?;
+            writeln!(out, "    }};")?;
+            writeln!(out, "    if addr < start || addr >= end {{")?;
+            writeln!(out, "        return None;")?;
+            writeln!(out, "    }}")?;
+            writeln!(
+                out,
+                "    Some((addr - {}_ORIGIN + {}_ORIGIN) as *mut T)",
+                vma.to_uppercase(),
+                view.to_uppercase()
+            )?;
+            writeln!(out, "}}")?;
+        }
+    }
+    Ok(())
+}
+
+/// render the address ranges the encryption engine decrypts, only when
+/// regions are encrypted
+fn render_encrypted<W: Word, Wr: Write>(ls: &LinkerScript<W>, out: &mut Wr) -> Result<(), Error> {
+    let ranges = ls.encrypted_ranges();
+    if ranges.is_empty() {
+        return Ok(());
+    }
+    writeln!(out)?;
+    writeln!(
+        out,
+        "/// Start and end addresses of the ranges the encryption engine decrypts"
+    )?;
+    writeln!(
+        out,
+        "pub const ENCRYPTED_RANGES: [(usize, usize); {}] = [",
+        ranges.len()
+    )?;
+    for (start, end) in ranges {
+        writeln!(out, "    ({:#X}, {:#X}),", start, end)?;
+    }
+    writeln!(out, "];")?;
+    Ok(())
+}
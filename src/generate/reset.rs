@@ -1,7 +1,994 @@
-use crate::{LinkerScript, Word};
-use std::io::Error;
+use crate::{LinkerScript, Section, SectionSize, Word};
+use std::io::{Error, Write};
+
+/// ARM relocation adjusting a pointer by the load offset, ELF32 REL
+const R_ARM_RELATIVE: u32 = 23;
+
+/// AArch64 relocation adjusting a pointer by the load offset, ELF64 RELA
+const R_AARCH64_RELATIVE: u32 = 1027;
+
+/// Mailbox state once the primary core has posted the sections, "POST"
+const MAILBOX_POSTED: u32 = 0x504F_5354;
+
+/// Mailbox state once the second core has zeroed them, "DONE"
+const MAILBOX_DONE: u32 = 0x444F_4E45;
+
+/// Rust type of a machine word
+fn word_type<W: Word>() -> &'static str {
+    if std::mem::size_of::<W>() == 8 {
+        "u64"
+    } else {
+        "u32"
+    }
+}
+
+/// Mask of the bits of a machine word
+fn word_mask<W: Word>() -> u64 {
+    u64::MAX >> (64 - 8 * std::mem::size_of::<W>())
+}
+
+/// Sections the reset copies or zeroes, in placement order
+fn init_sections<W: Word>(ls: &LinkerScript<W>) -> Vec<&Section<W>> {
+    let mut sections: Vec<&Section<W>> = ls
+        .sections
+        .values()
+        .filter(|section| match section.size {
+            SectionSize::Linker => section.lma.is_some() || section.name == "bss",
+            _ => false,
+        })
+        .collect();
+    sections.sort_by(|a, b| ls.placement_order(a, b));
+    sections
+}
+
+/// render the extern block of the symbols the reset refers to
+fn render_externs<W: Word, Wr: Write>(
+    ls: &LinkerScript<W>,
+    out: &mut Wr,
+    sections: &[&Section<W>],
+) -> Result<(), Error> {
+    let word = word_type::<W>();
+    writeln!(out, "extern \"C\" {{")?;
+    for section in sections.iter() {
+        let symbol = section.symbol_name();
+        writeln!(out, "    static mut __start_{}: {};", symbol, word)?;
+        writeln!(out, "    static mut __end_{}: {};", symbol, word)?;
+        if section.lma.is_some() {
+            if ls.position_independent {
+                writeln!(out, "    static __load_offset_{}: u8;", symbol)?;
+            } else {
+                writeln!(out, "    static __load_{}: {};", symbol, word)?;
+            }
+        }
+    }
+    if ls.sections.contains_key("rel.dyn") {
+        writeln!(out, "    static __start_rel_dyn: {};", word)?;
+        writeln!(out, "    static __end_rel_dyn: {};", word)?;
+    }
+    if has_stub(ls) {
+        writeln!(out, "    static __start_vector_table: {};", word)?;
+    }
+    if ls.sections.contains_key("tls") {
+        writeln!(out, "    static __start_tdata: {};", word)?;
+        writeln!(out, "    static __start_tbss: {};", word)?;
+        writeln!(out, "    static __end_tbss: {};", word)?;
+        writeln!(out, "    static mut __start_tls: {};", word)?;
+    }
+    writeln!(out, "}}")?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// render the copy and zero routines, four words per iteration then the
+/// words left over, or calls to the ARM EABI memory functions
+///
+/// Tiny scripts get a single word loop each, the smallest code.
+fn render_loops<W: Word, Wr: Write>(ls: &LinkerScript<W>, out: &mut Wr) -> Result<(), Error> {
+    let word = word_type::<W>();
+    if ls.tiny {
+        writeln!(
+            out,
+            "#[inline(always)]
+unsafe fn __copy(mut dst: *mut {word}, end: *mut {word}, mut src: *const {word}) {{
+    while dst < end {{
+        core::ptr::write_volatile(dst, core::ptr::read(src));
+        dst = dst.add(1);
+        src = src.add(1);
+    }}
+}}
+
+#[inline(always)]
+unsafe fn __zero(mut dst: *mut {word}, end: *mut {word}) {{
+    while dst < end {{
+        core::ptr::write_volatile(dst, 0);
+        dst = dst.add(1);
+    }}
+}}
+",
+            word = word
+        )?;
+        return Ok(());
+    }
+    if ls.aeabi_memcpy && std::mem::size_of::<W>() == 4 {
+        writeln!(
+            out,
+            "extern \"C\" {{
+    fn __aeabi_memcpy4(dest: *mut u8, src: *const u8, n: usize);
+    fn __aeabi_memclr4(dest: *mut u8, n: usize);
+}}
+
+#[inline(always)]
+unsafe fn __copy(dst: *mut u32, end: *mut u32, src: *const u32) {{
+    __aeabi_memcpy4(dst as *mut u8, src as *const u8, end as usize - dst as usize);
+}}
+
+#[inline(always)]
+unsafe fn __zero(dst: *mut u32, end: *mut u32) {{
+    __aeabi_memclr4(dst as *mut u8, end as usize - dst as usize);
+}}
+"
+        )?;
+        return Ok(());
+    }
+    writeln!(
+        out,
+        "#[inline(always)]
+unsafe fn __copy(mut dst: *mut {word}, end: *mut {word}, mut src: *const {word}) {{
+    while end.offset_from(dst) >= 4 {{
+        core::ptr::write_volatile(dst, core::ptr::read(src));
+        core::ptr::write_volatile(dst.add(1), core::ptr::read(src.add(1)));
+        core::ptr::write_volatile(dst.add(2), core::ptr::read(src.add(2)));
+        core::ptr::write_volatile(dst.add(3), core::ptr::read(src.add(3)));
+        dst = dst.add(4);
+        src = src.add(4);
+    }}
+    while dst < end {{
+        core::ptr::write_volatile(dst, core::ptr::read(src));
+        dst = dst.add(1);
+        src = src.add(1);
+    }}
+}}
+
+#[inline(always)]
+unsafe fn __zero(mut dst: *mut {word}, end: *mut {word}) {{
+    while end.offset_from(dst) >= 4 {{
+        core::ptr::write_volatile(dst, 0);
+        core::ptr::write_volatile(dst.add(1), 0);
+        core::ptr::write_volatile(dst.add(2), 0);
+        core::ptr::write_volatile(dst.add(3), 0);
+        dst = dst.add(4);
+    }}
+    while dst < end {{
+        core::ptr::write_volatile(dst, 0);
+        dst = dst.add(1);
+    }}
+}}
+",
+        word = word
+    )?;
+    Ok(())
+}
+
+/// render the LZ4 block decoder of compressed sections
+fn render_unlz4<Wr: Write>(out: &mut Wr) -> Result<(), Error> {
+    writeln!(
+        out,
+        "#[inline(always)]
+unsafe fn __lz4_length(mut src: *const u8, mut length: usize) -> (*const u8, usize) {{
+    if length == 15 {{
+        loop {{
+            let byte = core::ptr::read(src);
+            src = src.add(1);
+            length += byte as usize;
+            if byte != 255 {{
+                break;
+            }}
+        }}
+    }}
+    (src, length)
+}}
+
+/// Decompress the LZ4 block at `src` into `dst..end`, returning the end
+/// of the block
+#[inline(always)]
+unsafe fn __unlz4(mut dst: *mut u8, end: *mut u8, mut src: *const u8) -> *const u8 {{
+    while dst < end {{
+        let token = core::ptr::read(src);
+        let (from, literals) = __lz4_length(src.add(1), (token >> 4) as usize);
+        src = from;
+        for _ in 0..literals {{
+            core::ptr::write_volatile(dst, core::ptr::read(src));
+            dst = dst.add(1);
+            src = src.add(1);
+        }}
+        if dst >= end {{
+            break;
+        }}
+        let offset = core::ptr::read(src) as usize | (core::ptr::read(src.add(1)) as usize) << 8;
+        let (from, length) = __lz4_length(src.add(2), (token & 0xF) as usize);
+        src = from;
+        let mut copy = dst.sub(offset) as *const u8;
+        for _ in 0..length + 4 {{
+            core::ptr::write_volatile(dst, core::ptr::read_volatile(copy));
+            dst = dst.add(1);
+            copy = copy.add(1);
+        }}
+    }}
+    src
+}}
+"
+    )?;
+    Ok(())
+}
+
+/// The load address of `section` as a word pointer
+fn load_address<W: Word>(ls: &LinkerScript<W>, section: &Section<W>) -> String {
+    let symbol = section.symbol_name();
+    if ls.position_independent {
+        format!(
+            "(load_base + core::ptr::addr_of!(__load_offset_{}) as usize) as *const {}",
+            symbol,
+            word_type::<W>()
+        )
+    } else {
+        format!("core::ptr::addr_of!(__load_{})", symbol)
+    }
+}
+
+/// render the eDMA routines starting a copy on a channel, given by its
+/// transfer control descriptor, and checking it is done
+fn render_dma<Wr: Write>(out: &mut Wr) -> Result<(), Error> {
+    writeln!(
+        out,
+        "/// Start a copy to `dst..end` from `src` in one minor loop of 32-bit
+/// transfers, on the eDMA channel whose transfer control descriptor is at
+/// `tcd`
+#[inline(always)]
+unsafe fn __dma_copy<T>(tcd: usize, dst: *mut T, end: *mut T, src: *const T) {{
+    let tcd = tcd as *mut u32;
+    let bytes = end as usize - dst as usize;
+    if bytes == 0 {{
+        return;
+    }}
+    // SADDR, then ATTR with 32-bit transfers and SOFF
+    core::ptr::write_volatile(tcd, src as u32);
+    core::ptr::write_volatile(tcd.add(1), 0x0202_0004);
+    // NBYTES, then SLAST
+    core::ptr::write_volatile(tcd.add(2), bytes as u32);
+    core::ptr::write_volatile(tcd.add(3), 0);
+    // DADDR, then CITER of one major loop and DOFF
+    core::ptr::write_volatile(tcd.add(4), dst as u32);
+    core::ptr::write_volatile(tcd.add(5), 0x0001_0004);
+    // DLAST_SGA, then BITER, and CSR with START set last
+    core::ptr::write_volatile(tcd.add(6), 0);
+    core::ptr::write_volatile(tcd.add(7), 0x0001_0000);
+    core::ptr::write_volatile(tcd.add(7), 0x0001_0001);
+}}
+
+/// Whether the copy to `dst..end` on the channel at `tcd` is done
+#[inline(always)]
+unsafe fn __dma_done<T>(tcd: usize, dst: *const T, end: *const T) -> bool {{
+    dst == end || core::ptr::read_volatile((tcd + 0x1C) as *const u16) & 0x80 != 0
+}}
+"
+    )?;
+    Ok(())
+}
+
+/// render the barriers and instruction cache and branch predictor
+/// invalidation making copied code visible to instruction fetch
+fn render_sync_code<Wr: Write>(out: &mut Wr) -> Result<(), Error> {
+    writeln!(
+        out,
+        "/// Make code copied into memory visible to instruction fetch: finish
+/// the copies, invalidate the instruction cache and branch predictor, and
+/// flush the pipeline
+#[inline(always)]
+unsafe fn __sync_code() {{
+    core::arch::asm!(\"dsb sy\");
+    // ICIALLU, then BPIALL
+    core::ptr::write_volatile(0xE000_EF50 as *mut u32, 0);
+    core::ptr::write_volatile(0xE000_EF78 as *mut u32, 0);
+    core::arch::asm!(\"dsb sy\", \"isb sy\");
+}}
+"
+    )?;
+    Ok(())
+}
+
+/// render the data cache maintenance by address range
+fn render_dcache<Wr: Write>(out: &mut Wr) -> Result<(), Error> {
+    writeln!(
+        out,
+        "/// Write each 32-byte data cache line of `start..end` to the cache
+/// maintenance register at `register`, then wait for the maintenance
+#[inline(always)]
+unsafe fn __dcache_range<T>(register: usize, start: *const T, end: *const T) {{
+    let mut line = start as usize & !31;
+    while line < end as usize {{
+        core::ptr::write_volatile(register as *mut u32, line as u32);
+        line += 32;
+    }}
+    core::arch::asm!(\"dsb sy\");
+}}
+
+/// Clean the data cache lines of `start..end` to memory, DCCMVAC
+#[inline(always)]
+unsafe fn __dcache_clean<T>(start: *const T, end: *const T) {{
+    __dcache_range(0xE000_EF68, start, end);
+}}
+
+/// Invalidate the data cache lines of `start..end`, DCIMVAC
+#[inline(always)]
+unsafe fn __dcache_invalidate<T>(start: *const T, end: *const T) {{
+    __dcache_range(0xE000_EF5C, start, end);
+}}
+"
+    )?;
+    Ok(())
+}
+
+/// True if the cached writes to `section` must be cleaned to memory, when
+/// it runs from a DMA capable region or holds code
+fn needs_clean<W: Word>(ls: &LinkerScript<W>, section: &Section<W>) -> bool {
+    ls.dcache_before_init
+        && std::mem::size_of::<W>() == 4
+        && section.dma_tcd.is_none()
+        && !section.second_core
+        && (section.is_code() || ls.regions[&section.vma.0].dma)
+}
+
+/// True if `section`, written by eDMA or the second core, must be
+/// invalidated from the cache
+fn needs_invalidate<W: Word>(ls: &LinkerScript<W>, section: &Section<W>) -> bool {
+    ls.dcache_before_init
+        && std::mem::size_of::<W>() == 4
+        && (section.dma_tcd.is_some() || section.second_core)
+}
+
+/// render a call to the cache maintenance routine `routine` over the run
+/// addresses of `section`
+fn render_maintenance<W: Word, Wr: Write>(
+    out: &mut Wr,
+    indent: &str,
+    routine: &str,
+    section: &Section<W>,
+) -> Result<(), Error> {
+    let symbol = section.symbol_name();
+    writeln!(
+        out,
+        "{}{}(core::ptr::addr_of!(__start_{}), core::ptr::addr_of!(__end_{}));",
+        indent, routine, symbol, symbol
+    )?;
+    Ok(())
+}
+
+/// True if the reset must synchronize instruction fetch after copying
+/// `sections`, only for Cortex-M parts with 32-bit words
+fn copies_code<W: Word>(sections: &[&&Section<W>]) -> bool {
+    std::mem::size_of::<W>() == 4 && sections.iter().any(|section| section.is_code())
+}
+
+/// render `init_complete` and `wait_init`, for the copies `init` leaves
+/// to eDMA
+fn render_wait<W: Word, Wr: Write>(
+    ls: &LinkerScript<W>,
+    out: &mut Wr,
+    sections: &[&Section<W>],
+) -> Result<(), Error> {
+    let copies: Vec<String> = sections
+        .iter()
+        .filter_map(|section| {
+            let symbol = section.symbol_name();
+            section.dma_tcd.map(|tcd| {
+                format!(
+                    "__dma_done({:#X}, core::ptr::addr_of!(__start_{}), core::ptr::addr_of!(__end_{}))",
+                    tcd, symbol, symbol
+                )
+            })
+        })
+        .collect();
+    writeln!(out)?;
+    writeln!(out, "/// Whether the eDMA copies `init` started are done")?;
+    writeln!(out, "pub fn init_complete() -> bool {{")?;
+    writeln!(out, "    unsafe {{ {} }}", copies.join(" && "))?;
+    writeln!(out, "}}")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "/// Wait for the eDMA copies `init` started, before the sections they
+/// copy are used or the data cache is enabled"
+    )?;
+    writeln!(out, "pub fn wait_init() {{")?;
+    writeln!(out, "    while !init_complete() {{}}")?;
+    let invalidated: Vec<&&Section<W>> = sections
+        .iter()
+        .filter(|section| section.dma_tcd.is_some() && needs_invalidate(ls, section))
+        .collect();
+    if !invalidated.is_empty() {
+        writeln!(out, "    unsafe {{")?;
+        for section in invalidated {
+            render_maintenance(out, "        ", "__dcache_invalidate", section)?;
+        }
+        writeln!(out, "    }}")?;
+    }
+    let dma: Vec<&&Section<W>> = sections
+        .iter()
+        .filter(|section| section.dma_tcd.is_some())
+        .collect();
+    if copies_code(&dma) {
+        writeln!(out, "    unsafe {{ __sync_code() }};")?;
+    }
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// render the start of `init` posting the sections the second core zeroes
+/// to its mailbox
+fn render_post<W: Word, Wr: Write>(
+    out: &mut Wr,
+    sections: &[&Section<W>],
+    mailbox: u64,
+) -> Result<(), Error> {
+    let handed: Vec<&&Section<W>> = sections
+        .iter()
+        .filter(|section| section.second_core)
+        .collect();
+    let names: Vec<String> = handed
+        .iter()
+        .map(|section| format!(".{}", section.output_name()))
+        .collect();
+    writeln!(
+        out,
+        "    // {}, zeroed by the second core",
+        names.join(", ")
+    )?;
+    writeln!(out, "    let mailbox = {:#X} as *mut u32;", mailbox)?;
+    writeln!(
+        out,
+        "    core::ptr::write_volatile(mailbox.add(1), {});",
+        handed.len()
+    )?;
+    for (index, section) in handed.iter().enumerate() {
+        let symbol = section.symbol_name();
+        writeln!(
+            out,
+            "    core::ptr::write_volatile(mailbox.add({}), core::ptr::addr_of!(__start_{}) as u32);",
+            2 + 2 * index,
+            symbol
+        )?;
+        writeln!(
+            out,
+            "    core::ptr::write_volatile(mailbox.add({}), core::ptr::addr_of!(__end_{}) as u32);",
+            3 + 2 * index,
+            symbol
+        )?;
+    }
+    writeln!(out, "    core::arch::asm!(\"dsb sy\");")?;
+    writeln!(
+        out,
+        "    core::ptr::write_volatile(mailbox, {:#X});",
+        MAILBOX_POSTED
+    )?;
+    Ok(())
+}
+
+/// render `wait_second_core_init`, the primary core's barrier for the
+/// sections the second core zeroes
+fn render_wait_second_core<W: Word, Wr: Write>(
+    ls: &LinkerScript<W>,
+    out: &mut Wr,
+    sections: &[&Section<W>],
+    mailbox: u64,
+) -> Result<(), Error> {
+    writeln!(out)?;
+    writeln!(
+        out,
+        "/// Wait for the second core's `second_core_init`, before the sections
+/// it zeroes are used"
+    )?;
+    writeln!(out, "pub fn wait_second_core_init() {{")?;
+    writeln!(out, "    unsafe {{")?;
+    writeln!(
+        out,
+        "        while core::ptr::read_volatile({:#X} as *const u32) != {:#X} {{}}",
+        mailbox, MAILBOX_DONE
+    )?;
+    writeln!(out, "        core::arch::asm!(\"dmb sy\");")?;
+    for section in sections
+        .iter()
+        .filter(|section| section.second_core && needs_invalidate(ls, section))
+    {
+        render_maintenance(out, "        ", "__dcache_invalidate", section)?;
+    }
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// Generate the second core's share of the reset, zeroing the sections
+/// the primary core posts to the mailbox
+///
+/// The module only refers to the mailbox, not to the primary's linker
+/// symbols, so the second core's firmware may `include!` it.
+pub fn render_second_core<W: Word, Wr: Write>(
+    ls: &LinkerScript<W>,
+    out: &mut Wr,
+) -> Result<(), Error> {
+    let mailbox: u64 = match ls.second_core_mailbox {
+        Some(mailbox) => mailbox.into(),
+        None => return Ok(()),
+    };
+    writeln!(
+        out,
+        "// Generated by imxrt-rt-gen, the second core's share of the reset
+
+/// Zero the sections the primary core's `init` posts to the mailbox at
+/// {mailbox:#X}, then mark the mailbox done for its `wait_second_core_init`
+///
+/// # Safety
+///
+/// Call once from the second core. It waits for the primary's `init` to
+/// post the sections, and must see them at the primary's addresses.
+pub unsafe fn second_core_init() {{
+    let mailbox = {mailbox:#X} as *mut u32;
+    while core::ptr::read_volatile(mailbox) != {posted:#X} {{}}
+    let count = core::ptr::read_volatile(mailbox.add(1)) as usize;
+    for index in 0..count {{
+        let mut dst = core::ptr::read_volatile(mailbox.add(2 + 2 * index)) as usize as *mut u32;
+        let end = core::ptr::read_volatile(mailbox.add(3 + 2 * index)) as usize as *mut u32;
+        while dst < end {{
+            core::ptr::write_volatile(dst, 0);
+            dst = dst.add(1);
+        }}
+    }}
+    core::arch::asm!(\"dsb sy\");
+    core::ptr::write_volatile(mailbox, {done:#X});
+}}",
+        mailbox = mailbox,
+        posted = MAILBOX_POSTED,
+        done = MAILBOX_DONE
+    )?;
+    Ok(())
+}
+
+/// render `init`, copying and zeroing every section
+fn render_init<W: Word, Wr: Write>(
+    ls: &LinkerScript<W>,
+    out: &mut Wr,
+    sections: &[&Section<W>],
+) -> Result<(), Error> {
+    writeln!(
+        out,
+        "/// Copy and zero the sections of the generated linker script"
+    )?;
+    writeln!(out, "///")?;
+    if ls.position_independent {
+        writeln!(
+            out,
+            "/// `load_base` is the address the load regions actually start at."
+        )?;
+        writeln!(out, "///")?;
+    }
+    writeln!(out, "/// # Safety")?;
+    writeln!(out, "///")?;
+    writeln!(
+        out,
+        "/// Call once from the reset handler, before any static is used."
+    )?;
+    writeln!(out, "#[inline(always)]")?;
+    if ls.position_independent {
+        writeln!(out, "pub unsafe fn init(load_base: usize) {{")?;
+    } else {
+        writeln!(out, "pub unsafe fn init() {{")?;
+    }
+    if let Some(mailbox) = ls.second_core_mailbox {
+        render_post(out, sections, mailbox.into())?;
+    }
+    for section in sections.iter() {
+        if let (Some(tcd), Some(lma)) = (section.dma_tcd, &section.lma) {
+            let symbol = section.symbol_name();
+            writeln!(
+                out,
+                "    // .{}, {} loaded from {} by eDMA",
+                section.output_name(),
+                section.vma.0,
+                lma.0
+            )?;
+            writeln!(
+                out,
+                "    __dma_copy({:#X}, core::ptr::addr_of_mut!(__start_{}), core::ptr::addr_of_mut!(__end_{}), {});",
+                tcd,
+                symbol,
+                symbol,
+                load_address(ls, section)
+            )?;
+        }
+    }
+    let mut packed: Option<&str> = None;
+    for section in sections
+        .iter()
+        .filter(|section| section.dma_tcd.is_none() && !section.second_core)
+    {
+        if ls.pack_loads {
+            if let Some(lma) = &section.lma {
+                if packed != Some(lma.0.as_str()) {
+                    packed = Some(&lma.0);
+                    render_packed(ls, out, sections, &lma.0)?;
+                }
+                continue;
+            }
+        }
+        let symbol = section.symbol_name();
+        let start = format!("core::ptr::addr_of_mut!(__start_{})", symbol);
+        let end = format!("core::ptr::addr_of_mut!(__end_{})", symbol);
+        match &section.lma {
+            Some(lma) => {
+                writeln!(
+                    out,
+                    "    // .{}, {} loaded from {}",
+                    section.output_name(),
+                    section.vma.0,
+                    lma.0
+                )?;
+                writeln!(
+                    out,
+                    "    __copy({}, {}, {});",
+                    start,
+                    end,
+                    load_address(ls, section)
+                )?;
+            }
+            None => {
+                writeln!(out, "    // .{}, {}", section.output_name(), section.vma.0)?;
+                writeln!(out, "    __zero({}, {});", start, end)?;
+            }
+        }
+    }
+    if ls.sections.contains_key("tls") {
+        writeln!(out, "    // .tls, the main thread's TLS block")?;
+        writeln!(out, "    init_tls(core::ptr::addr_of_mut!(__start_tls));")?;
+    }
+    if let Some(guard) = ls.stack_guard {
+        writeln!(
+            out,
+            "    // The stack protector's canary, after the statics"
+        )?;
+        writeln!(
+            out,
+            "    core::ptr::write_volatile(core::ptr::addr_of_mut!(__stack_chk_guard), {:#X});",
+            guard & word_mask::<W>()
+        )?;
+    }
+    for section in sections.iter().filter(|section| needs_clean(ls, section)) {
+        render_maintenance(out, "    ", "__dcache_clean", section)?;
+    }
+    let copied: Vec<&&Section<W>> = sections
+        .iter()
+        .filter(|section| section.lma.is_some() && section.dma_tcd.is_none())
+        .collect();
+    if copies_code(&copied) {
+        writeln!(out, "    // Code was copied, fetch it from memory")?;
+        writeln!(out, "    __sync_code();")?;
+    }
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// render the stack protector's guard, the function mixing entropy into
+/// it and the handler of a smashed stack
+fn render_stack_protector<W: Word, Wr: Write>(out: &mut Wr) -> Result<(), Error> {
+    writeln!(
+        out,
+        "
+/// The canary code built with `-fstack-protector` checks its frames with,
+/// seeded by `init`
+#[no_mangle]
+pub static mut __stack_chk_guard: {word} = 0;
+
+/// Mix `entropy`, such as a TRNG's output, into the stack protector's
+/// canary
+///
+/// # Safety
+///
+/// Call after `init` and before any function built with the stack
+/// protector runs, since their frames hold the previous canary.
+#[inline(always)]
+pub unsafe fn seed_stack_guard(entropy: {word}) {{
+    let guard = core::ptr::addr_of_mut!(__stack_chk_guard);
+    core::ptr::write_volatile(guard, (core::ptr::read_volatile(guard) ^ entropy) & !0xFF);
+}}
+
+/// Called by code built with `-fstack-protector` when a frame's canary
+/// was overwritten
+#[no_mangle]
+pub extern \"C\" fn __stack_chk_fail() -> ! {{
+    panic!(\"stack smashing detected\")
+}}",
+        word = word_type::<W>()
+    )
+}
+
+/// render `init_tls`, copying `.tdata` into a TLS block and zeroing the
+/// part for `.tbss`
+fn render_tls<W: Word, Wr: Write>(out: &mut Wr) -> Result<(), Error> {
+    writeln!(
+        out,
+        "
+/// Fill the TLS block at `block`, `__tls_size` bytes, from the TLS image
+///
+/// # Safety
+///
+/// `block` must be word aligned and writable for `__tls_size` bytes.
+#[inline(always)]
+pub unsafe fn init_tls(block: *mut {word}) {{
+    let tdata = core::ptr::addr_of!(__start_tdata);
+    let tbss = core::ptr::addr_of!(__start_tbss).offset_from(tdata);
+    let end = core::ptr::addr_of!(__end_tbss).offset_from(tdata);
+    __copy(block, block.offset(tbss), tdata);
+    __zero(block.offset(tbss), block.offset(end));
+}}",
+        word = word_type::<W>()
+    )
+}
+
+/// render one loop copying the sections packed in `lma`, from the load
+/// address of the first with a single source pointer, and one
+/// decompressing the compressed sections which follow them
+fn render_packed<W: Word, Wr: Write>(
+    ls: &LinkerScript<W>,
+    out: &mut Wr,
+    sections: &[&Section<W>],
+    lma: &str,
+) -> Result<(), Error> {
+    let word = word_type::<W>();
+    let (compressed, packed): (Vec<&Section<W>>, Vec<&Section<W>>) = sections
+        .iter()
+        .filter(|section| section.lma.as_ref().map(|lma| lma.0.as_str()) == Some(lma))
+        .filter(|section| section.dma_tcd.is_none())
+        .copied()
+        .partition(|section| section.compressed);
+    if !packed.is_empty() {
+        render_src(ls, out, &packed, word, "packed in", lma)?;
+        for section in packed.iter() {
+            let symbol = section.symbol_name();
+            writeln!(
+                out,
+                "        (core::ptr::addr_of_mut!(__start_{}), core::ptr::addr_of_mut!(__end_{})),",
+                symbol, symbol
+            )?;
+        }
+        writeln!(out, "    ]")?;
+        writeln!(out, "    .iter()")?;
+        writeln!(out, "    {{")?;
+        writeln!(out, "        __copy(start, end, src);")?;
+        writeln!(
+            out,
+            "        src = src.add(end.offset_from(start) as usize);"
+        )?;
+        writeln!(out, "    }}")?;
+    }
+    if !compressed.is_empty() {
+        render_src(ls, out, &compressed, "u8", "compressed in", lma)?;
+        for section in compressed.iter() {
+            let symbol = section.symbol_name();
+            writeln!(
+                out,
+                "        (core::ptr::addr_of_mut!(__start_{}) as *mut u8, core::ptr::addr_of_mut!(__end_{}) as *mut u8),",
+                symbol, symbol
+            )?;
+        }
+        writeln!(out, "    ]")?;
+        writeln!(out, "    .iter()")?;
+        writeln!(out, "    {{")?;
+        writeln!(out, "        src = __unlz4(start, end, src);")?;
+        writeln!(out, "    }}")?;
+    }
+    Ok(())
+}
+
+/// render the comment naming `sections`, the source pointer at the load
+/// address of the first, and the start of the loop over their run
+/// addresses
+fn render_src<W: Word, Wr: Write>(
+    ls: &LinkerScript<W>,
+    out: &mut Wr,
+    sections: &[&Section<W>],
+    pointee: &str,
+    how: &str,
+    lma: &str,
+) -> Result<(), Error> {
+    let names: Vec<String> = sections
+        .iter()
+        .map(|section| format!(".{}", section.output_name()))
+        .collect();
+    writeln!(out, "    // {}, {} {}", names.join(", "), how, lma)?;
+    let first = sections[0].symbol_name();
+    if ls.position_independent {
+        writeln!(
+            out,
+            "    let mut src = (load_base + core::ptr::addr_of!(__load_offset_{}) as usize) as *const {};",
+            first, pointee
+        )?;
+    } else {
+        writeln!(
+            out,
+            "    let mut src = core::ptr::addr_of!(__load_{}) as *const {};",
+            first, pointee
+        )?;
+    }
+    writeln!(out, "    for &(start, end) in [")?;
+    Ok(())
+}
+
+/// True if the reset module gets the position independent stub
+fn has_stub<W: Word>(ls: &LinkerScript<W>) -> bool {
+    ls.reset_stub && std::mem::size_of::<W>() == 4 && ls.sections.contains_key("vector_table")
+}
+
+/// render `run_offset` and `reset_stub`, which run correctly wherever the
+/// image is
+fn render_stub<Wr: Write>(out: &mut Wr) -> Result<(), Error> {
+    writeln!(out)?;
+    writeln!(
+        out,
+        "/// Address the code runs at less the address it was linked at, zero
+/// when running where linked
+///
+/// The run address of a label comes from the program counter, and its
+/// link address from the instruction stream, so no absolute address is
+/// loaded.
+#[inline(always)]
+pub fn run_offset() -> usize {{
+    let run: usize;
+    let link: usize;
+    unsafe {{
+        core::arch::asm!(
+            \"adr.w {{run}}, 2f\",
+            \"movw {{link}}, :lower16:2f\",
+            \"movt {{link}}, :upper16:2f\",
+            \".p2align 2\",
+            \"2:\",
+            run = out(reg) run,
+            link = out(reg) link,
+            options(nomem, nostack, preserves_flags),
+        );
+    }}
+    run.wrapping_sub(link)
+}}
+
+/// Point VTOR at the vector table where the image runs, returning
+/// `run_offset`
+///
+/// # Safety
+///
+/// Call first thing in the reset handler, before any code or data is used
+/// at its link address.
+#[inline(always)]
+pub unsafe fn reset_stub() -> usize {{
+    let offset = run_offset();
+    let vectors = (core::ptr::addr_of!(__start_vector_table) as usize).wrapping_add(offset);
+    core::ptr::write_volatile(0xE000_ED08 as *mut u32, vectors as u32);
+    core::arch::asm!(\"dsb sy\", \"isb sy\");
+    offset
+}}"
+    )?;
+    Ok(())
+}
+
+/// render `relocate`, applying the dynamic relocations of a PIE image
+fn render_relocate<W: Word, Wr: Write>(out: &mut Wr) -> Result<(), Error> {
+    writeln!(out)?;
+    writeln!(
+        out,
+        "/// Apply the relative dynamic relocations of the image
+///
+/// `delta` is the address the image runs from less the address it was
+/// linked at. Relocated pointers are written at their link addresses, so
+/// they must be in RAM, such as the global offset table and data.
+///
+/// # Safety
+///
+/// Call once, after `init`, before any relocated pointer is used."
+    )?;
+    if std::mem::size_of::<W>() == 8 {
+        writeln!(
+            out,
+            "pub unsafe fn relocate(delta: usize) {{
+    let mut rela = core::ptr::addr_of!(__start_rel_dyn) as *const [u64; 3];
+    let end = core::ptr::addr_of!(__end_rel_dyn) as *const [u64; 3];
+    while rela < end {{
+        let [offset, info, addend] = core::ptr::read(rela);
+        if info & 0xFFFF_FFFF == {} {{
+            let location = offset as usize as *mut usize;
+            core::ptr::write_volatile(location, delta.wrapping_add(addend as usize));
+        }}
+        rela = rela.add(1);
+    }}
+}}",
+            R_AARCH64_RELATIVE
+        )?;
+    } else {
+        writeln!(
+            out,
+            "pub unsafe fn relocate(delta: usize) {{
+    let mut rel = core::ptr::addr_of!(__start_rel_dyn) as *const [u32; 2];
+    let end = core::ptr::addr_of!(__end_rel_dyn) as *const [u32; 2];
+    while rel < end {{
+        let [offset, info] = core::ptr::read(rel);
+        if info & 0xFF == {} {{
+            let location = offset as usize as *mut usize;
+            core::ptr::write_volatile(
+                location,
+                core::ptr::read_volatile(location).wrapping_add(delta),
+            );
+        }}
+        rel = rel.add(1);
+    }}
+}}",
+            R_ARM_RELATIVE
+        )?;
+    }
+    Ok(())
+}
 
 /// Generate a reset module from a LinkerScript
-pub fn render<W: Word>(_ls: &LinkerScript<W>) -> Result<Vec<u8>, Error> {
-    Ok(Vec::new())
+///
+/// The module is Rust source to `include!` in the crate providing the
+/// reset handler. Its `init` function copies every section with a load
+/// region, or decompresses it when compressed, and zeroes every bss
+/// section, once it has started the eDMA copies `wait_init` waits for.
+/// Copied code is made visible to instruction fetch with the barriers and
+/// cache maintenance the Cortex-M7 needs.
+/// When the layout has dynamic relocations, its `relocate` function
+/// applies them.
+pub fn render<W: Word, Wr: Write>(ls: &LinkerScript<W>, out: &mut Wr) -> Result<(), Error> {
+    writeln!(
+        out,
+        "// Generated by imxrt-rt-gen from the sections of the linker script"
+    )?;
+    writeln!(out)?;
+    let sections = init_sections(ls);
+    render_externs(ls, out, &sections)?;
+    render_loops(ls, out)?;
+    if sections.iter().any(|section| section.compressed) {
+        render_unlz4(out)?;
+    }
+    let dma = sections.iter().any(|section| section.dma_tcd.is_some());
+    if dma {
+        render_dma(out)?;
+    }
+    let loaded: Vec<&&Section<W>> = sections
+        .iter()
+        .filter(|section| section.lma.is_some())
+        .collect();
+    if copies_code(&loaded) {
+        render_sync_code(out)?;
+    }
+    if sections
+        .iter()
+        .any(|section| needs_clean(ls, section) || needs_invalidate(ls, section))
+    {
+        render_dcache(out)?;
+    }
+    render_init(ls, out, &sections)?;
+    if ls.sections.contains_key("tls") {
+        render_tls::<W, Wr>(out)?;
+    }
+    if ls.stack_guard.is_some() {
+        render_stack_protector::<W, Wr>(out)?;
+    }
+    if dma {
+        render_wait(ls, out, &sections)?;
+    }
+    if let Some(mailbox) = ls.second_core_mailbox {
+        render_wait_second_core(ls, out, &sections, mailbox.into())?;
+    }
+    if has_stub(ls) {
+        render_stub(out)?;
+    }
+    if ls.sections.contains_key("rel.dyn") {
+        render_relocate::<W, Wr>(out)?;
+    }
+    Ok(())
 }
@@ -0,0 +1,34 @@
+use crate::VERSION_SIZE;
+use std::io::{Error, Write};
+
+/// Generate the static placed in the `.version` section, holding `version`
+/// NUL padded to the section's size
+///
+/// The text is cut to leave at least one NUL, so tools reading a C string
+/// from the section's address stop inside it.
+pub fn render<Wr: Write>(version: &str, out: &mut Wr) -> Result<(), Error> {
+    let size = VERSION_SIZE as usize;
+    let mut bytes = version.as_bytes().to_vec();
+    bytes.truncate(size - 1);
+    bytes.resize(size, 0);
+    let text: String = bytes
+        .iter()
+        .flat_map(|byte| std::ascii::escape_default(*byte))
+        .map(char::from)
+        .collect();
+    writeln!(
+        out,
+        "// Generated by imxrt-rt-gen from the package's Cargo metadata"
+    )?;
+    writeln!(out)?;
+    writeln!(out, "/// {:?}, NUL padded", version)?;
+    writeln!(out, "#[link_section = \".version\"]")?;
+    writeln!(out, "#[used]")?;
+    writeln!(out, "#[no_mangle]")?;
+    writeln!(
+        out,
+        "pub static IMXRT_RT_GEN_VERSION: [u8; {}] = *b\"{}\";",
+        size, text
+    )?;
+    Ok(())
+}
@@ -0,0 +1,140 @@
+//! Intel HEX and Motorola S-record export
+//!
+//! Both formats carry an address with every record, so unlike a flat
+//! [`Image`](../image/struct.Image.html) they may describe sparse contents.
+//! Records are built from the load addresses of the ELF file's segments,
+//! which for sections copied at reset is their flash address rather than
+//! their runtime address.
+
+use crate::elf::{Elf, PT_LOAD};
+use crate::image::{self, Image};
+use crate::layout::Layout;
+use crate::{LinkerError, Result};
+use std::fmt::Write;
+
+/// FlexSPI flash base address the Teensy 4 boot ROM and loader use
+pub const TEENSY_FLASH_BASE: u64 = 0x6000_0000;
+
+/// Tag at the start of a FlexSPI configuration block, "FCFB"
+const FCB_TAG: &[u8] = b"FCFB";
+
+/// Bytes of data per record
+const RECORD_LEN: usize = 16;
+
+/// Loadable contents of `elf` as (load address, bytes) pairs sorted by address
+pub fn load_segments(elf: &Elf) -> Vec<(u64, &[u8])> {
+    let mut segments: Vec<(u64, &[u8])> = elf
+        .segments()
+        .iter()
+        .filter(|seg| seg.kind == PT_LOAD && seg.filesz > 0)
+        .map(|seg| (seg.paddr, elf.segment_data(seg)))
+        .collect();
+    segments.sort_by_key(|(address, _)| *address);
+    segments
+}
+
+/// Split chunks into records which don't cross a 64KiB boundary
+fn records<'a>(chunks: &[(u64, &'a [u8])]) -> Vec<(u64, &'a [u8])> {
+    let mut records = Vec::new();
+    for (address, bytes) in chunks.iter() {
+        let mut offset = 0;
+        while offset < bytes.len() {
+            let address = address + offset as u64;
+            let to_boundary = (0x1_0000 - (address & 0xFFFF)) as usize;
+            let len = RECORD_LEN.min(bytes.len() - offset).min(to_boundary);
+            records.push((address, &bytes[offset..offset + len]));
+            offset += len;
+        }
+    }
+    records
+}
+
+fn ihex_record(out: &mut String, kind: u8, address: u16, data: &[u8]) {
+    let mut sum = data.len() as u8;
+    sum = sum.wrapping_add((address >> 8) as u8);
+    sum = sum.wrapping_add(address as u8);
+    sum = sum.wrapping_add(kind);
+    let _ = write!(out, ":{:02X}{:04X}{:02X}", data.len(), address, kind);
+    for byte in data {
+        sum = sum.wrapping_add(*byte);
+        let _ = write!(out, "{:02X}", byte);
+    }
+    let _ = writeln!(out, "{:02X}", sum.wrapping_neg());
+}
+
+/// Render chunks as Intel HEX with extended linear address records
+///
+/// The start linear address record is included when `entry` is given.
+pub fn ihex(chunks: &[(u64, &[u8])], entry: Option<u64>) -> String {
+    let mut out = String::new();
+    let mut upper = None;
+    for (address, data) in records(chunks) {
+        let high = (address >> 16) as u16;
+        if upper != Some(high) {
+            ihex_record(&mut out, 0x04, 0, &high.to_be_bytes());
+            upper = Some(high);
+        }
+        ihex_record(&mut out, 0x00, address as u16, data);
+    }
+    if let Some(entry) = entry {
+        ihex_record(&mut out, 0x05, 0, &(entry as u32).to_be_bytes());
+    }
+    ihex_record(&mut out, 0x01, 0, &[]);
+    out
+}
+
+fn srec_record(out: &mut String, kind: u8, address: &[u8], data: &[u8]) {
+    let count = (address.len() + data.len() + 1) as u8;
+    let mut sum = count;
+    let _ = write!(out, "S{}{:02X}", kind, count);
+    for byte in address.iter().chain(data) {
+        sum = sum.wrapping_add(*byte);
+        let _ = write!(out, "{:02X}", byte);
+    }
+    let _ = writeln!(out, "{:02X}", !sum);
+}
+
+/// Render chunks as Motorola S-records with 32 bit addresses
+///
+/// `header` is placed in the S0 record, `entry` in the S7 termination record.
+pub fn srec(chunks: &[(u64, &[u8])], header: &str, entry: Option<u64>) -> String {
+    let mut out = String::new();
+    srec_record(&mut out, 0, &[0, 0], header.as_bytes());
+    let records = records(chunks);
+    for (address, data) in records.iter() {
+        srec_record(&mut out, 3, &(*address as u32).to_be_bytes(), data);
+    }
+    if records.len() <= 0xFFFF {
+        srec_record(&mut out, 5, &(records.len() as u16).to_be_bytes(), &[]);
+    }
+    srec_record(&mut out, 7, &(entry.unwrap_or(0) as u32).to_be_bytes(), &[]);
+    out
+}
+
+/// Render an Intel HEX file for the Teensy 4 loader
+///
+/// The Teensy loader programs the flash image from 0x6000_0000 and the
+/// boot ROM reads the FCB at its start, so the flash region must start
+/// there, the image must begin with an FCB, and nothing may be loaded
+/// outside of flash. The image is emitted contiguously with gaps filled
+/// with the erased flash value, as `objcopy -O ihex` with the right
+/// section flags would.
+pub fn teensy(elf: &Elf, layout: &Layout) -> Result<String> {
+    let flash = layout
+        .region_containing(TEENSY_FLASH_BASE)
+        .filter(|region| region.origin == TEENSY_FLASH_BASE)
+        .ok_or_else(|| {
+            LinkerError::InvalidImage(format!(
+                "no region starts at the Teensy flash base {:#010X}",
+                TEENSY_FLASH_BASE
+            ))
+        })?;
+    let image = Image::from_elf(elf, layout, Some(&flash.name), image::ERASED)?;
+    if !image.data.starts_with(FCB_TAG) {
+        return Err(LinkerError::InvalidImage(format!(
+            "no FCB at the start of {}, the Teensy would not boot",
+            flash.name
+        )));
+    }
+    Ok(ihex(&[(image.base, &image.data)], None))
+}
@@ -0,0 +1,146 @@
+//! Profile guided placement of hot functions
+//!
+//! A profile lists the functions a program spends its time in, for example
+//! from sampling the program counter. The hottest functions which fit an
+//! ITCM budget are selected using their sizes from a previous build, then
+//! routed to ITCM with
+//! [`LinkerScript::hot_text`](../struct.LinkerScript.html#method.hot_text).
+
+use crate::elf::Elf;
+use std::fmt;
+use std::str::FromStr;
+
+/// Functions by the number of samples taken in them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    /// (samples, symbol) pairs, hottest first
+    pub functions: Vec<(u64, String)>,
+}
+
+impl FromStr for Profile {
+    type Err = String;
+
+    /// Parse a profile with one `samples symbol` pair per line
+    ///
+    /// A line with only a symbol counts as one sample, so a plain list of
+    /// hot symbols is kept in order. Blank lines and lines starting with
+    /// `#` are ignored.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut functions = Vec::new();
+        for (idx, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let entry = match (parts.next(), parts.next(), parts.next()) {
+                (Some(symbol), None, _) => (1, symbol),
+                (Some(samples), Some(symbol), None) => match samples.parse() {
+                    Ok(samples) => (samples, symbol),
+                    Err(_) => return Err(format!("profile line {} has no sample count", idx + 1)),
+                },
+                _ => {
+                    return Err(format!(
+                        "profile line {} is not of the form `samples symbol`",
+                        idx + 1
+                    ))
+                }
+            };
+            functions.push((entry.0, String::from(entry.1)));
+        }
+        // Stable, so equally hot functions keep the order of the profile
+        functions.sort_by_key(|f| std::cmp::Reverse(f.0));
+        Ok(Profile { functions })
+    }
+}
+
+/// A function selected for ITCM
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HotFunction {
+    /// Mangled symbol name
+    pub symbol: String,
+    /// Samples taken in the function
+    pub samples: u64,
+    /// Size of the function in bytes
+    pub size: u64,
+}
+
+/// The functions of a profile which fit a budget
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HotSet {
+    /// Selected functions, hottest first
+    pub functions: Vec<HotFunction>,
+    /// Profiled functions which did not fit the budget, or were not found
+    /// in the ELF file
+    pub skipped: Vec<String>,
+    /// Bytes available
+    pub budget: u64,
+}
+
+impl HotSet {
+    /// Total size of the selected functions
+    pub fn used(&self) -> u64 {
+        self.functions.iter().map(|f| f.size).sum()
+    }
+
+    /// Symbols of the selected functions, for `LinkerScript::hot_text`
+    pub fn symbols(&self) -> Vec<&str> {
+        self.functions.iter().map(|f| f.symbol.as_str()).collect()
+    }
+}
+
+/// Select the hottest functions of `profile` fitting `budget` bytes
+///
+/// Functions are sized from the symbol table of `elf`. A function too
+/// large for the remaining budget is skipped, and smaller, colder
+/// functions may still be selected after it. Thumb function symbols have
+/// the lowest bit of their address set, which does not matter here.
+pub fn select(profile: &Profile, elf: &Elf, budget: u64) -> HotSet {
+    let mut set = HotSet {
+        functions: Vec::new(),
+        skipped: Vec::new(),
+        budget,
+    };
+    let mut used = 0;
+    for (samples, symbol) in profile.functions.iter() {
+        match elf.symbol(symbol) {
+            Some(sym) if used + sym.size <= budget => {
+                used += sym.size;
+                set.functions.push(HotFunction {
+                    symbol: symbol.clone(),
+                    samples: *samples,
+                    size: sym.size,
+                });
+            }
+            _ => set.skipped.push(symbol.clone()),
+        }
+    }
+    set
+}
+
+impl fmt::Display for HotSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut cumulative = 0;
+        for function in self.functions.iter() {
+            cumulative += function.size;
+            writeln!(
+                f,
+                "{:>8} {:>8} {:>8} {}",
+                function.samples, function.size, cumulative, function.symbol
+            )?;
+        }
+        let percent = if self.budget == 0 {
+            0.0
+        } else {
+            self.used() as f64 * 100.0 / self.budget as f64
+        };
+        writeln!(
+            f,
+            "{} of {} bytes used, {:.2}%, {} functions skipped",
+            self.used(),
+            self.budget,
+            percent,
+            self.skipped.len()
+        )
+    }
+}
@@ -0,0 +1,183 @@
+//! Flat bootable images built from a linked ELF file
+//!
+//! An image is the contents of a region, usually the boot flash, as it
+//! should be programmed: every loadable segment stored at its load address
+//! relative to the region origin and any gaps filled with the erased flash
+//! value. On i.MX RT parts booting from FlexSPI this puts the FCB, IVT and
+//! application at the offsets the boot ROM expects without `objcopy` and
+//! `dd` recipes.
+
+use crate::elf::{Elf, PT_LOAD};
+use crate::layout::{Layout, LayoutRegion};
+use crate::{LinkerError, Result};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+/// Value of erased NOR flash
+pub const ERASED: u8 = 0xFF;
+
+/// Contiguous bytes to program starting at a base address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    /// Address of the first byte
+    pub base: u64,
+    /// Image contents
+    pub data: Vec<u8>,
+}
+
+fn invalid(reason: String) -> LinkerError {
+    LinkerError::InvalidImage(reason)
+}
+
+impl Image {
+    /// Build the image of `region` from the loadable segments of `elf`
+    ///
+    /// When `region` is `None` the region holding the lowest load address is
+    /// used, which is the boot flash for XIP images. Segments loaded outside
+    /// of the region are an error as they could not be programmed with it.
+    pub fn from_elf(elf: &Elf, layout: &Layout, region: Option<&str>, fill: u8) -> Result<Image> {
+        let segments: Vec<_> = elf
+            .segments()
+            .iter()
+            .filter(|seg| seg.kind == PT_LOAD && seg.filesz > 0)
+            .collect();
+        let region = match region {
+            Some(name) => layout
+                .region(name)
+                .ok_or_else(|| invalid(format!("unknown region {:?}", name)))?,
+            None => segments
+                .iter()
+                .map(|seg| seg.paddr)
+                .min()
+                .and_then(|lowest| layout.region_containing(lowest))
+                .ok_or_else(|| invalid(String::from("no loadable segment within a region")))?,
+        };
+
+        let mut image = Image {
+            base: region.origin,
+            data: Vec::new(),
+        };
+        for segment in segments {
+            let end = segment.paddr + segment.filesz;
+            if !region.contains(segment.paddr) || end > region.end() {
+                return Err(invalid(format!(
+                    "segment loaded at {:#010X}..{:#010X} is outside of region {}",
+                    segment.paddr, end, region.name
+                )));
+            }
+            image.write(segment.paddr, elf.segment_data(segment), fill);
+        }
+        Ok(image)
+    }
+
+    /// Copy `bytes` into the image at `address`, growing it with `fill`
+    pub fn write(&mut self, address: u64, bytes: &[u8], fill: u8) {
+        let offset = (address - self.base) as usize;
+        if self.data.len() < offset + bytes.len() {
+            self.data.resize(offset + bytes.len(), fill);
+        }
+        self.data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    /// Copy a prebuilt blob, such as an FCB, IVT, or DCD, into the fixed
+    /// size section reserved for it by the layout
+    ///
+    /// The blob may be smaller than the reservation, the remainder is left
+    /// as is. Blobs larger than the reservation are rejected.
+    pub fn patch(&mut self, layout: &Layout, section: &str, blob: &[u8]) -> Result<()> {
+        let (address, size) = layout.fixed_address(section).ok_or_else(|| {
+            invalid(format!(
+                "section {:?} is not a fixed size section at a known offset",
+                section
+            ))
+        })?;
+        if blob.len() as u64 > size {
+            return Err(invalid(format!(
+                "{} bytes do not fit in the {} bytes reserved for {:?}",
+                blob.len(),
+                size,
+                section
+            )));
+        }
+        if address < self.base {
+            return Err(invalid(format!(
+                "section {:?} at {:#010X} is before the image base {:#010X}",
+                section, address, self.base
+            )));
+        }
+        self.write(address, blob, ERASED);
+        Ok(())
+    }
+
+    /// Pad the image with `fill` to a multiple of `block` bytes, such as
+    /// the flash erase block size
+    pub fn pad_to(&mut self, block: u64, fill: u8) {
+        if block > 0 {
+            let len = (self.data.len() as u64).div_ceil(block) * block;
+            self.data.resize(len as usize, fill);
+        }
+    }
+
+    /// Remove trailing `fill` bytes from the image
+    pub fn trim(&mut self, fill: u8) {
+        let len = self
+            .data
+            .iter()
+            .rposition(|byte| *byte != fill)
+            .map(|idx| idx + 1)
+            .unwrap_or(0);
+        self.data.truncate(len);
+    }
+
+    /// Copy the part of the image covering `region`, for example one slot
+    /// of an A/B layout
+    ///
+    /// Parts of the region beyond the end of the image are not included,
+    /// the result may be padded with [`pad_to`](#method.pad_to) as needed.
+    pub fn slice(&self, region: &LayoutRegion) -> Result<Image> {
+        if region.origin < self.base {
+            return Err(invalid(format!(
+                "region {} at {:#010X} is before the image base {:#010X}",
+                region.name, region.origin, self.base
+            )));
+        }
+        let start = ((region.origin - self.base) as usize).min(self.data.len());
+        let end = ((region.end() - self.base) as usize).min(self.data.len());
+        Ok(Image {
+            base: region.origin,
+            data: self.data[start..end].to_vec(),
+        })
+    }
+
+    /// The update payload for the slot `region` of an A/B layout
+    ///
+    /// The part of the image in the slot is stripped of trailing `fill`
+    /// bytes and padded to a multiple of `align`, which is usually the
+    /// erase block size. The payload must still fit in the slot.
+    pub fn payload(&self, region: &LayoutRegion, align: u64, fill: u8) -> Result<Image> {
+        let mut payload = self.slice(region)?;
+        payload.trim(fill);
+        payload.pad_to(align, fill);
+        if payload.data.len() as u64 > region.size {
+            return Err(invalid(format!(
+                "{} byte payload does not fit in the {} bytes of slot {}",
+                payload.data.len(),
+                region.size,
+                region.name
+            )));
+        }
+        Ok(payload)
+    }
+
+    /// One past the last address of the image
+    pub fn end(&self) -> u64 {
+        self.base + self.data.len() as u64
+    }
+
+    /// Write the raw image to the file at `path`
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        File::create(path)?.write_all(&self.data)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,324 @@
+//! Minimal JSON support for the layout and configuration files
+//!
+//! Only what the crate needs is supported: integers (no fractions or
+//! exponents), strings, booleans, null, arrays, and objects. Object
+//! members keep their insertion order so output is stable.
+
+use std::fmt::{self, Write};
+
+/// A parsed JSON value
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Str(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Lookup a member of an object
+    pub(crate) fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Int(i) if *i >= 0 => Some(*i as u64),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    fn write_pretty<Wr: Write>(&self, out: &mut Wr, indent: usize) -> fmt::Result {
+        match self {
+            Value::Null => write!(out, "null"),
+            Value::Bool(b) => write!(out, "{}", b),
+            Value::Int(i) => write!(out, "{}", i),
+            Value::Str(s) => write_string(out, s),
+            Value::Array(items) if items.is_empty() => write!(out, "[]"),
+            Value::Array(items) => {
+                writeln!(out, "[")?;
+                for (idx, item) in items.iter().enumerate() {
+                    write!(out, "{:1$}", "", indent + 2)?;
+                    item.write_pretty(out, indent + 2)?;
+                    if idx + 1 < items.len() {
+                        write!(out, ",")?;
+                    }
+                    writeln!(out)?;
+                }
+                write!(out, "{:1$}]", "", indent)
+            }
+            Value::Object(members) if members.is_empty() => write!(out, "{{}}"),
+            Value::Object(members) => {
+                writeln!(out, "{{")?;
+                for (idx, (key, value)) in members.iter().enumerate() {
+                    write!(out, "{:1$}", "", indent + 2)?;
+                    write_string(out, key)?;
+                    write!(out, ": ")?;
+                    value.write_pretty(out, indent + 2)?;
+                    if idx + 1 < members.len() {
+                        write!(out, ",")?;
+                    }
+                    writeln!(out)?;
+                }
+                write!(out, "{:1$}}}", "", indent)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_pretty(f, 0)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Str(String::from(s))
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::Str(s)
+    }
+}
+
+impl From<u64> for Value {
+    fn from(i: u64) -> Self {
+        Value::Int(i as i64)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(i: i32) -> Self {
+        Value::Int(i64::from(i))
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(opt: Option<T>) -> Self {
+        opt.map(Into::into).unwrap_or(Value::Null)
+    }
+}
+
+fn write_string<Wr: Write>(out: &mut Wr, s: &str) -> fmt::Result {
+    out.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.write_char(c)?,
+        }
+    }
+    out.write_char('"')
+}
+
+/// Parse a JSON document
+pub(crate) fn parse(input: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        chars: input.char_indices().peekable(),
+        input,
+    };
+    let value = parser.value()?;
+    parser.whitespace();
+    match parser.chars.next() {
+        None => Ok(value),
+        Some((pos, c)) => Err(format!("unexpected {:?} at offset {}", c, pos)),
+    }
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    input: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn whitespace(&mut self) {
+        while let Some((_, c)) = self.chars.peek() {
+            if c.is_whitespace() {
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((pos, c)) => Err(format!(
+                "expected {:?} but found {:?} at offset {}",
+                expected, c, pos
+            )),
+            None => Err(format!("expected {:?} but found end of input", expected)),
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: Value) -> Result<Value, String> {
+        for expected in word.chars() {
+            self.expect(expected)?;
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.whitespace();
+        match self.chars.peek().cloned() {
+            Some((_, '{')) => self.object(),
+            Some((_, '[')) => self.array(),
+            Some((_, '"')) => self.string().map(Value::Str),
+            Some((_, 't')) => self.keyword("true", Value::Bool(true)),
+            Some((_, 'f')) => self.keyword("false", Value::Bool(false)),
+            Some((_, 'n')) => self.keyword("null", Value::Null),
+            Some((_, c)) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some((pos, c)) => Err(format!("unexpected {:?} at offset {}", c, pos)),
+            None => Err(String::from("unexpected end of input")),
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.chars.peek().map(|(pos, _)| *pos).unwrap_or(0);
+        let mut end = start;
+        while let Some((pos, c)) = self.chars.peek().cloned() {
+            if c == '-' || c.is_ascii_digit() {
+                end = pos + c.len_utf8();
+                self.chars.next();
+            } else if c == '.' || c == 'e' || c == 'E' {
+                return Err(format!("only integers are supported, at offset {}", pos));
+            } else {
+                break;
+            }
+        }
+        self.input[start..end]
+            .parse::<i64>()
+            .map(Value::Int)
+            .map_err(|err| format!("invalid number at offset {}, {}", start, err))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, '"')) => return Ok(s),
+                Some((_, '\\')) => match self.chars.next() {
+                    Some((_, '"')) => s.push('"'),
+                    Some((_, '\\')) => s.push('\\'),
+                    Some((_, '/')) => s.push('/'),
+                    Some((_, 'n')) => s.push('\n'),
+                    Some((_, 'r')) => s.push('\r'),
+                    Some((_, 't')) => s.push('\t'),
+                    Some((_, 'b')) => s.push('\u{8}'),
+                    Some((_, 'f')) => s.push('\u{c}'),
+                    Some((pos, 'u')) => {
+                        let mut code = 0;
+                        for _ in 0..4 {
+                            let digit = self
+                                .chars
+                                .next()
+                                .and_then(|(_, c)| c.to_digit(16))
+                                .ok_or_else(|| format!("invalid escape at offset {}", pos))?;
+                            code = code * 16 + digit;
+                        }
+                        s.push(
+                            std::char::from_u32(code)
+                                .ok_or_else(|| format!("invalid escape at offset {}", pos))?,
+                        );
+                    }
+                    Some((pos, c)) => {
+                        return Err(format!("invalid escape {:?} at offset {}", c, pos))
+                    }
+                    None => return Err(String::from("unterminated string")),
+                },
+                Some((_, c)) => s.push(c),
+                None => return Err(String::from("unterminated string")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        self.whitespace();
+        if let Some((_, ']')) = self.chars.peek() {
+            self.chars.next();
+            return Ok(Value::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.whitespace();
+            match self.chars.next() {
+                Some((_, ',')) => continue,
+                Some((_, ']')) => return Ok(Value::Array(items)),
+                Some((pos, c)) => return Err(format!("unexpected {:?} at offset {}", c, pos)),
+                None => return Err(String::from("unterminated array")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut members = Vec::new();
+        self.whitespace();
+        if let Some((_, '}')) = self.chars.peek() {
+            self.chars.next();
+            return Ok(Value::Object(members));
+        }
+        loop {
+            self.whitespace();
+            let key = self.string()?;
+            self.whitespace();
+            self.expect(':')?;
+            let value = self.value()?;
+            members.push((key, value));
+            self.whitespace();
+            match self.chars.next() {
+                Some((_, ',')) => continue,
+                Some((_, '}')) => return Ok(Value::Object(members)),
+                Some((pos, c)) => return Err(format!("unexpected {:?} at offset {}", c, pos)),
+                None => return Err(String::from("unterminated object")),
+            }
+        }
+    }
+}
@@ -0,0 +1,353 @@
+//! Exported description of a linker script's regions and sections
+//!
+//! A `Layout` is what post-build tools need to know about the model the
+//! linker script was generated from. It is written as JSON next to the
+//! linker script so that it may be read back without the build script.
+
+use crate::json::{self, Value};
+use crate::{LinkerError, LinkerScript, Result, Section, SectionSize, Word};
+use std::fmt;
+
+/// A memory region in the layout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutRegion {
+    /// Region name as used in the MEMORY command
+    pub name: String,
+    /// First address of the region
+    pub origin: u64,
+    /// Size of the region in bytes
+    pub size: u64,
+}
+
+impl LayoutRegion {
+    /// True if `address` is within the region
+    pub fn contains(&self, address: u64) -> bool {
+        address >= self.origin && address - self.origin < self.size
+    }
+
+    /// One past the last address of the region
+    pub fn end(&self) -> u64 {
+        self.origin.saturating_add(self.size)
+    }
+}
+
+/// How a section in the layout is sized
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutSize {
+    /// Sized by the linker from the input sections
+    Linker,
+    /// A fixed number of bytes
+    Fixed(u64),
+    /// The stack, taking the remaining region space
+    Stack,
+    /// The stack, of a fixed number of bytes
+    FixedStack(u64),
+    /// A heap, taking the remaining region space
+    Heap,
+}
+
+impl fmt::Display for LayoutSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LayoutSize::Linker => f.write_str("linker"),
+            LayoutSize::Fixed(size) => write!(f, "fixed {:#X}", size),
+            LayoutSize::Stack => f.write_str("stack"),
+            LayoutSize::FixedStack(size) => write!(f, "stack {:#X}", size),
+            LayoutSize::Heap => f.write_str("heap"),
+        }
+    }
+}
+
+/// A section in the layout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutSection {
+    /// Section name as given to the builder, for example "data"
+    pub name: String,
+    /// Output section name without the leading '.', for example "TCM.data"
+    pub output: String,
+    /// Name of the region the section runs from
+    pub vma: String,
+    /// Name of the region the section is loaded from, if any
+    pub lma: Option<String>,
+    /// Placement priority within the region, lower is placed first
+    pub priority: i32,
+    /// Offset from `priority` of a section placed before or after another,
+    /// lower is placed first among sections of the same priority
+    pub nudge: i32,
+    /// Placement group, packing places the loaded sections (1) after the
+    /// others (0) and the sections filling a region (2) last
+    pub group: u32,
+    /// Size mode of the section
+    pub size: LayoutSize,
+    /// True if the section name is prefixed by its region
+    pub prefix: bool,
+    /// True if the section is stored LZ4 compressed in its load region
+    pub compressed: bool,
+}
+
+/// Regions and sections of a linker script, in placement order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Layout {
+    /// Regions sorted by origin
+    pub regions: Vec<LayoutRegion>,
+    /// Sections in placement order
+    pub sections: Vec<LayoutSection>,
+}
+
+fn invalid(reason: String) -> LinkerError {
+    LinkerError::InvalidLayout(reason)
+}
+
+impl Layout {
+    pub(crate) fn from_linker_script<W: Word>(ls: &LinkerScript<W>) -> Layout {
+        let mut regions: Vec<LayoutRegion> = ls
+            .regions
+            .values()
+            .map(|region| LayoutRegion {
+                name: region.name.clone(),
+                origin: region.origin.into(),
+                size: region.size.into(),
+            })
+            .collect();
+        regions.sort_by(|a, b| a.origin.cmp(&b.origin).then(a.name.cmp(&b.name)));
+
+        let mut sorted_sections: Vec<&Section<W>> = ls.sections.values().collect();
+        sorted_sections.sort_by(|a, b| ls.placement_order(a, b));
+        let sections: Vec<LayoutSection> = sorted_sections
+            .into_iter()
+            .map(|section| LayoutSection {
+                name: section.name.clone(),
+                output: section.output_name(),
+                vma: section.vma.0.clone(),
+                lma: section.lma.as_ref().map(|lma| lma.0.clone()),
+                priority: section.priority,
+                nudge: section.nudge,
+                group: ls.placement_group(section),
+                size: match section.size {
+                    SectionSize::Linker => LayoutSize::Linker,
+                    SectionSize::Fixed(size) => LayoutSize::Fixed(size.into()),
+                    SectionSize::Stack => LayoutSize::Stack,
+                    SectionSize::FixedStack(size) => LayoutSize::FixedStack(size.into()),
+                    SectionSize::Heap => LayoutSize::Heap,
+                },
+                prefix: section.prefix,
+                compressed: section.compressed,
+            })
+            .collect();
+
+        Layout { regions, sections }
+    }
+
+    /// Find a region by name
+    pub fn region(&self, name: &str) -> Option<&LayoutRegion> {
+        self.regions.iter().find(|r| r.name == name)
+    }
+
+    /// Find the region containing `address`
+    pub fn region_containing(&self, address: u64) -> Option<&LayoutRegion> {
+        self.regions.iter().find(|r| r.contains(address))
+    }
+
+    /// Find a section by its output section name, with or without the leading '.'
+    pub fn section(&self, output: &str) -> Option<&LayoutSection> {
+        let output = output.strip_prefix('.').unwrap_or(output);
+        self.sections.iter().find(|s| s.output == output)
+    }
+
+    /// Address of a section, when it is known before linking
+    ///
+    /// Sections are placed from the origin of their region in placement
+    /// order, so the address is only known when every section placed
+    /// before it in the region is of a fixed size, including a stack
+    /// placed below the statics.
+    pub fn address(&self, output: &str) -> Option<u64> {
+        let target = self.section(output)?;
+        let mut address = self.region(&target.vma)?.origin;
+        for section in self.sections.iter().filter(|s| s.vma == target.vma) {
+            match section.size {
+                _ if section == target => return Some(address),
+                LayoutSize::Fixed(size) | LayoutSize::FixedStack(size) => address += size,
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    /// Address and size of a fixed size section, when its
+    /// [`address`](#method.address) is known
+    ///
+    /// This is the case for boot configuration sections such as the FCB.
+    pub fn fixed_address(&self, output: &str) -> Option<(u64, u64)> {
+        match self.section(output)?.size {
+            LayoutSize::Fixed(size) => Some((self.address(output)?, size)),
+            _ => None,
+        }
+    }
+
+    /// Read a layout from a JSON file
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Layout> {
+        Layout::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parse a layout from JSON
+    pub fn from_json(input: &str) -> Result<Layout> {
+        let doc = json::parse(input).map_err(invalid)?;
+        let field = |value: &Value, key: &str| -> Result<Value> {
+            value
+                .get(key)
+                .cloned()
+                .ok_or_else(|| invalid(format!("missing field {:?}", key)))
+        };
+        let string = |value: &Value, key: &str| -> Result<String> {
+            field(value, key)?
+                .as_str()
+                .map(String::from)
+                .ok_or_else(|| invalid(format!("field {:?} must be a string", key)))
+        };
+        let number = |value: &Value, key: &str| -> Result<u64> {
+            field(value, key)?
+                .as_u64()
+                .ok_or_else(|| invalid(format!("field {:?} must be an unsigned integer", key)))
+        };
+
+        let mut layout = Layout::default();
+        for region in field(&doc, "regions")?.as_array().unwrap_or(&[]) {
+            layout.regions.push(LayoutRegion {
+                name: string(region, "name")?,
+                origin: number(region, "origin")?,
+                size: number(region, "size")?,
+            });
+        }
+        for section in field(&doc, "sections")?.as_array().unwrap_or(&[]) {
+            let lma = field(section, "lma")?;
+            let size = match string(section, "size")?.as_str() {
+                "linker" => LayoutSize::Linker,
+                "fixed" => LayoutSize::Fixed(number(section, "fixed_size")?),
+                "stack" => LayoutSize::Stack,
+                "fixed_stack" => LayoutSize::FixedStack(number(section, "fixed_size")?),
+                "heap" => LayoutSize::Heap,
+                other => return Err(invalid(format!("unknown section size {:?}", other))),
+            };
+            layout.sections.push(LayoutSection {
+                name: string(section, "name")?,
+                output: string(section, "output")?,
+                vma: string(section, "vma")?,
+                lma: if lma.is_null() {
+                    None
+                } else {
+                    Some(string(section, "lma")?)
+                },
+                priority: field(section, "priority")?
+                    .as_i64()
+                    .ok_or_else(|| invalid(String::from("field \"priority\" must be an integer")))?
+                    as i32,
+                nudge: match section.get("nudge") {
+                    Some(nudge) => nudge.as_i64().ok_or_else(|| {
+                        invalid(String::from("field \"nudge\" must be an integer"))
+                    })? as i32,
+                    None => 0,
+                },
+                group: match section.get("group") {
+                    Some(_) => number(section, "group")? as u32,
+                    None => 0,
+                },
+                size,
+                prefix: field(section, "prefix")? == Value::Bool(true),
+                compressed: section.get("compressed") == Some(&Value::Bool(true)),
+            });
+        }
+        Ok(layout)
+    }
+
+    /// Render the layout as JSON
+    pub fn to_json(&self) -> String {
+        let regions = self
+            .regions
+            .iter()
+            .map(|region| {
+                Value::Object(vec![
+                    (String::from("name"), region.name.as_str().into()),
+                    (String::from("origin"), region.origin.into()),
+                    (String::from("size"), region.size.into()),
+                ])
+            })
+            .collect();
+        let sections = self
+            .sections
+            .iter()
+            .map(|section| {
+                let (size, fixed_size) = match section.size {
+                    LayoutSize::Linker => ("linker", None),
+                    LayoutSize::Fixed(size) => ("fixed", Some(size)),
+                    LayoutSize::Stack => ("stack", None),
+                    LayoutSize::FixedStack(size) => ("fixed_stack", Some(size)),
+                    LayoutSize::Heap => ("heap", None),
+                };
+                let mut members = vec![
+                    (String::from("name"), section.name.as_str().into()),
+                    (String::from("output"), section.output.as_str().into()),
+                    (String::from("vma"), section.vma.as_str().into()),
+                    (String::from("lma"), section.lma.clone().into()),
+                    (String::from("priority"), section.priority.into()),
+                    (String::from("nudge"), section.nudge.into()),
+                    (String::from("group"), u64::from(section.group).into()),
+                    (String::from("size"), size.into()),
+                    (String::from("prefix"), section.prefix.into()),
+                ];
+                if let Some(fixed_size) = fixed_size {
+                    members.push((String::from("fixed_size"), fixed_size.into()));
+                }
+                if section.compressed {
+                    members.push((String::from("compressed"), true.into()));
+                }
+                Value::Object(members)
+            })
+            .collect();
+        let doc = Value::Object(vec![
+            (String::from("regions"), Value::Array(regions)),
+            (String::from("sections"), Value::Array(sections)),
+        ]);
+        format!("{}\n", doc)
+    }
+}
+
+/// A table of the regions, then of the sections in placement order with
+/// their priority, size mode and regions
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:<24} {:>10} {:>10}", "region", "origin", "size")?;
+        for region in self.regions.iter() {
+            writeln!(
+                f,
+                "{:<24} {:#010X} {:>10}",
+                region.name,
+                region.origin,
+                format!("{:#X}", region.size)
+            )?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:<24} {:>11} {:<14} {:<16} lma",
+            "section", "priority", "size", "vma"
+        )?;
+        for section in self.sections.iter() {
+            let note = if section.compressed {
+                " (compressed)"
+            } else {
+                ""
+            };
+            writeln!(
+                f,
+                "{:<24} {:>11} {:<14} {:<16} {}{}",
+                section.output,
+                section.priority,
+                section.size.to_string(),
+                section.vma,
+                section.lma.as_deref().unwrap_or("-"),
+                note
+            )?;
+        }
+        Ok(())
+    }
+}
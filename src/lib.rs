@@ -1,33 +1,125 @@
+//! Generates linker scripts and reset functions at build time
+//! by building a description of the memory regions and sections in Rust.
+//!
+//! Furthermore support safer usage of memory regions by allowing for
+//! a double linking technique in cortex-m-rt-ld which ensures stack
+//! and heap overflows cause hardware exceptions rather than overwriting
+//! static data.
+//!
+//! Based on ideas from Jorge Aparicio
+//! * https://github.com/rust-embedded/cortex-m-rt/issues/164
+//! * https://github.com/japaric/cortex-m-rt-ld
+
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::error::Error;
 use std::fmt::{self, Display, UpperHex};
 use std::fs::File;
 use std::io::Write;
+use std::path::Path;
 
+pub mod audit;
+pub mod builder;
+pub mod chip;
+pub mod compat;
+pub mod compress;
+pub mod config;
+pub mod device;
+pub mod diff;
+pub mod dual;
+pub mod elf;
+pub mod factory;
 mod generate;
+pub mod hex;
+pub mod hot;
+pub mod image;
+mod json;
+pub mod layout;
+pub mod lint;
+pub mod map;
+pub mod preset;
+pub mod project;
+pub mod script;
+pub mod selftest;
+pub mod sign;
+pub mod size;
+pub mod stack;
+mod toml;
+pub mod uf2;
 
-/// Generates linker scripts and reset functions at build time
-/// by building a description of the memory regions and sections in Rust.
-///
-/// Furthermore support safer usage of memory regions by allowing for
-/// a double linking technique in cortex-m-rt-ld which ensures stack
-/// and heap overflows cause hardware exceptions rather than overwriting
-/// static data.
+/// Machine word trait, used for alignment, templating, and sizing
 ///
-/// Based on ideas from Jorge Aparicio
-/// * https://github.com/rust-embedded/cortex-m-rt/issues/164
-/// * https://github.com/japaric/cortex-m-rt-ld
+/// The arithmetic helpers return `None` rather than wrapping when the
+/// result doesn't fit in the word.
+pub trait Word: UpperHex + Clone + Display + Sized + Copy + Into<u64> + TryFrom<u64> {
+    /// `self + rhs`, such as an origin plus an offset
+    fn checked_add(self, rhs: Self) -> Option<Self> {
+        let sum = self.into().checked_add(rhs.into())?;
+        Self::try_from(sum).ok()
+    }
 
-/// Machine word trait, used for alignment, templating, and sizing
-pub trait Word: UpperHex + Clone + Display + Sized + Copy {}
+    /// `self - rhs`, such as the distance between two addresses
+    fn checked_sub(self, rhs: Self) -> Option<Self> {
+        let difference = self.into().checked_sub(rhs.into())?;
+        Self::try_from(difference).ok()
+    }
+
+    /// `self` rounded up to a multiple of `align`, a power of two
+    fn align_up(self, align: Self) -> Option<Self> {
+        let align: u64 = align.into();
+        if !align.is_power_of_two() {
+            return None;
+        }
+        let aligned = self.into().checked_add(align - 1)? & !(align - 1);
+        Self::try_from(aligned).ok()
+    }
+}
 impl Word for u32 {}
 impl Word for u64 {}
 
+/// Size of the i.MX RT image vector table
+const IVT_SIZE: u64 = 0x20;
+
+/// Size of a GNU build ID note with a SHA-1 descriptor: the name and
+/// descriptor sizes, the type, "GNU\0" and 20 bytes
+const BUILD_ID_SIZE: u64 = 36;
+
+/// Size of the version string section, NUL padded
+const VERSION_SIZE: u64 = 64;
+
+/// Input sections of LLVM's profile runtime written while running
+const PROFILE_COUNTERS: [&str; 3] = ["__llvm_prf_cnts", "__llvm_prf_bits", "__llvm_prf_vnds"];
+
+/// Input sections of LLVM's profile runtime and GCC's gcov which are only
+/// read
+const PROFILE_DATA: [&str; 3] = ["__llvm_prf_data", "__llvm_prf_names", ".gcov_info"];
+
 /// Commonly used FLASH region name
-pub const FLASH: &'static str = "FLASH";
+pub const FLASH: &str = "FLASH";
 
 /// Commonly used RAM region name
-pub const RAM: &'static str = "RAM";
+pub const RAM: &str = "RAM";
+
+/// Percent of a region fixed and minimum sizes may take before the region
+/// is linted as nearly full
+pub const NEARLY_FULL_PERCENT: u64 = 90;
+
+/// Smallest stack accepted, in bytes
+///
+/// Exception entry alone stacks up to 104 bytes with the FPU enabled, so a
+/// smaller stack can't run even the simplest handler.
+pub const MIN_STACK_SIZE: u64 = 256;
+
+/// The AAPCS requires the stack pointer to be 8 byte aligned at public
+/// interfaces
+const STACK_ALIGN: u64 = 8;
+
+/// The smallest region the Cortex-M7 MPU protects, in bytes
+const MIN_GUARD_GAP: u64 = 32;
+
+/// Stack protector canary unless another is given, a terminator canary of
+/// the bytes ending string copies and reads: NUL, CR, LF and 0xFF
+pub const STACK_CANARY: u64 = 0xFF0A_0D00;
 
 /// An ID given to a region
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -37,41 +129,428 @@ pub struct RegionID(String);
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct SectionID(String);
 
+/// Where a section is placed in its region, relative to the others
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Priority {
+    /// Placed by the value, lower values are placed first
+    Value(i32),
+    /// Placed right before another section
+    Before(SectionID),
+    /// Placed right after another section
+    After(SectionID),
+}
+
 /// LinkerError union type
 #[derive(Debug)]
 pub enum LinkerError {
-    UnknownVMA(RegionID),
-    UnknownLMA(RegionID),
+    /// A section runs from a region which was never added
+    UnknownVMA {
+        section: String,
+        region: RegionID,
+        known: Vec<String>,
+    },
+    /// A section is loaded from a region which was never added
+    UnknownLMA {
+        section: String,
+        region: RegionID,
+        known: Vec<String>,
+    },
+    /// A fixed size section is larger than the region it is placed in
+    SectionTooLarge {
+        section: String,
+        region: String,
+        size: u64,
+        available: u64,
+    },
     DuplicateRegion(String),
     DuplicateSection(String),
     MissingSection(String),
+    UnknownSection(SectionID),
+    UnknownRegion(RegionID),
+    InvalidElf(String),
+    InvalidLayout(String),
+    InvalidConfig(String),
+    InvalidScript(String),
+    InvalidImage(String),
+    InvalidDevice(String),
+    /// Encrypted regions the family's engine can't decrypt, or a section
+    /// which must stay plaintext in one
+    InvalidEncryption(String),
+    /// A secondary image the family's boot ROM can't boot
+    InvalidDualImage(String),
+    /// A section which can't be stored compressed
+    InvalidCompression(String),
+    /// A section which can't be copied by eDMA at reset
+    InvalidDmaInit(String),
+    /// Sections the second core can't be handed, or a part without one
+    InvalidDualCore(String),
+    /// A tiny script uses a feature tiny scripts leave out
+    NotTiny(String),
+    /// A section is placed in a region whose access doesn't allow it
+    InvalidAccess(String),
+    /// A section's view is not another view of the memory it runs from
+    InvalidView(String),
+    /// A constant's name is not an identifier or is already defined
+    InvalidSymbol(String),
+    /// A section can't spill into another region
+    InvalidSpill(String),
+    /// A section can't be loaded from the address given
+    InvalidLoadAddress(String),
+    /// A section copied from a load region at reset is given the NOLOAD
+    /// type, which would leave it uninitialized
+    LoadedNoload {
+        section: String,
+        lma: String,
+    },
+    /// A range can't be excluded from the heap and stack
+    InvalidExclusion(String),
+    /// A region can't be split into the sub-regions given
+    InvalidSplit(String),
+    /// A region placed relative to another ends past the largest address
+    AddressOverflow(String),
+    /// A region already has a heap taking its remaining space
+    InvalidHeap(String),
+    /// The start of a region can't be reserved
+    InvalidReservation(String),
+    /// A stack alignment is not a power of two of at least 8 bytes
+    InvalidStackAlignment(u64),
+    /// A stack guard gap is not a power of two of at least 32 bytes, the
+    /// smallest MPU region
+    InvalidGuardGap(u64),
+    /// The stub program didn't build or link against the generated files
+    SelfTest(String),
     IoError(std::io::Error),
+    /// The script is strict and lints were found
+    Lints(Vec<lint::Warning>),
+    /// The FCB is not where the family's boot ROM reads it, the address is
+    /// `None` when it can't be known before linking
+    BootOffset {
+        section: String,
+        family: chip::Family,
+        expected: u64,
+        actual: Option<u64>,
+    },
+    /// A method needs the chip family, which isn't set
+    MissingFamily(String),
+    /// The family's boot ROM can't boot from this FlexSPI controller
+    UnsupportedFlexSpi {
+        family: chip::Family,
+        flexspi: chip::FlexSpi,
+    },
+    /// The boot ROM can't reach the vector table at its VMA when the image
+    /// starts in this boot mode
+    UnreachableVectorTable {
+        mode: chip::BootMode,
+        vma: String,
+        lma: Option<String>,
+    },
+    /// A region has no bytes
+    ZeroSizeRegion(String),
+    /// A fixed size section has no bytes
+    ZeroSizeSection(String),
+    /// The space for the stack is smaller than `MIN_STACK_SIZE`
+    StackTooSmall {
+        section: String,
+        region: String,
+        size: u64,
+    },
+    /// A region both images of a project declare differs between them
+    RegionMismatch(String),
+    /// The bootloader and application of a project are stored in
+    /// overlapping regions
+    ImagesOverlap {
+        bootloader: String,
+        app: String,
+    },
+    /// The boot ROM loads the image into a region starting in memory it
+    /// reserves while booting
+    ReservedMemory {
+        region: String,
+        reservation: String,
+    },
+    /// A section is placed in a region only declared, which the image
+    /// must not use
+    ReservedRegion {
+        section: String,
+        region: String,
+    },
+    /// A project's slot overlaps a region another image is stored in
+    SlotOverlap {
+        slot: String,
+        region: String,
+    },
+    /// Writing a generated file failed
+    File {
+        path: String,
+        source: std::io::Error,
+    },
 }
 
 impl fmt::Display for LinkerError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            LinkerError::UnknownVMA(ref region_id) => {
-                write!(f, "Region with ID {:?} used as VMA is unknown", region_id)
-            }
-            LinkerError::UnknownLMA(ref region_id) => {
-                write!(f, "Region with ID {:?} used as LMA is unknown", region_id)
-            }
+            LinkerError::UnknownVMA {
+                ref section,
+                ref region,
+                ref known,
+            } => write!(
+                f,
+                "Section {:?} runs from region {:?} which is unknown, add it with region() first; known regions are {}",
+                section,
+                region.0,
+                known.join(", ")
+            ),
+            LinkerError::UnknownLMA {
+                ref section,
+                ref region,
+                ref known,
+            } => write!(
+                f,
+                "Section {:?} is loaded from region {:?} which is unknown, add it with region() first; known regions are {}",
+                section,
+                region.0,
+                known.join(", ")
+            ),
+            LinkerError::SectionTooLarge {
+                ref section,
+                ref region,
+                size,
+                available,
+            } => write!(
+                f,
+                "Section {:?} is {:#X} bytes but region {:?} is only {:#X} bytes, reduce the section or grow the region",
+                section, size, region, available
+            ),
             LinkerError::DuplicateRegion(ref name) => {
                 write!(f, "Duplicate region, {:?} already defined", name)
             }
-            LinkerError::DuplicateSection(ref name) => {
-                write!(f, "Duplicate section, {:?} already defined", name)
-            }
+            LinkerError::DuplicateSection(ref name) => write!(
+                f,
+                "Duplicate section, {:?} already defined; sections of the same kind in other regions must be prefixed",
+                name
+            ),
             LinkerError::MissingSection(ref name) => {
                 write!(f, "Missing required section {:?}", name)
             }
+            LinkerError::UnknownSection(ref section_id) => {
+                write!(f, "Section with ID {:?} is unknown", section_id)
+            }
+            LinkerError::UnknownRegion(ref region_id) => {
+                write!(f, "Region with ID {:?} is unknown", region_id)
+            }
+            LinkerError::InvalidElf(ref reason) => write!(f, "Invalid ELF file, {}", reason),
+            LinkerError::InvalidLayout(ref reason) => write!(f, "Invalid layout, {}", reason),
+            LinkerError::InvalidConfig(ref reason) => write!(f, "Invalid config, {}", reason),
+            LinkerError::InvalidScript(ref reason) => {
+                write!(f, "Invalid linker script, {}", reason)
+            }
+            LinkerError::InvalidImage(ref reason) => write!(f, "Invalid image, {}", reason),
+            LinkerError::InvalidDevice(ref reason) => {
+                write!(f, "Invalid interrupt list, {}", reason)
+            }
+            LinkerError::InvalidEncryption(ref reason) => {
+                write!(f, "Invalid encrypted regions, {}", reason)
+            }
+            LinkerError::InvalidDualImage(ref reason) => {
+                write!(f, "Invalid secondary image, {}", reason)
+            }
+            LinkerError::InvalidCompression(ref reason) => {
+                write!(f, "Invalid compressed section, {}", reason)
+            }
+            LinkerError::InvalidDmaInit(ref reason) => {
+                write!(f, "Invalid DMA initialized section, {}", reason)
+            }
+            LinkerError::InvalidDualCore(ref reason) => {
+                write!(f, "Invalid second core initialization, {}", reason)
+            }
+            LinkerError::NotTiny(ref feature) => {
+                write!(f, "Tiny scripts have no {}", feature)
+            }
+            LinkerError::InvalidAccess(ref reason) => {
+                write!(f, "Invalid region access, {}", reason)
+            }
+            LinkerError::InvalidView(ref reason) => {
+                write!(f, "Invalid section view, {}", reason)
+            }
+            LinkerError::InvalidSymbol(ref reason) => write!(f, "Invalid symbol, {}", reason),
+            LinkerError::InvalidSpill(ref reason) => {
+                write!(f, "Invalid spill section, {}", reason)
+            }
+            LinkerError::InvalidLoadAddress(ref reason) => {
+                write!(f, "Invalid load address, {}", reason)
+            }
+            LinkerError::LoadedNoload {
+                ref section,
+                ref lma,
+            } => write!(
+                f,
+                "Section {:?} is loaded from {:?}, which NOLOAD would leave uninitialized",
+                section, lma
+            ),
+            LinkerError::InvalidExclusion(ref reason) => {
+                write!(f, "Invalid exclusion, {}", reason)
+            }
+            LinkerError::InvalidSplit(ref reason) => write!(f, "Invalid region split, {}", reason),
+            LinkerError::AddressOverflow(ref name) => {
+                write!(f, "Region {} ends past the largest address", name)
+            }
+            LinkerError::InvalidHeap(ref reason) => write!(f, "Invalid heap, {}", reason),
+            LinkerError::InvalidReservation(ref reason) => {
+                write!(f, "Invalid reservation, {}", reason)
+            }
+            LinkerError::InvalidGuardGap(size) => write!(
+                f,
+                "Stack guard gap {:#X} is not a power of two of at least {} bytes",
+                size, MIN_GUARD_GAP
+            ),
+            LinkerError::InvalidStackAlignment(align) => write!(
+                f,
+                "Stack alignment {:#X} is not a power of two of at least {} bytes",
+                align, STACK_ALIGN
+            ),
+            LinkerError::SelfTest(ref output) => {
+                write!(f, "The generated runtime doesn't link, {}", output)
+            }
             LinkerError::IoError(ref err) => write!(f, "{:?}", err),
+            LinkerError::Lints(ref warnings) => {
+                write!(f, "Lints denied by strict mode")?;
+                for warning in warnings.iter() {
+                    write!(f, "; {}", warning)?;
+                }
+                Ok(())
+            }
+            LinkerError::BootOffset {
+                ref section,
+                family,
+                expected,
+                actual: Some(actual),
+            } => write!(
+                f,
+                "Section {:?} is at {:#010X} but the {} boot ROM reads it at {:#010X}; place a {:#X} byte boot_config before it or move the flash region",
+                section,
+                actual,
+                family,
+                expected,
+                expected.wrapping_sub(actual)
+            ),
+            LinkerError::BootOffset {
+                ref section,
+                family,
+                expected,
+                actual: None,
+            } => write!(
+                f,
+                "Section {:?} must be at {:#010X} for the {} boot ROM, but only fixed size sections may precede it",
+                section, expected, family
+            ),
+            LinkerError::MissingFamily(ref needed_by) => write!(
+                f,
+                "{} needs the chip family, set it first",
+                needed_by
+            ),
+            LinkerError::UnsupportedFlexSpi { family, flexspi } => write!(
+                f,
+                "The {} boot ROM can't boot from {}",
+                family, flexspi
+            ),
+            LinkerError::UnreachableVectorTable {
+                mode,
+                ref vma,
+                lma: Some(ref lma),
+            } => write!(
+                f,
+                "In {} boot the vector table is read from {} before it is loaded from {}; place it with no load region",
+                mode, vma, lma
+            ),
+            LinkerError::UnreachableVectorTable {
+                mode: chip::BootMode::Xip,
+                ref vma,
+                lma: None,
+            } => write!(
+                f,
+                "In xip boot the vector table must be in flash, not {}; place it in the flash region",
+                vma
+            ),
+            LinkerError::UnreachableVectorTable {
+                mode: mode @ chip::BootMode::RamLoad,
+                ref vma,
+                lma: None,
+            }
+            | LinkerError::UnreachableVectorTable {
+                mode: mode @ chip::BootMode::SerialDownload,
+                ref vma,
+                lma: None,
+            } => write!(
+                f,
+                "In {} boot the image runs from RAM, not {}; place the vector table in the RAM the image is loaded to",
+                mode, vma
+            ),
+            LinkerError::ZeroSizeRegion(ref name) => {
+                write!(f, "Region {:?} has a size of zero", name)
+            }
+            LinkerError::ZeroSizeSection(ref name) => {
+                write!(f, "Fixed size section {:?} has a size of zero", name)
+            }
+            LinkerError::StackTooSmall {
+                ref section,
+                ref region,
+                size,
+            } => write!(
+                f,
+                "Stack {:?} in region {:?} has at most {} bytes, less than the minimum of {} bytes",
+                section, region, size, MIN_STACK_SIZE
+            ),
+            LinkerError::RegionMismatch(ref name) => write!(
+                f,
+                "Region {:?} has a different origin or size in the bootloader and the application",
+                name
+            ),
+            LinkerError::ImagesOverlap {
+                ref bootloader,
+                ref app,
+            } => write!(
+                f,
+                "The bootloader is stored in {:?}, which overlaps {:?} the application is stored in",
+                bootloader, app
+            ),
+            LinkerError::ReservedMemory {
+                ref region,
+                ref reservation,
+            } => write!(
+                f,
+                "Region {:?} starts in {}, which the boot ROM uses while loading the image into it",
+                region, reservation
+            ),
+            LinkerError::ReservedRegion {
+                ref section,
+                ref region,
+            } => write!(
+                f,
+                "Section {:?} is placed in {:?}, which is reserved for something other than the image",
+                section, region
+            ),
+            LinkerError::SlotOverlap {
+                ref slot,
+                ref region,
+            } => write!(
+                f,
+                "Slot {:?} overlaps {:?}, which another image is stored in",
+                slot, region
+            ),
+            LinkerError::File { ref path, .. } => write!(f, "Failed to write {}", path),
         }
     }
 }
 
-impl Error for LinkerError {}
+impl Error for LinkerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            LinkerError::IoError(ref err) => Some(err),
+            LinkerError::File { ref source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
 
 impl From<std::io::Error> for LinkerError {
     fn from(error: std::io::Error) -> Self {
@@ -82,6 +561,48 @@ impl From<std::io::Error> for LinkerError {
 /// Result type alias
 type Result<T> = std::result::Result<T, LinkerError>;
 
+/// Create a generated file, with its path in the error
+fn create<P: AsRef<Path>>(path: P) -> Result<File> {
+    File::create(&path).map_err(|source| LinkerError::File {
+        path: path.as_ref().display().to_string(),
+        source,
+    })
+}
+
+/// Write a generated file, with its path in the error
+fn write_file<P: AsRef<Path>>(path: P, contents: &[u8]) -> Result<()> {
+    create(&path)?
+        .write_all(contents)
+        .map_err(|source| LinkerError::File {
+            path: path.as_ref().display().to_string(),
+            source,
+        })
+}
+
+/// Describe the package whose build script runs, as its name, version and
+/// short git hash
+///
+/// Cargo gives build scripts the name and version. The hash is left out
+/// when the package isn't in a git checkout or git isn't installed.
+pub fn package_version() -> String {
+    let mut parts: Vec<String> = ["CARGO_PKG_NAME", "CARGO_PKG_VERSION"]
+        .iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .collect();
+    let mut git = std::process::Command::new("git");
+    git.args(["rev-parse", "--short", "HEAD"]);
+    if let Some(dir) = std::env::var_os("CARGO_MANIFEST_DIR") {
+        git.current_dir(dir);
+    }
+    if let Ok(output) = git.output() {
+        let hash = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if output.status.success() && !hash.is_empty() {
+            parts.push(hash);
+        }
+    }
+    parts.join(" ")
+}
+
 /// SectionSize describes the way in which a section should be sized
 /// which maybe be linker, fixed, stack, or heap.
 #[derive(Debug, Clone)]
@@ -97,6 +618,11 @@ enum SectionSize<W: Word> {
     /// end of the space
     Stack,
 
+    /// A stack of a fixed size, placed by its priority like other sections
+    /// rather than at the end of the region. The start of the stack is at
+    /// the end of the space.
+    FixedStack(W),
+
     /// Heap sizing will take the remaining regions space. If both a
     /// stack and heap are assigned to the same region they will overlap.
     /// The start and end of the section will start at the lower address
@@ -115,6 +641,10 @@ struct Section<W: Word> {
     /// of a region in order of their priority.
     priority: i32,
 
+    /// Order among the sections of the same priority, placing a section
+    /// right before or after another
+    nudge: i32,
+
     /// Name given to region
     name: String,
 
@@ -136,105 +666,448 @@ struct Section<W: Word> {
 
     /// Linker template preamble if needed (vector table needs this)
     linker_preamble: Option<String>,
+
+    /// Smallest acceptable size of a stack or heap section, checked
+    /// by the linker once the remaining region space is known
+    min_size: Option<W>,
+
+    /// Input sections are wrapped in KEEP so `--gc-sections` can't drop them
+    keep: bool,
+
+    /// Names of the input sections when they aren't the output section
+    /// name, for example "linkme_HANDLERS" which has no leading '.'
+    inputs: Vec<String>,
+
+    /// Stored LZ4 compressed in its load region and decompressed at reset
+    compressed: bool,
+
+    /// Transfer control descriptor of the eDMA channel copying the section
+    /// at reset, rather than the CPU
+    dma_tcd: Option<u64>,
+
+    /// Zeroed by the second core of a dual-core part, rather than by the
+    /// primary core's reset
+    second_core: bool,
+
+    /// Aliases of the run region the section's symbols are also exported
+    /// in
+    views: Vec<RegionID>,
+
+    /// Output name of the section whose input sections this one takes
+    /// when they don't fit
+    spill_of: Option<String>,
+
+    /// Patterns of the files whose input sections are taken, all files
+    /// when empty
+    files: Vec<String>,
+
+    /// Largest size of a linker sized section, asserted by the linker
+    max_size: Option<W>,
+
+    /// Exact load address in the load region, rather than the next free
+    /// one
+    load_at: Option<W>,
+
+    /// Byte filling the gaps the input sections leave, such as alignment
+    /// and fixed size padding
+    fill: Option<u8>,
+
+    /// Output section of type NOLOAD, which takes space in its region but
+    /// nothing in the image
+    noload: bool,
 }
 
 impl<W: Word> Section<W> {
-    fn heap(vma: RegionID) -> Self {
+    /// A section without a prefix, preamble, or other attributes
+    fn new(
+        name: &str,
+        priority: i32,
+        size: SectionSize<W>,
+        vma: RegionID,
+        lma: Option<RegionID>,
+    ) -> Self {
         Section {
-            priority: i32::max_value(),
-            size: SectionSize::Heap,
+            priority,
+            nudge: 0,
+            name: String::from(name),
+            vma,
+            lma,
+            size,
             prefix: false,
-            name: String::from("heap"),
-            vma: vma,
-            lma: None,
             linker_preamble: None,
+            min_size: None,
+            keep: false,
+            inputs: Vec::new(),
+            compressed: false,
+            dma_tcd: None,
+            second_core: false,
+            views: Vec::new(),
+            spill_of: None,
+            files: Vec::new(),
+            max_size: None,
+            load_at: None,
+            fill: None,
+            noload: false,
         }
     }
 
+    fn heap(name: &str, vma: RegionID) -> Self {
+        Section::new(name, i32::MAX, SectionSize::Heap, vma, None)
+    }
+
     fn stack(vma: RegionID) -> Self {
+        Section::new("stack", i32::MAX - 1, SectionSize::Stack, vma, None)
+    }
+
+    fn stack_below_statics(size: W, vma: RegionID) -> Self {
+        Section::new("stack", i32::MIN, SectionSize::FixedStack(size), vma, None)
+    }
+
+    /// Placed after the statics, below a stack taking the rest of the
+    /// region
+    fn process_stack(vma: RegionID, size: W) -> Self {
+        Section::new(
+            "process_stack",
+            i32::MAX - 2,
+            SectionSize::FixedStack(size),
+            vma,
+            None,
+        )
+    }
+
+    fn boot_config(size: W, name: &str, vma: RegionID) -> Self {
         Section {
-            priority: i32::max_value() - 1,
-            size: SectionSize::Stack,
-            prefix: false,
-            name: String::from("stack"),
-            vma: vma,
-            lma: None,
-            linker_preamble: None,
+            keep: true,
+            ..Section::new(name, -1, SectionSize::Fixed(size), vma, None)
         }
     }
 
-    fn boot_config(size: W, name: &str, vma: RegionID) -> Self {
+    /// A fixed size section holding the input sections named like it
+    fn kept(name: &str, priority: i32, size: W, vma: RegionID) -> Self {
         Section {
-            priority: -1,
-            size: SectionSize::Fixed(size),
-            prefix: false,
-            name: String::from(name),
-            vma: vma,
-            lma: None,
-            linker_preamble: None,
+            keep: true,
+            ..Section::new(name, priority, SectionSize::Fixed(size), vma, None)
+        }
+    }
+
+    /// The linker's build ID note, padded to the size of a SHA-1 note
+    fn build_id(vma: RegionID) -> Self {
+        let size =
+            W::try_from(BUILD_ID_SIZE).unwrap_or_else(|_| unreachable!("36 fits in any word"));
+        Section {
+            linker_preamble: Some(String::from("KEEP(*(.note.gnu.build-id));")),
+            keep: true,
+            ..Section::new("build_id", -1, SectionSize::Fixed(size), vma, None)
         }
     }
 
+    /// The generated version string
+    fn version(vma: RegionID) -> Self {
+        let size =
+            W::try_from(VERSION_SIZE).unwrap_or_else(|_| unreachable!("64 fits in any word"));
+        Section::kept("version", -1, size, vma)
+    }
+
     fn vector_table(vma: RegionID, lma: Option<RegionID>) -> Self {
         Section {
-            priority: 0,
-            size: SectionSize::Linker,
-            prefix: false,
-            name: String::from("vector_table"),
-            vma: vma,
-            lma: lma,
             linker_preamble: Some(String::from("LONG(__start_stack);")),
+            keep: true,
+            ..Section::new("vector_table", 0, SectionSize::Linker, vma, lma)
         }
     }
 
     fn text(vma: RegionID, lma: Option<RegionID>) -> Self {
-        Section {
-            priority: 1,
-            size: SectionSize::Linker,
-            prefix: false,
-            name: String::from("text"),
-            vma: vma,
-            lma: lma,
-            linker_preamble: None,
-        }
+        Section::new("text", 1, SectionSize::Linker, vma, lma)
     }
 
     fn data(prefix: bool, vma: RegionID, lma: Option<RegionID>) -> Self {
         let priority = if prefix { 102 } else { 2 };
         Section {
-            priority: priority,
-            size: SectionSize::Linker,
-            prefix: prefix,
-            name: String::from("data"),
-            vma: vma,
-            lma: lma,
-            linker_preamble: None,
+            prefix,
+            ..Section::new("data", priority, SectionSize::Linker, vma, lma)
         }
     }
 
     fn rodata(prefix: bool, vma: RegionID, lma: Option<RegionID>) -> Self {
         let priority = if prefix { 103 } else { 3 };
         Section {
-            priority: priority,
-            size: SectionSize::Linker,
-            prefix: prefix,
-            name: String::from("rodata"),
-            vma: vma,
-            lma: lma,
-            linker_preamble: None,
+            prefix,
+            ..Section::new("rodata", priority, SectionSize::Linker, vma, lma)
         }
     }
 
     fn bss(prefix: bool, vma: RegionID, lma: Option<RegionID>) -> Self {
         let priority = if prefix { 104 } else { 4 };
         Section {
-            priority: priority,
-            size: SectionSize::Linker,
-            prefix: prefix,
-            name: String::from("bss"),
-            vma: vma,
-            lma: lma,
-            linker_preamble: None,
+            prefix,
+            ..Section::new("bss", priority, SectionSize::Linker, vma, lma)
+        }
+    }
+
+    fn uninit(vma: RegionID) -> Self {
+        Section {
+            noload: true,
+            ..Section::new("uninit", 5, SectionSize::Linker, vma, None)
+        }
+    }
+
+    /// Unwind tables, the ARM EHABI index and table and the DWARF frame
+    /// information with its lookup header
+    fn unwind_tables(vma: RegionID) -> Vec<Self> {
+        ["ARM.extab", "ARM.exidx", "eh_frame_hdr", "eh_frame"]
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| {
+                Section::new(name, 5 + idx as i32, SectionSize::Linker, vma.clone(), None)
+            })
+            .collect()
+    }
+
+    /// The TLS image, `.tdata` and `.tbss` read by the reset, and the main
+    /// thread's TLS block, `.tls`, sized by the linker to hold them
+    ///
+    /// `.tbss` takes no space, the linker places it after `.tdata` like the
+    /// TLS segment. Both follow `.rodata`, and `.tls` follows `.bss`.
+    fn thread_local(template: RegionID, vma: RegionID) -> Vec<Self> {
+        vec![
+            Section {
+                nudge: 1,
+                inputs: vec![String::from(".tdata")],
+                ..Section::new("tdata", 3, SectionSize::Linker, template.clone(), None)
+            },
+            Section {
+                nudge: 2,
+                inputs: vec![String::from(".tbss")],
+                ..Section::new("tbss", 3, SectionSize::Linker, template, None)
+            },
+            Section {
+                nudge: 1,
+                linker_preamble: Some(String::from(". += __end_tbss - __start_tdata;")),
+                ..Section::new("tls", 4, SectionSize::Linker, vma, None)
+            },
+        ]
+    }
+
+    /// Global offset table of position independent code, copied to RAM
+    /// like data so that RWPI code may address it through its base register
+    fn got(vma: RegionID, lma: Option<RegionID>) -> Self {
+        Section::new("got", 2, SectionSize::Linker, vma, lma)
+    }
+
+    /// Dynamic relocations of a position independent executable
+    fn dynamic_relocations(vma: RegionID) -> Self {
+        Section::new("rel.dyn", 9, SectionSize::Linker, vma, None)
+    }
+
+    /// User registered sections which nothing refers to directly, such as
+    /// linkme's distributed slices, placed after the read only data
+    fn retained(input: &str, vma: RegionID, lma: Option<RegionID>) -> Self {
+        let name = input.strip_prefix('.').unwrap_or(input);
+        Section {
+            keep: true,
+            inputs: vec![String::from(input)],
+            ..Section::new(name, 10, SectionSize::Linker, vma, lma)
+        }
+    }
+
+    /// A section of the application's own holding the input sections named
+    /// like it, sized by the linker or taking `size` bytes
+    fn custom(
+        name: &str,
+        prefix: bool,
+        vma: RegionID,
+        lma: Option<RegionID>,
+        size: Option<W>,
+    ) -> Self {
+        let size = size.map_or(SectionSize::Linker, SectionSize::Fixed);
+        Section {
+            prefix,
+            ..Section::new(name, 10, size, vma, lma)
+        }
+    }
+
+    /// The IVT's DCD pointer is the DCD's address with a `dcd` of some
+    /// size, or null without
+    fn serial_download_ivt(vma: RegionID, dcd: Option<u64>) -> Self {
+        let size = W::try_from(chip::SERIAL_DOWNLOAD_IMAGE_OFFSET)
+            .unwrap_or_else(|_| unreachable!("0x1000 fits in any word"));
+        let mut preamble = vec![
+            format!(". = __start_ivt + {:#X};", chip::SERIAL_DOWNLOAD_IVT_OFFSET),
+            String::from("__ivt = .;"),
+            String::from("LONG(0x412000D1); /* header */"),
+            String::from("LONG(__start_vector_table); /* entry */"),
+            String::from("LONG(0);"),
+            String::from(match dcd {
+                Some(_) => "LONG(__dcd); /* DCD */",
+                None => "LONG(0); /* DCD */",
+            }),
+            String::from("LONG(__boot_data);"),
+            String::from("LONG(__ivt); /* self */"),
+            String::from("LONG(0); /* CSF */"),
+            String::from("LONG(0);"),
+            String::from("__boot_data = .;"),
+            String::from("LONG(__start_ivt); /* image start */"),
+            format!("LONG(LENGTH({})); /* image size */", vma.0),
+            String::from("LONG(0); /* plugin */"),
+        ];
+        if let Some(dcd) = dcd {
+            preamble.extend([
+                format!(". = __start_ivt + {:#X};", chip::SERIAL_DOWNLOAD_DCD_OFFSET),
+                String::from("__dcd = .;"),
+                String::from("KEEP(*(.dcd .dcd.*));"),
+                format!(
+                    "ASSERT(. - __dcd <= {}, \"The DCD is larger than {} bytes\");",
+                    dcd, dcd
+                ),
+            ]);
+        }
+        Section {
+            linker_preamble: Some(preamble.join("\n\t\t")),
+            keep: true,
+            ..Section::new("ivt", -2, SectionSize::Fixed(size), vma, None)
+        }
+    }
+
+    /// Addresses of functions, referring to them keeps them from
+    /// `--gc-sections`
+    fn jump_table(functions: &[&str], vma: RegionID) -> Self {
+        let entries: Vec<String> = functions.iter().map(|f| format!("LONG({});", f)).collect();
+        Section {
+            linker_preamble: Some(entries.join("\n\t\t")),
+            keep: true,
+            ..Section::new("jump_table", -1, SectionSize::Linker, vma, None)
+        }
+    }
+
+    /// The code of the files matching `files`, routed out of `.text` and
+    /// placed before it like the hot functions
+    fn text_partition(
+        files: &[&str],
+        budget: Option<W>,
+        vma: RegionID,
+        lma: Option<RegionID>,
+    ) -> Self {
+        Section {
+            prefix: true,
+            inputs: vec![String::from(".text")],
+            files: files.iter().map(|file| String::from(*file)).collect(),
+            max_size: budget,
+            ..Section::new("text", 0, SectionSize::Linker, vma, lma)
+        }
+    }
+
+    /// Functions routed out of `.text`, each compiled into a section of
+    /// its own by `-ffunction-sections`. Placed before `.text` so that its
+    /// wildcard doesn't collect them first.
+    fn hot_text(functions: &[&str], vma: RegionID, lma: Option<RegionID>) -> Self {
+        Section {
+            inputs: functions.iter().map(|f| format!(".text.{}", f)).collect(),
+            ..Section::new("hot_text", 0, SectionSize::Linker, vma, lma)
+        }
+    }
+
+    /// Functions marked to run from RAM, placed before `.text` like the
+    /// hot functions so that its wildcard doesn't collect `.text.ram`
+    fn text_ram(prefix: bool, vma: RegionID, lma: Option<RegionID>) -> Self {
+        Section {
+            prefix,
+            inputs: vec![String::from(".text.ram"), String::from(".ramfunc")],
+            ..Section::new("text_ram", 0, SectionSize::Linker, vma, lma)
+        }
+    }
+
+    /// Output section name, without the leading '.'
+    ///
+    /// Prefixed sections are named after their VMA region, for example
+    /// "TCM.bss", so that they do not collide with the unprefixed section.
+    fn output_name(&self) -> String {
+        if self.prefix {
+            format!("{}.{}", self.vma.0, self.name)
+        } else {
+            self.name.clone()
+        }
+    }
+
+    /// Name used in the `__start_*`, `__end_*`, and `__load_*` symbols
+    fn symbol_name(&self) -> String {
+        self.output_name().replace('.', "_")
+    }
+
+    /// True if the section holds code
+    fn is_code(&self) -> bool {
+        ["text", "hot_text", "text_ram"].contains(&self.name.as_str())
+    }
+
+    /// True if the section is written at run time
+    fn is_writable(&self) -> bool {
+        match self.size {
+            SectionSize::Linker => {
+                ["data", "bss", "got", "tls"].contains(&self.name.as_str())
+                    || PROFILE_COUNTERS.contains(&self.name.as_str())
+            }
+            SectionSize::Fixed(_) => false,
+            _ => true,
+        }
+    }
+
+    /// True if the section has contents in the image, rather than space
+    /// reserved at run time
+    fn has_contents(&self) -> bool {
+        if self.noload {
+            return false;
+        }
+        match self.size {
+            SectionSize::Linker => !["bss", "tbss", "tls"].contains(&self.name.as_str()),
+            SectionSize::Fixed(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// What a region's memory allows, declared on its MEMORY entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionKind {
+    /// Read only memory holding code and constants, `(rx)`
+    Flash,
+    /// Memory for code and data, `(rwx)`
+    Ram,
+    /// Memory for data which must not run code, `(rw!x)`
+    Data,
+}
+
+impl RegionKind {
+    /// The attributes of the MEMORY entry
+    fn attributes(self) -> &'static str {
+        match self {
+            RegionKind::Flash => "rx",
+            RegionKind::Ram => "rwx",
+            RegionKind::Data => "rw!x",
+        }
+    }
+}
+
+impl std::str::FromStr for RegionKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "flash" => Ok(RegionKind::Flash),
+            "ram" => Ok(RegionKind::Ram),
+            "data" => Ok(RegionKind::Data),
+            _ => Err(format!(
+                "unknown region kind {:?}, expected flash, ram or data",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for RegionKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegionKind::Flash => f.write_str("flash"),
+            RegionKind::Ram => f.write_str("ram"),
+            RegionKind::Data => f.write_str("data"),
         }
     }
 }
@@ -245,6 +1118,28 @@ struct Region<W: Word> {
     name: String,
     origin: W,
     size: W,
+
+    /// DMA may access the region, used by the generated predicates
+    dma: bool,
+
+    /// The region is decrypted on the fly by the family's engine
+    encrypted: bool,
+
+    /// The region whose memory this region is another view of
+    alias_of: Option<String>,
+
+    /// What the region's memory allows, no attributes when unset
+    kind: Option<RegionKind>,
+
+    /// Only declared, owned by another image, the ROM or hardware
+    reserved: bool,
+
+    /// Bytes at the start of the region sections aren't placed in, such
+    /// as a vendor bootloader's
+    skip: u64,
+
+    /// The region this region was carved out of, which it overlaps
+    carved_from: Option<String>,
 }
 
 /// LinkerScript is a buildable descriptor of memory regions,
@@ -253,10 +1148,93 @@ struct Region<W: Word> {
 ///
 /// A sparse mapping of each regions virtual memory and load memory sections is
 /// tracked.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LinkerScript<W: Word> {
     regions: HashMap<String, Region<W>>,
     sections: HashMap<String, Section<W>>,
+
+    /// Firmware may run from another address than it was linked at, load
+    /// addresses are emitted as offsets from their region
+    position_independent: bool,
+
+    /// Whether lints are errors or warnings
+    validation: lint::Validation,
+
+    /// Chip family the boot sections are checked against
+    family: Option<chip::Family>,
+
+    /// How the boot ROM starts the image
+    boot_mode: Option<chip::BootMode>,
+
+    /// FlexSPI controller the boot ROM reads flash through, the family's
+    /// default when `None`
+    flexspi: Option<chip::FlexSpi>,
+
+    /// Symbols at fixed addresses, outside of any section
+    symbols: Vec<(String, W)>,
+
+    /// Symbols holding build time values, also given to `regions.rs`
+    constants: Vec<(String, W)>,
+
+    /// Memory the boot ROM uses while booting, besides the family's
+    reservations: Vec<chip::Reservation>,
+
+    /// Ranges at the top of a region the heap and stack don't fill, such
+    /// as a DMA pool, by region
+    exclusions: Vec<(RegionID, chip::Reservation)>,
+
+    /// Alignment of both ends of the stack
+    stack_align: u64,
+
+    /// Bytes left unused between the statics and the stack, for an MPU
+    /// region catching overflows
+    guard_gap: Option<u64>,
+
+    /// Loaded sections are placed together, contiguous in their load
+    /// region, and copied by one loop
+    pack_loads: bool,
+
+    /// The reset copies and zeroes through the ARM EABI memory functions
+    /// rather than its own loops
+    aeabi_memcpy: bool,
+
+    /// The data cache is enabled before the reset copies sections
+    dcache_before_init: bool,
+
+    /// Address of the mailbox the primary core hands sections to zero to
+    /// the second core through
+    second_core_mailbox: Option<W>,
+
+    /// Only the features of the smallest startup are allowed
+    tiny: bool,
+
+    /// The reset module has a stub finding where the image runs
+    reset_stub: bool,
+
+    /// The script declares its program headers
+    program_headers: bool,
+
+    /// Canary the reset seeds `__stack_chk_guard` with, when C code is
+    /// built with the stack protector
+    stack_guard: Option<u64>,
+
+    /// Sections which must not be referenced by the others listed with
+    /// them
+    no_cross_refs: Vec<(SectionID, Vec<SectionID>)>,
+}
+
+/// The [`layout`](struct.LinkerScript.html#method.layout) as a table, to
+/// see how the regions and sections are placed
+impl<W: Word> fmt::Display for LinkerScript<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.layout().fmt(f)
+    }
+}
+
+impl<W: Word> Default for LinkerScript<W> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<W: Word> LinkerScript<W> {
@@ -265,6 +1243,26 @@ impl<W: Word> LinkerScript<W> {
         LinkerScript {
             regions: HashMap::new(),
             sections: HashMap::new(),
+            position_independent: false,
+            validation: lint::Validation::Permissive,
+            family: None,
+            boot_mode: None,
+            flexspi: None,
+            symbols: Vec::new(),
+            constants: Vec::new(),
+            reservations: Vec::new(),
+            exclusions: Vec::new(),
+            stack_align: STACK_ALIGN,
+            guard_gap: None,
+            pack_loads: false,
+            aeabi_memcpy: false,
+            dcache_before_init: false,
+            second_core_mailbox: None,
+            tiny: false,
+            reset_stub: false,
+            program_headers: false,
+            stack_guard: None,
+            no_cross_refs: Vec::new(),
         }
     }
 
@@ -274,29 +1272,409 @@ impl<W: Word> LinkerScript<W> {
         if self.regions.contains_key(&name) {
             return Err(LinkerError::DuplicateRegion(name.clone()));
         }
+        if size.into() == 0 {
+            return Err(LinkerError::ZeroSizeRegion(name));
+        }
         let region = Region {
             name: name.clone(),
-            origin: origin,
-            size: size,
+            origin,
+            size,
+            dma: false,
+            encrypted: false,
+            alias_of: None,
+            kind: None,
+            reserved: false,
+            skip: 0,
+            carved_from: None,
         };
         self.regions.insert(name.clone(), region);
         Ok(RegionID(name.clone()))
     }
 
-    /// Required stack location
+    /// Add a named memory region `offset` bytes past the origin of `base`
     ///
-    /// The stack goes from the top address in the region downward.
-    pub fn stack(&mut self, vma: RegionID) -> Result<SectionID> {
-        let section = Section::stack(vma);
-        self.add_section(section)
+    /// Fails with [`AddressOverflow`](enum.LinkerError.html) when the
+    /// region would end past the largest address of the word.
+    pub fn region_at(
+        &mut self,
+        name: &str,
+        base: &RegionID,
+        offset: W,
+        size: W,
+    ) -> Result<RegionID> {
+        let origin = self
+            .regions
+            .get(&base.0)
+            .ok_or_else(|| LinkerError::UnknownRegion(base.clone()))?
+            .origin;
+        let overflow = || LinkerError::AddressOverflow(String::from(name));
+        let origin = origin.checked_add(offset).ok_or_else(overflow)?;
+        let last = origin
+            .into()
+            .checked_add(size.into().saturating_sub(1))
+            .ok_or_else(overflow)?;
+        if W::try_from(last).is_err() {
+            return Err(overflow());
+        }
+        self.region(name, origin, size)
     }
 
-    /// Optional heap location and size
-    ///
-    /// Places the heap as the last section in a region with addresses
+    /// Add a named memory region right after the end of `prev`, such as
+    /// an application's flash after its bootloader's
+    pub fn region_after(&mut self, name: &str, prev: &RegionID, size: W) -> Result<RegionID> {
+        let offset = self.region_size(prev)?;
+        self.region_at(name, prev, offset, size)
+    }
+
+    /// The origin of `region`
+    pub fn region_origin(&self, region: &RegionID) -> Result<W> {
+        self.regions
+            .get(&region.0)
+            .map(|found| found.origin)
+            .ok_or_else(|| LinkerError::UnknownRegion(region.clone()))
+    }
+
+    /// The size of `region` in bytes
+    pub fn region_size(&self, region: &RegionID) -> Result<W> {
+        self.regions
+            .get(&region.0)
+            .map(|found| found.size)
+            .ok_or_else(|| LinkerError::UnknownRegion(region.clone()))
+    }
+
+    /// The ID of the region called `name`, such as one a
+    /// [preset](preset/index.html) added
+    pub fn region_id(&self, name: &str) -> Result<RegionID> {
+        let id = RegionID(String::from(name));
+        if self.regions.contains_key(name) {
+            Ok(id)
+        } else {
+            Err(LinkerError::UnknownRegion(id))
+        }
+    }
+
+    /// The ID of the section whose output section is called `name`,
+    /// without the leading dot
+    pub fn section_id(&self, name: &str) -> Result<SectionID> {
+        let id = SectionID(String::from(name));
+        if self.sections.contains_key(name) {
+            Ok(id)
+        } else {
+            Err(LinkerError::UnknownSection(id))
+        }
+    }
+
+    /// Declare a region owned by something other than the image, such as
+    /// another image, the boot ROM or a peripheral's buffer
+    ///
+    /// The region is in MEMORY, the [`layout`](#method.layout) and the
+    /// generated predicates like any other, documenting the memory map,
+    /// but adding a section to it, or one of its aliases, fails with
+    /// [`ReservedRegion`](enum.LinkerError.html).
+    pub fn reserve_region(&mut self, name: &str, origin: W, size: W) -> Result<RegionID> {
+        let id = self.region(name, origin, size)?;
+        self.regions.get_mut(&id.0).expect("just added").reserved = true;
+        Ok(id)
+    }
+
+    /// Add a named memory region of a [`kind`](#method.region_kind), such
+    /// as ITCM with `RegionKind::Flash`, declared `ITCM (rx)` in MEMORY
+    pub fn region_with_kind(
+        &mut self,
+        name: &str,
+        origin: W,
+        size: W,
+        kind: RegionKind,
+    ) -> Result<RegionID> {
+        let id = self.region(name, origin, size)?;
+        self.region_kind(&id, kind)?;
+        Ok(id)
+    }
+
+    /// Another view of a region's memory, at a different address
+    ///
+    /// For example, the i.MX RT's FlexSPI flash or OCRAM may be reached
+    /// through a cacheable and a non-cacheable address. Sections may be
+    /// placed in either view and are packed into the memory one after
+    /// another, so sections in different views never overlap. The alias
+    /// has the size of `of`.
+    pub fn alias(&mut self, name: &str, of: &RegionID, origin: W) -> Result<RegionID> {
+        let name = String::from(name);
+        if self.regions.contains_key(&name) {
+            return Err(LinkerError::DuplicateRegion(name));
+        }
+        let of = self
+            .regions
+            .get(&of.0)
+            .ok_or_else(|| LinkerError::UnknownRegion(of.clone()))?;
+        let region = Region {
+            name: name.clone(),
+            origin,
+            size: of.size,
+            dma: false,
+            encrypted: false,
+            alias_of: Some(String::from(self.physical(&of.name))),
+            kind: of.kind,
+            reserved: of.reserved,
+            skip: 0,
+            carved_from: None,
+        };
+        self.regions.insert(name.clone(), region);
+        Ok(RegionID(name))
+    }
+
+    /// Split `parent` into sub-regions, one after another from its origin,
+    /// such as `[("OCRAM_DMA", Some(64K)), ("OCRAM_GP", None)]`
+    ///
+    /// A part without a size takes the rest of the parent, and may be
+    /// given once. The sub-regions replace the parent, keeping whether DMA
+    /// may access it, its encryption and its kind, so sections are placed
+    /// in a sub-region rather than at an offset into the parent. Fails with
+    /// [`InvalidSplit`](enum.LinkerError.html) when the parts don't fit,
+    /// or the parent is an alias, has aliases, exclusions or sections.
+    pub fn split_region(
+        &mut self,
+        parent: &RegionID,
+        parts: &[(&str, Option<W>)],
+    ) -> Result<Vec<RegionID>> {
+        let found = self
+            .regions
+            .get(&parent.0)
+            .ok_or_else(|| LinkerError::UnknownRegion(parent.clone()))?
+            .clone();
+        let invalid = |reason: String| Err(LinkerError::InvalidSplit(reason));
+        if self.is_aliased(&found.name) {
+            return invalid(format!("{} is another view of memory", found.name));
+        }
+        let used = self
+            .sections
+            .values()
+            .any(|section| section.vma == *parent || section.lma.as_ref() == Some(parent))
+            || self.exclusions.iter().any(|(region, _)| region == parent)
+            || found.skip > 0;
+        if used {
+            return invalid(format!("{} is already used", found.name));
+        }
+        if parts.iter().filter(|(_, size)| size.is_none()).count() > 1 {
+            return invalid(String::from("only one part may take the rest"));
+        }
+        let size: u64 = found.size.into();
+        let sized: u64 = parts
+            .iter()
+            .filter_map(|(_, size)| *size)
+            .map(Into::into)
+            .sum();
+        if sized > size {
+            return invalid(format!(
+                "the parts take {:#X} bytes of {}, which has {:#X}",
+                sized, found.name, size
+            ));
+        }
+        for (name, _) in parts {
+            if *name != found.name && self.regions.contains_key(*name) {
+                return Err(LinkerError::DuplicateRegion(String::from(*name)));
+            }
+        }
+        let mut origin: u64 = found.origin.into();
+        let mut children = Vec::new();
+        for (name, part) in parts {
+            let part = part.map_or(size - sized, Into::into);
+            if part == 0 {
+                return Err(LinkerError::ZeroSizeRegion(String::from(*name)));
+            }
+            let word = |value: u64| {
+                W::try_from(value).unwrap_or_else(|_| unreachable!("addresses in the parent fit"))
+            };
+            children.push(Region {
+                name: String::from(*name),
+                origin: word(origin),
+                size: word(part),
+                ..found.clone()
+            });
+            origin += part;
+        }
+        self.regions.remove(&parent.0);
+        let mut ids = Vec::new();
+        for child in children {
+            ids.push(RegionID(child.name.clone()));
+            self.regions.insert(child.name.clone(), child);
+        }
+        Ok(ids)
+    }
+
+    /// Carve a sub-region of `size` bytes at `offset` out of `parent`,
+    /// which keeps the rest, for example a 64 KiB `NONCACHEABLE` window
+    /// out of OCRAM
+    ///
+    /// The sub-region keeps whether DMA may access the parent, its
+    /// encryption and its kind. A window at the start of the parent's
+    /// free space is [skipped](#method.reserve_start) by the parent,
+    /// otherwise [excluded](#method.exclude) from it, so its sections must
+    /// end below the window and its heap and stack stop there. Unlike
+    /// [`split_region`](#method.split_region) the parent may already
+    /// hold sections. Fails with [`InvalidSplit`](enum.LinkerError.html)
+    /// when the window isn't in the parent's free space, overlaps an
+    /// exclusion, or the parent is an alias.
+    pub fn carve_region(
+        &mut self,
+        parent: &RegionID,
+        name: &str,
+        offset: W,
+        size: W,
+    ) -> Result<RegionID> {
+        let found = self
+            .regions
+            .get(&parent.0)
+            .ok_or_else(|| LinkerError::UnknownRegion(parent.clone()))?
+            .clone();
+        let invalid = |reason: String| Err(LinkerError::InvalidSplit(reason));
+        if let Some(of) = &found.alias_of {
+            return invalid(format!(
+                "{} is a view of {}, carve {} instead",
+                found.name, of, of
+            ));
+        }
+        if self.regions.contains_key(name) {
+            return Err(LinkerError::DuplicateRegion(String::from(name)));
+        }
+        if size.into() == 0 {
+            return Err(LinkerError::ZeroSizeRegion(String::from(name)));
+        }
+        let (offset, bytes): (u64, u64) = (offset.into(), size.into());
+        if offset < found.skip || offset + bytes > found.size.into() {
+            return invalid(format!(
+                "{:#X} bytes at {:#X} are not in the free space of {}",
+                bytes, offset, found.name
+            ));
+        }
+        let origin = found.origin.into() + offset;
+        let overlapped = self
+            .excluded(&found.name)
+            .into_iter()
+            .find(|reserved| reserved.origin < origin + bytes && origin < reserved.end());
+        if let Some(reserved) = overlapped {
+            return invalid(format!("{} overlaps {}", name, reserved.name));
+        }
+        if offset == found.skip {
+            self.regions.get_mut(&parent.0).expect("checked above").skip += bytes;
+        } else {
+            self.exclusions.push((
+                parent.clone(),
+                chip::Reservation {
+                    name: String::from(name),
+                    origin,
+                    size: bytes,
+                },
+            ));
+        }
+        let region = Region {
+            name: String::from(name),
+            origin: W::try_from(origin)
+                .unwrap_or_else(|_| unreachable!("within the parent region")),
+            size,
+            skip: 0,
+            carved_from: Some(found.name.clone()),
+            ..found
+        };
+        self.regions.insert(String::from(name), region);
+        Ok(RegionID(String::from(name)))
+    }
+
+    /// Required stack location
+    ///
+    /// The stack goes from the top address in the region downward.
+    pub fn stack(&mut self, vma: RegionID) -> Result<SectionID> {
+        let section = Section::stack(vma);
+        self.add_section(section)
+    }
+
+    /// Stack location below the statics of a region, as flip-link does
+    ///
+    /// The stack takes `size` bytes from the origin of the region with the
+    /// data and bss sections placed above it, so that an overflow runs off
+    /// the bottom of the region and faults rather than corrupting statics.
+    /// The size is usually what remained for the stack in a previous link,
+    /// see [`stack::below_statics`](stack/fn.below_statics.html).
+    pub fn stack_below_statics(&mut self, size: W, vma: RegionID) -> Result<SectionID> {
+        let section = Section::stack_below_statics(size, vma);
+        self.add_section(section)
+    }
+
+    /// Process stack of `size` bytes, for the threads of an RTOS, which
+    /// the PSP is set to while the MSP keeps the main stack
+    ///
+    /// The stack lies between `__end_process_stack` and
+    /// `__start_process_stack`, its top, and is placed after the statics of
+    /// `vma`, below the main stack when it shares the region.
+    pub fn process_stack(&mut self, vma: RegionID, size: W) -> Result<SectionID> {
+        let section = Section::process_stack(vma, size);
+        self.add_section(section)
+    }
+
+    /// Align both ends of the stack to `align` bytes rather than the 8 the
+    /// AAPCS requires, for example 32 for an MPU guard region below it
+    ///
+    /// The top of the stack is rounded down, so the stack may lose up to
+    /// `align` bytes. Fails with
+    /// [`InvalidStackAlignment`](enum.LinkerError.html) when `align` is
+    /// not a power of two of at least 8 bytes.
+    pub fn stack_alignment(&mut self, align: W) -> Result<()> {
+        let align: u64 = align.into();
+        if !align.is_power_of_two() || align < STACK_ALIGN {
+            return Err(LinkerError::InvalidStackAlignment(align));
+        }
+        self.stack_align = align;
+        Ok(())
+    }
+
+    /// Leave `size` bytes between the statics and a stack taking the
+    /// rest of its region, so that an overflow faults rather than
+    /// overwriting them
+    ///
+    /// The gap is aligned to its size and lies between
+    /// `__start_<stack>_guard` and `__end_<stack>_guard`, for example
+    /// `__start_stack_guard`, which the runtime programs an MPU region
+    /// over with no access. Fails with
+    /// [`InvalidGuardGap`](enum.LinkerError.html) when `size` is not a
+    /// power of two of at least 32 bytes, the smallest MPU region.
+    pub fn stack_guard_gap(&mut self, size: W) -> Result<()> {
+        let size: u64 = size.into();
+        if !size.is_power_of_two() || size < MIN_GUARD_GAP {
+            return Err(LinkerError::InvalidGuardGap(size));
+        }
+        self.guard_gap = Some(size);
+        Ok(())
+    }
+
+    /// Optional heap location and size
+    ///
+    /// Places the heap as the last section in a region with addresses
     /// going higher available to it.
     pub fn heap(&mut self, vma: RegionID) -> Result<SectionID> {
-        let section = Section::heap(vma);
+        self.named_heap("heap", vma)
+    }
+
+    /// Optional heap named `name`, such as `dma_heap`, for a second heap
+    /// in another region
+    ///
+    /// Placed like [`heap`](#method.heap), between `__start_<name>` and
+    /// `__end_<name>`. Fails with [`InvalidHeap`](enum.LinkerError.html)
+    /// when `vma` already has a heap, as both would take its remaining
+    /// space.
+    pub fn named_heap(&mut self, name: &str, vma: RegionID) -> Result<SectionID> {
+        let taken = self
+            .sections
+            .values()
+            .find(|section| matches!(section.size, SectionSize::Heap) && section.vma == vma);
+        if let Some(taken) = taken {
+            return Err(LinkerError::InvalidHeap(format!(
+                ".{} would overlap .{} in {}",
+                name,
+                taken.output_name(),
+                vma.0
+            )));
+        }
+        let section = Section::heap(name, vma);
         self.add_section(section)
     }
 
@@ -309,6 +1687,81 @@ impl<W: Word> LinkerScript<W> {
         self.add_section(section)
     }
 
+    /// Place the linker's build ID note at a known offset in `vma`, before
+    /// the vector table
+    ///
+    /// Link with `--build-id`. The note is kept in `.build_id`, a fixed
+    /// 36 byte section which fits a SHA-1 ID and the shorter IDs of other
+    /// styles, after any boot config sections and so at an address known
+    /// before linking. `__start_build_id` is the note, and the generated
+    /// `regions.rs` has `build_id()` returning the ID, empty when the
+    /// image was linked without one. Parts booting from the vector table
+    /// at the origin of `vma` need it in another region.
+    pub fn build_id(&mut self, vma: RegionID) -> Result<SectionID> {
+        let section = Section::build_id(vma);
+        self.add_section(section)
+    }
+
+    /// Place the package's name, version and git hash at a known offset in
+    /// `vma`, before the vector table
+    ///
+    /// The 64 byte `.version` section holds a NUL padded string such as
+    /// `"blinky 0.1.0 3f2c9e1"`, found by bootloaders at the section's
+    /// address and by `strings`. [`generate`](#method.generate) writes
+    /// `version.rs` with the static filling it, from the Cargo metadata of
+    /// the package whose build script runs, which the firmware must
+    /// include.
+    pub fn version_string(&mut self, vma: RegionID) -> Result<SectionID> {
+        let section = Section::version(vma);
+        self.add_section(section)
+    }
+
+    /// Sections for the `.fcb`, `.ivt` and `.boot_data` statics generated
+    /// by imxrt-boot-gen, at the start of the FlexSPI flash region `flash`
+    ///
+    /// Each static is kept and placed at the offset the family's boot ROM
+    /// reads it from: the FCB at the family's offset, the IVT at 0x1000
+    /// and the boot data after it, with the vector table following at
+    /// 0x2000. The [`family`](#method.family) must be set first, and
+    /// `flash` must start at the family's flash address.
+    pub fn boot_gen(&mut self, flash: RegionID) -> Result<Vec<SectionID>> {
+        let family = self
+            .family
+            .ok_or_else(|| LinkerError::MissingFamily(String::from("boot_gen")))?;
+        let word = |value: u64| {
+            W::try_from(value).unwrap_or_else(|_| unreachable!("boot offsets fit in any word"))
+        };
+        let fcb_offset = family.fcb_offset();
+        let ivt_offset = family.ivt_offset();
+        let mut sections = Vec::new();
+        if fcb_offset > 0 {
+            sections.push(Section::new(
+                "fcb_pad",
+                -5,
+                SectionSize::Fixed(word(fcb_offset)),
+                flash.clone(),
+                None,
+            ));
+        }
+        sections.push(Section::kept(
+            "fcb",
+            -4,
+            word(ivt_offset - fcb_offset),
+            flash.clone(),
+        ));
+        sections.push(Section::kept("ivt", -3, word(IVT_SIZE), flash.clone()));
+        sections.push(Section::kept(
+            "boot_data",
+            -2,
+            word(ivt_offset - IVT_SIZE),
+            flash,
+        ));
+        sections
+            .into_iter()
+            .map(|section| self.add_section(section))
+            .collect()
+    }
+
     /// Required vector table, by default this is placed at the beginning
     /// of the text section but maybe useful in some instances to load to a
     /// different location. By using this VTOR is updated
@@ -351,148 +1804,5449 @@ impl<W: Word> LinkerScript<W> {
         self.add_section(section)
     }
 
-    fn add_section(&mut self, section: Section<W>) -> Result<SectionID> {
-        let name = section.name.clone();
-        if self.sections.contains_key(&name) {
-            return Err(LinkerError::DuplicateSection(name.clone()));
-        }
-        self.sections.insert(name.clone(), section);
-        Ok(SectionID(name.clone()))
+    /// Optional uninit section, neither zeroed nor copied at reset
+    ///
+    /// Places the `.uninit` and `.uninit.*` input sections after the bss
+    /// in `vma`, between `__start_uninit` and `__end_uninit`, with the
+    /// NOLOAD type. Statics placed there keep their values across soft
+    /// resets, such as watchdog counters and panic messages, and RTIC
+    /// places its resources there.
+    pub fn uninit(&mut self, vma: RegionID) -> Result<SectionID> {
+        let section = Section::uninit(vma);
+        self.add_section(section)
     }
 
-    /// Generate a linker script and matching reset module
-    /// which correctly initializes sections.
+    /// Optional unwind tables, needed when building with `panic = "unwind"`
+    /// or for backtraces on target
     ///
-    /// The function places a linker script file, called `link.x`, in
-    /// the current working directory.
-    pub fn generate(self) -> Result<()> {
-        let mut link_x = File::create("link.x")?;
-        self.write(&mut link_x)
+    /// Places `.ARM.extab`, `.ARM.exidx`, `.eh_frame_hdr`, and `.eh_frame`
+    /// after the read only data in `vma`, and provides the `__exidx_start`
+    /// and `__exidx_end` symbols unwinders look for. Without them the
+    /// tables are discarded, as they are with `panic = "abort"`. A build
+    /// script may check the `CARGO_CFG_PANIC` environment variable to
+    /// decide.
+    pub fn unwind_tables(&mut self, vma: RegionID) -> Result<Vec<SectionID>> {
+        Section::unwind_tables(vma)
+            .into_iter()
+            .map(|section| self.add_section(section))
+            .collect()
     }
 
-    /// Write the linker script into the writer, `link_x`
-    pub fn write<Wr: Write>(self, link_x: &mut Wr) -> Result<()> {
-        const REQ_SEC_NAMES: [&str; 6] = ["stack", "vector_table", "text", "data", "rodata", "bss"];
-        for req_sec_name in REQ_SEC_NAMES.iter() {
-            let name = String::from(*req_sec_name);
-            if !self.sections.contains_key(&name) {
-                return Err(LinkerError::MissingSection(name));
-            }
-        }
-        generate::link::render(&self, link_x)?;
-        Ok(())
-        //let reset = generate::reset::render(&self)?;
-        //let mut reset_rs = File::create("reset.rs")?;
-        //reset_rs.write_all(&reset)?;
+    /// Sections of on-target coverage and profiling builds, with
+    /// `-C instrument-coverage` or GCC's `-fprofile-info-section`
+    ///
+    /// The counters of LLVM's profile runtime, `__llvm_prf_cnts`,
+    /// `__llvm_prf_bits` and `__llvm_prf_vnds`, run from `ram` and are
+    /// loaded from `flash`, so the reset copies their zeroed image rather
+    /// than zeroing them with `.bss`. The profile data and names, and
+    /// GCC's `.gcov_info`, stay in `flash`. All are kept, with the
+    /// `__start_*` and `__stop_*` symbols the runtimes walk them with. The
+    /// coverage mapping, `__llvm_covmap` and `__llvm_covfun`, isn't
+    /// loaded and stays in the ELF file for `llvm-cov`. The sections are
+    /// placed in this order after the other retained sections.
+    pub fn coverage(&mut self, flash: RegionID, ram: RegionID) -> Result<Vec<SectionID>> {
+        let counters = PROFILE_COUNTERS
+            .iter()
+            .enumerate()
+            .map(|(idx, input)| Section {
+                nudge: idx as i32 + 1,
+                ..Section::retained(input, ram.clone(), Some(flash.clone()))
+            });
+        let data = PROFILE_DATA.iter().enumerate().map(|(idx, input)| Section {
+            nudge: idx as i32 + 1,
+            ..Section::retained(input, flash.clone(), None)
+        });
+        counters
+            .chain(data)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|section| self.add_section(section))
+            .collect()
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    #[test]
-    fn generate_ok() {
-        let mut ls = LinkerScript::<u32>::new();
-        let flash = ls.region(FLASH, 0x0, 512).unwrap();
-        let ram = ls.region(RAM, 0x20000000, 128).unwrap();
-        ls.stack(ram.clone()).unwrap();
-        ls.heap(ram.clone()).unwrap();
-        ls.boot_config(512, "fcb", flash.clone()).unwrap();
-        ls.vector_table(flash.clone(), Some(ram.clone())).unwrap();
-        ls.text(flash.clone(), Some(ram.clone())).unwrap();
-        ls.data(false, flash.clone(), Some(ram.clone())).unwrap();
-        ls.rodata(false, flash.clone(), None).unwrap();
-        ls.bss(false, flash.clone(), Some(ram.clone())).unwrap();
-        ls.generate().unwrap();
+    /// Thread-local storage, for RTOS ports and `#[thread_local]`
+    ///
+    /// Places the TLS image, `.tdata` and `.tbss`, in `template` after the
+    /// read only data, and the main thread's TLS block, `.tls`, in `vma`.
+    /// The reset module's `init` fills the block, and its `init_tls`
+    /// fills another thread's block of `__tls_size` bytes the same way.
+    /// `__tdata_start`, `__tdata_end`, `__tbss_start` and `__tbss_end`
+    /// are provided for C runtimes and `__tls_block` is the main thread's
+    /// block. The thread pointer, `__aeabi_read_tp` on ARM, is left to
+    /// the RTOS. Blocks are word aligned, TLS variables aligned beyond the
+    /// word are not supported.
+    pub fn thread_local(&mut self, template: RegionID, vma: RegionID) -> Result<Vec<SectionID>> {
+        Section::thread_local(template, vma)
+            .into_iter()
+            .map(|section| self.add_section(section))
+            .collect()
     }
 
-    //
-    // The 'rejects_*' tests show that we reject linker scripts that are missing
-    // our required sections.
-    //
+    /// Generate a layout for position independent (ROPI/RWPI) firmware,
+    /// which may be loaded at any offset in flash
+    ///
+    /// Adds the global offset table, run from `vma` and loaded from `lma`
+    /// like data. The load address of every loaded section is also given
+    /// as an offset from the origin of its load region,
+    /// `__load_offset_<section>`, which the reset handler adds to the
+    /// address the image actually runs from.
+    pub fn position_independent(
+        &mut self,
+        vma: RegionID,
+        lma: Option<RegionID>,
+    ) -> Result<SectionID> {
+        let id = self.add_section(Section::got(vma, lma))?;
+        self.position_independent = true;
+        Ok(id)
+    }
 
-    #[derive(PartialEq, Eq)]
-    enum Required {
-        Stack,
-        VectorTable,
-        Text,
-        Data,
-        ROData,
-        Bss,
+    /// Pack the sections loaded from flash contiguously in their load
+    /// region, so that the reset copies them in one loop
+    ///
+    /// The loaded sections are placed after every other section but the
+    /// stack and heap, grouped by load region and in priority order
+    /// within each. The reset walks a table of their run addresses with a
+    /// single load pointer, rather than copying each from its own load
+    /// address, and the linker asserts their load images don't have gaps.
+    pub fn pack_loads(&mut self, pack: bool) {
+        self.pack_loads = pack;
     }
 
-    impl ToString for Required {
-        fn to_string(&self) -> String {
-            ToString::to_string(match self {
-                Required::Stack => "stack",
-                Required::VectorTable => "vector_table",
-                Required::Text => "text",
-                Required::Data => "data",
-                Required::ROData => "rodata",
-                Required::Bss => "bss",
-            })
-        }
+    /// Copy and zero sections at reset with `__aeabi_memcpy4` and
+    /// `__aeabi_memclr4`, which compiler-builtins provides for 32-bit ARM
+    /// targets, rather than the generated loops
+    ///
+    /// The generated loops move four words per iteration, then the words
+    /// left over. The EABI functions may use `LDM`/`STM` bursts and are
+    /// worth it when hundreds of KiB are copied into TCM at boot. Scripts
+    /// with 64-bit words keep the generated loops, the EABI functions are
+    /// only defined for 32-bit ARM.
+    pub fn aeabi_memcpy(&mut self, aeabi: bool) {
+        self.aeabi_memcpy = aeabi;
     }
 
-    fn reject_missing(required: Required) {
-        let mut ls = LinkerScript::<u32>::new();
-        let flash = ls.region(FLASH, 0x0, 512).unwrap();
-        let ram = ls.region(RAM, 0x20000000, 128).unwrap();
-        if Required::Stack != required {
-            ls.stack(ram.clone()).unwrap();
+    /// Define `__stack_chk_guard` and `__stack_chk_fail` in the reset
+    /// module, for C code built with `-fstack-protector`
+    ///
+    /// `init` seeds the guard with [`STACK_CANARY`](constant.STACK_CANARY.html),
+    /// or the canary given to [`stack_canary`](#method.stack_canary), so
+    /// the generated files are the same for every build.
+    /// `seed_stack_guard` mixes in entropy from a hardware random number
+    /// generator before any protected function runs, which is what makes
+    /// the canary hard to guess, and `__stack_chk_fail` panics.
+    pub fn stack_protector(&mut self, protect: bool) {
+        self.stack_guard = if protect {
+            Some(self.stack_guard.unwrap_or(STACK_CANARY))
+        } else {
+            None
+        };
+    }
+
+    /// Define the stack protector's symbols, as
+    /// [`stack_protector`](#method.stack_protector) does, with `canary`
+    /// as the guard `init` seeds
+    ///
+    /// The low byte is cleared, to stop string copies.
+    pub fn stack_canary(&mut self, canary: W) {
+        self.stack_guard = Some(canary.into() & !0xFF);
+    }
+
+    /// Generate `reset_stub` in the reset module, the first thing for the
+    /// reset handler to call, which works wherever the image runs
+    ///
+    /// The image may run XIP from flash where it was linked, or from RAM
+    /// where the boot ROM copied it. The stub finds the difference from
+    /// the program counter, without using an absolute address, points
+    /// VTOR at the vector table where it runs and returns the difference,
+    /// for the reset handler to branch to the linked address or to pass
+    /// on to a [position independent](#method.got) `init`. Only 32-bit
+    /// Cortex-M scripts get the stub.
+    pub fn reset_stub(&mut self, stub: bool) {
+        self.reset_stub = stub;
+    }
+
+    /// Also export the section's symbols as seen through `view`, another
+    /// view of the memory it runs from
+    ///
+    /// For example, a buffer in cacheable OCRAM gets
+    /// `__start_<section>_<view>` and `__end_<section>_<view>` at the same
+    /// bytes in the non-cacheable alias, for DMA. The generated
+    /// `regions.rs` has `<section>_in_<view>` to translate pointers into
+    /// the section, returning `None` for pointers outside it.
+    pub fn alias_view(&mut self, section: &SectionID, view: &RegionID) -> Result<()> {
+        if !self.regions.contains_key(&view.0) {
+            return Err(LinkerError::UnknownRegion(view.clone()));
         }
-        if Required::VectorTable != required {
-            ls.vector_table(flash.clone(), Some(ram.clone())).unwrap();
+        let vma = &self
+            .sections
+            .get(&section.0)
+            .ok_or_else(|| LinkerError::UnknownSection(section.clone()))?
+            .vma
+            .0;
+        if vma == &view.0 || self.physical(vma) != self.physical(&view.0) {
+            return Err(LinkerError::InvalidView(format!(
+                "{} is not an alias of {}, which .{} runs from",
+                view.0, vma, section.0
+            )));
         }
-        if Required::Text != required {
-            ls.text(flash.clone(), Some(ram.clone())).unwrap();
+        let views = &mut self
+            .sections
+            .get_mut(&section.0)
+            .expect("checked above")
+            .views;
+        if !views.contains(view) {
+            views.push(view.clone());
         }
-        if Required::Data != required {
-            ls.data(false, flash.clone(), Some(ram.clone())).unwrap();
+        Ok(())
+    }
+
+    /// Load `section` from exactly `address` in its load region, for
+    /// example where a bootloader expects a blob, rather than after the
+    /// sections loaded before it
+    ///
+    /// The linker doesn't move the sections loaded after it out of the
+    /// way, so the address should be past them or in a region of its own.
+    /// Fails with [`InvalidLoadAddress`](enum.LinkerError.html) when the
+    /// section isn't loaded, is packed, compressed or eDMA copied, or the
+    /// address isn't word aligned in the load region.
+    pub fn load_at(&mut self, section: &SectionID, address: W) -> Result<()> {
+        let found = self
+            .sections
+            .get(&section.0)
+            .ok_or_else(|| LinkerError::UnknownSection(section.clone()))?;
+        let output = found.output_name();
+        let lma = match &found.lma {
+            Some(lma) => &self.regions[&lma.0],
+            None => {
+                return Err(LinkerError::InvalidLoadAddress(format!(
+                    ".{} is not loaded",
+                    output
+                )))
+            }
+        };
+        if self.pack_loads || found.compressed || found.dma_tcd.is_some() {
+            return Err(LinkerError::InvalidLoadAddress(format!(
+                ".{} is packed, compressed or copied by eDMA",
+                output
+            )));
         }
-        if Required::ROData != required {
-            ls.rodata(false, flash.clone(), None).unwrap();
+        let (origin, at): (u64, u64) = (lma.origin.into(), address.into());
+        let align = std::mem::align_of::<W>() as u64;
+        if at < origin || at >= origin + lma.size.into() || !at.is_multiple_of(align) {
+            return Err(LinkerError::InvalidLoadAddress(format!(
+                "{:#X} is not a {} byte aligned address in {}, which .{} is loaded from",
+                at, align, lma.name, output
+            )));
         }
-        if Required::Bss != required {
-            ls.bss(false, flash.clone(), Some(ram.clone())).unwrap();
+        self.sections
+            .get_mut(&section.0)
+            .expect("checked above")
+            .load_at = Some(address);
+        Ok(())
+    }
+
+    /// Wrap the input sections of `section` in KEEP, so `--gc-sections`
+    /// can't drop them when nothing refers to them, or stop doing so
+    ///
+    /// The vector table, the boot config sections, including those of
+    /// [`boot_gen`](#method.boot_gen), and the version string are kept
+    /// unless this is called with `false`.
+    pub fn keep(&mut self, section: &SectionID, keep: bool) -> Result<()> {
+        self.sections
+            .get_mut(&section.0)
+            .ok_or_else(|| LinkerError::UnknownSection(section.clone()))?
+            .keep = keep;
+        Ok(())
+    }
+
+    /// Fill the gaps in `section` the input sections leave, such as the
+    /// padding of a fixed size boot config section or alignment, with
+    /// `byte` rather than zeroes
+    ///
+    /// `0xFF` makes the image match erased flash. The fill is repeated
+    /// to four bytes, which GNU ld and LLD read alike.
+    pub fn fill(&mut self, section: &SectionID, byte: u8) -> Result<()> {
+        self.sections
+            .get_mut(&section.0)
+            .ok_or_else(|| LinkerError::UnknownSection(section.clone()))?
+            .fill = Some(byte);
+        Ok(())
+    }
+
+    /// Give `section` the NOLOAD type, so it takes space in its region but
+    /// nothing in the image, or stop doing so
+    ///
+    /// Without it `objcopy -O binary` stores the zeroes of large zero
+    /// initialized or uninitialized RAM sections. The reset still zeroes a
+    /// NOLOAD bss section. Fails with
+    /// [`LoadedNoload`](enum.LinkerError.html) when the section is copied
+    /// from a load region at reset.
+    pub fn noload(&mut self, section: &SectionID, noload: bool) -> Result<()> {
+        let found = self
+            .sections
+            .get_mut(&section.0)
+            .ok_or_else(|| LinkerError::UnknownSection(section.clone()))?;
+        if let (Some(lma), true) = (&found.lma, noload) {
+            return Err(LinkerError::LoadedNoload {
+                section: found.output_name(),
+                lma: lma.0.clone(),
+            });
         }
-        match ls.generate() {
-            Err(LinkerError::MissingSection(section)) if section == required.to_string() => {}
-            result => panic!(
-                "Expected missing {}, but got {:?}",
-                required.to_string(),
-                result
-            ),
+        found.noload = noload;
+        Ok(())
+    }
+
+    /// Let the input sections of `section` which don't fit its region
+    /// spill into `vma`, for parts with split or external flash
+    ///
+    /// Adds `.<section>_spill`, run from `vma` and loaded like `section`,
+    /// taking the same input sections, and `__size_<section>` giving the
+    /// bytes of both. The spill section is copied at reset when loaded.
+    /// The linker fills the first and moves what doesn't fit to the second
+    /// only when linking with GNU ld's `--enable-non-contiguous-regions`;
+    /// other linkers leave the spill section empty and fail when the
+    /// first is full, which [`lints`](#method.lints) warns about. Only
+    /// linker sized sections with contents spill.
+    pub fn spill(&mut self, section: &SectionID, vma: RegionID) -> Result<SectionID> {
+        let first = self
+            .sections
+            .get(&section.0)
+            .ok_or_else(|| LinkerError::UnknownSection(section.clone()))?;
+        let output = first.output_name();
+        if !matches!(first.size, SectionSize::Linker) || !first.has_contents() {
+            return Err(LinkerError::InvalidSpill(format!(
+                ".{} is not a linker sized section with contents",
+                output
+            )));
+        }
+        if first.vma == vma {
+            return Err(LinkerError::InvalidSpill(format!(
+                ".{} already runs from {}",
+                output, vma.0
+            )));
+        }
+        let inputs = if first.inputs.is_empty() {
+            vec![format!(".{}", output)]
+        } else {
+            first.inputs.clone()
+        };
+        let spilled = Section {
+            keep: first.keep,
+            inputs,
+            spill_of: Some(output.clone()),
+            ..Section::new(
+                &format!("{}_spill", output),
+                first.priority,
+                SectionSize::Linker,
+                vma,
+                first.lma.clone(),
+            )
         };
+        self.add_section(spilled)
     }
 
-    #[test]
-    fn rejects_missing_vector_table() {
-        reject_missing(Required::VectorTable);
+    /// Fail the link when a section in `from` references `to`
+    ///
+    /// For example, code which runs before SDRAM is initialized must not
+    /// call into a section run from SDRAM. The script gets a
+    /// `NOCROSSREFS_TO` command, and the linker reports the offending
+    /// reference. `to` in `from` is ignored, a section may refer to
+    /// itself.
+    pub fn no_cross_refs(&mut self, to: &SectionID, from: &[SectionID]) -> Result<()> {
+        for id in std::iter::once(to).chain(from) {
+            if !self.sections.contains_key(&id.0) {
+                return Err(LinkerError::UnknownSection(id.clone()));
+            }
+        }
+        let from: Vec<SectionID> = from.iter().filter(|id| *id != to).cloned().collect();
+        if !from.is_empty() {
+            self.no_cross_refs.push((to.clone(), from));
+        }
+        Ok(())
     }
 
-    #[test]
-    fn rejects_missing_stack() {
-        reject_missing(Required::Stack);
+    /// Declare the ELF program headers in a `PHDRS` command, rather than
+    /// leave the segments to the linker
+    ///
+    /// Each run and load region pair with contents gets a `PT_LOAD`
+    /// segment, named `load_<VMA>` or `load_<VMA>_from_<LMA>`, in
+    /// placement order. Sections without contents, such as `.bss`, the
+    /// stack and the heap, are kept out of every segment with `:NONE`, so
+    /// flashing tools which program the load segments never see RAM. The
+    /// [cortex-m-rt supplement](#method.write_insert) has no program
+    /// headers, since link.x places the stock sections.
+    pub fn program_headers(&mut self, phdrs: bool) {
+        self.program_headers = phdrs;
     }
 
-    #[test]
-    fn rejects_missing_text() {
-        reject_missing(Required::Text);
+    /// Generate the smallest startup, for bootloaders and images short of
+    /// space
+    ///
+    /// The reset copies and zeroes sections with plain word loops.
+    /// Writing the script fails with [`NotTiny`](enum.LinkerError.html)
+    /// when it has a heap, prefixed sections, or an optional feature: a
+    /// position independent layout, packed, compressed or eDMA copied
+    /// sections, alias views, thread local storage, the EABI copy
+    /// routines, cache maintenance, the stack protector, the reset stub,
+    /// or a second core.
+    pub fn tiny(&mut self, tiny: bool) {
+        self.tiny = tiny;
     }
 
-    #[test]
-    fn rejects_missing_data() {
-        reject_missing(Required::Data);
+    /// The firmware enables the data cache before calling the reset's
+    /// `init`, so the copies and zeroes land in the cache first
+    ///
+    /// `init` then cleans the sections running from
+    /// [DMA capable](#method.dma_capable) regions, and those holding code,
+    /// to memory once they are written, for DMA and instruction fetch to
+    /// see them. `wait_init` invalidates the sections copied by eDMA, for
+    /// the CPU not to read stale lines. Only 32-bit Cortex-M7 scripts get
+    /// the maintenance.
+    pub fn dcache_before_init(&mut self, enabled: bool) {
+        self.dcache_before_init = enabled;
     }
 
-    #[test]
-    fn rejects_missing_rodata() {
-        reject_missing(Required::ROData);
+    /// Store `section` LZ4 compressed in its load region, decompressing
+    /// it at reset rather than copying it
+    ///
+    /// Compressing packs the loaded sections, see
+    /// [`pack_loads`](#method.pack_loads), with the compressed ones last in
+    /// their load region. The linker still places them uncompressed, the
+    /// [`compress`](compress/index.html) module replaces them with their
+    /// compressed blocks in the flat image after linking. The reset then
+    /// decompresses each in turn from the load address of the first.
+    pub fn compress(&mut self, section: &SectionID) -> Result<()> {
+        let section = self
+            .sections
+            .get_mut(&section.0)
+            .ok_or_else(|| LinkerError::UnknownSection(section.clone()))?;
+        match (&section.lma, &section.size) {
+            (Some(_), SectionSize::Linker) => {}
+            (None, _) => {
+                return Err(LinkerError::InvalidCompression(format!(
+                    "section .{} is not loaded from another region",
+                    section.output_name()
+                )))
+            }
+            _ => {
+                return Err(LinkerError::InvalidCompression(format!(
+                    "section .{} is not sized by the linker",
+                    section.output_name()
+                )))
+            }
+        }
+        if section.dma_tcd.is_some() {
+            return Err(LinkerError::InvalidCompression(format!(
+                "section .{} is copied by eDMA",
+                section.output_name()
+            )));
+        }
+        section.compressed = true;
+        self.pack_loads = true;
+        Ok(())
     }
 
-    #[test]
-    fn rejects_missing_bss() {
-        reject_missing(Required::Bss);
+    /// Copy `section` from its load region with an eDMA channel, started
+    /// by the reset's `init` before the CPU copies and zeroes the rest
+    ///
+    /// This suits large sections the firmware doesn't need right away,
+    /// such as lookup tables, which are copied while critical
+    /// initialization goes on. The generated reset module has
+    /// `init_complete` and `wait_init`, to check or wait for the copies
+    /// before the sections are used, and before the data cache is enabled.
+    ///
+    /// Sections take channels from 0 up in the order they're added, which
+    /// are free again once `wait_init` returns. The [`family`](#method.family)
+    /// must be set first, for the eDMA controller's address, and `section`
+    /// must run from a [DMA capable](#method.dma_capable) region. When
+    /// packing, these sections are placed after the others copied by the
+    /// CPU in their load region.
+    pub fn dma_init(&mut self, section: &SectionID) -> Result<()> {
+        let family = self
+            .family
+            .ok_or_else(|| LinkerError::MissingFamily(String::from("dma_init")))?;
+        let channel = self
+            .sections
+            .values()
+            .filter(|section| section.dma_tcd.is_some())
+            .count() as u64;
+        let found = self
+            .sections
+            .get(&section.0)
+            .ok_or_else(|| LinkerError::UnknownSection(section.clone()))?;
+        let name = found.output_name();
+        let reason = if found.lma.is_none() || !matches!(found.size, SectionSize::Linker) {
+            Some(String::from("is not loaded from another region"))
+        } else if found.compressed {
+            Some(String::from("is compressed"))
+        } else if !self.regions[&found.vma.0].dma {
+            Some(format!(
+                "runs from {}, which is not DMA capable",
+                found.vma.0
+            ))
+        } else if channel >= family.edma_channels() {
+            Some(format!(
+                "needs more than the {} eDMA channels of the {}",
+                family.edma_channels(),
+                family
+            ))
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            return Err(LinkerError::InvalidDmaInit(format!(
+                "section .{} {}",
+                name, reason
+            )));
+        }
+        let section = self.sections.get_mut(&section.0).expect("found above");
+        section.dma_tcd = Some(family.edma_tcd_base() + 32 * channel);
+        Ok(())
+    }
+
+    /// Hand zeroing the bss `sections` to the second core of a dual-core
+    /// part, the RT1170's Cortex-M4, through a mailbox at `mailbox`
+    ///
+    /// The primary core's `init` posts the sections' run addresses to the
+    /// mailbox instead of zeroing them. `second_core.rs` is generated with
+    /// `second_core_init`, for the second core's firmware to call once
+    /// started, which zeroes them and marks the mailbox done. The primary's
+    /// reset module gets `wait_second_core_init`, the barrier to pass
+    /// before the sections are used.
+    ///
+    /// The mailbox takes two words, and two more per section, in memory
+    /// neither core caches. Both cores must see the sections at the same
+    /// addresses, which rules out the cores' TCMs.
+    pub fn second_core_init(&mut self, sections: &[SectionID], mailbox: W) -> Result<()> {
+        let family = self
+            .family
+            .ok_or_else(|| LinkerError::MissingFamily(String::from("second_core_init")))?;
+        if family != chip::Family::Imxrt1170 {
+            return Err(LinkerError::InvalidDualCore(format!(
+                "{} has a single core",
+                family
+            )));
+        }
+        let origin: u64 = mailbox.into();
+        let end = origin + 8 + 8 * sections.len() as u64;
+        let fits = self.regions.values().any(|region| {
+            let start: u64 = region.origin.into();
+            start <= origin && end <= start + region.size.into()
+        });
+        if !origin.is_multiple_of(4) || !fits {
+            return Err(LinkerError::InvalidDualCore(format!(
+                "the mailbox at {:#X} is not aligned within a region",
+                origin
+            )));
+        }
+        for id in sections {
+            let section = self
+                .sections
+                .get(&id.0)
+                .ok_or_else(|| LinkerError::UnknownSection(id.clone()))?;
+            if section.lma.is_some() || !matches!(section.size, SectionSize::Linker) {
+                return Err(LinkerError::InvalidDualCore(format!(
+                    "section .{} is not zeroed at reset",
+                    section.output_name()
+                )));
+            }
+        }
+        for id in sections {
+            self.sections
+                .get_mut(&id.0)
+                .expect("checked above")
+                .second_core = true;
+        }
+        self.second_core_mailbox = Some(mailbox);
+        Ok(())
+    }
+
+    /// Order sections are placed in, by priority and then name so the
+    /// output doesn't depend on the iteration order of the section map
+    ///
+    /// Packing places the loaded sections, by load region, after the
+    /// others but those filling the rest of a region. Sections copied by
+    /// eDMA, then compressed sections, come last in their load region.
+    fn placement_order(&self, a: &Section<W>, b: &Section<W>) -> std::cmp::Ordering {
+        let key = |section: &Section<W>| {
+            let lma = match section.lma.as_ref() {
+                Some(lma) if self.pack_loads => lma.0.clone(),
+                _ => String::new(),
+            };
+            (
+                self.placement_group(section),
+                lma,
+                section.compressed,
+                section.dma_tcd.is_some(),
+                section.priority,
+                section.nudge,
+                section.output_name(),
+            )
+        };
+        key(a).cmp(&key(b))
+    }
+
+    /// Group of `section` in placement order, with packing 1 for the
+    /// loaded sections and 2 for those filling the rest of a region, 0
+    /// otherwise
+    fn placement_group(&self, section: &Section<W>) -> u32 {
+        match section.size {
+            _ if !self.pack_loads => 0,
+            SectionSize::Stack | SectionSize::Heap => 2,
+            _ if section.lma.is_some() => 1,
+            _ => 0,
+        }
+    }
+
+    /// Place the dynamic relocations of a position independent executable,
+    /// linked with `-pie`, in `vma`
+    ///
+    /// The generated reset module then has a `relocate` function applying
+    /// the relative relocations once the image knows where it runs from.
+    pub fn dynamic_relocations(&mut self, vma: RegionID) -> Result<SectionID> {
+        let section = Section::dynamic_relocations(vma);
+        self.add_section(section)
+    }
+
+    /// Table of the addresses of `functions`, which must be `#[no_mangle]`,
+    /// for another image to call at a fixed address
+    ///
+    /// The table is placed in `vma`, which should be a region of its own so
+    /// that the address doesn't move as the image changes. Entry `i` is at
+    /// the origin of `vma` plus 4 times `i`, see
+    /// [`Project::jump_table`](project/struct.Project.html#method.jump_table)
+    /// for the image calling it.
+    pub fn jump_table(&mut self, functions: &[&str], vma: RegionID) -> Result<SectionID> {
+        let section = Section::jump_table(functions, vma);
+        self.add_section(section)
+    }
+
+    /// Define a symbol at a fixed address, such as a function or table of
+    /// another image
+    pub fn symbol(&mut self, name: &str, address: W) {
+        self.symbols.push((String::from(name), address));
+    }
+
+    /// Define an absolute symbol holding a build time value, such as
+    /// `define_symbol("__feature_flags", 0x3)`
+    ///
+    /// Linker expressions read the value as the symbol, and firmware as the
+    /// address of an `extern` static of that name or as the constant of
+    /// the generated `regions.rs`, named without the leading underscores in
+    /// upper case, `FEATURE_FLAGS`. Fails with
+    /// [`InvalidSymbol`](enum.LinkerError.html) when the name isn't an
+    /// identifier, is already defined, or its constant is another's, such
+    /// as `_feature_flags` or a region's `FLASH_ORIGIN`.
+    pub fn define_symbol(&mut self, name: &str, value: W) -> Result<()> {
+        let mut chars = name.chars();
+        let identifier = chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !name.trim_start_matches('_').is_empty();
+        if !identifier {
+            return Err(LinkerError::InvalidSymbol(format!(
+                "{:?} is not an identifier",
+                name
+            )));
+        }
+        let defined = self
+            .symbols
+            .iter()
+            .chain(self.constants.iter())
+            .any(|(symbol, _)| symbol == name);
+        if defined {
+            return Err(LinkerError::InvalidSymbol(format!(
+                "{} is already defined",
+                name
+            )));
+        }
+        let constant = generate::predicates::constant(name);
+        let clash = self
+            .constants
+            .iter()
+            .find(|(symbol, _)| generate::predicates::constant(symbol) == constant);
+        if let Some((symbol, _)) = clash {
+            return Err(LinkerError::InvalidSymbol(format!(
+                "{} and {} are both the constant {}",
+                symbol, name, constant
+            )));
+        }
+        self.constants.push((String::from(name), value));
+        if let Err(err) = self.check_constants() {
+            self.constants.pop();
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// Fail when a defined symbol's constant is one `regions.rs` defines for
+    /// a region or the encrypted ranges
+    fn check_constants(&self) -> Result<()> {
+        let mut taken = vec![(
+            String::from("ENCRYPTED_RANGES"),
+            String::from("the encrypted ranges"),
+        )];
+        for region in self.regions.values() {
+            for constant in generate::predicates::region_constants(region).iter() {
+                taken.push((constant.clone(), format!("region {}", region.name)));
+            }
+        }
+        for (symbol, _) in self.constants.iter() {
+            let constant = generate::predicates::constant(symbol);
+            if let Some((_, owner)) = taken.iter().find(|(taken, _)| *taken == constant) {
+                return Err(LinkerError::InvalidSymbol(format!(
+                    "the constant of {}, {}, is that of {}",
+                    symbol, constant, owner
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// IVT and boot data for an image the boot ROM receives over serial
+    /// download, placed at the start of the image in `vma`
+    ///
+    /// The image starts at the origin of `vma`, with the IVT at 0x400 and
+    /// the boot data after it describing the image as the whole region.
+    /// The section is 0x1000 bytes, so that the vector table placed after
+    /// it in `vma` starts at 0x1000, which is where the IVT points. The
+    /// host jumps to the IVT at `__ivt` once the image is written.
+    ///
+    /// With a `dcd` size, the `.dcd` input sections are placed at 0x430
+    /// and the IVT points at them, otherwise the IVT's DCD pointer is
+    /// null. The largest DCD the boot ROM runs fits before the vector
+    /// table, which stays at 0x1000 either way.
+    pub fn serial_download_ivt(&mut self, vma: RegionID, dcd: Option<W>) -> Result<SectionID> {
+        let dcd = dcd.map(Into::into);
+        if let Some(size) = dcd.filter(|size| *size > chip::DCD_MAX_SIZE) {
+            return Err(LinkerError::SectionTooLarge {
+                section: String::from("dcd"),
+                region: vma.0,
+                size,
+                available: chip::DCD_MAX_SIZE,
+            });
+        }
+        let section = Section::serial_download_ivt(vma, dcd);
+        self.add_section(section)
+    }
+
+    /// Keep the input sections named `input` in an output section of their
+    /// own, for registries like linkme's distributed slices or test lists
+    ///
+    /// Nothing refers to such sections but their start and end symbols, so
+    /// with a fully custom script `--gc-sections` drops them silently. The
+    /// output section is named like the input sections, with a leading '.',
+    /// wraps them in KEEP, and is placed after the read only data of `vma`.
+    /// Besides the `__start_*` and `__end_*` symbols, `__stop_*` is provided
+    /// for linkme, for example `__start_linkme_HANDLERS` and
+    /// `__stop_linkme_HANDLERS` for `retain("linkme_HANDLERS", ..)`.
+    pub fn retain(
+        &mut self,
+        input: &str,
+        vma: RegionID,
+        lma: Option<RegionID>,
+    ) -> Result<SectionID> {
+        let section = Section::retained(input, vma, lma);
+        self.add_section(section)
+    }
+
+    /// A section of the application's own, such as `.dma_buffers` or
+    /// `.framebuffer`, holding the input sections named like it
+    ///
+    /// The section runs from `vma` and, with an `lma`, is loaded from it
+    /// and copied by the reset. It is sized by the linker, or takes `size`
+    /// bytes however much of it the input sections fill. Prefixed, it is
+    /// named by its region, `.OCRAM.dma_buffers` in OCRAM, as
+    /// [`data`](#method.data) is. It is placed after the read only data,
+    /// see [`priority`](#method.priority) to move it.
+    pub fn custom_section(
+        &mut self,
+        name: &str,
+        prefix: bool,
+        vma: RegionID,
+        lma: Option<RegionID>,
+        size: Option<W>,
+    ) -> Result<SectionID> {
+        let section = Section::custom(name, prefix, vma, lma, size);
+        self.add_section(section)
+    }
+
+    /// Place `section` by `priority` rather than its default, lower
+    /// priorities are placed first in their region
+    pub fn priority(&mut self, section: &SectionID, priority: i32) -> Result<()> {
+        self.set_priority(section, Priority::Value(priority))
+    }
+
+    /// Place `section` by `priority`, by value or right before or after
+    /// another section, such as a custom section between `.rodata` and
+    /// `.bss` or `.data` ahead of `.text` in a RAM only image
+    ///
+    /// The other section's priority is taken when this is called, so
+    /// changing it later doesn't move `section` along.
+    pub fn set_priority(&mut self, section: &SectionID, priority: Priority) -> Result<()> {
+        let (priority, nudge) = match &priority {
+            Priority::Value(value) => (*value, 0),
+            Priority::Before(other) | Priority::After(other) => {
+                let found = self
+                    .sections
+                    .get(&other.0)
+                    .ok_or_else(|| LinkerError::UnknownSection(other.clone()))?;
+                let step = if matches!(priority, Priority::Before(_)) {
+                    -1
+                } else {
+                    1
+                };
+                (found.priority, found.nudge + step)
+            }
+        };
+        let section = self
+            .sections
+            .get_mut(&section.0)
+            .ok_or_else(|| LinkerError::UnknownSection(section.clone()))?;
+        section.priority = priority;
+        section.nudge = nudge;
+        Ok(())
+    }
+
+    /// Route the functions named `functions`, mangled as in the symbol
+    /// table, into a `.hot_text` section running from `vma`, usually ITCM
+    ///
+    /// See the [`hot`](hot/index.html) module for selecting the functions
+    /// from a profile within an ITCM budget.
+    pub fn hot_text(
+        &mut self,
+        functions: &[&str],
+        vma: RegionID,
+        lma: Option<RegionID>,
+    ) -> Result<SectionID> {
+        let section = Section::hot_text(functions, vma, lma);
+        self.add_section(section)
+    }
+
+    /// Run the functions placed in `.text.ram` or `.ramfunc` input
+    /// sections from `vma`, usually ITCM, copied from `lma` at reset
+    ///
+    /// Flash programming code must not run from the flash it programs,
+    /// and interrupt handlers run from ITCM without the latency of a
+    /// cache miss. Mark such functions with
+    /// `#[link_section = ".text.ram.<name>"]`, or GCC's
+    /// `__attribute__((section(".ramfunc")))`.
+    pub fn text_ram(
+        &mut self,
+        prefix: bool,
+        vma: RegionID,
+        lma: Option<RegionID>,
+    ) -> Result<SectionID> {
+        let section = Section::text_ram(prefix, vma, lma);
+        self.add_section(section)
+    }
+
+    /// Partition the code between `vma`, usually ITCM, and `.text`
+    ///
+    /// Adds the prefixed `.<VMA>.text`, loaded from `lma` and copied at
+    /// reset, taking the code of the object files and archives matching
+    /// `files`, such as `"*libdrivers*"`. The rest stays in `.text`. With
+    /// a `budget`, the linker fails when the partition is larger. With no
+    /// `files` all code goes to the partition first, and what doesn't fit
+    /// the region moves to `.text` when linking with GNU ld's
+    /// `--enable-non-contiguous-regions`, which [`lints`](#method.lints)
+    /// warns about.
+    pub fn text_partition(
+        &mut self,
+        files: &[&str],
+        budget: Option<W>,
+        vma: RegionID,
+        lma: Option<RegionID>,
+    ) -> Result<SectionID> {
+        let section = Section::text_partition(files, budget, vma, lma);
+        self.add_section(section)
+    }
+
+    /// Mark a region as accessible by DMA, for the `is_dma_capable`
+    /// predicate of the generated `regions.rs`
+    pub fn dma_capable(&mut self, region: &RegionID) -> Result<()> {
+        let region = self
+            .regions
+            .get_mut(&region.0)
+            .ok_or_else(|| LinkerError::UnknownRegion(region.clone()))?;
+        region.dma = true;
+        Ok(())
+    }
+
+    /// Reserve memory the boot ROM uses while booting, such as a fuse
+    /// shadow area
+    ///
+    /// The [`family`](#method.family)'s ROM workspace is reserved without
+    /// this. When the boot ROM loads the image into RAM, the image may not
+    /// be stored in a reservation. The regions it is stored in may not start
+    /// in one, and the linker asserts the image ends before any reservation
+    /// above their origin.
+    pub fn reserve(&mut self, name: &str, origin: W, size: W) {
+        self.reservations.push(chip::Reservation {
+            name: String::from(name),
+            origin: origin.into(),
+            size: size.into(),
+        });
+    }
+
+    /// Skip the first `bytes` of `region`, such as the 8 KiB of flash a
+    /// vendor bootloader takes, while still describing the whole memory
+    ///
+    /// The region's MEMORY entry starts after the skipped bytes, so
+    /// sections are placed from there, while `__<region>_origin`,
+    /// `__<region>_size`, the layout and the generated predicates keep
+    /// the whole region. Fails with
+    /// [`InvalidReservation`](enum.LinkerError.html) for an alias, which
+    /// shares the reservation of the memory it views, or when nothing
+    /// would be left of the region.
+    pub fn reserve_start(&mut self, region: &RegionID, bytes: W) -> Result<()> {
+        let found = self
+            .regions
+            .get_mut(&region.0)
+            .ok_or_else(|| LinkerError::UnknownRegion(region.clone()))?;
+        let (bytes, size): (u64, u64) = (bytes.into(), found.size.into());
+        if let Some(of) = &found.alias_of {
+            return Err(LinkerError::InvalidReservation(format!(
+                "{} is a view of {}, reserve the start of {} instead",
+                found.name, of, of
+            )));
+        }
+        if bytes >= size {
+            return Err(LinkerError::InvalidReservation(format!(
+                "{:#X} bytes leave nothing of {}, which has {:#X}",
+                bytes, found.name, size
+            )));
+        }
+        found.skip = bytes;
+        Ok(())
+    }
+
+    /// Exclude `size` bytes at `origin` in `region` from the space a heap
+    /// or stack there fills, for example a DMA pool at a fixed address at
+    /// the top of OCRAM
+    ///
+    /// The heap and stack end at the lowest exclusion of their region
+    /// rather than at its end, and the script defines `__start_{name}`
+    /// and `__end_{name}`. Sections fill a region from its origin, so the
+    /// linker asserts they end before every exclusion. Fails with
+    /// [`InvalidExclusion`](enum.LinkerError.html) when the range is
+    /// empty, isn't in the region or overlaps another exclusion.
+    pub fn exclude(&mut self, region: &RegionID, name: &str, origin: W, size: W) -> Result<()> {
+        let found = self
+            .regions
+            .get(&region.0)
+            .ok_or_else(|| LinkerError::UnknownRegion(region.clone()))?;
+        let excluded = chip::Reservation {
+            name: String::from(name),
+            origin: origin.into(),
+            size: size.into(),
+        };
+        let start: u64 = found.origin.into();
+        let end = start + found.size.into();
+        if excluded.size == 0 || excluded.origin < start || excluded.end() > end {
+            return Err(LinkerError::InvalidExclusion(format!(
+                "{} is not a range in {}",
+                name, region.0
+            )));
+        }
+        let overlapped = self.exclusions.iter().find(|(other, reserved)| {
+            other == region && reserved.origin < excluded.end() && excluded.origin < reserved.end()
+        });
+        if let Some((_, reserved)) = overlapped {
+            return Err(LinkerError::InvalidExclusion(format!(
+                "{} overlaps {}",
+                name, reserved.name
+            )));
+        }
+        self.exclusions.push((region.clone(), excluded));
+        Ok(())
+    }
+
+    /// The exclusions of `region`, lowest first
+    fn excluded(&self, region: &str) -> Vec<&chip::Reservation> {
+        let mut excluded: Vec<&chip::Reservation> = self
+            .exclusions
+            .iter()
+            .filter(|(excluded, _)| excluded.0 == region)
+            .map(|(_, reserved)| reserved)
+            .collect();
+        excluded.sort_by_key(|reserved| reserved.origin);
+        excluded
+    }
+
+    /// Reservations the image must stay clear of, with the region storing
+    /// part of the image which they overlap, when the boot ROM loads the
+    /// image
+    fn reserved(&self) -> Vec<(&Region<W>, chip::Reservation)> {
+        match self.boot_mode {
+            Some(chip::BootMode::RamLoad) | Some(chip::BootMode::SerialDownload) => {}
+            _ => return Vec::new(),
+        }
+        let mut reservations = self.reservations.clone();
+        if let Some(family) = self.family {
+            reservations.extend(family.rom_reservations());
+        }
+        let mut reserved = Vec::new();
+        for region in self.stored() {
+            let origin: u64 = region.origin.into();
+            let end = origin + region.size.into();
+            for reservation in reservations.iter() {
+                if reservation.origin < end && origin < reservation.end() {
+                    reserved.push((region, reservation.clone()));
+                }
+            }
+        }
+        reserved
+    }
+
+    /// A copy of the script with `region` moved to `origin`, keeping its
+    /// size, for linking the same image to run from another slot
+    pub fn relocated(&self, region: &RegionID, origin: W) -> Result<LinkerScript<W>> {
+        let mut ls = self.clone();
+        ls.regions
+            .get_mut(&region.0)
+            .ok_or_else(|| LinkerError::UnknownRegion(region.clone()))?
+            .origin = origin;
+        Ok(ls)
+    }
+
+    /// Declare what a region's memory allows
+    ///
+    /// The region's MEMORY entry gets the kind's attributes, so the linker
+    /// rejects input sections which don't match them. When writing the
+    /// linker script, code must not run from a [`Data`](enum.RegionKind.html)
+    /// region and writable sections must not run from a
+    /// [`Flash`](enum.RegionKind.html) region. Aliases declared after this
+    /// share the kind.
+    pub fn region_kind(&mut self, region: &RegionID, kind: RegionKind) -> Result<()> {
+        let region = self
+            .regions
+            .get_mut(&region.0)
+            .ok_or_else(|| LinkerError::UnknownRegion(region.clone()))?;
+        region.kind = Some(kind);
+        Ok(())
+    }
+
+    /// Mark a region as encrypted, decrypted on the fly by the engine of
+    /// the chip [`family`](#method.family)
+    ///
+    /// When writing the linker script, the encrypted regions must fit the
+    /// engine's count of address ranges and their alignment, and the FCB
+    /// and key blob boot config sections must not be in them. The generated
+    /// `regions.rs` lists the ranges for programming the engine.
+    pub fn encrypted(&mut self, region: &RegionID) -> Result<()> {
+        let region = self
+            .regions
+            .get_mut(&region.0)
+            .ok_or_else(|| LinkerError::UnknownRegion(region.clone()))?;
+        region.encrypted = true;
+        Ok(())
+    }
+
+    /// Address ranges of the encrypted regions, as `(start, end)` with
+    /// adjacent regions merged, for programming the engine
+    pub fn encrypted_ranges(&self) -> Vec<(u64, u64)> {
+        let mut ranges: Vec<(u64, u64)> = self
+            .regions
+            .values()
+            .filter(|region| region.encrypted)
+            .map(|region| {
+                let origin = region.origin.into();
+                (origin, origin + region.size.into())
+            })
+            .collect();
+        ranges.sort_unstable();
+        let mut merged: Vec<(u64, u64)> = Vec::new();
+        for (start, end) in ranges {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        merged
+    }
+
+    /// Check the boot sections against the boot ROM of a chip family
+    ///
+    /// When writing the linker script, the boot config section named
+    /// "fcb" must be at the offset from the start of FlexSPI flash that
+    /// the family's boot ROM reads it from.
+    pub fn family(&mut self, family: chip::Family) {
+        self.family = Some(family);
+    }
+
+    /// Boot from flash on `flexspi` rather than the family's default
+    /// controller, as selected by the fuses
+    ///
+    /// The FCB and an XIP vector table are then checked against the
+    /// address this controller maps flash to, for example 0x7000_0000
+    /// rather than 0x6000_0000 for FlexSPI2 on an RT1060.
+    pub fn boot_flexspi(&mut self, flexspi: chip::FlexSpi) {
+        self.flexspi = Some(flexspi);
+    }
+
+    /// Check the vector table is reachable when the boot ROM starts the
+    /// image in `mode`
+    ///
+    /// In any mode the boot ROM reads the vector table before `init`
+    /// runs, so it may not have a load region. With a [`family`], an XIP
+    /// vector table must be in the region holding FlexSPI flash and a RAM
+    /// loaded one must not be. A serial download image's vector table must
+    /// also follow its [`serial_download_ivt`].
+    ///
+    /// [`serial_download_ivt`]: #method.serial_download_ivt
+    ///
+    /// [`family`]: #method.family
+    pub fn boot_mode(&mut self, mode: chip::BootMode) {
+        self.boot_mode = Some(mode);
+    }
+
+    /// Turn lints into errors when writing the linker script
+    pub fn strict(&mut self, strict: bool) {
+        self.validation(if strict {
+            lint::Validation::Strict
+        } else {
+            lint::Validation::Permissive
+        });
+    }
+
+    /// Choose whether writing the script fails on lints, or returns them
+    /// as warnings
+    pub fn validation(&mut self, validation: lint::Validation) {
+        self.validation = validation;
+    }
+
+    /// Check the script for likely mistakes which still generate a valid
+    /// linker script, such as regions no section uses, regions nearly
+    /// filled by fixed sizes, code next to writable sections or sections
+    /// whose order in a region is only decided by their names
+    pub fn lints(&self) -> Vec<lint::Warning> {
+        let mut warnings = Vec::new();
+        let align = std::mem::align_of::<W>() as u64;
+        let mut regions: Vec<&Region<W>> = self.regions.values().collect();
+        regions.sort_by(|a, b| a.name.cmp(&b.name));
+        for region in regions {
+            let name = &region.name;
+            let used = self.sections.values().any(|section| {
+                &section.vma.0 == name || section.lma.as_ref().map(|lma| &lma.0) == Some(name)
+            });
+            if !used && !region.reserved {
+                warnings.push(lint::Warning::UnusedRegion(name.clone()));
+            }
+            let (origin, size): (u64, u64) = (region.origin.into(), region.size.into());
+            if origin % align != 0 || size % align != 0 {
+                warnings.push(lint::Warning::UnalignedRegion(name.clone(), align));
+            }
+            // Sections in any view of the memory take from it
+            let in_memory = |region: &RegionID| self.physical(&region.0) == name;
+            let taken: u64 = region.skip
+                + self
+                    .sections
+                    .values()
+                    .map(|section| match section.size {
+                        SectionSize::Fixed(fixed)
+                            if in_memory(&section.vma)
+                                || section.lma.as_ref().is_some_and(in_memory) =>
+                        {
+                            fixed.into()
+                        }
+                        SectionSize::FixedStack(fixed) if in_memory(&section.vma) => fixed.into(),
+                        SectionSize::Stack | SectionSize::Heap if in_memory(&section.vma) => {
+                            section.min_size.map_or(0, Into::into)
+                        }
+                        _ => 0,
+                    })
+                    .sum::<u64>();
+            if region.alias_of.is_none() && taken * 100 >= size * NEARLY_FULL_PERCENT {
+                warnings.push(lint::Warning::NearlyFull(name.clone(), taken, size));
+            }
+        }
+
+        let mut regions: Vec<&String> = self.regions.keys().collect();
+        regions.sort();
+        for region in regions {
+            let mut code = Vec::new();
+            let mut written = Vec::new();
+            for section in self.sections.values().filter(|s| &s.vma.0 == region) {
+                if section.is_code() {
+                    code.push(section.output_name());
+                } else if section.is_writable() {
+                    written.push(section.output_name());
+                }
+            }
+            if !code.is_empty() && !written.is_empty() {
+                code.sort();
+                written.sort();
+                warnings.push(lint::Warning::WritableCode(region.clone(), code, written));
+            }
+        }
+
+        let mut regions: Vec<&Region<W>> = self.regions.values().collect();
+        regions.sort_by(|a, b| a.name.cmp(&b.name));
+        for (i, a) in regions.iter().enumerate() {
+            for b in regions[i + 1..].iter() {
+                if self.physical(&a.name) == self.physical(&b.name)
+                    || self.carved(&a.name, &b.name)
+                    || self.carved(&b.name, &a.name)
+                {
+                    continue;
+                }
+                let (a_origin, b_origin): (u64, u64) = (a.origin.into(), b.origin.into());
+                let a_end = a_origin + a.size.into();
+                let b_end = b_origin + b.size.into();
+                if a_origin < b_end && b_origin < a_end {
+                    warnings.push(lint::Warning::OverlappingRegions(
+                        a.name.clone(),
+                        b.name.clone(),
+                    ));
+                }
+            }
+        }
+
+        let mut ignored: Vec<String> = self
+            .sections
+            .values()
+            .filter(|section| match section.size {
+                SectionSize::Linker | SectionSize::Fixed(_) => section.min_size.is_some(),
+                _ => false,
+            })
+            .map(Section::output_name)
+            .collect();
+        ignored.sort();
+        warnings.extend(ignored.into_iter().map(lint::Warning::IgnoredMinSize));
+
+        // A spill section, or the code left out of a partition taking all
+        // of it, is only filled by GNU ld
+        let mut spilled: Vec<(String, String)> = self
+            .sections
+            .values()
+            .filter_map(|section| match &section.spill_of {
+                Some(first) => Some((first.clone(), section.output_name())),
+                None if section.name == "text" && section.prefix && section.files.is_empty() => {
+                    Some((section.output_name(), String::from("text")))
+                }
+                None => None,
+            })
+            .collect();
+        spilled.sort();
+        warnings.extend(
+            spilled
+                .into_iter()
+                .map(|(section, into)| lint::Warning::NonContiguousSpill(section, into)),
+        );
+
+        let mut placed: Vec<(&String, i32, i32, String)> = self
+            .sections
+            .values()
+            .map(|section| {
+                (
+                    &section.vma.0,
+                    section.priority,
+                    section.nudge,
+                    section.output_name(),
+                )
+            })
+            .collect();
+        placed.sort();
+        for group in placed.chunk_by(|a, b| (a.0, a.1, a.2) == (b.0, b.1, b.2)) {
+            if group.len() > 1 {
+                warnings.push(lint::Warning::PriorityCollision(
+                    group[0].0.clone(),
+                    group[0].1,
+                    group.iter().map(|(_, _, _, name)| name.clone()).collect(),
+                ));
+            }
+        }
+        warnings
+    }
+
+    /// Require a stack or heap section to be at least `size` bytes
+    ///
+    /// Stack and heap sections take whatever space is left in their region,
+    /// the generated script asserts that this is no less than `size`. See
+    /// the [`stack`](stack/index.html) module for deriving a size from the
+    /// measured stack frames of a previous build.
+    pub fn min_size(&mut self, section: &SectionID, size: W) -> Result<()> {
+        let section = self
+            .sections
+            .get_mut(&section.0)
+            .ok_or_else(|| LinkerError::UnknownSection(section.clone()))?;
+        section.min_size = Some(size);
+        Ok(())
+    }
+
+    /// The region whose memory `region` views, `region` itself unless it
+    /// is an alias
+    fn physical<'a>(&'a self, region: &'a str) -> &'a str {
+        self.regions
+            .get(region)
+            .and_then(|region| region.alias_of.as_deref())
+            .unwrap_or(region)
+    }
+
+    /// True if `region` was carved out of `parent`, or out of a region
+    /// carved out of it
+    fn carved(&self, region: &str, parent: &str) -> bool {
+        let mut carved_from = self.regions[region].carved_from.as_deref();
+        while let Some(from) = carved_from {
+            if from == parent {
+                return true;
+            }
+            carved_from = self
+                .regions
+                .get(from)
+                .and_then(|found| found.carved_from.as_deref());
+        }
+        false
+    }
+
+    /// True if the memory of `region` has more than one view
+    fn is_aliased(&self, region: &str) -> bool {
+        let physical = self.physical(region);
+        physical != region
+            || self
+                .regions
+                .values()
+                .any(|region| region.alias_of.as_deref() == Some(physical))
+    }
+
+    fn check_boot_offset(&self, family: chip::Family) -> Result<()> {
+        const FCB: &str = "fcb";
+        if !self.sections.contains_key(FCB) {
+            return Ok(());
+        }
+        let expected = self.flash_base(family)? + family.fcb_offset();
+        let actual = self.layout().fixed_address(FCB).map(|(address, _)| address);
+        if actual != Some(expected) {
+            return Err(LinkerError::BootOffset {
+                section: String::from(FCB),
+                family,
+                expected,
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    /// Address the boot ROM reads flash from, through the selected FlexSPI
+    fn flash_base(&self, family: chip::Family) -> Result<u64> {
+        let flexspi = self.flexspi.unwrap_or_else(|| family.default_flexspi());
+        family
+            .flexspi_base(flexspi)
+            .ok_or(LinkerError::UnsupportedFlexSpi { family, flexspi })
+    }
+
+    /// Regions the image is stored in, which hold a section's load image
+    fn stored(&self) -> Vec<&Region<W>> {
+        let mut regions: Vec<&Region<W>> = self
+            .sections
+            .values()
+            .filter(|section| section.has_contents())
+            .filter_map(|section| {
+                self.regions
+                    .get(&section.lma.as_ref().unwrap_or(&section.vma).0)
+            })
+            .collect();
+        regions.sort_by(|a, b| a.name.cmp(&b.name));
+        regions.dedup_by(|a, b| a.name == b.name);
+        regions
+    }
+
+    /// Check the sections run from regions whose kind allows them
+    fn check_access(&self) -> Result<()> {
+        let mut sections: Vec<&Section<W>> = self.sections.values().collect();
+        sections.sort_by(|a, b| a.name.cmp(&b.name));
+        for section in sections {
+            let kind = match self.regions.get(&section.vma.0).and_then(|r| r.kind) {
+                Some(kind) => kind,
+                None => continue,
+            };
+            let denied = match kind {
+                RegionKind::Data if section.is_code() => "code",
+                RegionKind::Flash if section.is_writable() => "writable",
+                _ => continue,
+            };
+            return Err(LinkerError::InvalidAccess(format!(
+                "{} section .{} runs from {} region {}",
+                denied,
+                section.output_name(),
+                kind,
+                section.vma.0
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_tiny(&self) -> Result<()> {
+        if !self.tiny {
+            return Ok(());
+        }
+        let mut sections: Vec<&Section<W>> = self.sections.values().collect();
+        sections.sort_by(|a, b| self.placement_order(a, b));
+        let section = sections.iter().find_map(|section| {
+            if matches!(section.size, SectionSize::Heap) {
+                Some(String::from("heap"))
+            } else if section.prefix {
+                Some(format!("prefixed section .{}", section.output_name()))
+            } else if section.compressed {
+                Some(String::from("compressed sections"))
+            } else if section.dma_tcd.is_some() {
+                Some(String::from("eDMA copied sections"))
+            } else if !section.views.is_empty() {
+                Some(format!("alias views of .{}", section.output_name()))
+            } else {
+                None
+            }
+        });
+        let feature = section.or_else(|| {
+            [
+                (self.position_independent, "position independent layout"),
+                (self.pack_loads, "packed sections"),
+                (self.sections.contains_key("tls"), "thread local storage"),
+                (self.aeabi_memcpy, "EABI copy routines"),
+                (self.dcache_before_init, "cache maintenance"),
+                (self.stack_guard.is_some(), "stack protector"),
+                (self.reset_stub, "reset stub"),
+                (self.second_core_mailbox.is_some(), "second core"),
+            ]
+            .iter()
+            .find(|(used, _)| *used)
+            .map(|(_, feature)| String::from(*feature))
+        });
+        match feature {
+            Some(feature) => Err(LinkerError::NotTiny(feature)),
+            None => Ok(()),
+        }
+    }
+
+    fn check_reservations(&self) -> Result<()> {
+        for (region, reservation) in self.reserved() {
+            if reservation.origin <= region.origin.into() {
+                return Err(LinkerError::ReservedMemory {
+                    region: region.name.clone(),
+                    reservation: reservation.name,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn check_encryption(&self) -> Result<()> {
+        let ranges = self.encrypted_ranges();
+        if ranges.is_empty() {
+            return Ok(());
+        }
+        let invalid = |reason: String| Err(LinkerError::InvalidEncryption(reason));
+        let family = match self.family {
+            Some(family) => family,
+            None => return invalid(String::from("set the chip family to pick the engine")),
+        };
+        let engine = match family.encryption_engine() {
+            Some(engine) => engine,
+            None => return invalid(format!("the {} can't decrypt flash", family)),
+        };
+        if ranges.len() > engine.max_regions() {
+            return invalid(format!(
+                "{} ranges are encrypted but the {} decrypts at most {}",
+                ranges.len(),
+                engine,
+                engine.max_regions()
+            ));
+        }
+        for &(start, end) in ranges.iter() {
+            if !start.is_multiple_of(engine.alignment()) || !end.is_multiple_of(engine.alignment())
+            {
+                return invalid(format!(
+                    "{:#010X}..{:#010X} is not aligned to {:#X} bytes as the {} needs",
+                    start,
+                    end,
+                    engine.alignment(),
+                    engine
+                ));
+            }
+        }
+        let layout = self.layout();
+        for name in chip::PLAINTEXT_SECTIONS.iter() {
+            let section = match self.sections.get(*name) {
+                Some(section) => section,
+                None => continue,
+            };
+            let encrypted = match layout.fixed_address(name) {
+                Some((address, size)) => ranges
+                    .iter()
+                    .any(|&(start, end)| address < end && start < address + size),
+                None => self.regions[&section.vma.0].encrypted,
+            };
+            if encrypted {
+                return invalid(format!(
+                    "the boot ROM reads .{} before decrypting, move it out of the encrypted regions",
+                    name
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_vector_table(&self, mode: chip::BootMode) -> Result<()> {
+        let section = match self.sections.get("vector_table") {
+            Some(section) => section,
+            None => return Ok(()),
+        };
+        let base = match self.family {
+            Some(family) => Some(self.flash_base(family)?),
+            None => None,
+        };
+        let in_flash = base.and_then(|base| {
+            let region = self.regions.get(&section.vma.0)?;
+            let origin = region.origin.into();
+            Some(origin <= base && base - origin < region.size.into())
+        });
+        let reachable = section.lma.is_none()
+            && match mode {
+                chip::BootMode::Xip => in_flash != Some(false),
+                chip::BootMode::RamLoad => in_flash != Some(true),
+                chip::BootMode::SerialDownload => {
+                    in_flash != Some(true)
+                        && self
+                            .sections
+                            .get("ivt")
+                            .is_none_or(|ivt| ivt.vma == section.vma)
+                }
+            };
+        if !reachable {
+            return Err(LinkerError::UnreachableVectorTable {
+                mode,
+                vma: section.vma.0.clone(),
+                lma: section.lma.as_ref().map(|lma| lma.0.clone()),
+            });
+        }
+        Ok(())
+    }
+
+    fn add_section(&mut self, section: Section<W>) -> Result<SectionID> {
+        let name = section.output_name();
+        if self.sections.contains_key(&name) {
+            return Err(LinkerError::DuplicateSection(name.clone()));
+        }
+        let known = || {
+            let mut known: Vec<String> = self.regions.keys().cloned().collect();
+            known.sort();
+            known
+        };
+        let vma = match self.regions.get(&section.vma.0) {
+            Some(vma) => vma,
+            None => {
+                return Err(LinkerError::UnknownVMA {
+                    section: name,
+                    region: section.vma.clone(),
+                    known: known(),
+                })
+            }
+        };
+        if let Some(lma) = &section.lma {
+            if !self.regions.contains_key(&lma.0) {
+                return Err(LinkerError::UnknownLMA {
+                    section: name,
+                    region: lma.clone(),
+                    known: known(),
+                });
+            }
+        }
+        let reserved = Some(&section.vma)
+            .into_iter()
+            .chain(section.lma.as_ref())
+            .find(|region| self.regions[&region.0].reserved);
+        if let Some(region) = reserved {
+            return Err(LinkerError::ReservedRegion {
+                section: name,
+                region: region.0.clone(),
+            });
+        }
+        if let SectionSize::Fixed(size) | SectionSize::FixedStack(size) = section.size {
+            if size.into() == 0 {
+                return Err(LinkerError::ZeroSizeSection(name));
+            }
+            if size.into() > vma.size.into() {
+                return Err(LinkerError::SectionTooLarge {
+                    section: name,
+                    region: vma.name.clone(),
+                    size: size.into(),
+                    available: vma.size.into(),
+                });
+            }
+        }
+        let stack_size = match section.size {
+            SectionSize::Stack => Some(vma.size.into()),
+            SectionSize::FixedStack(size) => Some(size.into()),
+            _ => None,
+        };
+        if let Some(size) = stack_size.filter(|size| *size < MIN_STACK_SIZE) {
+            return Err(LinkerError::StackTooSmall {
+                section: name,
+                region: vma.name.clone(),
+                size,
+            });
+        }
+        self.sections.insert(name.clone(), section);
+        Ok(SectionID(name.clone()))
+    }
+
+    /// Generate a linker script and matching reset module
+    /// which correctly initializes sections.
+    ///
+    /// The function places a linker script file, called `link.x`, in
+    /// the current working directory.
+    ///
+    /// The reset module, `reset.rs`, is written alongside it to be
+    /// included by the crate providing the reset handler, as is
+    /// `regions.rs`, a module of address predicates such as `in_dtcm` for
+    /// drivers to check buffer placement. A description of the layout,
+    /// `layout.json`, is also written for use by post-build tools such as
+    /// `imxrt-size`. With [`second_core_init`](#method.second_core_init),
+    /// `second_core.rs` is written for the second core's firmware, with a
+    /// [version string](#method.version_string) `version.rs`, and with
+    /// prefixed sections `placement.rs`, see
+    /// [`write_placement`](#method.write_placement).
+    ///
+    /// Returns the script's lints, as [`write`](#method.write) does.
+    pub fn generate(self) -> Result<Vec<lint::Warning>> {
+        self.generate_to(".")
+    }
+
+    /// Generate the files of [`generate`](#method.generate) in the
+    /// directory `dir` rather than the current working directory
+    ///
+    /// Every file is rendered before any is written, so a script which
+    /// fails, such as on a denied lint, leaves the files of a previous
+    /// run as they were.
+    pub fn generate_to<P: AsRef<Path>>(self, dir: P) -> Result<Vec<lint::Warning>> {
+        let dir = dir.as_ref();
+        let layout = self.layout();
+        let mut reset = Vec::new();
+        self.write_reset(&mut reset)?;
+        let mut regions = Vec::new();
+        self.write_regions(&mut regions)?;
+        let version = if self.sections.contains_key("version") {
+            let mut version = Vec::new();
+            self.write_version(&package_version(), &mut version)?;
+            Some(version)
+        } else {
+            None
+        };
+        let placement = if self.sections.values().any(|section| section.prefix) {
+            let mut placement = Vec::new();
+            self.write_placement(&mut placement)?;
+            Some(placement)
+        } else {
+            None
+        };
+        let second_core = match self.second_core_mailbox {
+            Some(_) => {
+                let mut second_core = Vec::new();
+                self.write_second_core(&mut second_core)?;
+                Some(second_core)
+            }
+            None => None,
+        };
+        let mut link_x = Vec::new();
+        let warnings = self.write(&mut link_x)?;
+        write_file(dir.join("link.x"), &link_x)?;
+        write_file(dir.join("reset.rs"), &reset)?;
+        write_file(dir.join("regions.rs"), &regions)?;
+        write_file(dir.join("layout.json"), layout.to_json().as_bytes())?;
+        if let Some(second_core) = second_core {
+            write_file(dir.join("second_core.rs"), &second_core)?;
+        }
+        if let Some(version) = version {
+            write_file(dir.join("version.rs"), &version)?;
+        }
+        if let Some(placement) = placement {
+            write_file(dir.join("placement.rs"), &placement)?;
+        }
+        Ok(warnings)
+    }
+
+    /// Generate every artifact into the directory `dir`, with a manifest
+    ///
+    /// Writes the files of [`generate_to`](#method.generate_to), the C
+    /// header `memory_map.h` and, when `interrupts` isn't empty,
+    /// `device.x`. `manifest.json` lists each file written with its size in
+    /// bytes, and the lints, so a build system can check what it has to
+    /// pick up.
+    pub fn generate_all<P: AsRef<Path>>(
+        self,
+        dir: P,
+        interrupts: &[device::Interrupt],
+    ) -> Result<Vec<lint::Warning>> {
+        let dir = dir.as_ref();
+        let mut header = Vec::new();
+        self.write_header(&mut header)?;
+        let mut files = vec![
+            "link.x",
+            "reset.rs",
+            "regions.rs",
+            "layout.json",
+            "memory_map.h",
+        ];
+        if self.second_core_mailbox.is_some() {
+            files.push("second_core.rs");
+        }
+        if self.sections.contains_key("version") {
+            files.push("version.rs");
+        }
+        if self.sections.values().any(|section| section.prefix) {
+            files.push("placement.rs");
+        }
+        let warnings = self.generate_to(dir)?;
+        write_file(dir.join("memory_map.h"), &header)?;
+        if !interrupts.is_empty() {
+            write_file(dir.join("device.x"), device::render(interrupts).as_bytes())?;
+            files.push("device.x");
+        }
+
+        let mut entries = Vec::new();
+        for file in files {
+            let path = dir.join(file);
+            let size = std::fs::metadata(&path)
+                .map_err(|source| LinkerError::File {
+                    path: path.display().to_string(),
+                    source,
+                })?
+                .len();
+            entries.push(json::Value::Object(vec![
+                (String::from("name"), file.into()),
+                (String::from("size"), size.into()),
+            ]));
+        }
+        let manifest = json::Value::Object(vec![
+            (String::from("files"), json::Value::Array(entries)),
+            (
+                String::from("warnings"),
+                json::Value::Array(
+                    warnings
+                        .iter()
+                        .map(|warning| warning.to_string().into())
+                        .collect(),
+                ),
+            ),
+        ]);
+        write_file(
+            dir.join("manifest.json"),
+            format!("{}\n", manifest).as_bytes(),
+        )?;
+        Ok(warnings)
+    }
+
+    /// Generate a supplement to cortex-m-rt's stock linker script
+    ///
+    /// Instead of replacing cortex-m-rt's `link.x`, the function places
+    /// `insert.x` in the current working directory. It describes only the
+    /// regions other than FLASH and RAM, which cortex-m-rt takes from
+    /// `memory.x`, and the sections which are prefixed or placed in those
+    /// regions, inserted after cortex-m-rt's `.bss`. Link with
+    /// `-Tlink.x -Tinsert.x` to add, for example, TCM sections to a
+    /// cortex-m-rt application.
+    pub fn generate_insert(self) -> Result<()> {
+        let mut insert_x = create("insert.x")?;
+        self.write_insert(&mut insert_x)
+    }
+
+    /// Write the cortex-m-rt supplement into the writer, `insert_x`
+    pub fn write_insert<Wr: Write>(mut self, insert_x: &mut Wr) -> Result<()> {
+        self.program_headers = false;
+        generate::link::render_insert(&self, insert_x)?;
+        Ok(())
+    }
+
+    /// Describe the regions and sections as a [`Layout`](layout/struct.Layout.html)
+    pub fn layout(&self) -> layout::Layout {
+        layout::Layout::from_linker_script(self)
+    }
+
+    /// Write the reset module into the writer, `reset_rs`
+    pub fn write_reset<Wr: Write>(&self, reset_rs: &mut Wr) -> Result<()> {
+        generate::reset::render(self, reset_rs)?;
+        Ok(())
+    }
+
+    /// Write the second core's share of the reset into the writer,
+    /// `second_core_rs`, see [`second_core_init`](#method.second_core_init)
+    pub fn write_second_core<Wr: Write>(&self, second_core_rs: &mut Wr) -> Result<()> {
+        generate::reset::render_second_core(self, second_core_rs)?;
+        Ok(())
+    }
+
+    /// Write the address predicates module into the writer, `regions_rs`
+    pub fn write_regions<Wr: Write>(&self, regions_rs: &mut Wr) -> Result<()> {
+        self.check_constants()?;
+        generate::predicates::render(self, regions_rs)?;
+        Ok(())
+    }
+
+    /// Write the placement module into the writer, `placement_rs`
+    ///
+    /// Each prefixed section gets a constant naming its input section and
+    /// a macro placing items there, such as `dtcm_data!` for `.DTCM.data`,
+    /// so application code and the linker script can't drift apart.
+    /// Statics placed in the writable sections of
+    /// [DMA capable](#method.dma_capable) regions must be aligned to the
+    /// data cache line, with the generated `CacheAligned` for example.
+    pub fn write_placement<Wr: Write>(&self, placement_rs: &mut Wr) -> Result<()> {
+        generate::placement::render(self, placement_rs)?;
+        Ok(())
+    }
+
+    /// Write the static of the [version string](#method.version_string)
+    /// section into the writer, `version_rs`, holding `version`
+    ///
+    /// [`package_version`](fn.package_version.html) describes the package
+    /// whose build script runs.
+    pub fn write_version<Wr: Write>(&self, version: &str, version_rs: &mut Wr) -> Result<()> {
+        generate::version::render(version, version_rs)?;
+        Ok(())
+    }
+
+    /// Write the C header of region origins and sizes into the writer,
+    /// `memory_map_h`
+    pub fn write_header<Wr: Write>(&self, memory_map_h: &mut Wr) -> Result<()> {
+        generate::header::render(self, memory_map_h)?;
+        Ok(())
+    }
+
+    /// Write the JSON layout description into the writer, `out`
+    pub fn write_layout<Wr: Write>(&self, out: &mut Wr) -> Result<()> {
+        out.write_all(self.layout().to_json().as_bytes())?;
+        Ok(())
+    }
+
+    /// Run every check of [`write`](#method.write) without writing
+    /// anything
+    ///
+    /// Required sections, boot offsets, the vector table and the features
+    /// of a tiny script are checked and the lints returned, or they fail
+    /// the check when the script is strict.
+    pub fn check(self) -> Result<Vec<lint::Warning>> {
+        self.write(&mut std::io::sink())
+    }
+
+    /// Write the linker script into the writer, `link_x`
+    ///
+    /// Returns the script's [`lints`](#method.lints) for the caller to
+    /// report, or fails with them when the script is strict.
+    pub fn write<Wr: Write>(self, link_x: &mut Wr) -> Result<Vec<lint::Warning>> {
+        const REQ_SEC_NAMES: [&str; 6] = ["stack", "vector_table", "text", "data", "rodata", "bss"];
+        for req_sec_name in REQ_SEC_NAMES.iter() {
+            let name = String::from(*req_sec_name);
+            if !self.sections.contains_key(&name) {
+                return Err(LinkerError::MissingSection(name));
+            }
+        }
+        if let Some(family) = self.family {
+            self.check_boot_offset(family)?;
+        }
+        if let Some(mode) = self.boot_mode {
+            self.check_vector_table(mode)?;
+        }
+        self.check_encryption()?;
+        self.check_reservations()?;
+        self.check_access()?;
+        self.check_tiny()?;
+        let warnings = self.lints();
+        if self.validation == lint::Validation::Strict && !warnings.is_empty() {
+            return Err(LinkerError::Lints(warnings));
+        }
+        generate::link::render(&self, link_x)?;
+        Ok(warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn generate_ok() {
+        let mut ls = LinkerScript::<u32>::new();
+        let flash = ls.region(FLASH, 0x0, 512).unwrap();
+        let ram = ls.region(RAM, 0x20000000, 1024).unwrap();
+        ls.stack(ram.clone()).unwrap();
+        ls.heap(ram.clone()).unwrap();
+        ls.boot_config(512, "fcb", flash.clone()).unwrap();
+        ls.vector_table(flash.clone(), Some(ram.clone())).unwrap();
+        ls.text(flash.clone(), Some(ram.clone())).unwrap();
+        ls.data(false, flash.clone(), Some(ram.clone())).unwrap();
+        ls.rodata(false, flash.clone(), None).unwrap();
+        ls.bss(false, flash.clone(), Some(ram.clone())).unwrap();
+        ls.generate().unwrap();
+    }
+
+    //
+    // The 'rejects_*' tests show that we reject linker scripts that are missing
+    // our required sections.
+    //
+
+    #[derive(PartialEq, Eq)]
+    enum Required {
+        Stack,
+        VectorTable,
+        Text,
+        Data,
+        ROData,
+        Bss,
+    }
+
+    impl fmt::Display for Required {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str(match self {
+                Required::Stack => "stack",
+                Required::VectorTable => "vector_table",
+                Required::Text => "text",
+                Required::Data => "data",
+                Required::ROData => "rodata",
+                Required::Bss => "bss",
+            })
+        }
+    }
+
+    fn reject_missing(required: Required) {
+        let mut ls = LinkerScript::<u32>::new();
+        let flash = ls.region(FLASH, 0x0, 512).unwrap();
+        let ram = ls.region(RAM, 0x20000000, 1024).unwrap();
+        if Required::Stack != required {
+            ls.stack(ram.clone()).unwrap();
+        }
+        if Required::VectorTable != required {
+            ls.vector_table(flash.clone(), Some(ram.clone())).unwrap();
+        }
+        if Required::Text != required {
+            ls.text(flash.clone(), Some(ram.clone())).unwrap();
+        }
+        if Required::Data != required {
+            ls.data(false, flash.clone(), Some(ram.clone())).unwrap();
+        }
+        if Required::ROData != required {
+            ls.rodata(false, flash.clone(), None).unwrap();
+        }
+        if Required::Bss != required {
+            ls.bss(false, flash.clone(), Some(ram.clone())).unwrap();
+        }
+        match ls.generate() {
+            Err(LinkerError::MissingSection(section)) if section == required.to_string() => {}
+            result => panic!("Expected missing {}, but got {:?}", required, result),
+        };
+    }
+
+    #[test]
+    fn rejects_missing_vector_table() {
+        reject_missing(Required::VectorTable);
+    }
+
+    #[test]
+    fn rejects_missing_stack() {
+        reject_missing(Required::Stack);
+    }
+
+    #[test]
+    fn rejects_missing_text() {
+        reject_missing(Required::Text);
+    }
+
+    #[test]
+    fn rejects_missing_data() {
+        reject_missing(Required::Data);
+    }
+
+    #[test]
+    fn rejects_missing_rodata() {
+        reject_missing(Required::ROData);
+    }
+
+    #[test]
+    fn rejects_missing_bss() {
+        reject_missing(Required::Bss);
+    }
+
+    #[test]
+    fn builder_places_required_sections() {
+        let mut builder = builder::LinkerScriptBuilder::<u32>::new();
+        let flash = builder.region(FLASH, 0x60000000, 0x1000).unwrap();
+        let ram = builder.region(RAM, 0x20000000, 0x400).unwrap();
+        builder.script().heap(ram.clone()).unwrap();
+        let mut builder = builder
+            .stack(ram.clone())
+            .unwrap()
+            .vector_table(flash.clone(), None)
+            .unwrap()
+            .text(flash.clone(), None)
+            .unwrap();
+        match builder.script().text(flash.clone(), None) {
+            Err(LinkerError::DuplicateSection(_)) => {}
+            result => panic!("Expected duplicate section, but got {:?}", result),
+        }
+        let ls = builder
+            .rodata(false, flash.clone(), None)
+            .unwrap()
+            .data(false, ram.clone(), Some(flash))
+            .unwrap()
+            .bss(false, ram, None)
+            .unwrap()
+            .build();
+        let script = render(ls.clone());
+        assert!(script.contains("__start_heap"));
+        ls.check().unwrap();
+    }
+
+    fn example() -> LinkerScript<u32> {
+        let mut ls = LinkerScript::<u32>::new();
+        let flash = ls.region(FLASH, 0x60000000, 0x1000).unwrap();
+        let ram = ls.region(RAM, 0x20000000, 0x400).unwrap();
+        ls.stack(ram.clone()).unwrap();
+        ls.vector_table(flash.clone(), None).unwrap();
+        ls.text(flash.clone(), None).unwrap();
+        ls.data(false, ram.clone(), Some(flash.clone())).unwrap();
+        ls.data(true, ram.clone(), Some(flash.clone())).unwrap();
+        ls.rodata(false, flash.clone(), None).unwrap();
+        ls.bss(false, ram.clone(), None).unwrap();
+        ls
+    }
+
+    fn render(ls: LinkerScript<u32>) -> String {
+        let mut out = Vec::new();
+        ls.write(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn prefixed_sections_are_named_by_region() {
+        let script = render(example());
+        assert!(script.contains("\t.RAM.data :"));
+        assert!(script.contains("*(.RAM.data .RAM.data.*);"));
+        assert!(script.contains("__start_RAM_data = .;"));
+        assert!(script.contains("__load_RAM_data = LOADADDR(.RAM.data);"));
+        assert!(script.contains("\t.data :"));
+    }
+
+    #[test]
+    fn layout_json_round_trip() {
+        let layout = example().layout();
+        assert_eq!(layout.regions[0].name, RAM);
+        assert_eq!(layout.section(".RAM.data").unwrap().name, "data");
+        let parsed = layout::Layout::from_json(&layout.to_json()).unwrap();
+        assert_eq!(parsed, layout);
+    }
+
+    #[test]
+    fn size_report_groups_by_region() {
+        let mut builder = elf::testing::ElfBuilder::default();
+        builder.progbits(".vector_table", 0x60000000, 0x60000000, &[0; 0x40]);
+        builder.progbits(".text", 0x60000040, 0x60000040, &[0; 0x100]);
+        builder.progbits(".RAM.data", 0x20000000, 0x60000140, &[0; 0x10]);
+        builder.nobits(".bss", 0x20000010, 0x20);
+        builder.progbits(".mystery", 0x20000100, 0x20000100, &[0; 4]);
+        let elf = elf::Elf::parse(builder.build()).unwrap();
+
+        let report = size::report(&example().layout(), &elf);
+        let flash = report.region(FLASH).unwrap();
+        assert_eq!(flash.used(), 0x150);
+        let load = flash.sections.iter().find(|s| s.load_copy).unwrap();
+        assert_eq!(load.name, ".RAM.data");
+        assert_eq!(load.address, 0x60000140);
+        let ram = report.region(RAM).unwrap();
+        assert_eq!(ram.used(), 0x34);
+        assert!(ram
+            .sections
+            .iter()
+            .any(|s| s.name == ".mystery" && s.orphan));
+    }
+
+    #[test]
+    fn crate_names_from_mangled_symbols() {
+        assert_eq!(
+            size::crate_name("_ZN4core3fmt5write17h0123456789abcdefE").unwrap(),
+            "core"
+        );
+        assert_eq!(
+            size::crate_name("_RNvNtCs1234_7smoltcp5iface4poll").unwrap(),
+            "smoltcp"
+        );
+        assert_eq!(size::crate_name("_RNvCsabc_3app4main").unwrap(), "app");
+        assert_eq!(size::crate_name("memcpy"), None);
+
+        // <app::Pin as core::fmt::Debug>::fmt, then with &T as the type
+        assert_eq!(
+            size::crate_name(
+                "_ZN45_$LT$app..Pin$u20$as$u20$core..fmt..Debug$GT$3fmt17h0123456789abcdefE"
+            )
+            .unwrap(),
+            "app"
+        );
+        assert_eq!(
+            size::crate_name(
+                "_ZN42_$LT$$RF$T$u20$as$u20$core..fmt..Debug$GT$3fmt17h0123456789abcdefE"
+            )
+            .unwrap(),
+            "core"
+        );
+        // impl hal::Pin { fn set } in app
+        assert_eq!(
+            size::crate_name("_RNvMCs1234_3appNtCs5678_3hal3Pin3set").unwrap(),
+            "app"
+        );
+        // impl core::fmt::Debug for hal::Pin in app
+        assert_eq!(
+            size::crate_name("_RNvXs_Cs1234_3appNtCs5678_3hal3PinNtNtCs9_4core3fmt5Debug3fmt")
+                .unwrap(),
+            "app"
+        );
+        // <hal::Pin as core::fmt::Debug>::fmt, then <u32 as ...>
+        assert_eq!(
+            size::crate_name("_RNvYNtCs5678_3hal3PinNtNtCs9_4core3fmt5Debug3fmt").unwrap(),
+            "hal"
+        );
+        assert_eq!(
+            size::crate_name("_RNvYmNtNtCs9_4core3fmt5Debug3fmt").unwrap(),
+            "core"
+        );
+        // <&hal::Pin as ...>, with an erased lifetime
+        assert_eq!(
+            size::crate_name("_RNvYRL_NtCs5678_3hal3PinNtNtCs9_4core3fmt5Debug3fmt").unwrap(),
+            "hal"
+        );
+        // app::send::<u32>, and a name after a '_' separator
+        assert_eq!(size::crate_name("_RINvCs1234_3app4sendmE").unwrap(), "app");
+        assert_eq!(size::crate_name("_RNvCs1234_4__app4main").unwrap(), "_app");
+        // A back reference to the instantiating crate, at offset 16 after _R
+        assert_eq!(
+            size::crate_name("_RNvNtBf_4gpio3setCs1_3app").unwrap(),
+            "app"
+        );
+    }
+
+    #[test]
+    fn crate_budgets() {
+        let mut builder = elf::testing::ElfBuilder::default();
+        let text = builder.progbits(".text", 0x60000040, 0x60000040, &[0; 0x100]);
+        let data = builder.progbits(".data", 0x20000000, 0x60000140, &[0; 0x10]);
+        builder.file("startup.c");
+        builder.local("init_clocks", 0x60000040, 0x20, text);
+        builder.symbol(
+            "_ZN7smoltcp4poll17h0123456789abcdefE",
+            0x60000061,
+            0x80,
+            text,
+        );
+        builder.symbol(
+            "_ZN7smoltcp6BUFFER17h0123456789abcdefE",
+            0x20000000,
+            0x10,
+            data,
+        );
+        builder.symbol("memcpy", 0x600000E0, 0x20, text);
+        let elf = elf::Elf::parse(builder.build()).unwrap();
+
+        let report = size::crates(&example().layout(), &elf);
+        let smoltcp = report.krate("smoltcp").unwrap();
+        assert_eq!(smoltcp.in_region(FLASH), 0x80);
+        assert_eq!(smoltcp.in_region(RAM), 0x10);
+        assert_eq!(report.krate("startup.c").unwrap().total(), 0x20);
+        assert_eq!(report.krate(size::UNKNOWN_CRATE).unwrap().total(), 0x20);
+
+        let budgets = vec![
+            "smoltcp:FLASH=0x40".parse::<size::Budget>().unwrap(),
+            "smoltcp:RAM=1K".parse::<size::Budget>().unwrap(),
+        ];
+        let violations = report.check(&budgets);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].budget.region, FLASH);
+        assert_eq!(violations[0].used, 0x80);
+    }
+
+    #[test]
+    fn stack_sizes_and_minimum() {
+        let mut builder = elf::testing::ElfBuilder::default();
+        let text = builder.progbits(".text", 0x60000000, 0x60000000, &[0; 0x100]);
+        // (address, ULEB128 size) pairs, the second size takes two bytes
+        builder.progbits(
+            stack::STACK_SIZES_SECTION,
+            0,
+            0,
+            &[
+                0x01, 0, 0, 0x60, 16, 0x41, 0, 0, 0x60, 0x80, 0x01, 0x81, 0, 0, 0x60, 8,
+            ],
+        );
+        builder.symbol("main", 0x60000001, 0x40, text);
+        builder.symbol("process", 0x60000041, 0x40, text);
+        builder.symbol("leaf", 0x60000081, 0x40, text);
+        let elf = elf::Elf::parse(builder.build()).unwrap();
+
+        let sizes = stack::StackSizes::from_elf(&elf).unwrap();
+        assert_eq!(sizes.frame("process").unwrap().size, 128);
+        assert_eq!(sizes.max_frame(), 128);
+        assert_eq!(sizes.estimate(2), 144);
+
+        let mut graph: stack::CallGraph = "main -> process\nmain -> leaf".parse().unwrap();
+        assert_eq!(sizes.worst_case(&graph, "main"), Some(144));
+        graph.call("process", "main");
+        assert_eq!(sizes.worst_case(&graph, "main"), None);
+        assert_eq!(stack::suggest(144, 25), 184);
+
+        let mut ls = example();
+        ls.min_size(&SectionID(String::from("stack")), 184).unwrap();
+        assert!(render(ls).contains(
+            "\n}\nASSERT(__start_stack - __end_stack >= 184, \"Section .stack is smaller than 184 bytes\");"
+        ));
+        let mut ls = linkable();
+        ls.min_size(&SectionID(String::from("stack")), 0x1000)
+            .unwrap();
+        assert_links(ls, "min_size");
+        let mut ls = example();
+        match ls.min_size(&SectionID(String::from("heap")), 184) {
+            Err(LinkerError::UnknownSection(_)) => {}
+            result => panic!("Expected unknown section, but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn diff_layouts_and_elfs() {
+        let old = example().layout();
+        let mut ls = LinkerScript::<u32>::new();
+        let flash = ls.region(FLASH, 0x60000000, 0x2000).unwrap();
+        let ram = ls.region(RAM, 0x20000000, 0x400).unwrap();
+        ls.stack(ram.clone()).unwrap();
+        ls.vector_table(flash.clone(), None).unwrap();
+        ls.text(ram.clone(), Some(flash.clone())).unwrap();
+        ls.data(false, ram.clone(), Some(flash.clone())).unwrap();
+        ls.rodata(false, flash.clone(), None).unwrap();
+        ls.bss(false, ram.clone(), None).unwrap();
+        let changes = diff::layouts(&old, &ls.layout());
+        assert_eq!(
+            changes,
+            vec![
+                diff::Change::RegionChanged(
+                    String::from(FLASH),
+                    0x60000000,
+                    0x1000,
+                    0x60000000,
+                    0x2000
+                ),
+                diff::Change::SectionRemoved(String::from(".RAM.data")),
+                diff::Change::SectionPlaced(
+                    String::from(".text"),
+                    String::from(FLASH),
+                    String::from("RAM AT> FLASH")
+                ),
+            ]
+        );
+
+        let build = |text_size: usize| {
+            let mut builder = elf::testing::ElfBuilder::default();
+            let text = builder.progbits(".text", 0x60000000, 0x60000000, &vec![0; text_size]);
+            let api = 0x60000000 + text_size as u64;
+            let api_section = builder.progbits(".api", api, api, &[0; 8]);
+            builder.symbol("main", 0x60000001, 4, text);
+            builder.symbol("BOOTLOADER_API", api, 8, api_section);
+            elf::Elf::parse(builder.build()).unwrap()
+        };
+        let changes = diff::elfs(&build(0x100), &build(0x104));
+        assert!(changes.contains(&diff::Change::SectionResized(
+            String::from(".text"),
+            0x100,
+            0x104
+        )));
+        assert!(changes.iter().any(|c| c.breaks_symbol("BOOTLOADER_API")));
+        assert!(!changes.iter().any(|c| c.breaks_symbol("main")));
+    }
+
+    #[test]
+    fn memory_map_svg() {
+        let layout = example().layout();
+        let planned = map::svg(&layout, None);
+        assert!(planned.contains(">RAM</text>"));
+        assert!(planned.contains("<title>.RAM.data (linker sized)</title>"));
+
+        let mut builder = elf::testing::ElfBuilder::default();
+        builder.progbits(".text", 0x60000000, 0x60000000, &[0; 0x800]);
+        let elf = elf::Elf::parse(builder.build()).unwrap();
+        let report = size::report(&layout, &elf);
+        let linked = map::svg(&layout, Some(&report));
+        assert!(linked.contains("height=\"300\" fill=\"#4e79a7\""));
+        assert!(map::html(&layout, Some(&report)).contains("<td>0x60000000</td><td>2048</td>"));
+    }
+
+    fn boot_elf() -> elf::Elf {
+        let mut builder = elf::testing::ElfBuilder::default();
+        builder.progbits(".fcb", 0x60000000, 0x60000000, &[0x46, 0x43, 0x46, 0x42]);
+        builder.progbits(".vector_table", 0x60001000, 0x60001000, &[1, 2, 3, 4]);
+        builder.progbits(".data", 0x20000000, 0x60001004, &[5, 6]);
+        builder.nobits(".bss", 0x20000010, 0x20);
+        elf::Elf::parse(builder.build()).unwrap()
+    }
+
+    fn boot_layout() -> layout::Layout {
+        let mut ls = LinkerScript::<u32>::new();
+        let flash = ls.region(FLASH, 0x60000000, 0x2000).unwrap();
+        let ram = ls.region(RAM, 0x20000000, 0x400).unwrap();
+        ls.boot_config(0x1000, "fcb", flash.clone()).unwrap();
+        ls.stack(ram.clone()).unwrap();
+        ls.vector_table(flash.clone(), None).unwrap();
+        ls.data(false, ram, Some(flash)).unwrap();
+        ls.layout()
+    }
+
+    #[test]
+    fn flat_image_from_elf() {
+        let img = image::Image::from_elf(&boot_elf(), &boot_layout(), None, image::ERASED).unwrap();
+        assert_eq!(img.base, 0x60000000);
+        assert_eq!(img.data.len(), 0x1006);
+        assert_eq!(&img.data[0..4], b"FCFB");
+        assert_eq!(img.data[4], 0xFF);
+        assert_eq!(&img.data[0x1000..], &[1, 2, 3, 4, 5, 6]);
+
+        match image::Image::from_elf(&boot_elf(), &boot_layout(), Some(RAM), 0) {
+            Err(LinkerError::InvalidImage(_)) => {}
+            result => panic!("Expected invalid image, but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn hex_and_srec_use_load_addresses() {
+        let elf = boot_elf();
+        let segments = hex::load_segments(&elf);
+        assert_eq!(segments[2], (0x60001004, &[5u8, 6][..]));
+
+        let ihex = hex::ihex(&segments, Some(0x60001001));
+        let lines: Vec<&str> = ihex.lines().collect();
+        assert_eq!(lines[0], ":0200000460009A");
+        assert_eq!(lines[1], ":0400000046434642EB");
+        assert_eq!(lines[3], ":021004000506DF");
+        assert_eq!(lines[4], ":040000056000100186");
+        assert_eq!(lines[5], ":00000001FF");
+
+        let srec = hex::srec(&segments, "fw", None);
+        let lines: Vec<&str> = srec.lines().collect();
+        assert_eq!(lines[0], "S005000066771D");
+        assert_eq!(lines[1], "S309600000004643464285");
+        assert_eq!(lines[4], "S5030003F9");
+        assert_eq!(lines[5], "S70500000000FA");
+    }
+
+    #[test]
+    fn patch_boot_blobs() {
+        let mut ls = LinkerScript::<u32>::new();
+        let flash = ls.region(FLASH, 0x60000000, 0x4000).unwrap();
+        ls.boot_config(0x400, "fcb", flash.clone()).unwrap();
+        ls.text(flash, None).unwrap();
+        let layout = ls.layout();
+        assert_eq!(layout.fixed_address("fcb"), Some((0x60000000, 0x400)));
+        assert_eq!(layout.fixed_address("text"), None);
+
+        let mut img = image::Image {
+            base: 0x60000000,
+            data: vec![0; 0x800],
+        };
+        img.patch(&layout, "fcb", b"FCFB").unwrap();
+        assert_eq!(&img.data[0..5], b"FCFB\0");
+        match img.patch(&layout, "fcb", &[0; 0x401]) {
+            Err(LinkerError::InvalidImage(_)) => {}
+            result => panic!("Expected invalid image, but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn patch_nudged_boot_blob() {
+        let mut ls = LinkerScript::<u32>::new();
+        let flash = ls.region(FLASH, 0x60000000, 0x4000).unwrap();
+        let fcb = ls.boot_config(0x400, "fcb", flash.clone()).unwrap();
+        let header = ls
+            .custom_section("serial_header", false, flash.clone(), None, Some(0x100))
+            .unwrap();
+        ls.set_priority(&header, Priority::Value(0)).unwrap();
+        ls.set_priority(&fcb, Priority::After(header)).unwrap();
+        ls.text(flash, None).unwrap();
+        let layout = ls.layout();
+        assert_eq!(layout.fixed_address("fcb"), Some((0x60000100, 0x400)));
+        let fcb = layout.section("fcb").unwrap();
+        assert_eq!((fcb.priority, fcb.nudge), (0, 1));
+        let parsed = layout::Layout::from_json(&layout.to_json()).unwrap();
+        assert_eq!(parsed, layout);
+
+        let mut img = image::Image {
+            base: 0x60000000,
+            data: vec![0; 0x800],
+        };
+        img.patch(&parsed, "fcb", b"FCFB").unwrap();
+        assert_eq!(&img.data[0x100..0x105], b"FCFB\0");
+        assert_eq!(&img.data[0..4], &[0; 4]);
+    }
+
+    #[test]
+    fn prepare_image_for_signing() {
+        let mut img = image::Image {
+            base: 0x60000000,
+            data: vec![0xFF; 0x1800],
+        };
+        // IVT at 0x1000 with boot data at 0x1020
+        let mut ivt = [0u8; 0x2C];
+        ivt[0..4].copy_from_slice(&[0xD1, 0x00, 0x20, 0x41]);
+        ivt[16..20].copy_from_slice(&0x60001020u32.to_le_bytes());
+        img.write(0x60001000, &ivt, 0);
+
+        let manifest =
+            sign::prepare(&mut img, 0x60001000, sign::SigningOptions::default()).unwrap();
+        assert_eq!(manifest.signed_length, 0x2000);
+        assert_eq!(manifest.csf, 0x60002000);
+        assert_eq!(manifest.total_length, 0x4000);
+        assert_eq!(img.data.len(), 0x2000);
+        assert_eq!(&img.data[0x1018..0x101C], &0x60002000u32.to_le_bytes());
+        assert_eq!(&img.data[0x1024..0x1028], &0x4000u32.to_le_bytes());
+        assert_eq!(
+            manifest.csf_blocks("fw.bin"),
+            "Blocks = 0x60000000 0x0 0x2000 \"fw.bin\""
+        );
+
+        match sign::prepare(&mut img, 0x60000000, sign::SigningOptions::default()) {
+            Err(LinkerError::InvalidImage(_)) => {}
+            result => panic!("Expected invalid image, but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn teensy_hex_requires_fcb_at_flash_base() {
+        let hex = hex::teensy(&boot_elf(), &boot_layout()).unwrap();
+        let lines: Vec<&str> = hex.lines().collect();
+        assert_eq!(lines[0], ":0200000460009A");
+        assert!(lines[1].starts_with(":1000000046434642FFFF"));
+        assert_eq!(lines.last(), Some(&":00000001FF"));
+
+        let mut builder = elf::testing::ElfBuilder::default();
+        builder.progbits(".vector_table", 0x60000000, 0x60000000, &[1, 2, 3, 4]);
+        let elf = elf::Elf::parse(builder.build()).unwrap();
+        match hex::teensy(&elf, &boot_layout()) {
+            Err(LinkerError::InvalidImage(_)) => {}
+            result => panic!("Expected invalid image, but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn uf2_blocks() {
+        let img = image::Image {
+            base: 0x60000000,
+            data: vec![0xA5; 300],
+        };
+        let out = uf2::from_image(&img, uf2::MIMXRT10XX_FAMILY_ID);
+        assert_eq!(out.len(), 1024);
+        let word = |offset: usize| {
+            u32::from_le_bytes([
+                out[offset],
+                out[offset + 1],
+                out[offset + 2],
+                out[offset + 3],
+            ])
+        };
+        assert_eq!(word(0), 0x0A324655);
+        assert_eq!(word(12), 0x60000000);
+        assert_eq!(word(512 + 12), 0x60000100);
+        assert_eq!(word(512 + 16), 44);
+        assert_eq!(word(512 + 20), 1);
+        assert_eq!(word(512 + 24), 2);
+        assert_eq!(word(28), uf2::MIMXRT10XX_FAMILY_ID);
+        assert_eq!(word(508), 0x0AB16F30);
+    }
+
+    #[test]
+    fn pad_trim_and_slice_images() {
+        let mut img = image::Image {
+            base: 0x60000000,
+            data: vec![1, 2, 3, 0xFF, 0xFF],
+        };
+        img.trim(image::ERASED);
+        assert_eq!(img.data, vec![1, 2, 3]);
+        img.pad_to(8, image::ERASED);
+        assert_eq!(img.data, vec![1, 2, 3, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+
+        let slot = layout::LayoutRegion {
+            name: String::from("SLOT_B"),
+            origin: 0x60000004,
+            size: 2,
+        };
+        let b = img.slice(&slot).unwrap();
+        assert_eq!(b.base, 0x60000004);
+        assert_eq!(b.data, vec![0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn audit_elf_against_layout() {
+        let findings = audit::audit(&boot_layout(), &boot_elf());
+        assert_eq!(
+            findings,
+            vec![audit::Finding::Orphan(
+                String::from(".bss"),
+                0x20000010,
+                Some(String::from(RAM))
+            )]
+        );
+
+        let mut builder = elf::testing::ElfBuilder::default();
+        builder.progbits(".vector_table", 0x20000000, 0x20000000, &[1, 2, 3, 4]);
+        builder.progbits(".data", 0x20000010, 0x20000010, &[5, 6]);
+        let elf = elf::Elf::parse(builder.build()).unwrap();
+        let findings = audit::audit(&boot_layout(), &elf);
+        assert_eq!(
+            findings,
+            vec![
+                audit::Finding::WrongRegion(
+                    String::from(".vector_table"),
+                    0x20000000,
+                    String::from(FLASH)
+                ),
+                audit::Finding::WrongLoadRegion(
+                    String::from(".data"),
+                    0x20000010,
+                    String::from(FLASH)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn factory_image_parts() {
+        let mut ls = LinkerScript::<u32>::new();
+        ls.region(FLASH, 0x60000000, 0x100).unwrap();
+        ls.region("BOOT", 0x60000000, 0x10).unwrap();
+        ls.region("APP", 0x60000010, 0x20).unwrap();
+        ls.region("FS", 0x60000080, 0x80).unwrap();
+        let layout = ls.layout();
+        let part = |name: &str, region: &str, data: &[u8]| factory::Part {
+            name: String::from(name),
+            region: String::from(region),
+            data: data.to_vec(),
+        };
+
+        let parts = [
+            part("app.bin", "APP", &[3, 4]),
+            part("boot.bin", "BOOT", &[1, 2]),
+            part("fs.bin", "FS", &[5]),
+        ];
+        let (img, manifest) = factory::assemble(&layout, FLASH, &parts, 0xFF).unwrap();
+        assert_eq!(img.base, 0x60000000);
+        assert_eq!(img.data.len(), 0x81);
+        assert_eq!(&img.data[0..3], &[1, 2, 0xFF]);
+        assert_eq!(&img.data[0x10..0x12], &[3, 4]);
+        assert_eq!(img.data[0x80], 5);
+        assert_eq!(manifest.parts[0].name, "boot.bin");
+        assert_eq!(manifest.parts[2].offset, 0x80);
+        assert!(manifest.to_json().contains("\"length\": 129"));
+
+        let parts = [part("boot.bin", "BOOT", &[0; 0x11])];
+        match factory::assemble(&layout, FLASH, &parts, 0xFF) {
+            Err(LinkerError::InvalidImage(_)) => {}
+            result => panic!("Expected invalid image, but got {:?}", result),
+        }
+        let parts = [
+            part("a.bin", "FLASH", &[0; 0x20]),
+            part("b.bin", "APP", &[0]),
+        ];
+        match factory::assemble(&layout, FLASH, &parts, 0xFF) {
+            Err(LinkerError::InvalidImage(_)) => {}
+            result => panic!("Expected invalid image, but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn slot_payloads() {
+        let mut img = image::Image {
+            base: 0x60000000,
+            data: vec![0xFF; 0x30],
+        };
+        img.write(0x60000000, &[1, 2, 3], 0xFF);
+        img.write(0x60000020, &[4], 0xFF);
+        let slot = |name: &str, origin, size| layout::LayoutRegion {
+            name: String::from(name),
+            origin,
+            size,
+        };
+
+        let a = img
+            .payload(&slot("SLOT_A", 0x60000000, 0x20), 8, 0xFF)
+            .unwrap();
+        assert_eq!(a.data, vec![1, 2, 3, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+        let b = img
+            .payload(&slot("SLOT_B", 0x60000020, 0x10), 8, 0xFF)
+            .unwrap();
+        assert_eq!(b.base, 0x60000020);
+        assert_eq!(b.data.len(), 8);
+        match img.payload(&slot("SLOT_B", 0x60000020, 0x4), 8, 0xFF) {
+            Err(LinkerError::InvalidImage(_)) => {}
+            result => panic!("Expected invalid image, but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn insert_after_cortex_m_rt() {
+        let mut ls = LinkerScript::<u32>::new();
+        let flash = ls.region(FLASH, 0x60000000, 0x1000).unwrap();
+        let ram = ls.region(RAM, 0x20200000, 0x1000).unwrap();
+        let tcm = ls.region("DTCM", 0x20000000, 0x400).unwrap();
+        ls.stack(ram.clone()).unwrap();
+        ls.text(flash.clone(), None).unwrap();
+        ls.data(false, ram, Some(flash.clone())).unwrap();
+        ls.data(true, tcm.clone(), Some(flash)).unwrap();
+        ls.heap(tcm).unwrap();
+
+        let mut out = Vec::new();
+        ls.write_insert(&mut out).unwrap();
+        let script = String::from_utf8(out).unwrap();
+        assert!(script.contains("\tDTCM : ORIGIN = 0x20000000, LENGTH = 0x400"));
+        assert!(!script.contains("\tFLASH : ORIGIN"));
+        assert!(!script.contains("INCLUDE device.x"));
+        assert!(script.contains("\t.DTCM.data :"));
+        assert!(script.contains("\t.heap :"));
+        assert!(!script.contains("\t.data :"));
+        assert!(!script.contains("\t.stack :"));
+        assert!(script.ends_with("} INSERT AFTER .bss;\n"));
+    }
+
+    #[test]
+    fn device_x_from_svd_and_pac() {
+        let svd = "<device><peripherals><peripheral><name>LPUART1</name>
+            <interrupt><name>LPUART1</name><value>20</value></interrupt>
+            </peripheral><peripheral><name>GPIO1</name>
+            <interrupt><name>GPIO1_COMBINED_0_15</name><value>0x50</value></interrupt>
+            <interrupt><name>LPUART1</name><value>20</value></interrupt>
+            </peripheral></peripherals></device>";
+        let interrupts = device::from_svd(svd).unwrap();
+        assert_eq!(interrupts.len(), 2);
+        assert_eq!(
+            device::render(&interrupts),
+            "PROVIDE(LPUART1 = DefaultHandler); /* 20 */\n\
+             PROVIDE(GPIO1_COMBINED_0_15 = DefaultHandler); /* 80 */\n"
+        );
+
+        let pac = "pub enum Interrupt {
+            #[doc = \"80 - GPIO1\"]
+            GPIO1_COMBINED_0_15 = 80,
+            #[doc = \"20 - LPUART1\"]
+            LPUART1 = 20,
+        }";
+        assert_eq!(device::from_pac(pac).unwrap(), interrupts);
+
+        match device::from_svd("<device></device>") {
+            Err(LinkerError::InvalidDevice(_)) => {}
+            result => panic!("Expected invalid device, but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn rtic_and_embassy_compatibility() {
+        use compat::{Framework, Issue};
+        assert!(compat::check(&boot_layout(), Framework::Embassy).is_empty());
+        assert_eq!(
+            compat::check(&boot_layout(), Framework::Rtic),
+            vec![Issue::MissingUninit]
+        );
+
+        let mut ls = LinkerScript::<u32>::new();
+        let flash = ls.region(FLASH, 0x60000000, 0x2000).unwrap();
+        let ram = ls.region(RAM, 0x20000000, 0x400).unwrap();
+        let fcb = ls.boot_config(0x200, "fcb", flash.clone()).unwrap();
+        let vector_table = ls.vector_table(flash, None).unwrap();
+        ls.stack(ram.clone()).unwrap();
+        ls.heap(ram.clone()).unwrap();
+        ls.uninit(ram).unwrap();
+        assert!(!compat::check(&ls.layout(), Framework::Rtic).contains(&Issue::MissingUninit));
+        assert_eq!(
+            compat::check(&ls.layout(), Framework::Embassy),
+            vec![
+                Issue::UnalignedVectorTable(0x60000200),
+                Issue::StackHeapOverlap(String::from(RAM))
+            ]
+        );
+        // Placed right before the FCB, the table is at the region's origin
+        ls.set_priority(&vector_table, Priority::Before(fcb))
+            .unwrap();
+        let layout = ls.layout();
+        assert_eq!(layout.address("vector_table"), Some(0x60000000));
+        assert_eq!(layout.fixed_address("fcb"), None);
+        assert_eq!(
+            compat::check(&layout, Framework::Embassy),
+            vec![Issue::StackHeapOverlap(String::from(RAM))]
+        );
+        assert_eq!("rtic".parse(), Ok(Framework::Rtic));
+    }
+
+    #[test]
+    fn stack_below_statics() {
+        let mut ls = LinkerScript::<u32>::new();
+        let flash = ls.region(FLASH, 0x60000000, 0x1000).unwrap();
+        let ram = ls.region(RAM, 0x20000000, 0x400).unwrap();
+        ls.stack_below_statics(0x200, ram.clone()).unwrap();
+        ls.vector_table(flash.clone(), None).unwrap();
+        ls.text(flash.clone(), None).unwrap();
+        ls.data(false, ram.clone(), Some(flash.clone())).unwrap();
+        ls.rodata(false, flash, None).unwrap();
+        ls.bss(false, ram.clone(), None).unwrap();
+        match ls.stack(ram) {
+            Err(LinkerError::DuplicateSection(_)) => {}
+            result => panic!("Expected duplicate section, but got {:?}", result),
+        }
+
+        let layout = ls.layout();
+        assert_eq!(
+            layout.sections[0].size,
+            layout::LayoutSize::FixedStack(0x200)
+        );
+        assert_eq!(
+            layout::Layout::from_json(&layout.to_json()).unwrap(),
+            layout
+        );
+
+        let script = render(ls);
+        let stack = script.find("\t.stack :").unwrap();
+        assert!(stack < script.find("\t.data :").unwrap());
+        assert!(
+            script[stack..].contains("\t\t__end_stack = .;\n\t\t. += 512;\n\t\t__start_stack = .;")
+        );
+
+        let mut builder = elf::testing::ElfBuilder::default();
+        builder.nobits(".stack", 0x20000000, 0x200);
+        builder.progbits(".data", 0x20000200, 0x60000100, &[1, 2]);
+        builder.nobits(".bss", 0x20000210, 0x20);
+        let elf = elf::Elf::parse(builder.build()).unwrap();
+        let report = size::report(&layout, &elf);
+        assert_eq!(stack::below_statics(&layout, &report, RAM), Some(984));
+    }
+
+    #[test]
+    fn unwind_tables_placed_or_discarded() {
+        let script = render(example());
+        assert!(script.contains("\t/DISCARD/ :"));
+        assert!(!script.contains("__exidx_start"));
+
+        let mut ls = example();
+        let flash = RegionID(String::from(FLASH));
+        let tables = ls.unwind_tables(flash.clone()).unwrap();
+        assert_eq!(tables.len(), 4);
+        match ls.unwind_tables(flash) {
+            Err(LinkerError::DuplicateSection(_)) => {}
+            result => panic!("Expected duplicate section, but got {:?}", result),
+        }
+        let layout = ls.layout();
+        assert_eq!(layout.section(".ARM.exidx").unwrap().vma, FLASH);
+        let script = render(ls);
+        assert!(script.contains("\t.ARM.exidx :"));
+        assert!(script.contains("\t\t*(.eh_frame .eh_frame.*);"));
+        assert!(script.contains("\tPROVIDE(__exidx_start = __start_ARM_exidx);"));
+        assert!(!script.contains("/DISCARD/"));
+    }
+
+    #[test]
+    fn position_independent_layout() {
+        assert!(!render(example()).contains("__load_offset_"));
+
+        let mut ls = example();
+        let got = ls
+            .position_independent(
+                RegionID(String::from(RAM)),
+                Some(RegionID(String::from(FLASH))),
+            )
+            .unwrap();
+        assert_eq!(got, SectionID(String::from("got")));
+        let script = render(ls);
+        assert!(script.contains("\t\t*(.got .got.*);"));
+        assert!(script.contains("\t__load_offset_got = LOADADDR(.got) - ORIGIN(FLASH);"));
+        assert!(script.contains("\t__load_offset_data = LOADADDR(.data) - ORIGIN(FLASH);"));
+    }
+
+    fn render_reset(ls: &LinkerScript<u32>) -> String {
+        let mut out = Vec::new();
+        ls.write_reset(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn reset_copies_and_zeroes_sections() {
+        let reset = render_reset(&example());
+        assert!(reset.contains("pub unsafe fn init() {"));
+        assert!(reset.contains("    static __load_data: u32;"));
+        assert!(reset.contains(
+            "    __copy(core::ptr::addr_of_mut!(__start_data), core::ptr::addr_of_mut!(__end_data), core::ptr::addr_of!(__load_data));"
+        ));
+        assert!(reset.contains(
+            "    __zero(core::ptr::addr_of_mut!(__start_bss), core::ptr::addr_of_mut!(__end_bss));"
+        ));
+        assert!(!reset.contains("__start_text"));
+        assert!(!reset.contains("fn relocate"));
+    }
+
+    #[test]
+    fn reset_relocates_pie_images() {
+        let mut ls = example();
+        let ram = RegionID(String::from(RAM));
+        let flash = RegionID(String::from(FLASH));
+        ls.position_independent(ram, Some(flash.clone())).unwrap();
+        ls.dynamic_relocations(flash).unwrap();
+
+        let reset = render_reset(&ls);
+        assert!(reset.contains("pub unsafe fn init(load_base: usize) {"));
+        assert!(reset.contains("(load_base + core::ptr::addr_of!(__load_offset_got) as usize)"));
+        assert!(reset.contains("pub unsafe fn relocate(delta: usize) {"));
+        assert!(reset.contains("if info & 0xFF == 23 {"));
+
+        let script = render(ls);
+        assert!(script.contains("\t\t*(.rel.dyn .rel.dyn.*);"));
+        assert!(script.contains("\t\t__start_rel_dyn = .;"));
+    }
+
+    #[test]
+    fn retained_user_sections() {
+        let mut ls = example();
+        let flash = RegionID(String::from(FLASH));
+        let id = ls.retain("linkme_HANDLERS", flash.clone(), None).unwrap();
+        assert_eq!(id, SectionID(String::from("linkme_HANDLERS")));
+        ls.retain(".tests", flash.clone(), None).unwrap();
+        match ls.retain("tests", flash, None) {
+            Err(LinkerError::DuplicateSection(_)) => {}
+            result => panic!("Expected duplicate section, but got {:?}", result),
+        }
+
+        let script = render(ls);
+        assert!(script.contains("\t.linkme_HANDLERS :"));
+        assert!(script.contains("\t\tKEEP(*(linkme_HANDLERS linkme_HANDLERS.*));"));
+        assert!(script.contains("\t\t__start_linkme_HANDLERS = .;"));
+        assert!(script.contains("\t\t__stop_linkme_HANDLERS = .;"));
+        assert!(script.contains("\t\tKEEP(*(.tests .tests.*));"));
+        assert!(script.contains("\t\t*(.text .text.*);"));
+    }
+
+    #[test]
+    fn qemu_presets() {
+        let ls = preset::lm3s6965evb().unwrap();
+        let layout = ls.layout();
+        assert_eq!(layout.region(FLASH).unwrap().size, 0x40000);
+        assert_eq!(layout.region(RAM).unwrap().origin, 0x20000000);
+        let script = render(ls);
+        assert!(script.contains("\tFLASH : ORIGIN = 0x0, LENGTH = 0x40000"));
+        assert!(script.contains("\t} > RAM AT> FLASH"));
+
+        let mut ls = preset::mps2_an385().unwrap();
+        let ram = RegionID(String::from(RAM));
+        ls.heap(ram).unwrap();
+        assert_eq!(ls.layout().region(RAM).unwrap().size, 0x400000);
+        render(ls);
+    }
+
+    #[test]
+    fn hot_functions_in_itcm() {
+        let mut builder = elf::testing::ElfBuilder::default();
+        let text = builder.progbits(".text", 0x60002000, 0x60002000, &[0; 0x100]);
+        builder.symbol("_ZN3app4fast17h0123456789abcdefE", 0x60002001, 0x40, text);
+        builder.symbol("_ZN3app3big17h0123456789abcdefE", 0x60002041, 0x80, text);
+        builder.symbol("_ZN3app4tiny17h0123456789abcdefE", 0x600020C1, 0x10, text);
+        let elf = elf::Elf::parse(builder.build()).unwrap();
+
+        let profile: hot::Profile = "# samples symbol
+            90 _ZN3app4fast17h0123456789abcdefE
+            50 _ZN3app3big17h0123456789abcdefE
+            _ZN3app4tiny17h0123456789abcdefE
+            40 missing"
+            .parse()
+            .unwrap();
+        let set = hot::select(&profile, &elf, 0x60);
+        assert_eq!(
+            set.symbols(),
+            vec![
+                "_ZN3app4fast17h0123456789abcdefE",
+                "_ZN3app4tiny17h0123456789abcdefE"
+            ]
+        );
+        assert_eq!(set.used(), 0x50);
+        assert_eq!(set.skipped.len(), 2);
+        assert!(set.to_string().contains("80 of 96 bytes used"));
+        assert!("1 2 3".parse::<hot::Profile>().is_err());
+
+        let mut ls = example();
+        let itcm = ls.region("ITCM", 0, 0x80).unwrap();
+        ls.hot_text(&set.symbols(), itcm, Some(RegionID(String::from(FLASH))))
+            .unwrap();
+        let script = render(ls);
+        let hot = script.find("\t.hot_text :").unwrap();
+        assert!(hot < script.find("\t.text :").unwrap());
+        assert!(script.contains(
+            "\t\t*(.text._ZN3app4fast17h0123456789abcdefE .text._ZN3app4fast17h0123456789abcdefE.* \
+             .text._ZN3app4tiny17h0123456789abcdefE .text._ZN3app4tiny17h0123456789abcdefE.*);"
+        ));
+    }
+
+    #[test]
+    fn errors_carry_context() {
+        let mut ls = LinkerScript::<u32>::new();
+        let flash = ls.region(FLASH, 0x60000000, 0x1000).unwrap();
+        let dtcm = RegionID(String::from("DTCM"));
+        match ls.data(true, dtcm.clone(), Some(flash.clone())) {
+            Err(LinkerError::UnknownVMA {
+                section,
+                region,
+                known,
+            }) => {
+                assert_eq!(section, "DTCM.data");
+                assert_eq!(region, dtcm);
+                assert_eq!(known, vec![String::from(FLASH)]);
+            }
+            result => panic!("Expected unknown VMA, but got {:?}", result),
+        }
+        match ls.data(false, flash.clone(), Some(dtcm)) {
+            Err(err @ LinkerError::UnknownLMA { .. }) => {
+                assert!(err.to_string().contains("known regions are FLASH"))
+            }
+            result => panic!("Expected unknown LMA, but got {:?}", result),
+        }
+        match ls.boot_config(0x2000, "fcb", flash) {
+            Err(LinkerError::SectionTooLarge {
+                size, available, ..
+            }) => assert_eq!((size, available), (0x2000, 0x1000)),
+            result => panic!("Expected section too large, but got {:?}", result),
+        }
+
+        let err = LinkerError::File {
+            path: String::from("link.x"),
+            source: std::io::Error::from(std::io::ErrorKind::PermissionDenied),
+        };
+        assert_eq!(err.to_string(), "Failed to write link.x");
+        assert!(err.source().is_some());
+    }
+
+    #[test]
+    fn region_predicates() {
+        let mut ls = example();
+        let ocram = ls.region("OCRAM", 0x20200000, 0x80000).unwrap();
+        ls.dma_capable(&ocram).unwrap();
+        match ls.dma_capable(&RegionID(String::from("DTCM"))) {
+            Err(LinkerError::UnknownRegion(_)) => {}
+            result => panic!("Expected unknown region, but got {:?}", result),
+        }
+
+        let mut out = Vec::new();
+        ls.write_regions(&mut out).unwrap();
+        let regions = String::from_utf8(out).unwrap();
+        assert!(regions.contains("pub const OCRAM_ORIGIN: usize = 0x20200000;"));
+        assert!(regions.contains("pub fn in_flash(addr: usize) -> bool {"));
+        assert!(regions.contains("pub fn within_ram(addr: usize, len: usize) -> bool {"));
+        assert!(regions.contains("    within_ocram(addr, bytes)\n"));
+    }
+
+    #[test]
+    fn unused_regions() {
+        assert!(example().lints().is_empty());
+
+        let mut ls = example();
+        ls.region("ITCM", 0, 0x1000).unwrap();
+        assert_eq!(
+            ls.lints(),
+            vec![lint::Warning::UnusedRegion(String::from("ITCM"))]
+        );
+        ls.strict(true);
+        match ls.write(&mut Vec::new()) {
+            Err(LinkerError::Lints(warnings)) => assert_eq!(warnings.len(), 1),
+            result => panic!("Expected lints, but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn priority_collisions() {
+        let mut ls = example();
+        let flash = RegionID(String::from(FLASH));
+        let b = ls.retain("linkme_B", flash.clone(), None).unwrap();
+        let a = ls.retain("linkme_A", flash, None).unwrap();
+        assert_eq!(
+            ls.lints(),
+            vec![lint::Warning::PriorityCollision(
+                String::from(FLASH),
+                10,
+                vec![String::from("linkme_A"), String::from("linkme_B")]
+            )]
+        );
+        let script = render(ls.clone());
+        assert!(script.find("\t.linkme_A :").unwrap() < script.find("\t.linkme_B :").unwrap());
+
+        ls.set_priority(&a, Priority::After(b.clone())).unwrap();
+        assert_eq!(ls.lints(), vec![]);
+        ls.set_priority(&a, Priority::Before(b)).unwrap();
+        assert_eq!(ls.lints(), vec![]);
+    }
+
+    #[test]
+    fn rejects_degenerate_inputs() {
+        let mut ls = LinkerScript::<u32>::new();
+        match ls.region("EMPTY", 0x20000000, 0) {
+            Err(LinkerError::ZeroSizeRegion(_)) => {}
+            result => panic!("Expected zero size region, but got {:?}", result),
+        }
+        let flash = ls.region(FLASH, 0x60000000, 0x1000).unwrap();
+        let tiny = ls.region("TINY", 0x20000000, 0x80).unwrap();
+        match ls.boot_config(0, "fcb", flash) {
+            Err(LinkerError::ZeroSizeSection(_)) => {}
+            result => panic!("Expected zero size section, but got {:?}", result),
+        }
+        match ls.stack(tiny) {
+            Err(LinkerError::StackTooSmall { size, .. }) => assert_eq!(size, 0x80),
+            result => panic!("Expected stack too small, but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn boot_offset_per_family() {
+        let boot = |family: &str| {
+            let mut ls = example();
+            let flash = RegionID(String::from(FLASH));
+            ls.boot_config(0x200, "fcb", flash).unwrap();
+            ls.family(family.parse().unwrap());
+            ls.write(&mut Vec::new())
+        };
+        boot("imxrt1062").unwrap();
+        match boot("imxrt1011") {
+            Err(LinkerError::BootOffset {
+                expected, actual, ..
+            }) => assert_eq!((expected, actual), (0x60000400, Some(0x60000000))),
+            result => panic!("Expected boot offset error, but got {:?}", result),
+        }
+        match boot("imxrt1064") {
+            Err(err @ LinkerError::BootOffset { .. }) => {
+                assert!(err.to_string().contains("imxrt1064 boot ROM"))
+            }
+            result => panic!("Expected boot offset error, but got {:?}", result),
+        }
+        assert_eq!(chip::Family::Imxrt1170.fcb_offset(), 0x400);
+        assert!("imxrt9999".parse::<chip::Family>().is_err());
+    }
+
+    #[test]
+    fn vector_table_reachable_at_boot() {
+        let boot = |mode: &str, vma: &str, lma: Option<&str>| {
+            let mut ls = LinkerScript::<u32>::new();
+            let flash = ls.region(FLASH, 0x60000000, 0x1000).unwrap();
+            let ram = ls.region(RAM, 0x20000000, 0x400).unwrap();
+            ls.stack(ram.clone()).unwrap();
+            ls.vector_table(
+                RegionID(String::from(vma)),
+                lma.map(|lma| RegionID(String::from(lma))),
+            )
+            .unwrap();
+            ls.text(flash.clone(), None).unwrap();
+            ls.data(false, ram.clone(), Some(flash.clone())).unwrap();
+            ls.rodata(false, flash, None).unwrap();
+            ls.bss(false, ram, None).unwrap();
+            ls.family(chip::Family::Imxrt1060);
+            ls.boot_mode(mode.parse().unwrap());
+            ls.write(&mut Vec::new())
+        };
+        boot("xip", FLASH, None).unwrap();
+        boot("ram-load", RAM, None).unwrap();
+        for (mode, vma, lma) in [
+            ("xip", RAM, Some(FLASH)),
+            ("xip", RAM, None),
+            ("ram-load", FLASH, None),
+        ] {
+            match boot(mode, vma, lma) {
+                Err(err @ LinkerError::UnreachableVectorTable { .. }) => {
+                    assert!(err.to_string().starts_with(&format!("In {} boot", mode)))
+                }
+                result => panic!("Expected unreachable vector table, but got {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn warnings_returned_from_write() {
+        assert!(example().write(&mut Vec::new()).unwrap().is_empty());
+
+        let mut ls = example();
+        let flash = RegionID(String::from(FLASH));
+        ls.boot_config(0xF00, "fcb", flash).unwrap();
+        let ocram = ls.region("OCRAM", 0x20200002, 0x400).unwrap();
+        let heap = ls.heap(ocram).unwrap();
+        ls.min_size(&heap, 0x100).unwrap();
+        let text = SectionID(String::from("text"));
+        ls.min_size(&text, 0x100).unwrap();
+        assert_eq!(
+            ls.write(&mut Vec::new()).unwrap(),
+            vec![
+                lint::Warning::NearlyFull(String::from(FLASH), 0xF00, 0x1000),
+                lint::Warning::UnalignedRegion(String::from("OCRAM"), 4),
+                lint::Warning::IgnoredMinSize(String::from("text")),
+            ]
+        );
+    }
+
+    #[test]
+    fn aliased_regions_share_memory() {
+        let mut ls = example();
+        let ocram = ls.region("OCRAM", 0x20200000, 0x1000).unwrap();
+        let uncached = ls.alias("OCRAM_NC", &ocram, 0x20280000).unwrap();
+        ls.data(true, ocram.clone(), None).unwrap();
+        ls.bss(true, uncached.clone(), None).unwrap();
+        match ls.alias("OCRAM_NC", &ocram, 0) {
+            Err(LinkerError::DuplicateRegion(_)) => {}
+            result => panic!("Expected duplicate region, but got {:?}", result),
+        }
+        let script = render(ls);
+        assert!(script.contains("\tOCRAM_NC : ORIGIN = 0x20280000, LENGTH = 0x1000"));
+        assert!(!script.contains("__OCRAM_NC_used = 0;"));
+        assert!(script.contains("\t.OCRAM_NC.bss (__OCRAM_NC_origin + __OCRAM_used) :"));
+        assert!(script.contains("\t.OCRAM.data (__OCRAM_origin + __OCRAM_used) :"));
+        assert!(script.contains("\t__OCRAM_used = __OCRAM_used + SIZEOF(.OCRAM_NC.bss);"));
+        assert!(script.contains("\n}\nASSERT(__OCRAM_used <= __OCRAM_size"));
+        assert!(!script.contains("(__RAM_origin"));
+
+        let mut ls = linkable();
+        let flash = RegionID(String::from(FLASH));
+        let boot = ls.alias("FLASH_BOOT", &flash, 0x70000000).unwrap();
+        let ocram = ls.region("OCRAM", 0x20200000, 0x1000).unwrap();
+        let uncached = ls.alias("OCRAM_NC", &ocram, 0x20280000).unwrap();
+        ls.data(true, ocram, Some(boot)).unwrap();
+        ls.bss(true, uncached, None).unwrap();
+        let script = render(ls.clone());
+        assert!(script.contains("\t.OCRAM.data (__OCRAM_origin + __OCRAM_used) : AT(__FLASH_BOOT_origin + __FLASH_used)\n"));
+        assert!(script.contains("\t} > OCRAM\n\t__load_OCRAM_data = LOADADDR(.OCRAM.data);\n"));
+        assert_links(ls, "aliases");
+    }
+
+    #[test]
+    fn validation_levels() {
+        let hot = || {
+            let mut ls = example();
+            let ram = RegionID(String::from(RAM));
+            ls.hot_text(&["isr"], ram, Some(RegionID(String::from(FLASH))))
+                .unwrap();
+            ls
+        };
+        let warnings = vec![lint::Warning::WritableCode(
+            String::from(RAM),
+            vec![String::from("hot_text")],
+            vec![
+                String::from("RAM.data"),
+                String::from("bss"),
+                String::from("data"),
+                String::from("stack"),
+            ],
+        )];
+        assert_eq!(hot().lints(), warnings);
+        assert_eq!(hot().write(&mut Vec::new()).unwrap(), warnings);
+
+        let mut ls = hot();
+        ls.validation("strict".parse().unwrap());
+        match ls.write(&mut Vec::new()) {
+            Err(LinkerError::Lints(denied)) => assert_eq!(denied, warnings),
+            result => panic!("Expected lints, but got {:?}", result),
+        }
+        assert!("pedantic".parse::<lint::Validation>().is_err());
+    }
+
+    #[test]
+    fn config_builds_script() {
+        let config = config::Config::from_json(
+            r#"{
+                "preset": "lm3s6965evb",
+                "validation": "strict",
+                "regions": [
+                    { "name": "OCRAM", "origin": "0x20200000", "size": "64K", "dma": true },
+                    { "name": "OCRAM_NC", "alias_of": "OCRAM", "origin": 539492352 }
+                ],
+                "sections": [
+                    { "kind": "heap", "vma": "OCRAM", "min_size": "4K" },
+                    { "kind": "bss", "prefix": true, "vma": "OCRAM_NC" },
+                    { "kind": "hot_text", "vma": "OCRAM", "lma": "FLASH", "functions": ["isr"] }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.validation, lint::Validation::Strict);
+        assert_eq!(config.regions[1].origin, 0x20280000);
+        assert_eq!(config.sections[0].min_size, Some(0x1000));
+        let ls = config.linker_script().unwrap();
+        assert_eq!(ls.regions["OCRAM_NC"].size, 0x10000);
+        assert!(ls.regions["OCRAM"].dma);
+        assert_eq!(ls.sections["heap"].min_size, Some(0x1000));
+        match ls.write(&mut Vec::new()) {
+            Err(LinkerError::Lints(warnings)) => assert_eq!(warnings.len(), 1),
+            result => panic!("Expected lints, but got {:?}", result),
+        }
+
+        for (json, reason) in [
+            (r#"{ "preset": "pdp11" }"#, "unknown preset"),
+            (
+                r#"{ "regions": [{ "name": "A", "origin": 0 }] }"#,
+                "needs a size",
+            ),
+            (
+                r#"{ "sections": [{ "kind": "boot_config", "name": "fcb", "vma": "FLASH" }] }"#,
+                "needs a \"size\"",
+            ),
+            (
+                r#"{ "regions": [{ "name": "A", "origin": "lots", "size": 1 }] }"#,
+                "not a size",
+            ),
+            (
+                r#"{ "presets": "lm3s6965evb" }"#,
+                "unknown field \"presets\"",
+            ),
+            (
+                r#"{ "regions": [{ "name": "A", "origin": 0, "size": 1, "dam": true }] }"#,
+                "unknown field \"dam\" in regions[0]",
+            ),
+            (
+                r#"{ "sections": [{ "kind": "heap", "vma": "RAM", "minsize": 4 }] }"#,
+                "unknown field \"minsize\" in sections[0]",
+            ),
+            (r#"{ "sections": { "kind": "heap" } }"#, "must be an array"),
+        ] {
+            match config::Config::from_json(json).and_then(|config| config.linker_script()) {
+                Err(err @ LinkerError::InvalidConfig(_)) => {
+                    assert!(err.to_string().contains(reason), "{}", err)
+                }
+                result => panic!("Expected invalid config, but got {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn config_from_toml() {
+        let config = config::Config::from_toml(
+            r#"
+            # Same as the JSON config
+            preset = "lm3s6965evb"
+            validation = 'strict'
+
+            [constants]
+            BOARD_REV = 0x0000_0002
+
+            [[regions]]
+            name = "OCRAM"
+            origin = "0x20200000"
+            size = "64K"
+            dma = true
+
+            [[regions]]
+            name = "OCRAM_NC"
+            alias_of = "OCRAM"
+            origin = 539492352
+
+            [[sections]]
+            kind = "heap"
+            vma = "OCRAM"
+            min_size = "4K"
+
+            [[sections]]
+            kind = "hot_text"
+            vma = "OCRAM"
+            lma = "FLASH"
+            functions = [
+                "isr", # the only one
+            ]
+            "#,
+        )
+        .unwrap();
+        let json = config::Config::from_json(
+            r#"{
+                "preset": "lm3s6965evb",
+                "validation": "strict",
+                "constants": { "BOARD_REV": 2 },
+                "regions": [
+                    { "name": "OCRAM", "origin": "0x20200000", "size": "64K", "dma": true },
+                    { "name": "OCRAM_NC", "alias_of": "OCRAM", "origin": 539492352 }
+                ],
+                "sections": [
+                    { "kind": "heap", "vma": "OCRAM", "min_size": "4K" },
+                    { "kind": "hot_text", "vma": "OCRAM", "lma": "FLASH", "functions": ["isr"] }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config, json);
+        assert_eq!(
+            config::Config::from_toml("regions = [{ name = \"A\", origin = 0, size = 1 }]")
+                .unwrap()
+                .regions[0]
+                .size,
+            Some(1)
+        );
+
+        for (toml, reason) in [
+            ("tiny = true\ntiny = false", "defined twice at line 2"),
+            ("stack_alignment = 1.5", "only integers"),
+            (
+                "[[regions]]\nname = \"A\"\norign = 0",
+                "unknown field \"orign\"",
+            ),
+            ("preset = \"\"\"x\"\"\"", "multi-line strings"),
+            ("preset = \"x\" y", "end of the line"),
+        ] {
+            match config::Config::from_toml(toml) {
+                Err(err @ LinkerError::InvalidConfig(_)) => {
+                    assert!(err.to_string().contains(reason), "{}", err)
+                }
+                result => panic!("Expected invalid config, but got {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn describe_presets() {
+        for (name, _) in preset::PRESETS.iter() {
+            preset::named(name).unwrap().unwrap();
+        }
+        let description = preset::describe("lm3s6965evb").unwrap().unwrap();
+        assert!(description.contains("  FLASH            0x00000000    256KiB\n"));
+        assert!(description.contains("  .data            RAM loaded from FLASH\n"));
+        assert!(description.contains("  .stack           RAM, rest of region\n"));
+        assert!(preset::describe("pdp11").is_none());
+    }
+
+    #[test]
+    fn check_without_writing() {
+        assert!(example().check().unwrap().is_empty());
+
+        let mut ls = example();
+        ls.region("ITCM", 0x0, 0x1000).unwrap();
+        let ocram = ls.region("OCRAM", 0x20000200, 0x1000).unwrap();
+        ls.alias("OCRAM_NC", &ocram, 0x0).unwrap();
+        ls.strict(true);
+        match ls.check() {
+            Err(LinkerError::Lints(warnings)) => assert_eq!(
+                warnings,
+                vec![
+                    lint::Warning::UnusedRegion(String::from("ITCM")),
+                    lint::Warning::UnusedRegion(String::from("OCRAM")),
+                    lint::Warning::UnusedRegion(String::from("OCRAM_NC")),
+                    lint::Warning::OverlappingRegions(
+                        String::from("ITCM"),
+                        String::from("OCRAM_NC")
+                    ),
+                    lint::Warning::OverlappingRegions(String::from("OCRAM"), String::from(RAM)),
+                ]
+            ),
+            result => panic!("Expected lints, but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn explain_config() {
+        let config = config::Config::from_json(
+            r#"{
+                "preset": "lm3s6965evb",
+                "sections": [{ "kind": "boot_config", "name": "fcb", "size": 512, "vma": "FLASH" }]
+            }"#,
+        )
+        .unwrap();
+        let table = config.explain().unwrap();
+        let row = |name: &str| {
+            table
+                .lines()
+                .find(|line| line.starts_with(name))
+                .unwrap()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            row("FLASH"),
+            [
+                "FLASH",
+                "0x00000000",
+                "262144",
+                "0x00040000",
+                "preset",
+                "lm3s6965evb"
+            ]
+        );
+        assert_eq!(
+            row(".fcb"),
+            [
+                ".fcb",
+                "FLASH",
+                "-",
+                "0x00000000",
+                "512",
+                "sections[0]",
+                "boot_config"
+            ]
+        );
+        assert_eq!(
+            row(".data"),
+            [
+                ".data",
+                "RAM",
+                "FLASH",
+                "-",
+                "linked",
+                "preset",
+                "lm3s6965evb"
+            ]
+        );
+    }
+
+    #[test]
+    fn starter_config() {
+        let starter = config::starter("mps2_an385").unwrap();
+        let config = config::Config::from_json(&starter).unwrap();
+        assert_eq!(config.preset.as_deref(), Some("mps2_an385"));
+        config.linker_script().unwrap().check().unwrap();
+        assert!(config::starter("pdp11").is_none());
+        assert!(config::build_rs("memory.json").contains("Config::open(\"memory.json\")"));
+    }
+
+    #[test]
+    fn read_hand_written_script() {
+        let layout = example().layout();
+        let parsed = script::layout(&render(example())).unwrap();
+        assert_eq!(diff::layouts(&parsed, &layout), vec![]);
+
+        let parsed = script::layout(
+            "MEMORY
+            {
+                FLASH (rx) : ORIGIN = 0x60000000, LENGTH = 8M - 4K /* less the tail */
+                RAM : org = ORIGIN(FLASH) + LENGTH(FLASH), len = 0x400
+            }
+            SECTIONS
+            {
+                PROVIDE(_stack_start = ORIGIN(RAM) + LENGTH(RAM));
+                .vector_table ORIGIN(FLASH) : { KEEP(*(.vector_table)); } > FLASH
+                .text : { *(.text .text.*); }
+                .data : ALIGN(4) { *(.data .data.*); } > RAM AT>FLASH
+                /DISCARD/ : { *(.ARM.exidx); }
+            }",
+        )
+        .unwrap();
+        assert_eq!(parsed.regions[0].size, 8 * 1024 * 1024 - 4096);
+        assert_eq!(
+            parsed.regions[1].origin,
+            0x60000000 + 8 * 1024 * 1024 - 4096
+        );
+        let placement: Vec<_> = parsed
+            .sections
+            .iter()
+            .map(|s| (s.output.as_str(), s.vma.as_str(), s.lma.as_deref()))
+            .collect();
+        assert_eq!(
+            placement,
+            [
+                ("vector_table", FLASH, None),
+                ("text", FLASH, None),
+                ("data", RAM, Some(FLASH)),
+            ]
+        );
+        match script::layout("MEMORY { FLASH : ORIGIN = 0 }") {
+            Err(LinkerError::InvalidScript(_)) => {}
+            result => panic!("Expected invalid script, but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn convert_hand_written_script() {
+        let text = "MEMORY {
+                FLASH : ORIGIN = 0x60000000, LENGTH = 0x1000
+                RAM : ORIGIN = 0x20000000, LENGTH = 0x400
+            }
+            SECTIONS {
+                .vector_table : { } > FLASH
+                .text : { } > FLASH
+                .rodata : { } > FLASH
+                .data : { } > RAM AT> FLASH
+                .RAM.bss (NOLOAD) : { } > RAM
+                .bss (NOLOAD) : { } > RAM
+                .uninit (NOLOAD) : { } > RAM
+            }";
+        let layout = script::layout(text).unwrap();
+        let (converted, skipped) = config::Config::from_layout(&layout);
+        assert!(skipped.is_empty());
+
+        let config = config::Config::from_json(&converted.to_json()).unwrap();
+        assert_eq!(config, converted);
+        let generated = config.linker_script().unwrap().layout();
+        let changes = diff::layouts(&layout, &generated);
+        assert_eq!(
+            changes,
+            vec![diff::Change::SectionAdded(String::from(".stack"))]
+        );
+
+        let builder = config.to_builder();
+        assert!(builder.contains("let flash = ls.region(\"FLASH\", 0x60000000, 0x1000)?;\n"));
+        assert!(builder.contains("ls.data(false, ram.clone(), Some(flash.clone()))?;\n"));
+        assert!(builder.contains("ls.bss(true, ram.clone(), None)?;\n"));
+        assert!(builder.contains("ls.uninit(ram.clone())?;\n"));
+        assert!(builder.contains("ls.stack(ram.clone())?;\n"));
+    }
+
+    #[test]
+    fn generate_all_with_manifest() {
+        let dir = std::env::temp_dir().join("imxrt-rt-gen-generate-all");
+        std::fs::create_dir_all(&dir).unwrap();
+        let interrupts = [device::Interrupt {
+            name: String::from("GPIO1"),
+            value: 80,
+        }];
+        example().generate_all(&dir, &interrupts).unwrap();
+
+        let header = std::fs::read_to_string(dir.join("memory_map.h")).unwrap();
+        assert!(header.contains("#define FLASH_ORIGIN 0x60000000u\n#define FLASH_SIZE 0x1000u\n"));
+        let device_x = std::fs::read_to_string(dir.join("device.x")).unwrap();
+        assert!(device_x.contains("PROVIDE(GPIO1 = DefaultHandler);"));
+        let manifest = std::fs::read_to_string(dir.join("manifest.json")).unwrap();
+        let manifest = json::parse(&manifest).unwrap();
+        let files: Vec<&str> = manifest
+            .get("files")
+            .and_then(json::Value::as_array)
+            .unwrap()
+            .iter()
+            .map(|file| file.get("name").and_then(json::Value::as_str).unwrap())
+            .collect();
+        assert_eq!(
+            files,
+            [
+                "link.x",
+                "reset.rs",
+                "regions.rs",
+                "layout.json",
+                "memory_map.h",
+                "placement.rs",
+                "device.x"
+            ]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn failed_generate_leaves_files() {
+        let dir = std::env::temp_dir().join("imxrt-rt-gen-generate-failed");
+        std::fs::create_dir_all(&dir).unwrap();
+        example().generate_to(&dir).unwrap();
+        let link_x = std::fs::read_to_string(dir.join("link.x")).unwrap();
+
+        let mut ls = example();
+        ls.region("ITCM", 0x0, 0x1000).unwrap();
+        ls.strict(true);
+        match ls.clone().generate_to(&dir) {
+            Err(LinkerError::Lints(_)) => {}
+            result => panic!("Expected lints, but got {:?}", result),
+        }
+        assert_eq!(std::fs::read_to_string(dir.join("link.x")).unwrap(), link_x);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(ls.generate_to(&dir).is_err());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn project_images_checked_against_each_other() {
+        let project = |app_origin: u32, app_ram: u32| {
+            let mut project = project::Project::<u32>::new();
+            let boot = project
+                .bootloader
+                .region("BOOT", 0x60000000, 0x1000)
+                .unwrap();
+            let app = project.app.region("APP", app_origin, 0x1000).unwrap();
+            project.bootloader.region(RAM, 0x20000000, 0x400).unwrap();
+            let ram = project.app.region(RAM, 0x20000000, app_ram).unwrap();
+            for (ls, flash) in [(&mut project.bootloader, boot), (&mut project.app, app)] {
+                ls.stack(ram.clone()).unwrap();
+                ls.vector_table(flash.clone(), None).unwrap();
+                ls.text(flash.clone(), None).unwrap();
+                ls.data(false, ram.clone(), Some(flash.clone())).unwrap();
+                ls.rodata(false, flash.clone(), None).unwrap();
+                ls.bss(false, ram.clone(), None).unwrap();
+            }
+            project
+        };
+
+        project(0x60001000, 0x400).check().unwrap();
+        match project(0x60000800, 0x400).check() {
+            Err(LinkerError::ImagesOverlap { bootloader, app }) => {
+                assert_eq!((bootloader.as_str(), app.as_str()), ("BOOT", "APP"))
+            }
+            result => panic!("Expected ImagesOverlap, but got {:?}", result),
+        }
+        match project(0x60001000, 0x200).check() {
+            Err(LinkerError::RegionMismatch(name)) => assert_eq!(name, RAM),
+            result => panic!("Expected RegionMismatch, but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn jump_table_shared_between_images() {
+        let mut project = project::Project::<u32>::new();
+        project
+            .jump_table(
+                "JUMP_TABLE",
+                0x60000400,
+                &[
+                    ("boot_version", "extern \"C\" fn() -> u32"),
+                    ("boot_reset", "extern \"C\" fn() -> !"),
+                ],
+            )
+            .unwrap();
+
+        let mut out = Vec::new();
+        project.write_jump_table(&mut out).unwrap();
+        let jump_table = String::from_utf8(out).unwrap();
+        assert!(jump_table.contains(
+            "    /// Entry at 0x60000404\n    #[link_name = \"__jump_table_boot_reset\"]\n    pub static boot_reset: extern \"C\" fn() -> !;\n"
+        ));
+
+        let boot = project.bootloader.sections.get("jump_table").unwrap();
+        assert_eq!(
+            boot.linker_preamble.as_deref(),
+            Some("LONG(boot_version);\n\t\tLONG(boot_reset);")
+        );
+        let mut app = example();
+        app.symbols = project.app.symbols;
+        let script = render(app);
+        assert!(script.contains(
+            "__jump_table_boot_version = 0x60000400;\n__jump_table_boot_reset = 0x60000404;\n"
+        ));
+    }
+
+    #[test]
+    fn serial_download_ivt() {
+        let mut ls = LinkerScript::<u32>::new();
+        let ocram = ls.region("OCRAM", 0x20208000, 0x78000).unwrap();
+        ls.serial_download_ivt(ocram.clone(), None).unwrap();
+        ls.stack(ocram.clone()).unwrap();
+        ls.vector_table(ocram.clone(), None).unwrap();
+        ls.text(ocram.clone(), None).unwrap();
+        ls.data(false, ocram.clone(), None).unwrap();
+        ls.rodata(false, ocram.clone(), None).unwrap();
+        ls.bss(false, ocram, None).unwrap();
+        ls.family(chip::Family::Imxrt1060);
+        ls.boot_mode(chip::BootMode::SerialDownload);
+        assert_eq!(ls.layout().fixed_address("ivt"), Some((0x20208000, 0x1000)));
+        let script = render(ls);
+        assert!(script.contains(
+            "\t\t__start_ivt = .;\n\t\t. = __start_ivt + 0x400;\n\t\t__ivt = .;\n\t\tLONG(0x412000D1); /* header */\n"
+        ));
+        assert!(script.contains(
+            "\t\tLONG(LENGTH(OCRAM)); /* image size */\n\t\tLONG(0); /* plugin */\n\t\t. = __start_ivt + 4096;\n"
+        ));
+    }
+
+    #[test]
+    fn boot_flexspi_windows() {
+        let boot = |family: &str, flexspi: Option<&str>, origin: u32| {
+            let mut ls = LinkerScript::<u32>::new();
+            let flash = ls.region(FLASH, origin, 0x1000).unwrap();
+            let ram = ls.region(RAM, 0x20000000, 0x400).unwrap();
+            ls.boot_config(0x200, "fcb", flash.clone()).unwrap();
+            ls.stack(ram.clone()).unwrap();
+            ls.vector_table(flash.clone(), None).unwrap();
+            ls.text(flash.clone(), None).unwrap();
+            ls.data(false, ram.clone(), Some(flash.clone())).unwrap();
+            ls.rodata(false, flash, None).unwrap();
+            ls.bss(false, ram, None).unwrap();
+            ls.family(family.parse().unwrap());
+            ls.boot_mode(chip::BootMode::Xip);
+            if let Some(flexspi) = flexspi {
+                ls.boot_flexspi(flexspi.parse().unwrap());
+            }
+            ls.write(&mut Vec::new())
+        };
+        boot("imxrt1062", None, 0x60000000).unwrap();
+        boot("imxrt1062", Some("flexspi2"), 0x70000000).unwrap();
+        boot("imxrt1064", None, 0x70000000).unwrap();
+        boot("imxrt1064", Some("flexspi1"), 0x60000000).unwrap();
+        match boot("imxrt1062", Some("flexspi2"), 0x60000000) {
+            Err(LinkerError::BootOffset { expected, .. }) => assert_eq!(expected, 0x70000000),
+            result => panic!("Expected boot offset, but got {:?}", result),
+        }
+        match boot("imxrt1052", Some("flexspi2"), 0x70000000) {
+            Err(LinkerError::UnsupportedFlexSpi { .. }) => {}
+            result => panic!("Expected unsupported FlexSPI, but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn encrypted_regions_fit_the_engine() {
+        let boot = |family: &str, encrypted: &[(&str, u32, u32)], fcb_encrypted: bool| {
+            let mut ls = LinkerScript::<u32>::new();
+            let boot = ls.region("BOOT", 0x60000000, 0x1000).unwrap();
+            let flash = ls.region(FLASH, 0x60001000, 0x10000).unwrap();
+            let ram = ls.region(RAM, 0x20000000, 0x400).unwrap();
+            ls.boot_config(0x200, "fcb", boot.clone()).unwrap();
+            ls.stack(ram.clone()).unwrap();
+            ls.vector_table(flash.clone(), None).unwrap();
+            ls.text(flash.clone(), None).unwrap();
+            ls.data(false, ram.clone(), Some(flash.clone())).unwrap();
+            ls.rodata(false, flash.clone(), None).unwrap();
+            ls.bss(false, ram, None).unwrap();
+            ls.encrypted(&flash).unwrap();
+            if fcb_encrypted {
+                ls.encrypted(&boot).unwrap();
+            }
+            for (name, origin, size) in encrypted {
+                let region = ls.region(name, *origin, *size).unwrap();
+                ls.encrypted(&region).unwrap();
+            }
+            ls.family(family.parse().unwrap());
+            let mut regions = Vec::new();
+            ls.write_regions(&mut regions).unwrap();
+            ls.write(&mut Vec::new())
+                .map(|_| String::from_utf8(regions).unwrap())
+        };
+
+        let regions = boot("imxrt1062", &[], false).unwrap();
+        assert!(regions.contains(
+            "pub const ENCRYPTED_RANGES: [(usize, usize); 1] = [\n    (0x60001000, 0x60011000),\n];"
+        ));
+        let invalid = |result: Result<String>, reason: &str| match result {
+            Err(LinkerError::InvalidEncryption(message)) => {
+                assert!(message.contains(reason), "{}", message)
+            }
+            result => panic!("Expected invalid encryption, but got {:?}", result),
+        };
+        invalid(boot("imxrt1015", &[], false), "can't decrypt");
+        invalid(boot("imxrt1062", &[], true), ".fcb");
+        invalid(
+            boot("imxrt1062", &[("DATA", 0x60020000, 0x100)], false),
+            "aligned",
+        );
+        let ranges = [("A", 0x60020000, 0x400), ("B", 0x60030000, 0x400)];
+        invalid(boot("imxrt1062", &ranges, false), "at most 2");
+        let otfad = chip::Family::Imxrt1170.encryption_engine().unwrap();
+        assert_eq!(otfad.max_regions(), 4);
+    }
+
+    #[test]
+    fn recovery_slot_relinks_the_app() {
+        let mut project = project::Project::<u32>::new();
+        let boot = project
+            .bootloader
+            .region("BOOT", 0x60000000, 0x1000)
+            .unwrap();
+        let app = project.app.region("APP", 0x60001000, 0x1000).unwrap();
+        let ram = project.region(RAM, 0x20000000, 0x400).unwrap();
+        for (ls, flash) in [
+            (&mut project.bootloader, boot),
+            (&mut project.app, app.clone()),
+        ] {
+            ls.stack(ram.clone()).unwrap();
+            ls.vector_table(flash.clone(), None).unwrap();
+            ls.text(flash.clone(), None).unwrap();
+            ls.data(false, ram.clone(), Some(flash.clone())).unwrap();
+            ls.rodata(false, flash.clone(), None).unwrap();
+            ls.bss(false, ram.clone(), None).unwrap();
+        }
+
+        project.recovery_slot("RECOVERY", &app, 0x60002000).unwrap();
+        project.check().unwrap();
+        let recovery = project.recovery().unwrap().unwrap();
+        assert_eq!(recovery.layout().region("APP").unwrap().origin, 0x60002000);
+        let mut out = Vec::new();
+        project.write_slots(&mut out).unwrap();
+        let slots = String::from_utf8(out).unwrap();
+        assert!(slots.contains(
+            "pub const SLOTS: [Slot; 2] = [\n    Slot { name: \"APP\", origin: 0x60001000, size: 0x1000 },\n    Slot { name: \"RECOVERY\", origin: 0x60002000, size: 0x1000 },\n];"
+        ));
+
+        project.recovery_slot("RECOVERY", &app, 0x60001800).unwrap();
+        match project.check() {
+            Err(LinkerError::SlotOverlap { slot, region }) => {
+                assert_eq!((slot.as_str(), region.as_str()), ("RECOVERY", "APP"))
+            }
+            result => panic!("Expected slot overlap, but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn rt1170_secondary_image() {
+        let script = |family: chip::Family| {
+            let mut ls = LinkerScript::<u32>::new();
+            let flash = ls.region(FLASH, 0x30000400, 0x100000).unwrap();
+            let ram = ls.region(RAM, 0x20000000, 0x400).unwrap();
+            ls.boot_config(0x200, "fcb", flash.clone()).unwrap();
+            ls.stack(ram.clone()).unwrap();
+            ls.vector_table(flash.clone(), None).unwrap();
+            ls.text(flash.clone(), None).unwrap();
+            ls.data(false, ram.clone(), Some(flash.clone())).unwrap();
+            ls.rodata(false, flash.clone(), None).unwrap();
+            ls.bss(false, ram, None).unwrap();
+            ls.family(family);
+            (ls, flash)
+        };
+        let (ls, flash) = script(chip::Family::Imxrt1170);
+        let (secondary, image) = dual::secondary(&ls, &flash, 0x200000).unwrap();
+        assert_eq!((image.secondary, image.fuse), (0x30200400, 2));
+        assert!(image.to_json().contains("\"secondary\": \"0x30200400\""));
+        assert_eq!(secondary.layout().region(FLASH).unwrap().origin, 0x30200400);
+        secondary.write(&mut Vec::new()).unwrap();
+        ls.write(&mut Vec::new()).unwrap();
+
+        let (ls, flash) = script(chip::Family::Imxrt1170);
+        for offset in [0x80000, 0x180000, 0x1000000] {
+            match dual::secondary(&ls, &flash, offset) {
+                Err(LinkerError::InvalidDualImage(_)) => {}
+                result => panic!(
+                    "Expected invalid dual image, but got {:?}",
+                    result.map(|r| r.1)
+                ),
+            }
+        }
+        let (ls, flash) = script(chip::Family::Imxrt1060);
+        match dual::secondary(&ls, &flash, 0x200000) {
+            Err(LinkerError::InvalidDualImage(_)) => {}
+            result => panic!(
+                "Expected invalid dual image, but got {:?}",
+                result.map(|r| r.1)
+            ),
+        }
+    }
+
+    #[test]
+    fn image_loaded_clear_of_rom_workspace() {
+        let boot = |origin: u32| {
+            let mut ls = LinkerScript::<u32>::new();
+            let ocram = ls.region("OCRAM", origin, 0x40000).unwrap();
+            ls.stack(ocram.clone()).unwrap();
+            ls.vector_table(ocram.clone(), None).unwrap();
+            ls.text(ocram.clone(), None).unwrap();
+            ls.data(false, ocram.clone(), None).unwrap();
+            ls.rodata(false, ocram.clone(), None).unwrap();
+            ls.bss(false, ocram, None).unwrap();
+            ls.reserve("FUSE_SHADOW", 0x20230000, 0x100);
+            ls.family(chip::Family::Imxrt1060);
+            ls.boot_mode(chip::BootMode::RamLoad);
+            ls
+        };
+        assert_links(boot(0x20208000), "reserved");
+        let script = render(boot(0x20208000));
+        assert!(script.contains(
+            "\nASSERT(__OCRAM_origin + __OCRAM_used <= 0x20230000, \"The image in OCRAM overlaps the boot ROM's FUSE_SHADOW\");"
+        ));
+        assert!(!script.contains("ROM_WORKSPACE"));
+        match boot(0x20200000).check() {
+            Err(LinkerError::ReservedMemory { reservation, .. }) => {
+                assert_eq!(reservation, "ROM_WORKSPACE")
+            }
+            result => panic!("Expected reserved memory, but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn serial_download_dcd_wired_into_ivt() {
+        let ivt = |dcd: Option<u32>| {
+            let mut ls = LinkerScript::<u32>::new();
+            let ocram = ls.region("OCRAM", 0x20208000, 0x78000).unwrap();
+            ls.serial_download_ivt(ocram, dcd)
+                .map(|_| (ls.layout().fixed_address("ivt"), ls.sections["ivt"].clone()))
+        };
+        let (address, section) = ivt(None).unwrap();
+        assert_eq!(address, Some((0x20208000, 0x1000)));
+        let preamble = section.linker_preamble.unwrap();
+        assert!(preamble.contains("LONG(0); /* DCD */") && !preamble.contains("__dcd"));
+
+        let (address, section) = ivt(Some(0x200)).unwrap();
+        assert_eq!(address, Some((0x20208000, 0x1000)));
+        let preamble = section.linker_preamble.unwrap();
+        assert!(preamble.contains("LONG(__dcd); /* DCD */"));
+        assert!(preamble
+            .contains(". = __start_ivt + 0x430;\n\t\t__dcd = .;\n\t\tKEEP(*(.dcd .dcd.*));"));
+
+        let (address, _) = ivt(Some(0x6E8)).unwrap();
+        assert_eq!(address, Some((0x20208000, 0x1000)));
+        match ivt(Some(0x800)) {
+            Err(LinkerError::SectionTooLarge { available, .. }) => assert_eq!(available, 1768),
+            result => panic!(
+                "Expected section too large, but got {:?}",
+                result.map(|r| r.0)
+            ),
+        }
+    }
+
+    #[test]
+    fn boot_gen_statics_placed() {
+        let boot = |family: Option<chip::Family>, origin: u32| {
+            let mut ls = LinkerScript::<u32>::new();
+            let flash = ls.region(FLASH, origin, 0x10000).unwrap();
+            let ram = ls.region(RAM, 0x20000000, 0x400).unwrap();
+            if let Some(family) = family {
+                ls.family(family);
+            }
+            ls.boot_gen(flash.clone())?;
+            ls.stack(ram.clone()).unwrap();
+            ls.vector_table(flash.clone(), None).unwrap();
+            ls.text(flash.clone(), None).unwrap();
+            ls.data(false, ram.clone(), Some(flash.clone())).unwrap();
+            ls.rodata(false, flash, None).unwrap();
+            ls.bss(false, ram, None).unwrap();
+            Ok(ls)
+        };
+        let ls = boot(Some(chip::Family::Imxrt1060), 0x60000000).unwrap();
+        let layout = ls.layout();
+        assert_eq!(layout.fixed_address("fcb"), Some((0x60000000, 0x1000)));
+        assert_eq!(layout.fixed_address("ivt"), Some((0x60001000, 0x20)));
+        assert_eq!(layout.fixed_address("boot_data"), Some((0x60001020, 0xFE0)));
+        let script = render(ls);
+        assert!(script.contains("\t\tKEEP(*(.ivt .ivt.*));\n\t\t. = __start_ivt + 32;\n"));
+
+        let ls = boot(Some(chip::Family::Imxrt1170), 0x30000000).unwrap();
+        assert_eq!(ls.layout().fixed_address("fcb"), Some((0x30000400, 0xC00)));
+        ls.write(&mut Vec::new()).unwrap();
+
+        match boot(None, 0x60000000) {
+            Err(LinkerError::MissingFamily(_)) => {}
+            result => panic!("Expected missing family, but got {:?}", result.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn packed_loads_copied_in_one_loop() {
+        let mut ls = example();
+        ls.pack_loads(true);
+        let mut out = Vec::new();
+        ls.write_reset(&mut out).unwrap();
+        let reset = String::from_utf8(out).unwrap();
+        assert!(reset.contains(
+            "    // .data, .RAM.data, packed in FLASH\n    let mut src = core::ptr::addr_of!(__load_data) as *const u32;\n"
+        ));
+        assert_eq!(reset.matches("__copy(start, end, src);").count(), 1);
+        assert!(!reset.contains("__copy(core::ptr"));
+
+        let script = render(ls);
+        assert!(script.contains(
+            "\nASSERT(__load_RAM_data == __load_data + SIZEOF(.data), \"Packed section .RAM.data does not follow .data\");"
+        ));
+        assert!(script.find("\t.bss :").unwrap() < script.find("\t.data :").unwrap());
+        assert!(script.find("\t.RAM.data :").unwrap() < script.find("\t.stack :").unwrap());
+
+        let mut ls = linkable();
+        ls.pack_loads(true);
+        assert_links(ls, "packed");
+    }
+
+    #[test]
+    fn lz4_round_trip() {
+        let mut noise = Vec::new();
+        let mut state = 0x1234_5678u32;
+        for _ in 0..1000 {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            noise.push((state >> 16) as u8);
+        }
+        let inputs: Vec<Vec<u8>> = vec![
+            Vec::new(),
+            vec![7],
+            b"abcdabcdabcdabcdabcd".to_vec(),
+            vec![0; 5000],
+            noise.clone(),
+            noise.iter().chain(noise.iter()).cloned().collect(),
+        ];
+        for input in inputs.iter() {
+            let block = compress::lz4(input);
+            assert_eq!(compress::unlz4(&block, input.len()).unwrap(), *input);
+        }
+        assert!(compress::lz4(&[0; 5000]).len() < 50);
+        match compress::unlz4(&compress::lz4(&noise)[..10], noise.len()) {
+            Err(LinkerError::InvalidImage(_)) => {}
+            result => panic!("Expected invalid image, but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn compressed_data_decompressed_at_reset() {
+        let mut ls = example();
+        ls.compress(&SectionID(String::from("data"))).unwrap();
+        match ls.compress(&SectionID(String::from("bss"))) {
+            Err(LinkerError::InvalidCompression(_)) => {}
+            result => panic!("Expected invalid compression, but got {:?}", result),
+        }
+        assert!(ls.layout().section(".data").unwrap().compressed);
+        let mut out = Vec::new();
+        ls.write_reset(&mut out).unwrap();
+        let reset = String::from_utf8(out).unwrap();
+        assert!(reset.contains(
+            "    // .RAM.data, packed in FLASH\n    let mut src = core::ptr::addr_of!(__load_RAM_data) as *const u32;\n"
+        ));
+        assert!(reset.contains(
+            "    // .data, compressed in FLASH\n    let mut src = core::ptr::addr_of!(__load_data) as *const u8;\n"
+        ));
+        assert!(reset.contains("        src = __unlz4(start, end, src);\n"));
+        let script = render(ls);
+        assert!(script.find("\t.RAM.data :").unwrap() < script.find("\t.data :").unwrap());
+
+        let data: Vec<u8> = b"compressible "
+            .iter()
+            .cycle()
+            .take(0x200)
+            .cloned()
+            .collect();
+        let mut builder = elf::testing::ElfBuilder::default();
+        builder.progbits(".text", 0x60000000, 0x60000000, &[0x55; 0x100]);
+        builder.progbits(".data", 0x20000000, 0x60000100, &data);
+        let elf = elf::Elf::parse(builder.build()).unwrap();
+        let mut ls = example();
+        ls.compress(&SectionID(String::from("data"))).unwrap();
+        let layout = ls.layout();
+        let mut img = image::Image::from_elf(&elf, &layout, None, image::ERASED).unwrap();
+        let saved = compress::compress(&mut img, &elf, &layout).unwrap();
+        assert_eq!(img.end(), 0x60000300 - saved);
+        assert_eq!(img.data[..0x100], [0x55; 0x100][..]);
+        assert_eq!(
+            compress::unlz4(&img.data[0x100..], data.len()).unwrap(),
+            data
+        );
+
+        let mut img = image::Image::from_elf(&elf, &example().layout(), None, 0).unwrap();
+        assert_eq!(
+            compress::compress(&mut img, &elf, &example().layout()).unwrap(),
+            0
+        );
+        assert_eq!(img.end(), 0x60000300);
+    }
+
+    #[test]
+    fn copy_loops_unrolled_or_aeabi() {
+        let reset = |ls: &LinkerScript<u32>| {
+            let mut out = Vec::new();
+            ls.write_reset(&mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let mut ls = example();
+        let unrolled = reset(&ls);
+        assert!(unrolled.contains("    while end.offset_from(dst) >= 4 {\n"));
+        assert!(unrolled.contains("core::ptr::write_volatile(dst.add(3), 0);"));
+        assert!(!unrolled.contains("__aeabi"));
+
+        ls.aeabi_memcpy(true);
+        let aeabi = reset(&ls);
+        assert!(aeabi.contains(
+            "    __aeabi_memcpy4(dst as *mut u8, src as *const u8, end as usize - dst as usize);"
+        ));
+        assert!(aeabi.contains("    __aeabi_memclr4(dst as *mut u8, end as usize - dst as usize);"));
+        assert!(!aeabi.contains("offset_from(dst)"));
+    }
+
+    #[test]
+    fn dma_init_started_before_cpu_copies() {
+        let data = SectionID(String::from("data"));
+        let mut ls = example();
+        match ls.dma_init(&data) {
+            Err(LinkerError::MissingFamily(_)) => {}
+            result => panic!("Expected missing family, but got {:?}", result),
+        }
+        ls.family(chip::Family::Imxrt1060);
+        match ls.dma_init(&data) {
+            Err(LinkerError::InvalidDmaInit(_)) => {}
+            result => panic!("Expected invalid DMA init, but got {:?}", result),
+        }
+        ls.dma_capable(&RegionID(String::from(RAM))).unwrap();
+        match ls.dma_init(&SectionID(String::from("bss"))) {
+            Err(LinkerError::InvalidDmaInit(_)) => {}
+            result => panic!("Expected invalid DMA init, but got {:?}", result),
+        }
+        ls.dma_init(&data).unwrap();
+        ls.dma_init(&SectionID(String::from("RAM.data"))).unwrap();
+        let mut channels = ls.clone();
+        channels.family(chip::Family::Imxrt1010);
+        for idx in 2..=16 {
+            let tables = channels
+                .custom_section(
+                    &format!("tables{}", idx),
+                    false,
+                    RegionID(String::from(RAM)),
+                    Some(RegionID(String::from(FLASH))),
+                    None,
+                )
+                .unwrap();
+            match channels.dma_init(&tables) {
+                Ok(()) if idx < 16 => {}
+                Err(err @ LinkerError::InvalidDmaInit(_)) if idx == 16 => assert!(
+                    err.to_string()
+                        .contains("needs more than the 16 eDMA channels of the imxrt1010"),
+                    "{}",
+                    err
+                ),
+                result => panic!("Unexpected DMA init of channel {}, {:?}", idx, result),
+            }
+        }
+        match ls.compress(&data) {
+            Err(LinkerError::InvalidCompression(_)) => {}
+            result => panic!("Expected invalid compression, but got {:?}", result),
+        }
+
+        let mut out = Vec::new();
+        ls.write_reset(&mut out).unwrap();
+        let reset = String::from_utf8(out).unwrap();
+        let init = reset.find("pub unsafe fn init() {\n").unwrap();
+        assert!(reset[init..].starts_with(
+            "pub unsafe fn init() {\n    // .data, RAM loaded from FLASH by eDMA\n    __dma_copy(0x400E9000, core::ptr::addr_of_mut!(__start_data), core::ptr::addr_of_mut!(__end_data), core::ptr::addr_of!(__load_data));\n"
+        ));
+        assert!(reset.contains("__dma_copy(0x400E9020, core::ptr::addr_of_mut!(__start_RAM_data)"));
+        assert!(!reset.contains("__copy(core::ptr"));
+        assert!(reset.contains(
+            "    unsafe { __dma_done(0x400E9000, core::ptr::addr_of!(__start_data), core::ptr::addr_of!(__end_data)) && __dma_done(0x400E9020,"
+        ));
+        assert!(reset.contains("pub fn wait_init() {\n"));
+
+        let mut out = Vec::new();
+        example().write_reset(&mut out).unwrap();
+        assert!(!String::from_utf8(out).unwrap().contains("wait_init"));
+    }
+
+    #[test]
+    fn copied_code_synchronized() {
+        let reset = |ls: &LinkerScript<u32>| {
+            let mut out = Vec::new();
+            ls.write_reset(&mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let plain = reset(&example());
+        assert!(!plain.contains("__sync_code"));
+
+        let mut ls = example();
+        let itcm = ls.region("ITCM", 0x0, 0x8000).unwrap();
+        ls.hot_text(&["isr"], itcm, Some(RegionID(String::from(FLASH))))
+            .unwrap();
+        let copied = reset(&ls);
+        assert!(copied.contains("    core::ptr::write_volatile(0xE000_EF50 as *mut u32, 0);\n"));
+        assert!(copied
+            .ends_with("    // Code was copied, fetch it from memory\n    __sync_code();\n}\n"));
+    }
+
+    #[test]
+    fn dcache_maintenance_after_cached_init() {
+        let reset = |ls: &LinkerScript<u32>| {
+            let mut out = Vec::new();
+            ls.write_reset(&mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let mut ls = example();
+        ls.family(chip::Family::Imxrt1060);
+        let ram = RegionID(String::from(RAM));
+        ls.dma_capable(&ram).unwrap();
+        ls.dma_init(&SectionID(String::from("RAM.data"))).unwrap();
+        assert!(!reset(&ls).contains("__dcache"));
+
+        ls.dcache_before_init(true);
+        let cached = reset(&ls);
+        assert!(cached.contains(
+            "    __dcache_clean(core::ptr::addr_of!(__start_data), core::ptr::addr_of!(__end_data));\n"
+        ));
+        assert!(cached.contains(
+            "    __dcache_clean(core::ptr::addr_of!(__start_bss), core::ptr::addr_of!(__end_bss));\n"
+        ));
+        assert!(!cached.contains("__dcache_clean(core::ptr::addr_of!(__start_RAM_data)"));
+        assert!(cached.contains(
+            "    while !init_complete() {}\n    unsafe {\n        __dcache_invalidate(core::ptr::addr_of!(__start_RAM_data), core::ptr::addr_of!(__end_RAM_data));\n    }\n"
+        ));
+    }
+
+    #[test]
+    fn second_core_zeroes_handed_sections() {
+        let bss = SectionID(String::from("bss"));
+        let mut ls = example();
+        ls.family(chip::Family::Imxrt1060);
+        match ls.second_core_init(std::slice::from_ref(&bss), 0x20000000) {
+            Err(LinkerError::InvalidDualCore(_)) => {}
+            result => panic!("Expected invalid dual core, but got {:?}", result),
+        }
+        ls.family(chip::Family::Imxrt1170);
+        for (sections, mailbox) in [
+            (vec![SectionID(String::from("data"))], 0x20000000),
+            (vec![bss.clone()], 0x20000002),
+            (vec![bss.clone()], 0x200003FC),
+        ] {
+            match ls.second_core_init(&sections, mailbox) {
+                Err(LinkerError::InvalidDualCore(_)) => {}
+                result => panic!("Expected invalid dual core, but got {:?}", result),
+            }
+        }
+        ls.second_core_init(&[bss], 0x200003F0).unwrap();
+
+        let mut out = Vec::new();
+        ls.write_reset(&mut out).unwrap();
+        let reset = String::from_utf8(out).unwrap();
+        assert!(reset.contains(
+            "pub unsafe fn init() {\n    // .bss, zeroed by the second core\n    let mailbox = 0x200003F0 as *mut u32;\n    core::ptr::write_volatile(mailbox.add(1), 1);\n    core::ptr::write_volatile(mailbox.add(2), core::ptr::addr_of!(__start_bss) as u32);\n"
+        ));
+        assert!(!reset.contains("__zero(core::ptr::addr_of_mut!(__start_bss)"));
+        assert!(reset.contains(
+            "        while core::ptr::read_volatile(0x200003F0 as *const u32) != 0x444F4E45 {}\n"
+        ));
+
+        let mut out = Vec::new();
+        ls.write_second_core(&mut out).unwrap();
+        let second_core = String::from_utf8(out).unwrap();
+        assert!(second_core.contains(
+            "pub unsafe fn second_core_init() {\n    let mailbox = 0x200003F0 as *mut u32;\n"
+        ));
+        assert!(!second_core.contains("__start_"));
+
+        let config = config::Config::from_json(
+            r#"{
+                "family": "imxrt1176",
+                "second_core_mailbox": "0x200003F0",
+                "regions": [
+                    { "name": "FLASH", "origin": "0x30000000", "size": "4K" },
+                    { "name": "RAM", "origin": "0x20000000", "size": "1K" }
+                ],
+                "sections": [
+                    { "kind": "stack", "vma": "RAM" },
+                    { "kind": "vector_table", "vma": "FLASH" },
+                    { "kind": "text", "vma": "FLASH" },
+                    { "kind": "rodata", "vma": "FLASH" },
+                    { "kind": "data", "vma": "RAM", "lma": "FLASH" },
+                    { "kind": "bss", "vma": "RAM", "second_core": true }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config::Config::from_json(&config.to_json()).unwrap(),
+            config
+        );
+        assert!(config
+            .to_builder()
+            .ends_with("ls.second_core_init(&[bss.clone()], 0x200003F0)?;\n"));
+        assert!(config.linker_script().unwrap().sections["bss"].second_core);
+    }
+
+    #[test]
+    fn tiny_scripts_leave_out_optional_features() {
+        let tiny = || {
+            let mut ls = LinkerScript::<u32>::new();
+            let flash = ls.region(FLASH, 0x60000000, 0x1000).unwrap();
+            let ram = ls.region(RAM, 0x20000000, 0x400).unwrap();
+            ls.stack(ram.clone()).unwrap();
+            ls.vector_table(flash.clone(), None).unwrap();
+            ls.text(flash.clone(), None).unwrap();
+            ls.data(false, ram.clone(), Some(flash.clone())).unwrap();
+            ls.rodata(false, flash.clone(), None).unwrap();
+            ls.bss(false, ram.clone(), None).unwrap();
+            ls.tiny(true);
+            ls
+        };
+        let ls = tiny();
+        let mut out = Vec::new();
+        ls.write_reset(&mut out).unwrap();
+        let reset = String::from_utf8(out).unwrap();
+        assert!(!reset.contains("offset_from"));
+        assert_eq!(reset.matches("    __").count(), 2);
+        ls.check().unwrap();
+
+        let mut example = example();
+        example.tiny(true);
+        match example.check() {
+            Err(LinkerError::NotTiny(feature)) => assert_eq!(feature, "prefixed section .RAM.data"),
+            result => panic!("Expected not tiny, but got {:?}", result),
+        }
+        let mut ls = tiny();
+        ls.heap(RegionID(String::from(RAM))).unwrap();
+        match ls.check() {
+            Err(LinkerError::NotTiny(feature)) => assert_eq!(feature, "heap"),
+            result => panic!("Expected not tiny, but got {:?}", result),
+        }
+        let mut ls = tiny();
+        ls.pack_loads(true);
+        match ls.check() {
+            Err(LinkerError::NotTiny(feature)) => assert_eq!(feature, "packed sections"),
+            result => panic!("Expected not tiny, but got {:?}", result),
+        }
+
+        let ram = RegionID(String::from(RAM));
+        let flash = RegionID(String::from(FLASH));
+        let mut tls = tiny();
+        tls.thread_local(flash, ram.clone()).unwrap();
+        let mut protector = tiny();
+        protector.stack_protector(true);
+        let mut stub = tiny();
+        stub.reset_stub(true);
+        let mut view = tiny();
+        let alias = view.alias("RAM_ALIAS", &ram, 0x30000000).unwrap();
+        view.alias_view(&SectionID(String::from("bss")), &alias)
+            .unwrap();
+        for (expected, ls) in [
+            ("thread local storage", tls),
+            ("stack protector", protector),
+            ("reset stub", stub),
+            ("alias views of .bss", view),
+        ]
+        .iter_mut()
+        {
+            match ls.clone().check() {
+                Err(LinkerError::NotTiny(feature)) => assert_eq!(&feature, expected),
+                result => panic!("Expected not tiny, but got {:?}", result),
+            }
+            ls.tiny(false);
+            ls.clone().check().unwrap();
+        }
+    }
+
+    #[test]
+    fn reset_stub_points_vtor_where_running() {
+        let reset = |ls: &LinkerScript<u32>| {
+            let mut out = Vec::new();
+            ls.write_reset(&mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let mut ls = example();
+        assert!(!reset(&ls).contains("reset_stub"));
+        ls.reset_stub(true);
+        let stub = reset(&ls);
+        assert!(stub.contains("    static __start_vector_table: u32;\n"));
+        assert!(stub
+            .contains("pub unsafe fn reset_stub() -> usize {\n    let offset = run_offset();\n"));
+        assert!(stub.contains("            \"adr.w {run}, 2f\",\n"));
+        assert!(
+            stub.contains("core::ptr::write_volatile(0xE000_ED08 as *mut u32, vectors as u32);")
+        );
+    }
+
+    #[test]
+    fn program_headers_keep_ram_out_of_load_segments() {
+        let mut ls = example();
+        ls.program_headers(true);
+        let script = render(ls);
+        assert!(
+            script.contains("PHDRS {\n\tload_FLASH PT_LOAD;\n\tload_RAM_from_FLASH PT_LOAD;\n}\n")
+        );
+        assert!(script.contains("\t} > FLASH :load_FLASH\n"));
+        assert!(script.contains("\t} > RAM AT> FLASH :load_RAM_from_FLASH\n"));
+        assert!(script.contains("\t.bss :"));
+        assert!(!script.contains("\t} > RAM\n"));
+        assert!(script.contains("\t} > RAM :NONE\n"));
+        assert!(!render(example()).contains("PHDRS"));
+    }
+
+    #[test]
+    fn region_kinds_set_memory_attributes() {
+        let kinds = |ram: RegionKind| {
+            let mut ls = example();
+            ls.region_kind(&RegionID(String::from(FLASH)), RegionKind::Flash)
+                .unwrap();
+            ls.region_kind(&RegionID(String::from(RAM)), ram).unwrap();
+            ls
+        };
+        let script = render(kinds(RegionKind::Ram));
+        assert!(script.contains("\tFLASH (rx) : ORIGIN = 0x60000000, LENGTH = 0x1000\n"));
+        assert!(script.contains("\tRAM (rwx) : ORIGIN = 0x20000000, LENGTH = 0x400\n"));
+        assert!(render(kinds(RegionKind::Data)).contains("\tRAM (rw!x) :"));
+        assert!(render(example()).contains("\tFLASH : ORIGIN"));
+
+        let mut ls = kinds(RegionKind::Data);
+        ls.hot_text(
+            &[],
+            RegionID(String::from(RAM)),
+            Some(RegionID(String::from(FLASH))),
+        )
+        .unwrap();
+        match ls.check() {
+            Err(LinkerError::InvalidAccess(reason)) => {
+                assert_eq!(reason, "code section .hot_text runs from data region RAM")
+            }
+            result => panic!("Expected invalid access, but got {:?}", result),
+        }
+
+        let mut ls = LinkerScript::<u32>::new();
+        let flash = ls.region(FLASH, 0x60000000, 0x1000).unwrap();
+        ls.region_kind(&flash, RegionKind::Flash).unwrap();
+        ls.stack(flash.clone()).unwrap();
+        ls.vector_table(flash.clone(), None).unwrap();
+        ls.text(flash.clone(), None).unwrap();
+        ls.data(false, flash.clone(), None).unwrap();
+        ls.rodata(false, flash.clone(), None).unwrap();
+        ls.bss(false, flash, None).unwrap();
+        match ls.check() {
+            Err(LinkerError::InvalidAccess(reason)) => {
+                assert_eq!(reason, "writable section .bss runs from flash region FLASH")
+            }
+            result => panic!("Expected invalid access, but got {:?}", result),
+        }
+        assert!("rom".parse::<RegionKind>().is_err());
+    }
+
+    #[test]
+    fn no_cross_refs_to_sdram() {
+        let mut ls = example();
+        let sdram = ls.region("SDRAM", 0x80000000, 0x1000).unwrap();
+        let data = ls.data(true, sdram, None).unwrap();
+        let text = SectionID(String::from("text"));
+        ls.no_cross_refs(&data, &[text.clone(), data.clone()])
+            .unwrap();
+        match ls.no_cross_refs(&data, &[SectionID(String::from("ram_func"))]) {
+            Err(LinkerError::UnknownSection(_)) => {}
+            result => panic!("Expected unknown section, but got {:?}", result),
+        }
+        let script = render(ls);
+        assert!(script.ends_with("}\nNOCROSSREFS_TO(.SDRAM.data .text);\n"));
+
+        let config = config::Config::from_json(
+            r#"{
+                "regions": [
+                    { "name": "FLASH", "origin": "0x60000000", "size": "4K" },
+                    { "name": "RAM", "origin": "0x20000000", "size": "1K" },
+                    { "name": "SDRAM", "origin": "0x80000000", "size": "4K" }
+                ],
+                "sections": [
+                    { "kind": "stack", "vma": "RAM" },
+                    { "kind": "vector_table", "vma": "FLASH" },
+                    { "kind": "text", "vma": "FLASH" },
+                    { "kind": "rodata", "vma": "FLASH" },
+                    { "kind": "data", "vma": "RAM", "lma": "FLASH" },
+                    { "kind": "bss", "vma": "RAM" },
+                    { "kind": "bss", "vma": "SDRAM", "prefix": true, "no_cross_refs_from": ["text", "data"] }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config::Config::from_json(&config.to_json()).unwrap(),
+            config
+        );
+        assert!(config
+            .to_builder()
+            .contains("ls.no_cross_refs(&bss, &[text.clone(), data.clone()])?;"));
+        let mut out = Vec::new();
+        config.linker_script().unwrap().write(&mut out).unwrap();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .contains("NOCROSSREFS_TO(.SDRAM.bss .text .data);"));
+    }
+
+    #[test]
+    fn alias_views_export_symbols() {
+        let mut ls = example();
+        let ocram = ls.region("OCRAM", 0x20200000, 0x1000).unwrap();
+        let ocram_nc = ls.alias("OCRAM_NC", &ocram, 0x20280000).unwrap();
+        let bss = ls.bss(true, ocram.clone(), None).unwrap();
+        match ls.alias_view(&bss, &RegionID(String::from(RAM))) {
+            Err(LinkerError::InvalidView(_)) => {}
+            result => panic!("Expected invalid view, but got {:?}", result),
+        }
+        match ls.alias_view(&bss, &ocram) {
+            Err(LinkerError::InvalidView(_)) => {}
+            result => panic!("Expected invalid view, but got {:?}", result),
+        }
+        ls.alias_view(&bss, &ocram_nc).unwrap();
+        let mut regions = Vec::new();
+        ls.write_regions(&mut regions).unwrap();
+        let regions = String::from_utf8(regions).unwrap();
+        assert!(regions
+            .contains("pub fn ocram_bss_in_ocram_nc<T>(ptr: *const T) -> Option<*mut T> {\n"));
+        assert!(regions.contains("        static __start_OCRAM_bss: u8;\n"));
+        assert!(regions.contains("    if addr < start || addr >= end {\n        return None;\n"));
+        assert!(regions.contains("    Some((addr - OCRAM_ORIGIN + OCRAM_NC_ORIGIN) as *mut T)\n"));
+        assert!(!regions.contains("    if !in_ocram(addr) {\n"));
+        let script = render(ls);
+        assert!(script.contains(
+            "\t__start_OCRAM_bss_OCRAM_NC = __start_OCRAM_bss - __OCRAM_origin + __OCRAM_NC_origin;\n"
+        ));
+        assert!(script.contains(
+            "\t__end_OCRAM_bss_OCRAM_NC = __end_OCRAM_bss - __OCRAM_origin + __OCRAM_NC_origin;\n"
+        ));
+
+        let mut ls = linkable();
+        let ocram = ls.region("OCRAM", 0x20200000, 0x1000).unwrap();
+        let ocram_nc = ls.alias("OCRAM_NC", &ocram, 0x20280000).unwrap();
+        let bss = ls.bss(true, ocram, None).unwrap();
+        ls.alias_view(&bss, &ocram_nc).unwrap();
+        assert_links(ls, "views");
+    }
+
+    #[test]
+    fn build_id_before_vector_table() {
+        let mut ls = example();
+        ls.build_id(RegionID(String::from(FLASH))).unwrap();
+        let mut regions = Vec::new();
+        ls.write_regions(&mut regions).unwrap();
+        let regions = String::from_utf8(regions).unwrap();
+        assert!(regions.contains("pub fn build_id() -> &'static [u8] {\n"));
+        let script = render(ls);
+        let build_id = script.find("\t.build_id :").unwrap();
+        assert!(build_id < script.find("\t.vector_table :").unwrap());
+        assert!(
+            script.contains("\t\tKEEP(*(.note.gnu.build-id));\n\t\t. = __start_build_id + 36;\n")
+        );
+        assert!(!render(example()).contains("build_id"));
+    }
+
+    #[test]
+    fn version_string_is_nul_padded() {
+        let mut ls = example();
+        ls.version_string(RegionID(String::from(FLASH))).unwrap();
+        let mut out = Vec::new();
+        ls.write_version("blinky 0.1.0 3f2c9e1", &mut out).unwrap();
+        let version = String::from_utf8(out).unwrap();
+        assert!(version.contains("#[link_section = \".version\"]\n"));
+        assert!(version.contains(&format!(
+            "pub static IMXRT_RT_GEN_VERSION: [u8; 64] = *b\"blinky 0.1.0 3f2c9e1{}\";",
+            "\\x00".repeat(44)
+        )));
+        let mut out = Vec::new();
+        ls.write_version(&"x".repeat(80), &mut out).unwrap();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .contains(&format!("*b\"{}\\x00\";", "x".repeat(63))));
+        let script = render(ls);
+        assert!(
+            script.contains("\t\tKEEP(*(.version .version.*));\n\t\t. = __start_version + 64;\n")
+        );
+        assert!(package_version().starts_with("imxrt-rt-gen "));
+    }
+
+    #[test]
+    fn defined_symbols_reach_linker_and_firmware() {
+        let mut ls = example();
+        ls.symbol("BOOTLOADER_API", 0x60001000);
+        ls.define_symbol("__feature_flags", 0x3).unwrap();
+        for name in [
+            "BOOTLOADER_API",
+            "__feature_flags",
+            "__",
+            "2fast",
+            "a-b",
+            "_feature_flags",
+            "FEATURE_FLAGS",
+            "__flash_origin",
+            "RAM_SIZE",
+            "encrypted_ranges",
+        ]
+        .iter()
+        {
+            match ls.define_symbol(name, 0) {
+                Err(LinkerError::InvalidSymbol(_)) => {}
+                result => panic!("Expected invalid symbol, but got {:?}", result),
+            }
+        }
+        let mut regions = Vec::new();
+        ls.write_regions(&mut regions).unwrap();
+        assert!(String::from_utf8(regions)
+            .unwrap()
+            .contains("/// Value of the linker symbol `__feature_flags`\npub const FEATURE_FLAGS: usize = 0x3;\n"));
+        assert!(
+            render(ls.clone()).contains("BOOTLOADER_API = 0x60001000;\n__feature_flags = 0x3;\n")
+        );
+        assert_eq!(ls.constants.len(), 1);
+
+        ls.define_symbol("__ocram_size", 0x8000).unwrap();
+        ls.region("OCRAM", 0x20200000, 0x8000).unwrap();
+        match ls.write_regions(&mut Vec::new()) {
+            Err(LinkerError::InvalidSymbol(reason)) => assert!(reason.contains("region OCRAM")),
+            result => panic!("Expected invalid symbol, but got {:?}", result),
+        }
+
+        let mut ls = linkable();
+        ls.define_symbol("__feature_flags", 0x3).unwrap();
+        ls.define_symbol("board_rev", 0x2).unwrap();
+        assert_links(ls, "constants");
+
+        let config = config::Config::from_json(
+            r#"{ "constants": { "__feature_flags": 3, "__board_rev": "0x2" } }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.constants,
+            vec![
+                (String::from("__feature_flags"), 3),
+                (String::from("__board_rev"), 2)
+            ]
+        );
+        assert_eq!(
+            config::Config::from_json(&config.to_json()).unwrap(),
+            config
+        );
+        assert!(config
+            .to_builder()
+            .contains("ls.define_symbol(\"__board_rev\", 0x2)?;"));
+    }
+
+    #[test]
+    fn rodata_spills_into_second_flash() {
+        let mut ls = example();
+        let flash2 = ls.region("FLASH2", 0x70000000, 0x1000).unwrap();
+        let rodata = SectionID(String::from("rodata"));
+        let bss = SectionID(String::from("bss"));
+        match ls.spill(&bss, flash2.clone()) {
+            Err(LinkerError::InvalidSpill(_)) => {}
+            result => panic!("Expected invalid spill, but got {:?}", result),
+        }
+        match ls.spill(&rodata, RegionID(String::from(FLASH))) {
+            Err(LinkerError::InvalidSpill(_)) => {}
+            result => panic!("Expected invalid spill, but got {:?}", result),
+        }
+        let spilled = ls.spill(&rodata, flash2).unwrap();
+        assert_eq!(spilled, SectionID(String::from("rodata_spill")));
+        assert!(ls.lints().contains(&lint::Warning::NonContiguousSpill(
+            String::from("rodata"),
+            String::from("rodata_spill")
+        )));
+        let script = render(ls);
+        assert!(script.find("\t.rodata :").unwrap() < script.find("\t.rodata_spill :").unwrap());
+        assert!(script.contains("\t\t*(.rodata .rodata.*);\n\t\t. = ALIGN(4);\n\t\t__end_rodata_spill = .;\n\t} > FLASH2\n"));
+        assert!(script.contains(
+            "\t__size_rodata = __end_rodata - __start_rodata + __end_rodata_spill - __start_rodata_spill;\n"
+        ));
+    }
+
+    #[test]
+    fn text_partition_routes_files_to_itcm() {
+        let mut ls = example();
+        let itcm = ls.region("ITCM", 0x0, 0x800).unwrap();
+        ls.text_partition(
+            &["*libdrivers*", "*isr.o"],
+            Some(0x400),
+            itcm,
+            Some(RegionID(String::from(FLASH))),
+        )
+        .unwrap();
+        assert!(!ls
+            .lints()
+            .iter()
+            .any(|lint| matches!(lint, lint::Warning::NonContiguousSpill(..))));
+        let script = render(ls);
+        assert!(script.find("\t.ITCM.text :").unwrap() < script.find("\t.text :").unwrap());
+        assert!(script.contains("\t\t*libdrivers*(.text .text.*);\n\t\t*isr.o(.text .text.*);\n"));
+        assert!(script.contains("\t} > ITCM AT> FLASH\n"));
+        assert!(script.contains(
+            "\nASSERT(SIZEOF(.ITCM.text) <= 1024, \"Section .ITCM.text is larger than 1024 bytes\");\n"
+        ));
+        assert!(script.contains("\t\t*(.text .text.*);\n"));
+
+        let mut ls = linkable();
+        let itcm = ls.region("ITCM", 0x0, 0x8000).unwrap();
+        ls.text_partition(
+            &["*selftest*"],
+            Some(0x4000),
+            itcm,
+            Some(RegionID(String::from(FLASH))),
+        )
+        .unwrap();
+        assert_links(ls, "text_partition");
+    }
+
+    #[test]
+    fn text_partition_is_copied_at_reset() {
+        let mut ls = example();
+        let itcm = ls.region("ITCM", 0x0, 0x800).unwrap();
+        ls.text_partition(&[], None, itcm, Some(RegionID(String::from(FLASH))))
+            .unwrap();
+        assert!(ls.lints().contains(&lint::Warning::NonContiguousSpill(
+            String::from("ITCM.text"),
+            String::from("text")
+        )));
+        let mut reset = Vec::new();
+        ls.write_reset(&mut reset).unwrap();
+        let reset = String::from_utf8(reset).unwrap();
+        assert!(reset.contains("    // .ITCM.text, ITCM loaded from FLASH\n"));
+        assert!(reset.contains("    __sync_code();\n"));
+        assert!(!render(ls).contains("ASSERT(SIZEOF(.ITCM.text)"));
+    }
+
+    #[test]
+    fn load_at_exact_address() {
+        let mut ls = example();
+        let data = SectionID(String::from("data"));
+        for (section, address) in [
+            ("bss", 0x60000800),
+            ("data", 0x60000802),
+            ("data", 0x60001000),
+        ]
+        .iter()
+        {
+            match ls.load_at(&SectionID(String::from(*section)), *address) {
+                Err(LinkerError::InvalidLoadAddress(_)) => {}
+                result => panic!("Expected invalid load address, but got {:?}", result),
+            }
+        }
+        ls.load_at(&data, 0x60000800).unwrap();
+        let script = render(ls);
+        assert!(script.contains("\t.data : AT(0x60000800)\n"));
+        assert!(script.contains("\t} > RAM\n\t__load_data = LOADADDR(.data);\n"));
+        assert!(script.contains(
+            "\n}\nASSERT(LOADADDR(.data) + SIZEOF(.data) <= __FLASH_origin + __FLASH_size, \"Section .data is not loaded within FLASH\");\n"
+        ));
+        assert!(!script.contains("__FLASH_used = __FLASH_used + SIZEOF(.data);"));
+
+        let mut ls = linkable();
+        ls.load_at(&data, 0x60008000).unwrap();
+        assert_links(ls, "load_at");
+    }
+
+    #[test]
+    fn thread_local_block_is_filled_at_reset() {
+        let mut ls = example();
+        let ids = ls
+            .thread_local(RegionID(String::from(FLASH)), RegionID(String::from(RAM)))
+            .unwrap();
+        assert_eq!(ids.len(), 3);
+        assert_eq!(ls.lints(), vec![]);
+        let mut reset = Vec::new();
+        ls.write_reset(&mut reset).unwrap();
+        let reset = String::from_utf8(reset).unwrap();
+        assert!(reset.contains("    init_tls(core::ptr::addr_of_mut!(__start_tls));\n"));
+        assert!(reset.contains("pub unsafe fn init_tls(block: *mut u32) {\n"));
+        assert!(!reset.contains("__zero(core::ptr::addr_of_mut!(__start_tbss)"));
+        let script = render(ls);
+        let tdata = script.find("\t.tdata :").unwrap();
+        assert!(script.find("\t.rodata :").unwrap() < tdata);
+        assert!(tdata < script.find("\t.tbss :").unwrap());
+        assert!(script.find("\t.bss :").unwrap() < script.find("\t.tls :").unwrap());
+        assert!(script.contains("\t\t. += __end_tbss - __start_tdata;\n"));
+        assert!(script.contains("\tPROVIDE(__tbss_end = __end_tbss);\n"));
+        assert!(script.contains("\t__tls_size = __end_tls - __start_tls;\n"));
+
+        let config = config::Config::from_json(
+            r#"{ "preset": "lm3s6965evb", "sections": [{ "kind": "thread_local", "vma": "RAM", "lma": "FLASH" }] }"#,
+        )
+        .unwrap();
+        assert!(config
+            .to_builder()
+            .contains("ls.thread_local(flash.clone(), ram.clone())?;"));
+        config.linker_script().unwrap();
+    }
+
+    #[test]
+    fn coverage_counters_are_copied_not_zeroed() {
+        let mut ls = example();
+        let ids = ls
+            .coverage(RegionID(String::from(FLASH)), RegionID(String::from(RAM)))
+            .unwrap();
+        assert_eq!(ids.len(), 6);
+        assert_eq!(ls.lints(), vec![]);
+        let mut strict = ls.clone();
+        strict.strict(true);
+        strict.write(&mut Vec::new()).unwrap();
+        let mut reset = Vec::new();
+        ls.write_reset(&mut reset).unwrap();
+        let reset = String::from_utf8(reset).unwrap();
+        assert!(reset.contains("    // .__llvm_prf_cnts, RAM loaded from FLASH\n"));
+        assert!(!reset.contains("__llvm_prf_data"));
+        let script = render(ls);
+        assert!(script.contains("\t\tKEEP(*(__llvm_prf_cnts __llvm_prf_cnts.*));\n"));
+        assert!(script.contains("\t\t__stop___llvm_prf_cnts = .;\n\t} > RAM AT> FLASH\n"));
+        assert!(script.contains("\t\tKEEP(*(.gcov_info .gcov_info.*));\n"));
+        assert!(script.contains("\t\t__stop_gcov_info = .;\n\t} > FLASH\n"));
+        assert!(!script.contains("__llvm_covmap"));
+    }
+
+    #[test]
+    fn heap_and_stack_stop_at_exclusions() {
+        let mut ls = example();
+        let ram = RegionID(String::from(RAM));
+        ls.exclude(&ram, "DMA_POOL", 0x20000300, 0x100).unwrap();
+        match ls.exclude(&ram, "OTHER", 0x20000380, 0x100) {
+            Err(LinkerError::InvalidExclusion(_)) => {}
+            result => panic!("Expected invalid exclusion, but got {:?}", result),
+        }
+        match ls.exclude(&ram, "OTHER", 0x200002C0, 0x80) {
+            Err(LinkerError::InvalidExclusion(reason)) => assert!(reason.contains("DMA_POOL")),
+            result => panic!("Expected invalid exclusion, but got {:?}", result),
+        }
+        ls.heap(ram).unwrap();
+        let script = render(ls);
+        assert!(script.contains("\t\t. = 0x20000300;\n\t\t__end_heap = .;\n"));
+        assert!(
+            script.contains("\t__start_DMA_POOL = 0x20000300;\n\t__end_DMA_POOL = 0x20000400;\n")
+        );
+        assert!(script.contains(
+            "ASSERT(__RAM_origin + __RAM_used <= 0x20000300, \"Sections in RAM overlap DMA_POOL\");\n"
+        ));
+
+        let mut ls = linkable();
+        let ocram = ls.region("OCRAM", 0x20200000, 0x10000).unwrap();
+        ls.exclude(&ocram, "DMA_POOL", 0x20208000, 0x1000).unwrap();
+        ls.heap(ocram).unwrap();
+        assert_links(ls, "exclusions");
+    }
+
+    #[test]
+    fn split_region_into_parts() {
+        let mut ls = LinkerScript::<u32>::new();
+        let flash = ls.region(FLASH, 0x60000000, 0x1000).unwrap();
+        let ram = ls.region(RAM, 0x20000000, 0x400).unwrap();
+        let parts = ls
+            .split_region(&ram, &[("RAM_DMA", Some(0x100)), ("RAM_GP", None)])
+            .unwrap();
+        assert_eq!(
+            parts,
+            vec![
+                RegionID(String::from("RAM_DMA")),
+                RegionID(String::from("RAM_GP"))
+            ]
+        );
+        ls.vector_table(flash.clone(), None).unwrap();
+        ls.text(flash.clone(), None).unwrap();
+        ls.rodata(false, flash.clone(), None).unwrap();
+        ls.data(false, parts[1].clone(), Some(flash.clone()))
+            .unwrap();
+        ls.bss(false, parts[0].clone(), None).unwrap();
+        ls.stack(parts[1].clone()).unwrap();
+        match ls.split_region(&flash, &[("A", None)]) {
+            Err(LinkerError::InvalidSplit(reason)) => assert!(reason.contains("already used")),
+            result => panic!("Expected invalid split, but got {:?}", result),
+        }
+        match ls.split_region(&parts[0], &[("A", None), ("B", None)]) {
+            Err(LinkerError::InvalidSplit(_)) => {}
+            result => panic!("Expected invalid split, but got {:?}", result),
+        }
+        let script = render(ls);
+        assert!(script.contains("\tRAM_DMA : ORIGIN = 0x20000000, LENGTH = 0x100\n"));
+        assert!(script.contains("\tRAM_GP : ORIGIN = 0x20000100, LENGTH = 0x300\n"));
+        assert!(!script.contains("\tRAM :"));
+
+        let mut ls = LinkerScript::<u32>::new();
+        let flash = ls.region(FLASH, 0x60000000, 0x1000).unwrap();
+        ls.region(RAM, 0x20000000, 0x400).unwrap();
+        match ls.split_region(&flash, &[("A", Some(0x800)), ("B", Some(0x900))]) {
+            Err(LinkerError::InvalidSplit(_)) => {}
+            result => panic!("Expected invalid split, but got {:?}", result),
+        }
+        match ls.split_region(&flash, &[("A", Some(0x1000)), ("B", None)]) {
+            Err(LinkerError::ZeroSizeRegion(name)) => assert_eq!(name, "B"),
+            result => panic!("Expected zero size region, but got {:?}", result),
+        }
+        match ls.split_region(&flash, &[("RAM", None)]) {
+            Err(LinkerError::DuplicateRegion(name)) => assert_eq!(name, RAM),
+            result => panic!("Expected duplicate region, but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn reserved_regions_are_declared_only() {
+        let mut ls = example();
+        let rom = ls
+            .reserve_region("ROM_WORKSPACE", 0x20200000, 0x8000)
+            .unwrap();
+        let alias = ls.alias("ROM_ALIAS", &rom, 0x20300000).unwrap();
+        match ls.heap(rom) {
+            Err(LinkerError::ReservedRegion { section, region }) => {
+                assert_eq!(section, "heap");
+                assert_eq!(region, "ROM_WORKSPACE");
+            }
+            result => panic!("Expected reserved region, but got {:?}", result),
+        }
+        match ls.rodata(true, RegionID(String::from(RAM)), Some(alias)) {
+            Err(LinkerError::ReservedRegion { region, .. }) => assert_eq!(region, "ROM_ALIAS"),
+            result => panic!("Expected reserved region, but got {:?}", result),
+        }
+        assert!(!ls
+            .lints()
+            .iter()
+            .any(|warning| matches!(warning, lint::Warning::UnusedRegion(_))));
+        assert!(ls.layout().region("ROM_WORKSPACE").is_some());
+        let script = render(ls);
+        assert!(script.contains("\tROM_WORKSPACE : ORIGIN = 0x20200000, LENGTH = 0x8000\n"));
+    }
+
+    #[test]
+    fn display_tables_the_layout() {
+        let table = example().to_string();
+        let mut lines = table.lines();
+        assert_eq!(
+            lines.next(),
+            Some("region                       origin       size")
+        );
+        assert_eq!(
+            lines.next(),
+            Some("RAM                      0x20000000      0x400")
+        );
+        assert!(table.contains(
+            "\nsection                     priority size           vma              lma\n"
+        ));
+        assert!(table.contains(
+            "\nvector_table                       0 linker         FLASH            -\n"
+        ));
+        assert!(table.contains(
+            "\nstack                     2147483646 stack          RAM              -\n"
+        ));
+        assert!(table.contains(
+            "\nRAM.data                         102 linker         RAM              FLASH\n"
+        ));
+    }
+
+    #[test]
+    fn stack_ends_are_aligned() {
+        let script = render(example());
+        assert!(script.contains(
+            "\t\t. = ALIGN(8);\n\t\t__end_stack = .;\n\t\t. = __RAM_origin + __RAM_size;\n\t\t__start_stack = .;\n"
+        ));
+
+        let mut ls = example();
+        let ram = RegionID(String::from(RAM));
+        ls.exclude(&ram, "POOL", 0x200003F4, 0xC).unwrap();
+        ls.stack_alignment(32).unwrap();
+        for align in [4, 24].iter() {
+            match ls.stack_alignment(*align) {
+                Err(LinkerError::InvalidStackAlignment(found)) => assert_eq!(found, *align as u64),
+                result => panic!("Expected invalid stack alignment, but got {:?}", result),
+            }
+        }
+        let script = render(ls);
+        assert!(script.contains(
+            "\t\t. = ALIGN(32);\n\t\t__end_stack = .;\n\t\t. = 0x200003F4;\n\t\t__start_stack = ALIGN(. - 31, 32);\n"
+        ));
+
+        let mut ls = LinkerScript::<u32>::new();
+        let ram = ls.region(RAM, 0x20000000, 0x400).unwrap();
+        ls.stack_below_statics(0x104, ram).unwrap();
+        let mut out = Vec::new();
+        generate::link::render(&ls, &mut out).unwrap();
+        let script = String::from_utf8(out).unwrap();
+        assert!(script.contains("\t\t. += 260;\n\t\t__start_stack = ALIGN(. - 7, 8);\n"));
+    }
+
+    #[test]
+    fn stack_protector_seeds_the_guard() {
+        let mut ls = example();
+        ls.stack_protector(true);
+        let guard = ls.stack_guard.unwrap();
+        assert_eq!(guard, STACK_CANARY);
+        assert_eq!(guard & 0xFF, 0);
+        let mut reset = Vec::new();
+        ls.write_reset(&mut reset).unwrap();
+        let reset = String::from_utf8(reset).unwrap();
+        assert!(reset.contains("#[no_mangle]\npub static mut __stack_chk_guard: u32 = 0;\n"));
+        assert!(reset.contains(&format!(
+            "    core::ptr::write_volatile(core::ptr::addr_of_mut!(__stack_chk_guard), {:#X});\n",
+            guard as u32
+        )));
+        assert!(reset.contains("pub unsafe fn seed_stack_guard(entropy: u32) {"));
+        assert!(reset.contains("pub extern \"C\" fn __stack_chk_fail() -> ! {"));
+        let mut again = Vec::new();
+        ls.write_reset(&mut again).unwrap();
+        assert_eq!(String::from_utf8(again).unwrap(), reset);
+
+        ls.stack_canary(0x1234_5678);
+        ls.stack_protector(true);
+        let mut reset = Vec::new();
+        ls.write_reset(&mut reset).unwrap();
+        assert!(String::from_utf8(reset).unwrap().contains(
+            "    core::ptr::write_volatile(core::ptr::addr_of_mut!(__stack_chk_guard), 0x12345600);\n"
+        ));
+        let config = config::Config::from_json(
+            r#"{ "preset": "lm3s6965evb", "stack_protector": true, "stack_canary": "0x12345678" }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config::Config::from_json(&config.to_json()).unwrap(),
+            config
+        );
+        assert!(config
+            .to_builder()
+            .contains("ls.stack_canary(0x12345678);\n"));
+        assert_eq!(
+            config.linker_script().unwrap().stack_guard,
+            Some(0x1234_5600)
+        );
+        match config::Config::from_json(r#"{ "preset": "lm3s6965evb", "stack_canary": 1 }"#)
+            .and_then(|config| config.linker_script())
+        {
+            Err(LinkerError::InvalidConfig(_)) => {}
+            result => panic!("Expected invalid config, but got {:?}", result),
+        }
+
+        ls.stack_protector(false);
+        let mut reset = Vec::new();
+        ls.write_reset(&mut reset).unwrap();
+        assert!(!String::from_utf8(reset).unwrap().contains("__stack_chk"));
+    }
+
+    #[test]
+    fn placement_module_names_prefixed_sections() {
+        let mut ls = example();
+        ls.dma_capable(&RegionID(String::from(RAM))).unwrap();
+        ls.bss(true, RegionID(String::from(RAM)), None).unwrap();
+        let mut out = Vec::new();
+        ls.write_placement(&mut out).unwrap();
+        let placement = String::from_utf8(out).unwrap();
+        assert!(placement.contains("pub struct CacheAligned<T>(pub T);\n"));
+        assert!(placement.contains("pub const RAM_DATA_SECTION: &str = \".RAM.data\";\n"));
+        assert!(placement.contains("macro_rules! ram_data {\n"));
+        assert!(placement.contains("macro_rules! ram_bss {\n"));
+        assert!(placement.contains("        #[link_section = \".RAM.bss\"]\n"));
+        assert!(placement.contains("        $(#[link_section = \".RAM.data\"] $item)*\n"));
+        assert!(!placement.contains("SECTION: &str = \".data\""));
+    }
+
+    #[test]
+    fn selftest_stub_calls_init() {
+        let ls = example();
+        let stub = selftest::stub(&ls);
+        assert!(stub.contains("    include!(\"reset.rs\");\n"));
+        assert!(stub.contains("    reset::init();\n"));
+        assert!(
+            stub.contains("pub static __RESET_VECTOR: unsafe extern \"C\" fn() -> ! = Reset;\n")
+        );
+
+        let dir = std::env::temp_dir().join("imxrt-rt-gen-selftest");
+        std::fs::create_dir_all(&dir).unwrap();
+        match selftest::link(ls, &dir, "no-such-target") {
+            Err(LinkerError::SelfTest(output)) => assert!(!output.is_empty()),
+            Err(LinkerError::IoError(_)) => {}
+            result => panic!("Expected a failed link, but got {:?}", result),
+        }
+        assert!(dir.join("selftest.rs").exists());
+        assert!(dir.join("device.x").exists());
+    }
+
+    /// Link the stub against `ls` for the default target, skipped when the
+    /// target isn't installed
+    fn assert_links(ls: LinkerScript<u32>, name: &str) {
+        if !selftest::installed(selftest::TARGET) {
+            eprintln!("{} isn't installed, not linking {}", selftest::TARGET, name);
+            return;
+        }
+        let dir = std::env::temp_dir().join(format!("imxrt-rt-gen-link-{}", name));
+        std::fs::create_dir_all(&dir).unwrap();
+        if let Err(err) = selftest::link(ls, &dir, selftest::TARGET) {
+            panic!("The {} script doesn't link: {}", name, err);
+        }
+    }
+
+    /// `example` with room for the stub's code
+    fn linkable() -> LinkerScript<u32> {
+        let mut ls = LinkerScript::<u32>::new();
+        let flash = ls.region(FLASH, 0x60000000, 0x10000).unwrap();
+        let ram = ls.region(RAM, 0x20000000, 0x10000).unwrap();
+        ls.stack(ram.clone()).unwrap();
+        ls.vector_table(flash.clone(), None).unwrap();
+        ls.text(flash.clone(), None).unwrap();
+        ls.data(false, ram.clone(), Some(flash.clone())).unwrap();
+        ls.data(true, ram.clone(), Some(flash.clone())).unwrap();
+        ls.rodata(false, flash.clone(), None).unwrap();
+        ls.bss(false, ram, None).unwrap();
+        ls
+    }
+
+    #[test]
+    fn scripts_link() {
+        assert_links(linkable(), "base");
+
+        let mut ls = linkable();
+        let ram = RegionID(String::from(RAM));
+        let flash = RegionID(String::from(FLASH));
+        let ocram = ls.region("OCRAM", 0x20200000, 0x10000).unwrap();
+        ls.heap(ocram).unwrap();
+        ls.uninit(ram).unwrap();
+        ls.build_id(flash.clone()).unwrap();
+        ls.version_string(flash.clone()).unwrap();
+        ls.unwind_tables(flash).unwrap();
+        ls.program_headers(true);
+        assert_links(ls, "features");
+    }
+
+    #[test]
+    fn custom_sections() {
+        let mut ls = example();
+        let ram = RegionID(String::from(RAM));
+        let flash = RegionID(String::from(FLASH));
+        let buffers = ls
+            .custom_section("dma_buffers", true, ram.clone(), None, None)
+            .unwrap();
+        assert_eq!(buffers, SectionID(String::from("RAM.dma_buffers")));
+        let table = ls
+            .custom_section("table", false, ram, Some(flash.clone()), None)
+            .unwrap();
+        ls.custom_section("framebuffer", false, flash, None, Some(0x100))
+            .unwrap();
+        ls.priority(&table, 1).unwrap();
+        match ls.priority(&SectionID(String::from("missing")), 1) {
+            Err(LinkerError::UnknownSection(_)) => {}
+            result => panic!("Expected unknown section, but got {:?}", result),
+        }
+        let mut reset = Vec::new();
+        ls.write_reset(&mut reset).unwrap();
+        let reset = String::from_utf8(reset).unwrap();
+        assert!(reset.contains("    // .table, RAM loaded from FLASH\n"));
+        let script = render(ls);
+        assert!(script.contains("\t\t*(.RAM.dma_buffers .RAM.dma_buffers.*);\n"));
+        assert!(script
+            .contains("\t\t*(.framebuffer .framebuffer.*);\n\t\t. = __start_framebuffer + 256;\n"));
+        let data = script.find("\t.data :").unwrap();
+        assert!(script.find("\t.table :").unwrap() < data);
+    }
+
+    #[test]
+    fn sections_filled_like_erased_flash() {
+        let mut ls = example();
+        let flash = RegionID(String::from(FLASH));
+        let fcb = ls.boot_config(0x200, "fcb", flash).unwrap();
+        ls.fill(&fcb, 0xFF).unwrap();
+        match ls.fill(&SectionID(String::from("missing")), 0xFF) {
+            Err(LinkerError::UnknownSection(_)) => {}
+            result => panic!("Expected unknown section, but got {:?}", result),
+        }
+        ls.program_headers(true);
+        let script = render(ls);
+        assert!(script.contains("\t\t__end_fcb = .;\n\t} > FLASH :load_FLASH =0xFFFFFFFF\n"));
+        assert!(script.contains("\t} > FLASH :load_FLASH\n"));
+    }
+
+    #[test]
+    fn vector_table_and_boot_config_are_kept() {
+        let mut ls = example();
+        let flash = RegionID(String::from(FLASH));
+        let fcb = ls.boot_config(0x200, "fcb", flash).unwrap();
+        let text = SectionID(String::from("text"));
+        ls.keep(&text, true).unwrap();
+        let script = render(ls.clone());
+        assert!(script
+            .contains("\t\tLONG(__start_stack);\n\t\tKEEP(*(.vector_table .vector_table.*));\n"));
+        assert!(script.contains("\t\tKEEP(*(.fcb .fcb.*));\n\t\t. = __start_fcb + 512;\n"));
+        assert!(script.contains("\t\tKEEP(*(.text .text.*));\n"));
+
+        ls.keep(&fcb, false).unwrap();
+        let script = render(ls);
+        assert!(script.contains("\t\t*(.fcb .fcb.*);\n\t\t. = __start_fcb + 512;\n"));
+
+        let mut ls = example();
+        ls.family(chip::Family::Imxrt1060);
+        let ids = ls.boot_gen(RegionID(String::from(FLASH))).unwrap();
+        let version = ls.version_string(RegionID(String::from(FLASH))).unwrap();
+        let script = render(ls.clone());
+        assert!(script.contains("\t\tKEEP(*(.ivt .ivt.*));\n"));
+        assert!(script.contains("\t\tKEEP(*(.version .version.*));\n"));
+        for id in ids.iter().chain(std::iter::once(&version)) {
+            ls.keep(id, false).unwrap();
+        }
+        let script = render(ls);
+        assert_eq!(script.matches("KEEP(").count(), 1, "{}", script);
+        assert!(script.contains("\t\t*(.ivt .ivt.*);\n"));
+        assert!(script.contains("\t\t*(.version .version.*);\n"));
+    }
+
+    #[test]
+    fn noload_sections_take_no_image() {
+        let mut ls = example();
+        let bss = SectionID(String::from("bss"));
+        ls.noload(&bss, true).unwrap();
+        match ls.noload(&SectionID(String::from("data")), true) {
+            Err(LinkerError::LoadedNoload { section, lma }) => {
+                assert_eq!(section, "data");
+                assert_eq!(lma, FLASH);
+            }
+            result => panic!("Expected a loaded NOLOAD section, but got {:?}", result),
+        }
+        ls.program_headers(true);
+        let script = render(ls.clone());
+        assert!(script.contains("\t.bss (NOLOAD) :\n"));
+        assert!(script.contains("\t.data :\n"));
+        assert!(script.contains("\t\t__end_bss = .;\n\t} > RAM :NONE\n"));
+
+        let mut reset = Vec::new();
+        generate::reset::render(&ls, &mut reset).unwrap();
+        let reset = String::from_utf8(reset).unwrap();
+        assert!(reset.contains("__zero(core::ptr::addr_of_mut!(__start_bss)"));
+    }
+
+    #[test]
+    fn uninit_is_neither_zeroed_nor_copied() {
+        let mut ls = example();
+        let ram = RegionID(String::from(RAM));
+        let uninit = ls.uninit(ram).unwrap();
+        let script = render(ls.clone());
+        assert!(script.contains(
+            "\t.uninit (NOLOAD) :\n\t{\n\t\t. = ALIGN(4);\n\t\t__start_uninit = .;\n\t\t*(.uninit .uninit.*);\n"
+        ));
+        assert!(script.contains("\t\t__end_uninit = .;\n\t} > RAM\n"));
+        assert!(script.find(".bss :").unwrap() < script.find(".uninit (NOLOAD) :").unwrap());
+
+        let mut reset = Vec::new();
+        generate::reset::render(&ls, &mut reset).unwrap();
+        assert!(!String::from_utf8(reset).unwrap().contains("uninit"));
+        ls.noload(&uninit, false).unwrap();
+        assert!(!render(ls).contains("(NOLOAD)"));
+    }
+
+    #[test]
+    fn text_ram_copied_to_itcm() {
+        let mut ls = example();
+        let itcm = ls.region("ITCM", 0x0, 0x8000).unwrap();
+        let flash = RegionID(String::from(FLASH));
+        ls.text_ram(true, itcm, Some(flash)).unwrap();
+        let script = render(ls.clone());
+        assert!(script.contains("\t\t*(.text.ram .text.ram.* .ramfunc .ramfunc.*);\n"));
+        assert!(script.contains("\t\t__end_ITCM_text_ram = .;\n\t} > ITCM AT> FLASH\n"));
+        assert!(script.find(".ITCM.text_ram :").unwrap() < script.find(".text :").unwrap());
+
+        let mut reset = Vec::new();
+        generate::reset::render(&ls, &mut reset).unwrap();
+        let reset = String::from_utf8(reset).unwrap();
+        assert!(reset.contains("// .ITCM.text_ram, ITCM loaded from FLASH\n"));
+    }
+
+    #[test]
+    fn heaps_in_two_regions() {
+        let mut ls = example();
+        let ram = RegionID(String::from(RAM));
+        let ocram = ls.region("OCRAM", 0x20200000, 0x80000).unwrap();
+        ls.heap(ram.clone()).unwrap();
+        let dma_heap = ls.named_heap("dma_heap", ocram.clone()).unwrap();
+        ls.min_size(&dma_heap, 0x1000).unwrap();
+        match ls.named_heap("heap2", ram) {
+            Err(LinkerError::InvalidHeap(_)) => {}
+            result => panic!("Expected invalid heap, but got {:?}", result),
+        }
+        match ls.named_heap("dma_heap", ocram) {
+            Err(LinkerError::InvalidHeap(_)) => {}
+            result => panic!("Expected invalid heap, but got {:?}", result),
+        }
+        let script = render(ls.clone());
+        assert!(script.contains("\t\t__start_heap = .;\n\t\t. = __RAM_origin + __RAM_size;\n"));
+        assert!(script.contains(
+            "\t\t__start_dma_heap = .;\n\t\t. = __OCRAM_origin + __OCRAM_size;\n\t\t__end_dma_heap = .;\n"
+        ));
+
+        let (config, skipped) = config::Config::from_layout(&ls.layout());
+        assert!(skipped.is_empty());
+        let builder = config.to_builder();
+        assert!(builder.contains("ls.heap(ram.clone())?;\n"));
+        assert!(builder.contains("ls.named_heap(\"dma_heap\", ocram.clone())?;\n"));
+        let generated = config.linker_script().unwrap().layout();
+        assert!(diff::layouts(&ls.layout(), &generated).is_empty());
+    }
+
+    #[test]
+    fn stack_guard_gap_below_the_stack() {
+        let mut ls = example();
+        for size in [16, 0x300].iter() {
+            match ls.stack_guard_gap(*size) {
+                Err(LinkerError::InvalidGuardGap(_)) => {}
+                result => panic!("Expected invalid guard gap, but got {:?}", result),
+            }
+        }
+        ls.stack_guard_gap(0x100).unwrap();
+        let script = render(ls);
+        assert!(script.contains(
+            "\t\t. = __RAM_origin + __RAM_used;\n\t\t. = ALIGN(256);\n\t\t__start_stack_guard = .;\n\t\t. = . + 256;\n\t\t__end_stack_guard = .;\n\t\t. = ALIGN(8);\n\t\t__end_stack = .;\n"
+        ));
+    }
+
+    #[test]
+    fn process_stack_below_the_main_stack() {
+        let mut ls = example();
+        let ram = RegionID(String::from(RAM));
+        ls.process_stack(ram, 0x100).unwrap();
+        let script = render(ls);
+        assert!(script.contains(
+            "\t.process_stack :\n\t{\n\t\t. = ALIGN(8);\n\t\t__end_process_stack = .;\n\t\t. += 256;\n\t\t__start_process_stack = .;\n"
+        ));
+        assert!(script.contains("\t__RAM_used = __RAM_used + SIZEOF(.process_stack);\n"));
+        let bss = script.find("\t.bss :").unwrap();
+        let process_stack = script.find("\t.process_stack :").unwrap();
+        assert!(bss < process_stack && process_stack < script.find("\t.stack :").unwrap());
+    }
+
+    #[test]
+    fn sections_placed_before_and_after_others() {
+        let mut ls = example();
+        let flash = RegionID(String::from(FLASH));
+        let ram = RegionID(String::from(RAM));
+        let table = ls
+            .custom_section("table", false, flash.clone(), None, None)
+            .unwrap();
+        let rodata = SectionID(String::from("rodata"));
+        ls.set_priority(&table, Priority::Before(rodata.clone()))
+            .unwrap();
+        let late = ls.custom_section("late", false, flash, None, None).unwrap();
+        ls.set_priority(&late, Priority::After(rodata)).unwrap();
+        let early = ls.custom_section("early", false, ram, None, None).unwrap();
+        ls.set_priority(
+            &early,
+            Priority::Before(SectionID(String::from("RAM.data"))),
+        )
+        .unwrap();
+        match ls.set_priority(&table, Priority::After(SectionID(String::from("missing")))) {
+            Err(LinkerError::UnknownSection(_)) => {}
+            result => panic!("Expected unknown section, but got {:?}", result),
+        }
+        let script = render(ls);
+        let order: Vec<usize> = [
+            "\t.data :",
+            "\t.table :",
+            "\t.rodata :",
+            "\t.late :",
+            "\t.bss :",
+            "\t.early :",
+            "\t.RAM.data :",
+        ]
+        .iter()
+        .map(|header| script.find(header).unwrap())
+        .collect();
+        assert!(order.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn region_added_with_its_kind() {
+        let mut ls = example();
+        let itcm = ls
+            .region_with_kind("ITCM", 0x0, 0x8000, RegionKind::Flash)
+            .unwrap();
+        match ls.region_with_kind("ITCM", 0x0, 0x8000, RegionKind::Ram) {
+            Err(LinkerError::DuplicateRegion(_)) => {}
+            result => panic!("Expected duplicate region, but got {:?}", result),
+        }
+        assert!(render(ls.clone()).contains("\tITCM (rx) : ORIGIN = 0x0, LENGTH = 0x8000\n"));
+        ls.custom_section("table", false, itcm, None, None).unwrap();
+        ls.bss(true, RegionID(String::from("ITCM")), None).unwrap();
+        match ls.check() {
+            Err(LinkerError::InvalidAccess(reason)) => {
+                assert_eq!(
+                    reason,
+                    "writable section .ITCM.bss runs from flash region ITCM"
+                )
+            }
+            result => panic!("Expected invalid access, but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn region_start_reserved_for_a_bootloader() {
+        let mut ls = example();
+        let flash = RegionID(String::from(FLASH));
+        let view = ls.alias("FLASH_VIEW", &flash, 0x70000000).unwrap();
+        match ls.reserve_start(&view, 0x200) {
+            Err(LinkerError::InvalidReservation(_)) => {}
+            result => panic!("Expected invalid reservation, but got {:?}", result),
+        }
+        match ls.reserve_start(&flash, 0x1000) {
+            Err(LinkerError::InvalidReservation(_)) => {}
+            result => panic!("Expected invalid reservation, but got {:?}", result),
+        }
+        ls.reserve_start(&flash, 0x200).unwrap();
+        let script = render(ls.clone());
+        assert!(script.contains("\tFLASH : ORIGIN = 0x60000200, LENGTH = 0xE00\n"));
+        assert!(script.contains("\t__FLASH_size = 4096;\n\t__FLASH_used = 512;\n"));
+        let layout = ls.layout();
+        let region = layout.region(FLASH).unwrap();
+        assert_eq!((region.origin, region.size), (0x60000000, 0x1000));
+    }
+
+    #[test]
+    fn carve_windows_out_of_a_region() {
+        let mut ls = example();
+        let ocram = ls.region("OCRAM", 0x20200000, 0x80000).unwrap();
+        ls.dma_capable(&ocram).unwrap();
+        ls.heap(ocram.clone()).unwrap();
+        let first = ls.carve_region(&ocram, "OCRAM_FIRST", 0, 0x1000).unwrap();
+        let window = ls
+            .carve_region(&ocram, "NONCACHEABLE", 0x70000, 0x10000)
+            .unwrap();
+        for (name, offset, size) in [
+            ("OCRAM_LOW", 0x800, 0x100),
+            ("OCRAM_HIGH", 0x78000, 0x100),
+            ("OCRAM_OUT", 0x7FF00, 0x200),
+        ]
+        .iter()
+        {
+            match ls.carve_region(&ocram, name, *offset, *size) {
+                Err(LinkerError::InvalidSplit(_)) => {}
+                result => panic!("Expected invalid split, but got {:?}", result),
+            }
+        }
+        match ls.carve_region(&ocram, "NONCACHEABLE", 0x2000, 0x100) {
+            Err(LinkerError::DuplicateRegion(_)) => {}
+            result => panic!("Expected duplicate region, but got {:?}", result),
+        }
+        assert!(ls.regions[&window.0].dma);
+        ls.custom_section("buffers", false, window, None, None)
+            .unwrap();
+        ls.custom_section("table", false, first, None, None)
+            .unwrap();
+        assert!(!ls
+            .lints()
+            .iter()
+            .any(|warning| matches!(warning, lint::Warning::OverlappingRegions(..))));
+        let mut strict = ls.clone();
+        strict.validation(lint::Validation::Strict);
+        strict.check().unwrap();
+        let script = render(ls);
+        assert!(script.contains("\tOCRAM : ORIGIN = 0x20201000, LENGTH = 0x7F000\n"));
+        assert!(script.contains("\tOCRAM_FIRST : ORIGIN = 0x20200000, LENGTH = 0x1000\n"));
+        assert!(script.contains("\tNONCACHEABLE : ORIGIN = 0x20270000, LENGTH = 0x10000\n"));
+        assert!(script.contains("\t\t__start_heap = .;\n\t\t. = 0x20270000;\n"));
+        assert!(script.contains(
+            "ASSERT(__OCRAM_origin + __OCRAM_used <= 0x20270000, \"Sections in OCRAM overlap NONCACHEABLE\");\n"
+        ));
+
+        let mut ls = linkable();
+        let ocram = ls.region("OCRAM", 0x20200000, 0x10000).unwrap();
+        ls.heap(ocram.clone()).unwrap();
+        let window = ls
+            .carve_region(&ocram, "NONCACHEABLE", 0x8000, 0x1000)
+            .unwrap();
+        let inner = ls
+            .carve_region(&window, "DESCRIPTORS", 0x800, 0x100)
+            .unwrap();
+        ls.custom_section("buffers", false, window, None, None)
+            .unwrap();
+        ls.custom_section("descriptors", false, inner, None, None)
+            .unwrap();
+        assert_eq!(ls.lints(), Vec::new());
+        assert_links(ls, "carved");
+    }
+
+    #[test]
+    fn itcm_placed_through_its_boot_alias() {
+        let mut ls = example();
+        let itcm = ls.region("ITCM", 0x0, 0x8000).unwrap();
+        let boot = ls.alias("ITCM_BOOT", &itcm, 0x00200000).unwrap();
+        let flash = RegionID(String::from(FLASH));
+        ls.text_ram(false, itcm, Some(flash.clone())).unwrap();
+        ls.hot_text(&["isr"], boot, Some(flash)).unwrap();
+        let script = render(ls.clone());
+        assert!(script.contains("\t.text_ram (__ITCM_origin + __ITCM_used) :"));
+        assert!(script.contains("\t.hot_text (__ITCM_BOOT_origin + __ITCM_used) :"));
+        assert!(script.contains("\t__ITCM_used = __ITCM_used + SIZEOF(.hot_text);"));
+
+        let mut regions = Vec::new();
+        ls.write_regions(&mut regions).unwrap();
+        let regions = String::from_utf8(regions).unwrap();
+        assert!(regions.contains(
+            "pub fn to_itcm(addr: usize) -> Option<usize> {\n    if in_itcm(addr) {\n        return Some(addr);\n    }\n    if in_itcm_boot(addr) {\n        return Some(addr - ITCM_BOOT_ORIGIN + ITCM_ORIGIN);\n    }\n    None\n}\n"
+        ));
+        assert!(regions.contains("pub fn to_itcm_boot(addr: usize) -> Option<usize> {\n"));
+        assert!(!regions.contains("pub fn to_ram("));
+    }
+
+    #[test]
+    fn word_arithmetic() {
+        assert_eq!(Word::checked_add(0xFFFF_0000u32, 0x1_0000), None);
+        assert_eq!(Word::checked_add(0x6000_0000u32, 0x1000), Some(0x6000_1000));
+        assert_eq!(Word::checked_sub(0x1000u32, 0x2000), None);
+        assert_eq!(Word::checked_sub(0x2000u64, 0x1000), Some(0x1000));
+        assert_eq!(0x6000_1001u32.align_up(0x1000), Some(0x6000_2000));
+        assert_eq!(0x6000_1000u32.align_up(0x1000), Some(0x6000_1000));
+        assert_eq!(0x6000_1000u32.align_up(0x1800), None);
+        assert_eq!(0xFFFF_F001u32.align_up(0x1000), None);
+    }
+
+    #[test]
+    fn regions_chained_after_another() {
+        let mut ls = LinkerScript::<u32>::new();
+        let flash = ls.region("BOOT", 0x6000_0000, 0x1_0000).unwrap();
+        let app = ls.region_after(FLASH, &flash, 0x10_0000).unwrap();
+        assert_eq!(ls.region_origin(&app).unwrap(), 0x6001_0000);
+        assert_eq!(ls.region_size(&app).unwrap(), 0x10_0000);
+        let config = ls.region_at("CONFIG", &app, 0xF_F000, 0x1000).unwrap();
+        assert_eq!(ls.region_origin(&config).unwrap(), 0x6010_F000);
+        let top = ls.region("TOP", 0xFFFF_0000, 0x1_0000).unwrap();
+        match ls.region_after("PAST", &top, 0x100) {
+            Err(LinkerError::AddressOverflow(name)) => assert_eq!(name, "PAST"),
+            result => panic!("Expected address overflow, but got {:?}", result),
+        }
+        match ls.region_at("LAST", &top, 0xF000, 0x2000) {
+            Err(LinkerError::AddressOverflow(_)) => {}
+            result => panic!("Expected address overflow, but got {:?}", result),
+        }
+        ls.region_at("LAST", &top, 0xF000, 0x1000).unwrap();
+
+        let mut ls = LinkerScript::<u64>::new();
+        let top = ls.region("TOP", u64::MAX - 0xFFFF, 0x1000).unwrap();
+        match ls.region_after("PAST", &top, 0x10000) {
+            Err(LinkerError::AddressOverflow(name)) => assert_eq!(name, "PAST"),
+            result => panic!("Expected address overflow, but got {:?}", result),
+        }
+        match ls.region_at("WRAP", &top, 0xF000, u64::MAX) {
+            Err(LinkerError::AddressOverflow(_)) => {}
+            result => panic!("Expected address overflow, but got {:?}", result),
+        }
+        let last = ls.region_at("LAST", &top, 0xF000, 0x1000).unwrap();
+        assert_eq!(ls.region_origin(&last).unwrap(), u64::MAX - 0xFFF);
     }
 }
@@ -0,0 +1,115 @@
+//! Non-fatal findings about a linker script
+//!
+//! Lints catch models which generate a valid script but are unlikely to
+//! be what was meant, such as a region declared and never used because a
+//! section was placed in the wrong one. Writing a script returns its lints
+//! for a build script to print, they are errors only when the script's
+//! [validation](../struct.LinkerScript.html#method.validation) is strict.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// What writing a script does with its lints
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Validation {
+    /// Lints are returned as warnings, for prototyping
+    #[default]
+    Permissive,
+    /// Any lint fails the write with `LinkerError::Lints`
+    Strict,
+}
+
+impl FromStr for Validation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "permissive" => Ok(Validation::Permissive),
+            "strict" => Ok(Validation::Strict),
+            _ => Err(format!(
+                "unknown validation {:?}, expected permissive or strict",
+                s
+            )),
+        }
+    }
+}
+
+/// A lint found in a linker script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// No section runs from or is loaded from the region
+    UnusedRegion(String),
+    /// Sections in a region share a priority and are ordered by name
+    /// (region, priority, output section names)
+    PriorityCollision(String, i32, Vec<String>),
+    /// Fixed sizes and minimum sizes take most of the region, before any
+    /// code or statics are linked into it (region, bytes taken, size)
+    NearlyFull(String, u64, u64),
+    /// The region's origin or size is not a multiple of the word alignment
+    /// (region, alignment)
+    UnalignedRegion(String, u64),
+    /// A minimum size was set on a section which is not a stack or heap,
+    /// so it is not asserted
+    IgnoredMinSize(String),
+    /// Code runs from a region which also holds writable sections, so a
+    /// stray write can change code and the MPU can't enforce W^X on it
+    /// (region, code sections, writable sections)
+    WritableCode(String, Vec<String>, Vec<String>),
+    /// Two regions which are not views of the same memory share addresses
+    OverlappingRegions(String, String),
+    /// Input sections move from a full section to another only when
+    /// linking with GNU ld's `--enable-non-contiguous-regions`, rust-lld
+    /// leaves the other empty (section, section spilled into)
+    NonContiguousSpill(String, String),
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::UnusedRegion(region) => write!(
+                f,
+                "region {} is not used by any section, was a section placed in the wrong region?",
+                region
+            ),
+            Warning::PriorityCollision(region, priority, sections) => write!(
+                f,
+                "sections .{} share priority {} in region {} and are placed in order of their names",
+                sections.join(", ."),
+                priority,
+                region
+            ),
+            Warning::NearlyFull(region, used, size) => write!(
+                f,
+                "region {} has {} of its {} bytes taken by fixed and minimum sizes alone",
+                region, used, size
+            ),
+            Warning::UnalignedRegion(region, align) => write!(
+                f,
+                "region {} does not start and end on a {} byte boundary",
+                region, align
+            ),
+            Warning::IgnoredMinSize(section) => write!(
+                f,
+                "section .{} is not a stack or heap, its minimum size is not checked",
+                section
+            ),
+            Warning::WritableCode(region, code, writable) => write!(
+                f,
+                "code in .{} runs from region {} with the writable .{}",
+                code.join(", ."),
+                region,
+                writable.join(", .")
+            ),
+            Warning::OverlappingRegions(a, b) => write!(
+                f,
+                "regions {} and {} overlap, declare one an alias if they view the same memory",
+                a, b
+            ),
+            Warning::NonContiguousSpill(section, into) => write!(
+                f,
+                "section .{} only spills into .{} with GNU ld's --enable-non-contiguous-regions, rust-lld leaves .{} empty",
+                section, into, into
+            ),
+        }
+    }
+}
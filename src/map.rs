@@ -0,0 +1,206 @@
+//! Memory map visualization
+//!
+//! Renders the regions of a [`Layout`](../layout/struct.Layout.html) as
+//! columns with their sections as blocks, as SVG or as an HTML page
+//! embedding the SVG and a table of the same data. When a
+//! [`SizeReport`](../size/struct.SizeReport.html) of the linked ELF file
+//! is given, blocks are drawn at their address with their size, otherwise
+//! the sections the layout places in each region are shown in order.
+
+use crate::layout::{Layout, LayoutSize};
+use crate::size::SizeReport;
+use std::fmt::Write;
+
+const COLUMN_WIDTH: u64 = 220;
+const COLUMN_GAP: u64 = 40;
+const COLUMN_HEIGHT: u64 = 600;
+const HEADER_HEIGHT: u64 = 50;
+
+/// A block to draw within a region column
+struct Block {
+    name: String,
+    /// Offset from the top of the column and height, both in pixels
+    top: u64,
+    height: u64,
+    color: &'static str,
+    label: String,
+    dashed: bool,
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn color(name: &str) -> &'static str {
+    let base = name.rsplit('.').next().unwrap_or(name);
+    match base {
+        "text" | "vector_table" => "#4e79a7",
+        "rodata" => "#76b7b2",
+        "data" => "#f28e2b",
+        "bss" => "#bab0ac",
+        _ if base.ends_with("stack") => "#e15759",
+        _ if base.ends_with("heap") => "#59a14f",
+        _ => "#b07aa1",
+    }
+}
+
+fn blocks(layout: &Layout, report: Option<&SizeReport>, region: &str) -> Vec<Block> {
+    let region = match layout.region(region) {
+        Some(region) => region,
+        None => return Vec::new(),
+    };
+    let scale = |bytes: u64| -> u64 {
+        if region.size == 0 {
+            0
+        } else {
+            (u128::from(bytes) * u128::from(COLUMN_HEIGHT) / u128::from(region.size)) as u64
+        }
+    };
+    match report.and_then(|r| r.region(&region.name)) {
+        Some(usage) => usage
+            .sections
+            .iter()
+            .map(|section| Block {
+                name: section.name.clone(),
+                top: scale(section.address.saturating_sub(region.origin)),
+                height: scale(section.size).max(2),
+                color: color(&section.name),
+                label: format!("{} ({} bytes)", section.name, section.size),
+                dashed: section.load_copy,
+            })
+            .collect(),
+        None => {
+            let sections: Vec<_> = layout
+                .sections
+                .iter()
+                .filter(|s| s.vma == region.name || s.lma.as_deref() == Some(&region.name))
+                .collect();
+            let height = COLUMN_HEIGHT / (sections.len() as u64).max(1);
+            sections
+                .iter()
+                .enumerate()
+                .map(|(idx, section)| {
+                    let size = match section.size {
+                        LayoutSize::Linker => String::from("linker sized"),
+                        LayoutSize::Fixed(size) => format!("{} bytes", size),
+                        LayoutSize::Stack => String::from("remaining space, stack"),
+                        LayoutSize::FixedStack(size) => format!("{} bytes, stack", size),
+                        LayoutSize::Heap => String::from("remaining space, heap"),
+                    };
+                    Block {
+                        name: format!(".{}", section.output),
+                        top: idx as u64 * height,
+                        height,
+                        color: color(&section.output),
+                        label: format!(".{} ({})", section.output, size),
+                        dashed: section.vma != region.name,
+                    }
+                })
+                .collect()
+        }
+    }
+}
+
+/// Render the memory map as an SVG image
+pub fn svg(layout: &Layout, report: Option<&SizeReport>) -> String {
+    let width = layout.regions.len() as u64 * (COLUMN_WIDTH + COLUMN_GAP) + COLUMN_GAP;
+    let height = COLUMN_HEIGHT + HEADER_HEIGHT + COLUMN_GAP;
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"monospace\" font-size=\"11\">",
+        width, height
+    );
+    for (idx, region) in layout.regions.iter().enumerate() {
+        let x = COLUMN_GAP + idx as u64 * (COLUMN_WIDTH + COLUMN_GAP);
+        let used = report
+            .and_then(|r| r.region(&region.name))
+            .map(|r| format!(", {} used", r.used()))
+            .unwrap_or_default();
+        let _ = writeln!(
+            out,
+            "  <text x=\"{}\" y=\"20\" font-weight=\"bold\">{}</text>",
+            x,
+            escape(&region.name)
+        );
+        let _ = writeln!(
+            out,
+            "  <text x=\"{}\" y=\"36\">{:#010X}, {} bytes{}</text>",
+            x, region.origin, region.size, used
+        );
+        let _ = writeln!(
+            out,
+            "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#f5f5f5\" stroke=\"#333\"/>",
+            x, HEADER_HEIGHT, COLUMN_WIDTH, COLUMN_HEIGHT
+        );
+        for block in blocks(layout, report, &region.name) {
+            let y = HEADER_HEIGHT + block.top;
+            let _ = writeln!(
+                out,
+                "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" fill-opacity=\"{}\" stroke=\"#333\"{}><title>{}</title></rect>",
+                x,
+                y,
+                COLUMN_WIDTH,
+                block.height,
+                block.color,
+                if block.dashed { "0.4" } else { "0.9" },
+                if block.dashed { " stroke-dasharray=\"4 2\"" } else { "" },
+                escape(&block.label)
+            );
+            if block.height >= 12 {
+                let _ = writeln!(
+                    out,
+                    "  <text x=\"{}\" y=\"{}\">{}</text>",
+                    x + 4,
+                    y + 12,
+                    escape(&block.name)
+                );
+            }
+        }
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+/// Render the memory map as an HTML page with the SVG image and a table
+pub fn html(layout: &Layout, report: Option<&SizeReport>) -> String {
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Memory map</title>\n\
+         <style>body { font-family: sans-serif; } td, th { padding: 2px 8px; text-align: left; }</style>\n\
+         </head>\n<body>\n<h1>Memory map</h1>\n",
+    );
+    out.push_str(&svg(layout, report));
+    out.push_str(
+        "<table>\n<tr><th>Region</th><th>Section</th><th>Address</th><th>Size</th></tr>\n",
+    );
+    for region in layout.regions.iter() {
+        let _ = writeln!(
+            out,
+            "<tr><th>{}</th><td></td><td>{:#010X}</td><td>{}</td></tr>",
+            escape(&region.name),
+            region.origin,
+            region.size
+        );
+        for block in blocks(layout, None, &region.name) {
+            let usage = report
+                .and_then(|r| r.region(&region.name))
+                .and_then(|r| r.sections.iter().find(|s| s.name == block.name));
+            let (address, size) = match usage {
+                Some(usage) => (format!("{:#010X}", usage.address), usage.size.to_string()),
+                None => (String::new(), String::new()),
+            };
+            let _ = writeln!(
+                out,
+                "<tr><td></td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(&block.label),
+                address,
+                size
+            );
+        }
+    }
+    out.push_str("</table>\n</body>\n</html>\n");
+    out
+}
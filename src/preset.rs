@@ -0,0 +1,120 @@
+//! Complete linker scripts for known memory maps
+//!
+//! A preset adds the regions of a machine and places every required
+//! section, returning a [`LinkerScript`](../struct.LinkerScript.html)
+//! which may be adjusted further before it is generated.
+
+use crate::layout::LayoutSize;
+use crate::{LinkerScript, RegionID, Result, FLASH, RAM};
+use std::fmt::Write;
+
+/// Names of the presets, as accepted by [`named`](fn.named.html), and a
+/// summary of each
+pub const PRESETS: [(&str, &str); 2] = [
+    (
+        "lm3s6965evb",
+        "QEMU's lm3s6965evb, a Cortex-M3 with 256KiB of flash and 64KiB of SRAM",
+    ),
+    (
+        "mps2_an385",
+        "QEMU's mps2-an385, a Cortex-M3 with 4MiB of code SRAM and 4MiB of data SRAM",
+    ),
+];
+
+/// Lookup a preset by its name
+pub fn named(name: &str) -> Option<Result<LinkerScript<u32>>> {
+    match name {
+        "lm3s6965evb" => Some(lm3s6965evb()),
+        "mps2_an385" => Some(mps2_an385()),
+        _ => None,
+    }
+}
+
+/// Describe the memory map and sections of a preset
+///
+/// Lists each region with its origin and size, then each section in the
+/// order it is placed with the region it runs from and, if it is copied,
+/// the region it is loaded from.
+pub fn describe(name: &str) -> Option<Result<String>> {
+    let summary = PRESETS.iter().find(|(preset, _)| *preset == name)?.1;
+    let layout = match named(name)? {
+        Ok(ls) => ls.layout(),
+        Err(err) => return Some(Err(err)),
+    };
+    let mut out = String::new();
+    writeln!(out, "{}: {}", name, summary).unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "Regions").unwrap();
+    for region in layout.regions.iter() {
+        writeln!(
+            out,
+            "  {:<16} {:#010X}  {:>8}",
+            region.name,
+            region.origin,
+            format_size(region.size)
+        )
+        .unwrap();
+    }
+    writeln!(out).unwrap();
+    writeln!(out, "Sections").unwrap();
+    for section in layout.sections.iter() {
+        let placement = match &section.lma {
+            Some(lma) => format!("{} loaded from {}", section.vma, lma),
+            None => section.vma.clone(),
+        };
+        let size = match section.size {
+            LayoutSize::Linker => String::new(),
+            LayoutSize::Fixed(size) => format!(", {}", format_size(size)),
+            LayoutSize::Stack => String::from(", rest of region"),
+            LayoutSize::FixedStack(size) => format!(", {} stack", format_size(size)),
+            LayoutSize::Heap => String::from(", rest of region"),
+        };
+        writeln!(out, "  .{:<15} {}{}", section.output, placement, size).unwrap();
+    }
+    Some(Ok(out))
+}
+
+/// Format a size in bytes, KiB or MiB, whichever is exact
+fn format_size(size: u64) -> String {
+    if size >= 1024 * 1024 && size.is_multiple_of(1024 * 1024) {
+        format!("{}MiB", size / (1024 * 1024))
+    } else if size >= 1024 && size.is_multiple_of(1024) {
+        format!("{}KiB", size / 1024)
+    } else {
+        format!("{}B", size)
+    }
+}
+
+/// Place the required sections for an image running from `flash`
+fn flash_image(ls: &mut LinkerScript<u32>, flash: RegionID, ram: RegionID) -> Result<()> {
+    ls.stack(ram.clone())?;
+    ls.vector_table(flash.clone(), None)?;
+    ls.text(flash.clone(), None)?;
+    ls.rodata(false, flash.clone(), None)?;
+    ls.data(false, ram.clone(), Some(flash))?;
+    ls.bss(false, ram, None)?;
+    Ok(())
+}
+
+/// QEMU's `lm3s6965evb` machine, a Cortex-M3 with 256KiB of flash and
+/// 64KiB of SRAM
+///
+/// This is the machine most Cortex-M examples are emulated on, for example
+/// with `qemu-system-arm -machine lm3s6965evb -nographic -kernel <elf>`.
+pub fn lm3s6965evb() -> Result<LinkerScript<u32>> {
+    let mut ls = LinkerScript::new();
+    let flash = ls.region(FLASH, 0x0000_0000, 256 * 1024)?;
+    let ram = ls.region(RAM, 0x2000_0000, 64 * 1024)?;
+    flash_image(&mut ls, flash, ram)?;
+    Ok(ls)
+}
+
+/// QEMU's `mps2-an385` machine, a Cortex-M3 with 4MiB of code SRAM,
+/// used as flash, and 4MiB of data SRAM
+pub fn mps2_an385() -> Result<LinkerScript<u32>> {
+    let mut ls = LinkerScript::new();
+    let flash = ls.region(FLASH, 0x0000_0000, 4 * 1024 * 1024)?;
+    let ram = ls.region(RAM, 0x2000_0000, 4 * 1024 * 1024)?;
+    flash_image(&mut ls, flash, ram)?;
+    Ok(ls)
+}
@@ -0,0 +1,279 @@
+//! A bootloader and an application linked for the same part
+//!
+//! The two images are linked separately but share the part's memory. A
+//! [`Project`] holds a linker script for each, adds the regions both know
+//! to both from one list, and checks the images don't store themselves in
+//! the same memory and agree on every region they both declare, before
+//! generating both from one build script.
+//!
+//! Calls from the application into the bootloader go through a jump table
+//! the bootloader places at a fixed address, see
+//! [`Project::jump_table`].
+//!
+//! A recovery copy of the application may be kept in a slot of its own,
+//! for the bootloader to fall back to when the application is corrupt.
+//! It is the application linked to run from the slot, and the bootloader
+//! gets the [`Slot`]s to choose between, see [`Project::recovery_slot`].
+
+use crate::lint::Warning;
+use crate::{LinkerError, LinkerScript, Region, RegionID, Result, Word};
+use std::io::Write;
+use std::path::Path;
+
+/// A bootloader and an application sharing a part's memory
+#[derive(Default)]
+pub struct Project<W: Word> {
+    /// Linker script of the bootloader
+    pub bootloader: LinkerScript<W>,
+    /// Linker script of the application the bootloader starts
+    pub app: LinkerScript<W>,
+
+    /// Name, Rust signature and address of each jump table entry
+    jump_table: Vec<(String, String, W)>,
+
+    /// Name of the recovery slot, the application region it replaces,
+    /// and its origin
+    recovery: Option<(String, RegionID, W)>,
+}
+
+/// Where an image of the application is stored, for the bootloader to
+/// choose from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slot {
+    pub name: String,
+    pub origin: u64,
+    pub size: u64,
+}
+
+/// An address or size within the jump table region `name`
+fn word<W: Word>(value: u64, name: &str) -> Result<W> {
+    W::try_from(value).map_err(|_| {
+        LinkerError::InvalidConfig(format!("jump table {} is past the address space", name))
+    })
+}
+
+/// Symbol the application's linker script defines at a jump table entry
+fn entry_symbol(name: &str) -> String {
+    format!("__jump_table_{}", name)
+}
+
+fn overlaps<W: Word>(a: &Region<W>, b: &Region<W>) -> bool {
+    let (a_origin, b_origin): (u64, u64) = (a.origin.into(), b.origin.into());
+    a_origin < b_origin + b.size.into() && b_origin < a_origin + a.size.into()
+}
+
+impl<W: Word> Project<W> {
+    pub fn new() -> Self {
+        Project {
+            bootloader: LinkerScript::new(),
+            app: LinkerScript::new(),
+            jump_table: Vec::new(),
+            recovery: None,
+        }
+    }
+
+    /// Add a region to both images
+    pub fn region(&mut self, name: &str, origin: W, size: W) -> Result<RegionID> {
+        self.bootloader.region(name, origin, size)?;
+        self.app.region(name, origin, size)
+    }
+
+    /// Place a table of bootloader functions the application calls at
+    /// `origin`, in a bootloader region of its own called `name`
+    ///
+    /// Each function is given with its signature as a Rust function
+    /// pointer type, for example `("boot_version", "extern \"C\" fn() -> u32")`,
+    /// and must be `#[no_mangle]` in the bootloader. The application's
+    /// linker script defines a symbol at each entry, and `jump_table.rs`
+    /// is generated for the application to include, declaring each entry
+    /// as an extern static under the function's name.
+    pub fn jump_table(
+        &mut self,
+        name: &str,
+        origin: W,
+        functions: &[(&str, &str)],
+    ) -> Result<RegionID> {
+        let size = word(4 * functions.len() as u64, name)?;
+        let region = self.bootloader.region(name, origin, size)?;
+        let names: Vec<&str> = functions.iter().map(|(function, _)| *function).collect();
+        self.bootloader.jump_table(&names, region.clone())?;
+        for (index, (function, signature)) in functions.iter().enumerate() {
+            let address = word(origin.into() + 4 * index as u64, name)?;
+            self.app.symbol(&entry_symbol(function), address);
+            self.jump_table
+                .push((String::from(*function), String::from(*signature), address));
+        }
+        Ok(region)
+    }
+
+    /// Write the application's declarations of the jump table entries
+    pub fn write_jump_table<Wr: Write>(&self, jump_table_rs: &mut Wr) -> Result<()> {
+        writeln!(
+            jump_table_rs,
+            "// Generated by imxrt-rt-gen, the bootloader's jump table"
+        )?;
+        writeln!(jump_table_rs, "extern \"C\" {{")?;
+        for (function, signature, address) in &self.jump_table {
+            writeln!(jump_table_rs, "    /// Entry at {:#X}", address)?;
+            writeln!(
+                jump_table_rs,
+                "    #[link_name = \"{}\"]",
+                entry_symbol(function)
+            )?;
+            writeln!(jump_table_rs, "    pub static {}: {};", function, signature)?;
+        }
+        writeln!(jump_table_rs, "}}")?;
+        Ok(())
+    }
+
+    /// Reserve a slot called `name` at `origin` for a recovery copy of the
+    /// application
+    ///
+    /// The copy is the application linked with its `region`, the one it
+    /// is stored in, moved to `origin`. It is generated into a directory
+    /// named after the slot in the application's directory, and the
+    /// bootloader's directory gets `slots.rs` describing both slots.
+    pub fn recovery_slot(&mut self, name: &str, region: &RegionID, origin: W) -> Result<()> {
+        self.app.relocated(region, origin)?;
+        self.recovery = Some((String::from(name), region.clone(), origin));
+        Ok(())
+    }
+
+    /// The recovery copy of the application, if a slot is reserved for it
+    pub fn recovery(&self) -> Result<Option<LinkerScript<W>>> {
+        match &self.recovery {
+            Some((_, region, origin)) => self.app.relocated(region, *origin).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The slots holding the application and its recovery copy, named
+    /// after the application's region and the recovery slot
+    pub fn slots(&self) -> Result<Vec<Slot>> {
+        let (name, region, origin) = match &self.recovery {
+            Some(recovery) => recovery,
+            None => return Ok(Vec::new()),
+        };
+        let app = &self.app.regions[&region.0];
+        Ok(vec![
+            Slot {
+                name: app.name.clone(),
+                origin: app.origin.into(),
+                size: app.size.into(),
+            },
+            Slot {
+                name: name.clone(),
+                origin: (*origin).into(),
+                size: app.size.into(),
+            },
+        ])
+    }
+
+    /// Write the bootloader's descriptors of the application slots
+    pub fn write_slots<Wr: Write>(&self, slots_rs: &mut Wr) -> Result<()> {
+        writeln!(
+            slots_rs,
+            "// Generated by imxrt-rt-gen, the slots the application is stored in"
+        )?;
+        writeln!(slots_rs)?;
+        writeln!(slots_rs, "/// Where an image of the application is stored")?;
+        writeln!(slots_rs, "pub struct Slot {{")?;
+        writeln!(slots_rs, "    pub name: &'static str,")?;
+        writeln!(slots_rs, "    pub origin: usize,")?;
+        writeln!(slots_rs, "    pub size: usize,")?;
+        writeln!(slots_rs, "}}")?;
+        let slots = self.slots()?;
+        writeln!(slots_rs)?;
+        writeln!(
+            slots_rs,
+            "/// The application's slot, then its recovery slot"
+        )?;
+        writeln!(slots_rs, "pub const SLOTS: [Slot; {}] = [", slots.len())?;
+        for slot in slots {
+            writeln!(
+                slots_rs,
+                "    Slot {{ name: {:?}, origin: {:#X}, size: {:#X} }},",
+                slot.name, slot.origin, slot.size
+            )?;
+        }
+        writeln!(slots_rs, "];")?;
+        Ok(())
+    }
+
+    /// Check the images against each other
+    ///
+    /// A region both scripts declare must have the same origin and size in
+    /// each, and no region the bootloader is stored in may overlap one the
+    /// application is stored in. RAM is not checked, since the application
+    /// only runs once the bootloader is done with it. A recovery slot may
+    /// not overlap what either of the images is stored in.
+    pub fn check(&self) -> Result<()> {
+        let mut names: Vec<&String> = self.bootloader.regions.keys().collect();
+        names.sort();
+        for name in names {
+            if let (Some(boot), Some(app)) = (
+                self.bootloader.regions.get(name),
+                self.app.regions.get(name),
+            ) {
+                if boot.origin.into() != app.origin.into() || boot.size.into() != app.size.into() {
+                    return Err(LinkerError::RegionMismatch(name.clone()));
+                }
+            }
+        }
+        for boot in self.bootloader.stored() {
+            for app in self.app.stored() {
+                if overlaps(boot, app) {
+                    return Err(LinkerError::ImagesOverlap {
+                        bootloader: boot.name.clone(),
+                        app: app.name.clone(),
+                    });
+                }
+            }
+        }
+        if let (Some(recovery), Some((name, region, _))) = (self.recovery()?, &self.recovery) {
+            let slot = &recovery.regions[&region.0];
+            for stored in self
+                .bootloader
+                .stored()
+                .into_iter()
+                .chain(self.app.stored())
+            {
+                if overlaps(slot, stored) {
+                    return Err(LinkerError::SlotOverlap {
+                        slot: name.clone(),
+                        region: stored.name.clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the images, then generate each into its own directory
+    ///
+    /// The application's directory also gets `jump_table.rs` when there is
+    /// a jump table, and the bootloader's `slots.rs` when there is a
+    /// recovery slot. Returns the lints of the bootloader and of the application.
+    pub fn generate_to<P: AsRef<Path>, Q: AsRef<Path>>(
+        self,
+        bootloader: P,
+        app: Q,
+    ) -> Result<(Vec<Warning>, Vec<Warning>)> {
+        self.check()?;
+        if !self.jump_table.is_empty() {
+            let path = app.as_ref().join("jump_table.rs");
+            let mut file = std::fs::File::create(&path)?;
+            self.write_jump_table(&mut file)?;
+        }
+        if let (Some(recovery), Some((name, _, _))) = (self.recovery()?, &self.recovery) {
+            let dir = app.as_ref().join(name.to_lowercase());
+            std::fs::create_dir_all(&dir)?;
+            recovery.generate_to(dir)?;
+            let mut file = std::fs::File::create(bootloader.as_ref().join("slots.rs"))?;
+            self.write_slots(&mut file)?;
+        }
+        let boot_warnings = self.bootloader.generate_to(bootloader)?;
+        let app_warnings = self.app.generate_to(app)?;
+        Ok((boot_warnings, app_warnings))
+    }
+}
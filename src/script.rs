@@ -0,0 +1,261 @@
+//! Read hand-written linker scripts
+//!
+//! Projects migrating to generated linker scripts have a `memory.x` or a
+//! whole `link.x` maintained by hand. This module reads the parts of such
+//! a script which the generator models, the `MEMORY` regions and the
+//! output sections with the regions they run from and are loaded from, as
+//! a [`Layout`](../layout/struct.Layout.html). The layout can then be
+//! compared with the one a config generates using
+//! [`diff::layouts`](../diff/fn.layouts.html).
+//!
+//! Only the placement of output sections is read, their contents and any
+//! symbols or assertions are skipped. Sections are given priorities in the
+//! order they appear, and sizes decided by the linker.
+
+use crate::layout::{Layout, LayoutRegion, LayoutSection, LayoutSize};
+use crate::{LinkerError, Result};
+use std::convert::TryFrom;
+use std::path::Path;
+
+fn invalid(reason: String) -> LinkerError {
+    LinkerError::InvalidScript(reason)
+}
+
+/// Remove `/* */` comments
+fn strip_comments(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("/*") {
+        out.push_str(&rest[..start]);
+        match rest[start + 2..].find("*/") {
+            Some(end) => rest = &rest[start + 2 + end + 2..],
+            None => return out,
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The body of the block `keyword { ... }`, for each occurrence
+fn blocks<'a>(text: &'a str, keyword: &str) -> Vec<&'a str> {
+    let mut found = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = text[offset..].find(keyword) {
+        let start = offset + start;
+        offset = start + keyword.len();
+        let preceded = text[..start]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_');
+        let rest = text[offset..].trim_start();
+        if preceded || !rest.starts_with('{') {
+            continue;
+        }
+        let open = text.len() - rest.len();
+        if let Some(close) = matching(text, open) {
+            found.push(&text[open + 1..close]);
+            offset = close + 1;
+        }
+    }
+    found
+}
+
+/// Index of the brace closing the one at `open`
+fn matching(text: &str, open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (index, c) in text[open..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Evaluate a sum of numbers, `K` or `M` suffixed, and `ORIGIN(region)` or
+/// `LENGTH(region)` of the regions read so far
+fn evaluate(expr: &str, regions: &[LayoutRegion]) -> Result<u64> {
+    let mut total: i128 = 0;
+    let mut sign = 1;
+    let mut rest = expr.trim();
+    while !rest.is_empty() {
+        let end = rest
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| *c == '+' || *c == '-')
+            .map_or(rest.len(), |(index, _)| index);
+        let term = rest[..end].trim();
+        let (term, next) = match term.strip_prefix('-') {
+            Some(term) => (term.trim(), -1),
+            None => (term.strip_prefix('+').unwrap_or(term).trim(), 1),
+        };
+        sign *= next;
+        let value = term_value(term, regions)
+            .ok_or_else(|| invalid(format!("can't evaluate {:?} in {:?}", term, expr)))?;
+        total += sign * i128::from(value);
+        sign = 1;
+        rest = rest[end..].trim_start();
+    }
+    u64::try_from(total).map_err(|_| invalid(format!("{:?} is negative", expr)))
+}
+
+fn term_value(term: &str, regions: &[LayoutRegion]) -> Option<u64> {
+    let region = |name: &str| {
+        let name = name.trim().strip_suffix(')')?.trim();
+        regions.iter().find(|region| region.name == name)
+    };
+    if let Some(name) = term.strip_prefix("ORIGIN(") {
+        return region(name).map(|region| region.origin);
+    }
+    if let Some(name) = term.strip_prefix("LENGTH(") {
+        return region(name).map(|region| region.size);
+    }
+    let (digits, scale) = match term.chars().last()? {
+        'K' | 'k' => (&term[..term.len() - 1], 1024),
+        'M' | 'm' => (&term[..term.len() - 1], 1024 * 1024),
+        _ => (term, 1),
+    };
+    let value = match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16).ok()?,
+        None => digits.parse().ok()?,
+    };
+    value.checked_mul(scale)
+}
+
+/// Read the regions of a `MEMORY` block
+fn memory(body: &str, regions: &mut Vec<LayoutRegion>) -> Result<()> {
+    for line in body.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let (name, rest) = line
+            .split_once(':')
+            .ok_or_else(|| invalid(format!("region without a ':', {:?}", line)))?;
+        let name = name.split('(').next().unwrap_or(name).trim();
+        let mut origin = None;
+        let mut length = None;
+        for attribute in rest.split(',') {
+            let (key, value) = match attribute.split_once('=') {
+                Some(pair) => pair,
+                None => continue,
+            };
+            match key.trim() {
+                "ORIGIN" | "org" | "o" => origin = Some(evaluate(value, regions)?),
+                "LENGTH" | "len" | "l" => length = Some(evaluate(value, regions)?),
+                _ => {}
+            }
+        }
+        match (origin, length) {
+            (Some(origin), Some(size)) => regions.push(LayoutRegion {
+                name: String::from(name),
+                origin,
+                size,
+            }),
+            _ => {
+                return Err(invalid(format!(
+                    "region {} needs an ORIGIN and a LENGTH",
+                    name
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Read the output sections of a `SECTIONS` block
+fn sections(body: &str, sections: &mut Vec<LayoutSection>) {
+    let mut rest = body;
+    let mut vma: Option<String> = None;
+    while let Some(open) = rest.find('{') {
+        // Statements such as symbol assignments end before the header, and
+        // the header follows the regions of the section before it
+        let header = rest[..open]
+            .rsplit(';')
+            .next()
+            .unwrap_or("")
+            .split_whitespace()
+            .find(|token| token.starts_with('.') || token.starts_with('/'))
+            .unwrap_or("");
+        let close = match matching(rest, open) {
+            Some(close) => close,
+            None => return,
+        };
+        let tail: Vec<&str> = rest[close + 1..].split_whitespace().collect();
+        let mut lma = None;
+        let mut placed = None;
+        let mut tokens = tail.iter();
+        while let Some(token) = tokens.next() {
+            match *token {
+                ">" => placed = tokens.next().map(|region| String::from(*region)),
+                "AT>" => lma = tokens.next().map(|region| String::from(*region)),
+                _ if token.starts_with("AT>") => lma = Some(String::from(&token[3..])),
+                _ if token.starts_with('>') => placed = Some(String::from(&token[1..])),
+                _ => break,
+            }
+        }
+        rest = &rest[close + 1..];
+        let name = header.split([':', '(']).next().unwrap_or("");
+        let output = match name.strip_prefix('.') {
+            Some(output) if !output.is_empty() => output,
+            _ => continue,
+        };
+        if placed.is_some() {
+            vma = placed;
+        }
+        let region = match &vma {
+            Some(region) => region.clone(),
+            None => continue,
+        };
+        sections.push(LayoutSection {
+            name: String::from(output),
+            output: String::from(output),
+            vma: region,
+            lma,
+            priority: sections.len() as i32,
+            nudge: 0,
+            group: 0,
+            size: LayoutSize::Linker,
+            prefix: false,
+            compressed: false,
+        });
+    }
+}
+
+/// Read the regions and section placement of a linker script
+pub fn layout(text: &str) -> Result<Layout> {
+    let text = strip_comments(text);
+    let mut layout = Layout::default();
+    for body in blocks(&text, "MEMORY") {
+        memory(body, &mut layout.regions)?;
+    }
+    for body in blocks(&text, "SECTIONS") {
+        sections(body, &mut layout.sections);
+    }
+    Ok(layout)
+}
+
+/// Read a linker script file, following its `INCLUDE`s of files in the
+/// same directory, such as cortex-m-rt's `link.x` including `memory.x`
+pub fn open<P: AsRef<Path>>(path: P) -> Result<Layout> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)?;
+    let mut included = String::new();
+    for line in text.lines() {
+        let file = match line.trim().strip_prefix("INCLUDE") {
+            Some(file) => file.trim().trim_end_matches(';').trim(),
+            None => continue,
+        };
+        let include = path.with_file_name(file);
+        if include.exists() {
+            included.push_str(&std::fs::read_to_string(include)?);
+            included.push('\n');
+        }
+    }
+    layout(&(included + &text))
+}
@@ -0,0 +1,140 @@
+//! Link check of the generated runtime
+//!
+//! [`link`](fn.link.html) generates a linker script's files, then compiles
+//! a stub program against them with `rustc` for an embedded target,
+//! `thumbv7em-none-eabihf` by default, and links it with the target's
+//! default linker. The stub includes `reset.rs` and `regions.rs`, calls
+//! `init` from its reset handler and provides the vector table and
+//! handlers `link.x` expects. A BSP crate may run it from a test to gate a
+//! release on the generated runtime actually linking. The target's
+//! standard library must be installed, with `rustup target add`.
+
+use crate::{write_file, LinkerError, LinkerScript, Result, Word};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Target the stub is built for unless another is given
+pub const TARGET: &str = "thumbv7em-none-eabihf";
+
+/// Name of the stub's source file and the linked ELF file, without
+/// extensions
+const STUB: &str = "selftest";
+
+/// Source of the stub program linked against the files of `ls`
+pub fn stub<W: Word>(ls: &LinkerScript<W>) -> String {
+    let init = if ls.position_independent {
+        "reset::init(0)"
+    } else {
+        "reset::init()"
+    };
+    format!(
+        "// Generated by imxrt-rt-gen to check that the generated runtime links
+#![no_std]
+#![no_main]
+
+#[allow(dead_code)]
+mod reset {{
+    include!(\"reset.rs\");
+}}
+
+#[allow(dead_code)]
+mod regions {{
+    include!(\"regions.rs\");
+}}
+
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {{
+    loop {{}}
+}}
+
+#[no_mangle]
+pub unsafe extern \"C\" fn Reset() -> ! {{
+    {init};
+    loop {{}}
+}}
+
+#[link_section = \".vector_table.reset_vector\"]
+#[no_mangle]
+pub static __RESET_VECTOR: unsafe extern \"C\" fn() -> ! = Reset;
+
+#[link_section = \".vector_table.exceptions\"]
+#[no_mangle]
+pub static __EXCEPTIONS: [usize; 14] = [0; 14];
+
+#[link_section = \".vector_table.interrupts\"]
+#[no_mangle]
+pub static __INTERRUPTS: [usize; 0] = [];
+
+#[no_mangle]
+pub extern \"C\" fn DefaultHandler_() {{}}
+
+#[no_mangle]
+pub extern \"C\" fn HardFaultTrampoline() {{}}
+
+#[no_mangle]
+pub extern \"C\" fn HardFault_() {{}}
+",
+        init = init
+    )
+}
+
+/// Whether the standard library of `target` is installed for `rustc`, so
+/// [`link`](fn.link.html) can build the stub
+pub fn installed(target: &str) -> bool {
+    let rustc = std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    let output = match Command::new(rustc).args(["--print", "sysroot"]).output() {
+        Ok(output) if output.status.success() => output,
+        _ => return false,
+    };
+    let sysroot = String::from_utf8_lossy(&output.stdout);
+    Path::new(sysroot.trim())
+        .join("lib")
+        .join("rustlib")
+        .join(target)
+        .join("lib")
+        .exists()
+}
+
+/// Generate the files of `ls` into `dir` and link the stub program
+/// against them for `target`, returning the path of the linked ELF file
+///
+/// `rustc` is the one Cargo gives build scripts in `RUSTC`, or the one on
+/// the path. An empty `device.x` is written when the directory has none.
+/// Fails with [`SelfTest`](../enum.LinkerError.html) and the compiler's
+/// output when the stub doesn't build or link.
+pub fn link<W: Word, P: AsRef<Path>>(ls: LinkerScript<W>, dir: P, target: &str) -> Result<PathBuf> {
+    let dir = dir.as_ref();
+    let source = dir.join(format!("{}.rs", STUB));
+    write_file(&source, stub(&ls).as_bytes())?;
+    ls.generate_to(dir)?;
+    let device_x = dir.join("device.x");
+    if !device_x.exists() {
+        write_file(&device_x, b"")?;
+    }
+    let elf = dir.join(format!("{}.elf", STUB));
+    let rustc = std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    let output = Command::new(rustc)
+        .args([
+            "--edition",
+            "2018",
+            "--crate-type",
+            "bin",
+            "--target",
+            target,
+        ])
+        .args(["-C", "panic=abort", "-C", "opt-level=s"])
+        .arg("-C")
+        .arg(format!("link-arg=-T{}", dir.join("link.x").display()))
+        .arg("-L")
+        .arg(dir)
+        .arg("-o")
+        .arg(&elf)
+        .arg(&source)
+        .output()?;
+    if !output.status.success() {
+        return Err(LinkerError::SelfTest(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(elf)
+}
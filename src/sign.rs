@@ -0,0 +1,140 @@
+//! Images prepared for HAB signing
+//!
+//! NXP's Code Signing Tool authenticates a contiguous block of the image
+//! and expects the Command Sequence File (CSF) it produces to be appended
+//! at the address the IVT points to. Preparing an image pads it to an
+//! aligned length, reserves the CSF gap after it, updates the IVT and
+//! boot data to describe the signed image, and produces a manifest with
+//! the addresses the signing step needs.
+
+use crate::image::Image;
+use crate::json::Value;
+use crate::{LinkerError, Result};
+
+/// IVT header tag
+const IVT_TAG: u8 = 0xD1;
+
+/// Offset of the CSF pointer in the IVT
+const IVT_CSF: u64 = 24;
+
+/// Offset of the boot data pointer in the IVT
+const IVT_BOOT_DATA: u64 = 16;
+
+/// Offset of the image length in the boot data
+const BOOT_DATA_LENGTH: u64 = 4;
+
+/// How an image is laid out for signing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SigningOptions {
+    /// The signed part of the image is padded to a multiple of this
+    pub align: u64,
+    /// Bytes reserved after the signed part for the CSF
+    pub csf_size: u64,
+    /// Value used for padding
+    pub fill: u8,
+}
+
+impl Default for SigningOptions {
+    fn default() -> Self {
+        SigningOptions {
+            align: 0x1000,
+            csf_size: 0x2000,
+            fill: 0x00,
+        }
+    }
+}
+
+/// Addresses and lengths of a prepared image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SigningManifest {
+    /// First address of the image
+    pub start: u64,
+    /// Length of the authenticated block, from `start`
+    pub signed_length: u64,
+    /// Address of the IVT
+    pub ivt: u64,
+    /// Address the CSF must be placed at
+    pub csf: u64,
+    /// Bytes reserved for the CSF
+    pub csf_size: u64,
+    /// Length of the image including the CSF, as written to the boot data
+    pub total_length: u64,
+}
+
+fn invalid(reason: String) -> LinkerError {
+    LinkerError::InvalidImage(reason)
+}
+
+fn read_u32(image: &Image, address: u64) -> Result<u32> {
+    let offset = address
+        .checked_sub(image.base)
+        .map(|offset| offset as usize)
+        .filter(|offset| offset + 4 <= image.data.len())
+        .ok_or_else(|| invalid(format!("{:#010X} is outside of the image", address)))?;
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(&image.data[offset..offset + 4]);
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Pad `image` for signing and point its IVT at the CSF gap
+///
+/// The IVT at `ivt` must already be present in the image. Its CSF pointer
+/// is set to the start of the gap, and the boot data it points to is
+/// updated with the total image length, including the CSF.
+pub fn prepare(image: &mut Image, ivt: u64, options: SigningOptions) -> Result<SigningManifest> {
+    let header = read_u32(image, ivt)?;
+    if header as u8 != IVT_TAG {
+        return Err(invalid(format!("no IVT header at {:#010X}", ivt)));
+    }
+    if options.align == 0 {
+        return Err(invalid(String::from("signing alignment must not be zero")));
+    }
+
+    let signed_length = (image.data.len() as u64).div_ceil(options.align) * options.align;
+    image.data.resize(signed_length as usize, options.fill);
+    let csf = image.base + signed_length;
+    let total_length = signed_length + options.csf_size;
+
+    image.write(ivt + IVT_CSF, &(csf as u32).to_le_bytes(), options.fill);
+    let boot_data = u64::from(read_u32(image, ivt + IVT_BOOT_DATA)?);
+    if boot_data != 0 {
+        read_u32(image, boot_data + BOOT_DATA_LENGTH)?;
+        image.write(
+            boot_data + BOOT_DATA_LENGTH,
+            &(total_length as u32).to_le_bytes(),
+            options.fill,
+        );
+    }
+
+    Ok(SigningManifest {
+        start: image.base,
+        signed_length,
+        ivt,
+        csf,
+        csf_size: options.csf_size,
+        total_length,
+    })
+}
+
+impl SigningManifest {
+    /// The `Blocks` parameter of the CSF's authenticate data command
+    pub fn csf_blocks(&self, file: &str) -> String {
+        format!(
+            "Blocks = {:#010x} {:#x} {:#x} \"{}\"",
+            self.start, 0, self.signed_length, file
+        )
+    }
+
+    /// Render the manifest as JSON
+    pub fn to_json(&self) -> String {
+        let doc = Value::Object(vec![
+            (String::from("start"), self.start.into()),
+            (String::from("signed_length"), self.signed_length.into()),
+            (String::from("ivt"), self.ivt.into()),
+            (String::from("csf"), self.csf.into()),
+            (String::from("csf_size"), self.csf_size.into()),
+            (String::from("total_length"), self.total_length.into()),
+        ]);
+        format!("{}\n", doc)
+    }
+}
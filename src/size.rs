@@ -0,0 +1,508 @@
+//! Size breakdown of a linked ELF file by region and output section
+//!
+//! The breakdown is driven by the exported [`Layout`](../layout/struct.Layout.html)
+//! so that output sections, including region prefixed sections such as
+//! `.TCM.data`, are attributed to the regions the model placed them in.
+//! A section with a load region is counted in both its runtime region and
+//! its load region, matching the `__<REGION>_used` accounting of the
+//! generated linker script.
+
+use crate::elf::Elf;
+use crate::layout::Layout;
+use std::fmt;
+
+/// Usage of a single output section within a region
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionUsage {
+    /// Output section name including the leading '.'
+    pub name: String,
+    /// Address of the section in this region
+    pub address: u64,
+    /// Size of the section in bytes
+    pub size: u64,
+    /// True if this entry is the load copy of a section running elsewhere
+    pub load_copy: bool,
+    /// True if the layout does not describe this section
+    pub orphan: bool,
+}
+
+/// Usage of a region
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionUsage {
+    /// Region name
+    pub name: String,
+    /// First address of the region
+    pub origin: u64,
+    /// Size of the region in bytes
+    pub size: u64,
+    /// Sections occupying the region, sorted by address
+    pub sections: Vec<SectionUsage>,
+}
+
+impl RegionUsage {
+    /// Total bytes used by sections in the region
+    pub fn used(&self) -> u64 {
+        self.sections.iter().map(|s| s.size).sum()
+    }
+}
+
+/// A size breakdown of an ELF file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeReport {
+    /// Regions in layout order
+    pub regions: Vec<RegionUsage>,
+    /// Allocated sections outside of every region
+    pub unplaced: Vec<SectionUsage>,
+}
+
+impl SizeReport {
+    /// Find the usage of a region by name
+    pub fn region(&self, name: &str) -> Option<&RegionUsage> {
+        self.regions.iter().find(|r| r.name == name)
+    }
+}
+
+/// Build a size breakdown of `elf` using the regions and sections in `layout`
+pub fn report(layout: &Layout, elf: &Elf) -> SizeReport {
+    let mut regions: Vec<RegionUsage> = layout
+        .regions
+        .iter()
+        .map(|region| RegionUsage {
+            name: region.name.clone(),
+            origin: region.origin,
+            size: region.size,
+            sections: Vec::new(),
+        })
+        .collect();
+    let mut unplaced = Vec::new();
+
+    for section in elf.sections().iter().filter(|s| s.is_alloc() && s.size > 0) {
+        let planned = layout.section(&section.name);
+        let vma_region = planned.map(|p| p.vma.clone()).or_else(|| {
+            layout
+                .region_containing(section.address)
+                .map(|r| r.name.clone())
+        });
+        let usage = SectionUsage {
+            name: section.name.clone(),
+            address: section.address,
+            size: section.size,
+            load_copy: false,
+            orphan: planned.is_none(),
+        };
+        match vma_region.and_then(|name| regions.iter_mut().find(|r| r.name == name)) {
+            Some(region) => region.sections.push(usage),
+            None => unplaced.push(usage),
+        }
+
+        let lma_region = planned.and_then(|p| p.lma.clone());
+        if let (Some(lma), true) = (lma_region, section.has_contents()) {
+            if let Some(region) = regions.iter_mut().find(|r| r.name == lma) {
+                region.sections.push(SectionUsage {
+                    name: section.name.clone(),
+                    address: elf.load_address(section),
+                    size: section.size,
+                    load_copy: true,
+                    orphan: false,
+                });
+            }
+        }
+    }
+
+    for region in regions.iter_mut() {
+        region.sections.sort_by_key(|s| s.address);
+    }
+    SizeReport { regions, unplaced }
+}
+
+fn percent(used: u64, size: u64) -> f64 {
+    if size == 0 {
+        0.0
+    } else {
+        used as f64 * 100.0 / size as f64
+    }
+}
+
+impl fmt::Display for SizeReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for region in self.regions.iter() {
+            let used = region.used();
+            writeln!(
+                f,
+                "{:<24} {:#010X} {:>10} / {:<10} {:>6.2}%",
+                region.name,
+                region.origin,
+                used,
+                region.size,
+                percent(used, region.size)
+            )?;
+            for section in region.sections.iter() {
+                let note = if section.load_copy {
+                    " (load)"
+                } else if section.orphan {
+                    " (orphan)"
+                } else {
+                    ""
+                };
+                writeln!(
+                    f,
+                    "  {:<22} {:#010X} {:>10} {:>19.2}%{}",
+                    section.name,
+                    section.address,
+                    section.size,
+                    percent(section.size, region.size),
+                    note
+                )?;
+            }
+        }
+        if !self.unplaced.is_empty() {
+            writeln!(f, "(outside of all regions)")?;
+            for section in self.unplaced.iter() {
+                writeln!(
+                    f,
+                    "  {:<22} {:#010X} {:>10}",
+                    section.name, section.address, section.size
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Name given to symbols which could not be attributed to a crate or file
+pub const UNKNOWN_CRATE: &str = "(unknown)";
+
+/// Crate name of a mangled Rust symbol
+///
+/// Both the legacy (`_ZN4core3fmt5write17h..E`) and v0
+/// (`_RNvNtCs1234_4core3fmt5write`) mangling schemes are understood. A
+/// method of a trait implementation, `<app::Pin as core::fmt::Debug>::fmt`,
+/// is attributed to the crate of the implementing type, or to the trait's
+/// crate when the type is generic or primitive. A v0 implementation path
+/// is attributed to the crate it's in. Returns `None` for symbols which
+/// are not mangled Rust names.
+pub fn crate_name(symbol: &str) -> Option<String> {
+    if let Some(rest) = symbol.strip_prefix("_ZN") {
+        return legacy_crate(rest);
+    }
+    let rest = symbol.strip_prefix("_R")?;
+    let version = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+    v0_path_crate(rest, version, 0)
+}
+
+/// Crate of a legacy symbol from its first path component
+fn legacy_crate(rest: &str) -> Option<String> {
+    let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+    let len: usize = rest[..digits].parse().ok()?;
+    let name = rest.get(digits..digits + len)?;
+    // A leading '$' is escaped by a '_'
+    let name = match name.strip_prefix('_') {
+        Some(escaped) if escaped.starts_with('$') => escaped,
+        _ => name,
+    };
+    let qualified = match name.strip_prefix("$LT$") {
+        Some(qualified) => qualified.strip_suffix("$GT$").unwrap_or(qualified),
+        None => return Some(String::from(name)),
+    };
+    let (self_type, trait_path) = match qualified.find("$u20$as$u20$") {
+        Some(idx) => (&qualified[..idx], Some(&qualified[idx + 12..])),
+        None => (qualified, None),
+    };
+    legacy_path_crate(self_type).or_else(|| trait_path.and_then(legacy_path_crate))
+}
+
+/// Crate of a demangled legacy path, `app..Pin`, behind references and
+/// pointers, or `None` for a single identifier such as a generic parameter
+fn legacy_path_crate(mut path: &str) -> Option<String> {
+    const SIGILS: [&str; 6] = [
+        "$RF$",
+        "$BP$",
+        "mut$u20$",
+        "const$u20$",
+        "dyn$u20$",
+        "$u5b$",
+    ];
+    while let Some(sigil) = SIGILS.iter().find(|sigil| path.starts_with(*sigil)) {
+        path = &path[sigil.len()..];
+    }
+    let end = path
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(path.len());
+    if path[end..].starts_with("..") {
+        Some(String::from(&path[..end]))
+    } else {
+        None
+    }
+}
+
+/// A v0 base 62 number at `at` in `s`, ended by '_', and the index after it
+fn v0_base62(s: &str, at: usize) -> Option<(usize, usize)> {
+    let rest = s.get(at..)?;
+    let end = rest.find('_')?;
+    if end == 0 {
+        return Some((0, at + 1));
+    }
+    let mut value: usize = 0;
+    for c in rest[..end].chars() {
+        let digit = match c {
+            '0'..='9' => c as usize - '0' as usize,
+            'a'..='z' => c as usize - 'a' as usize + 10,
+            'A'..='Z' => c as usize - 'A' as usize + 36,
+            _ => return None,
+        };
+        value = value.checked_mul(62)?.checked_add(digit)?;
+    }
+    Some((value + 1, at + end + 1))
+}
+
+/// Index after an optional v0 disambiguator, `s` and a base 62 number
+fn v0_skip_disambiguator(s: &str, at: usize) -> Option<usize> {
+    match s.get(at..)?.strip_prefix('s') {
+        Some(_) => v0_base62(s, at + 1).map(|(_, next)| next),
+        None => Some(at),
+    }
+}
+
+/// Crate of the v0 path at `at` in `s`, the symbol after `_R`
+///
+/// Nested paths, generic instantiations and back references lead to the
+/// crate root on the left. An implementation path, inherent (`M`) or of
+/// a trait (`X`), is in the crate of the module holding it. A qualified
+/// path (`Y`), `<Type as Trait>`, is attributed like a legacy one.
+fn v0_path_crate(s: &str, at: usize, depth: usize) -> Option<String> {
+    if depth > 64 {
+        return None;
+    }
+    let next = at + 1;
+    match *s.as_bytes().get(at)? {
+        b'C' => {
+            let mut rest = s.get(v0_skip_disambiguator(s, next)?..)?;
+            rest = rest.strip_prefix('u').unwrap_or(rest);
+            let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+            let len: usize = rest[..digits].parse().ok()?;
+            let rest = &rest[digits..];
+            // '_' separates the length from a name starting with a digit or '_'
+            let rest = rest.strip_prefix('_').unwrap_or(rest);
+            rest.get(..len).map(String::from)
+        }
+        b'N' => v0_path_crate(s, next + 1, depth + 1),
+        b'I' => v0_path_crate(s, next, depth + 1),
+        b'M' | b'X' => v0_path_crate(s, v0_skip_disambiguator(s, next)?, depth + 1),
+        b'Y' => match *s.as_bytes().get(next)? {
+            // A basic type, attribute to the trait
+            b'a'..=b'z' => v0_path_crate(s, next + 1, depth + 1),
+            _ => v0_type_crate(s, next, depth + 1),
+        },
+        b'B' => v0_path_crate(s, v0_base62(s, next)?.0, depth + 1),
+        _ => None,
+    }
+}
+
+/// Crate of the v0 type at `at` in `s`, a path behind references,
+/// pointers, slices and arrays
+fn v0_type_crate(s: &str, at: usize, depth: usize) -> Option<String> {
+    match *s.as_bytes().get(at)? {
+        b'R' | b'Q' => match s.get(at + 1..)?.strip_prefix('L') {
+            Some(_) => v0_type_crate(s, v0_base62(s, at + 2)?.1, depth + 1),
+            None => v0_type_crate(s, at + 1, depth + 1),
+        },
+        b'P' | b'O' | b'S' | b'A' => v0_type_crate(s, at + 1, depth + 1),
+        _ => v0_path_crate(s, at, depth),
+    }
+}
+
+/// Bytes attributed to a crate, or to a source file for non-Rust symbols
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrateUsage {
+    /// Crate or file name
+    pub name: String,
+    /// Bytes used per region, in layout order
+    pub regions: Vec<(String, u64)>,
+}
+
+impl CrateUsage {
+    /// Bytes used by the crate in `region`
+    pub fn in_region(&self, region: &str) -> u64 {
+        self.regions
+            .iter()
+            .find(|(name, _)| name == region)
+            .map(|(_, used)| *used)
+            .unwrap_or(0)
+    }
+
+    /// Total bytes used by the crate
+    pub fn total(&self) -> u64 {
+        self.regions.iter().map(|(_, used)| used).sum()
+    }
+}
+
+/// Size attribution of an ELF file's symbols to crates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrateReport {
+    /// Crates sorted by total size, largest first
+    pub crates: Vec<CrateUsage>,
+}
+
+/// Attribute the sized symbols of `elf` to crates and regions
+///
+/// Symbols are assigned to the region containing their address. Mangled
+/// Rust symbols are attributed to their crate, other symbols to the
+/// source file named by the preceding file symbol when they are local.
+pub fn crates(layout: &Layout, elf: &Elf) -> CrateReport {
+    let mut crates: Vec<CrateUsage> = Vec::new();
+    let mut file: Option<String> = None;
+    for symbol in elf.symbols() {
+        if symbol.kind == crate::elf::STT_FILE {
+            file = Some(symbol.name.clone());
+            continue;
+        }
+        if symbol.size == 0 {
+            continue;
+        }
+        let region = match layout.region_containing(symbol.value & !1) {
+            Some(region) => region,
+            None => continue,
+        };
+        let name = crate_name(&symbol.name)
+            .or_else(|| file.clone().filter(|_| !symbol.global))
+            .unwrap_or_else(|| String::from(UNKNOWN_CRATE));
+        let idx = match crates.iter().position(|c| c.name == name) {
+            Some(idx) => idx,
+            None => {
+                crates.push(CrateUsage {
+                    name,
+                    regions: layout.regions.iter().map(|r| (r.name.clone(), 0)).collect(),
+                });
+                crates.len() - 1
+            }
+        };
+        if let Some(used) = crates[idx]
+            .regions
+            .iter_mut()
+            .find(|(name, _)| *name == region.name)
+        {
+            used.1 += symbol.size;
+        }
+    }
+    crates.sort_by(|a, b| b.total().cmp(&a.total()).then(a.name.cmp(&b.name)));
+    CrateReport { crates }
+}
+
+impl CrateReport {
+    /// Find a crate by name
+    pub fn krate(&self, name: &str) -> Option<&CrateUsage> {
+        self.crates.iter().find(|c| c.name == name)
+    }
+
+    /// Check the report against `budgets`, returning every budget exceeded
+    pub fn check(&self, budgets: &[Budget]) -> Vec<BudgetViolation> {
+        budgets
+            .iter()
+            .filter_map(|budget| {
+                let used = self
+                    .krate(&budget.krate)
+                    .map(|c| c.in_region(&budget.region))
+                    .unwrap_or(0);
+                if used > budget.limit {
+                    Some(BudgetViolation {
+                        budget: budget.clone(),
+                        used,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for CrateReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let regions: Vec<&String> = self
+            .crates
+            .first()
+            .map(|c| c.regions.iter().map(|(name, _)| name).collect())
+            .unwrap_or_default();
+        write!(f, "{:<32}", "crate")?;
+        for region in regions.iter() {
+            write!(f, " {:>10}", region)?;
+        }
+        writeln!(f, " {:>10}", "total")?;
+        for krate in self.crates.iter() {
+            write!(f, "{:<32}", krate.name)?;
+            for (_, used) in krate.regions.iter() {
+                write!(f, " {:>10}", used)?;
+            }
+            writeln!(f, " {:>10}", krate.total())?;
+        }
+        Ok(())
+    }
+}
+
+/// An upper bound on the bytes a crate may use in a region
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Budget {
+    /// Crate (or file) name as reported by [`crates`](fn.crates.html)
+    pub krate: String,
+    /// Region name
+    pub region: String,
+    /// Maximum number of bytes
+    pub limit: u64,
+}
+
+/// Parse a byte count, accepting `0x` hexadecimal and `K`/`M` suffixes
+pub fn parse_size(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let (digits, scale) = match s.chars().last()? {
+        'K' | 'k' => (&s[..s.len() - 1], 1024),
+        'M' | 'm' => (&s[..s.len() - 1], 1024 * 1024),
+        _ => (s, 1),
+    };
+    let value = match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(&hex.replace('_', ""), 16).ok()?,
+        None => digits.replace('_', "").parse().ok()?,
+    };
+    value.checked_mul(scale)
+}
+
+impl std::str::FromStr for Budget {
+    type Err = String;
+
+    /// Parse a budget written as `crate:REGION=size`, for example `smoltcp:ITCM=16K`
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let err = || format!("budget {:?} is not of the form crate:REGION=size", s);
+        let (krate, rest) = s.split_at(s.find(':').ok_or_else(err)?);
+        let (region, limit) = rest[1..].split_at(rest.find('=').ok_or_else(err)? - 1);
+        Ok(Budget {
+            krate: String::from(krate),
+            region: String::from(region),
+            limit: parse_size(&limit[1..]).ok_or_else(err)?,
+        })
+    }
+}
+
+/// A budget which was exceeded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BudgetViolation {
+    /// The budget exceeded
+    pub budget: Budget,
+    /// Bytes actually used
+    pub used: u64,
+}
+
+impl fmt::Display for BudgetViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "crate {:?} uses {} bytes of {}, over its budget of {} bytes by {}",
+            self.budget.krate,
+            self.used,
+            self.budget.region,
+            self.budget.limit,
+            self.used - self.budget.limit
+        )
+    }
+}
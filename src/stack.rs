@@ -0,0 +1,225 @@
+//! Stack sizing from measured stack frames
+//!
+//! Building with `RUSTFLAGS="-Z emit-stack-sizes"` adds a `.stack_sizes`
+//! section to the ELF file describing the stack frame of every function.
+//! The frames are combined into a suggested stack size, either from the
+//! largest frames at an assumed call depth or, when a call graph is
+//! available, from the deepest path through it. The suggestion may then be
+//! given to [`LinkerScript::min_size`](../struct.LinkerScript.html#method.min_size)
+//! so the next link fails if the stack region can no longer hold it.
+
+use crate::elf::Elf;
+use crate::layout::{Layout, LayoutSize};
+use crate::size::SizeReport;
+use crate::{LinkerError, Result};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Name of the section emitted by `-Z emit-stack-sizes`
+pub const STACK_SIZES_SECTION: &str = ".stack_sizes";
+
+/// Stack frame of a single function
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// Function name, or its address when no symbol is known
+    pub name: String,
+    /// Function address with the thumb bit cleared
+    pub address: u64,
+    /// Bytes of stack used by the function itself
+    pub size: u64,
+}
+
+/// Stack frames of every function in an ELF file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackSizes {
+    /// Frames sorted by size, largest first
+    pub frames: Vec<Frame>,
+}
+
+/// Caller to callee edges between functions, by name
+#[derive(Debug, Clone, Default)]
+pub struct CallGraph {
+    calls: HashMap<String, Vec<String>>,
+}
+
+impl CallGraph {
+    /// Add a call from `caller` to `callee`
+    pub fn call(&mut self, caller: &str, callee: &str) {
+        self.calls
+            .entry(String::from(caller))
+            .or_default()
+            .push(String::from(callee));
+    }
+}
+
+impl std::str::FromStr for CallGraph {
+    type Err = String;
+
+    /// Parse a call graph with one `caller -> callee` edge per line
+    ///
+    /// Blank lines and lines starting with `#` are ignored.
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        let mut graph = CallGraph::default();
+        for (idx, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.splitn(2, "->");
+            match (parts.next(), parts.next()) {
+                (Some(caller), Some(callee)) => graph.call(caller.trim(), callee.trim()),
+                _ => {
+                    return Err(format!(
+                        "call graph line {} is not of the form `caller -> callee`",
+                        idx + 1
+                    ))
+                }
+            }
+        }
+        Ok(graph)
+    }
+}
+
+fn uleb128(data: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = *data.get(*pos)?;
+        *pos += 1;
+        value |= u64::from(byte & 0x7f).checked_shl(shift)?;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+        shift += 7;
+    }
+}
+
+impl StackSizes {
+    /// Read the `.stack_sizes` section of `elf`
+    pub fn from_elf(elf: &Elf) -> Result<StackSizes> {
+        let section = elf.section(STACK_SIZES_SECTION).ok_or_else(|| {
+            LinkerError::InvalidElf(String::from(
+                "no .stack_sizes section, build with -Z emit-stack-sizes",
+            ))
+        })?;
+        let data = elf.section_data(section);
+        let width = if elf.is64() { 8 } else { 4 };
+        let mut frames = Vec::new();
+        let mut pos = 0;
+        while pos < data.len() {
+            let raw = data
+                .get(pos..pos + width)
+                .ok_or_else(|| LinkerError::InvalidElf(String::from("truncated .stack_sizes")))?;
+            let mut bytes = [0u8; 8];
+            bytes[..width].copy_from_slice(raw);
+            pos += width;
+            let address = u64::from_le_bytes(bytes) & !1;
+            let size = uleb128(data, &mut pos)
+                .ok_or_else(|| LinkerError::InvalidElf(String::from("truncated .stack_sizes")))?;
+            let name = elf
+                .symbols()
+                .iter()
+                .find(|sym| sym.size > 0 && sym.value & !1 == address)
+                .map(|sym| sym.name.clone())
+                .unwrap_or_else(|| format!("{:#010X}", address));
+            frames.push(Frame {
+                name,
+                address,
+                size,
+            });
+        }
+        frames.sort_by(|a, b| b.size.cmp(&a.size).then(a.name.cmp(&b.name)));
+        Ok(StackSizes { frames })
+    }
+
+    /// Find the frame of a function by name
+    pub fn frame(&self, name: &str) -> Option<&Frame> {
+        self.frames.iter().find(|f| f.name == name)
+    }
+
+    /// Largest single stack frame
+    pub fn max_frame(&self) -> u64 {
+        self.frames.first().map(|f| f.size).unwrap_or(0)
+    }
+
+    /// Estimate the worst case stack use without a call graph as the sum of
+    /// the `depth` largest frames
+    pub fn estimate(&self, depth: usize) -> u64 {
+        self.frames.iter().take(depth).map(|f| f.size).sum()
+    }
+
+    /// Worst case stack use of the deepest path through `graph` from `root`
+    ///
+    /// Returns `None` if the path is unbounded, that is if the graph has
+    /// recursion reachable from `root`. Functions without a measured frame
+    /// are counted as using no stack.
+    pub fn worst_case(&self, graph: &CallGraph, root: &str) -> Option<u64> {
+        fn visit(
+            sizes: &StackSizes,
+            graph: &CallGraph,
+            function: &str,
+            path: &mut Vec<String>,
+            memo: &mut HashMap<String, u64>,
+        ) -> Option<u64> {
+            if let Some(depth) = memo.get(function) {
+                return Some(*depth);
+            }
+            if path.iter().any(|f| f == function) {
+                return None;
+            }
+            path.push(String::from(function));
+            let mut deepest = 0;
+            for callee in graph.calls.get(function).into_iter().flatten() {
+                deepest = deepest.max(visit(sizes, graph, callee, path, memo)?);
+            }
+            path.pop();
+            let own = sizes.frame(function).map(|f| f.size).unwrap_or(0);
+            memo.insert(String::from(function), own + deepest);
+            Some(own + deepest)
+        }
+        visit(self, graph, root, &mut Vec::new(), &mut HashMap::new())
+    }
+}
+
+/// Add `margin_percent` to `usage` and round up to the 8 byte stack alignment
+pub fn suggest(usage: u64, margin_percent: u64) -> u64 {
+    let with_margin = usage + (usage * margin_percent).div_ceil(100);
+    (with_margin + 7) & !7
+}
+
+/// Bytes left for a stack below the statics of `region`
+///
+/// The statics are the sections a previous link placed in the region,
+/// other than its stack and heap sections. The remaining space is rounded
+/// down to 8 bytes, ready for
+/// [`LinkerScript::stack_below_statics`](../struct.LinkerScript.html#method.stack_below_statics).
+pub fn below_statics(layout: &Layout, report: &SizeReport, region: &str) -> Option<u64> {
+    let usage = report.region(region)?;
+    let statics: u64 = usage
+        .sections
+        .iter()
+        .filter(|usage| {
+            !layout.section(&usage.name).is_some_and(|s| {
+                matches!(
+                    s.size,
+                    LayoutSize::Stack | LayoutSize::FixedStack(_) | LayoutSize::Heap
+                )
+            })
+        })
+        .map(|usage| usage.size)
+        .sum();
+    usage.size.checked_sub(statics).map(|size| size & !7)
+}
+
+impl fmt::Display for StackSizes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for frame in self.frames.iter() {
+            writeln!(
+                f,
+                "{:>8} {:#010X} {}",
+                frame.size, frame.address, frame.name
+            )?;
+        }
+        Ok(())
+    }
+}
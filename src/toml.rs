@@ -0,0 +1,389 @@
+//! Minimal TOML support for the configuration files
+//!
+//! A document is read into the same [`Value`] as a JSON one, so a config
+//! means the same in either format. Only what a config needs is supported:
+//! tables, arrays of tables, dotted keys, basic and literal strings,
+//! integers, booleans, arrays and inline tables. Floats, dates and
+//! multi-line strings are not.
+
+use crate::json::Value;
+
+/// Parse a TOML document
+pub(crate) fn parse(input: &str) -> Result<Value, String> {
+    let mut parser = Parser { input, pos: 0 };
+    let mut root = Value::Object(Vec::new());
+    // Path of the table the keys are added to, and of the tables defined
+    // by a header so far
+    let mut current = Vec::new();
+    let mut defined: Vec<Vec<String>> = Vec::new();
+    loop {
+        parser.blank();
+        match parser.peek() {
+            None => return Ok(root),
+            Some('[') => {
+                parser.bump();
+                let array = parser.eat('[');
+                parser.spaces();
+                let path = parser.key()?;
+                parser.spaces();
+                parser.expect(']')?;
+                if array {
+                    parser.expect(']')?;
+                }
+                parser.end_of_line()?;
+                if array {
+                    let (last, parent) = path.split_last().unwrap();
+                    let members = table(&mut root, parent).map_err(|err| parser.error(&err))?;
+                    match members.iter_mut().find(|(key, _)| key == last) {
+                        None => members
+                            .push((last.clone(), Value::Array(vec![Value::Object(Vec::new())]))),
+                        Some((_, Value::Array(tables))) => tables.push(Value::Object(Vec::new())),
+                        Some(_) => return Err(parser.error(&format!("{:?} is not an array", last))),
+                    }
+                    defined.retain(|table| !table.starts_with(&path));
+                } else {
+                    if defined.contains(&path) {
+                        return Err(parser.error(&format!("table {:?} defined twice", path)));
+                    }
+                    table(&mut root, &path).map_err(|err| parser.error(&err))?;
+                    defined.push(path.clone());
+                }
+                current = path;
+            }
+            Some(_) => {
+                let path = parser.key()?;
+                parser.spaces();
+                parser.expect('=')?;
+                let value = parser.value()?;
+                parser.end_of_line()?;
+                let members = table(&mut root, &current).map_err(|err| parser.error(&err))?;
+                insert(members, &path, value).map_err(|err| parser.error(&err))?;
+            }
+        }
+    }
+}
+
+/// Members of the table at `path`, added when missing, or of the last
+/// table of an array of tables
+fn table<'v>(root: &'v mut Value, path: &[String]) -> Result<&'v mut Vec<(String, Value)>, String> {
+    let mut value = root;
+    for key in path {
+        let members = match value {
+            Value::Object(members) => members,
+            _ => return Err(format!("{:?} is not a table", key)),
+        };
+        let idx = match members.iter().position(|(k, _)| k == key) {
+            Some(idx) => idx,
+            None => {
+                members.push((key.clone(), Value::Object(Vec::new())));
+                members.len() - 1
+            }
+        };
+        value = match &mut members[idx].1 {
+            Value::Array(tables) => tables
+                .last_mut()
+                .ok_or_else(|| format!("{:?} is not a table", key))?,
+            value => value,
+        };
+    }
+    match value {
+        Value::Object(members) => Ok(members),
+        _ => Err(format!("{:?} is not a table", path.last().unwrap())),
+    }
+}
+
+/// Add `value` at the dotted key `path` of a table
+fn insert(members: &mut Vec<(String, Value)>, path: &[String], value: Value) -> Result<(), String> {
+    let (last, parents) = path.split_last().unwrap();
+    let mut members = members;
+    for key in parents {
+        let idx = match members.iter().position(|(k, _)| k == key) {
+            Some(idx) => idx,
+            None => {
+                members.push((key.clone(), Value::Object(Vec::new())));
+                members.len() - 1
+            }
+        };
+        members = match &mut members[idx].1 {
+            Value::Object(members) => members,
+            _ => return Err(format!("{:?} is not a table", key)),
+        };
+    }
+    if members.iter().any(|(k, _)| k == last) {
+        return Err(format!("key {:?} defined twice", last));
+    }
+    members.push((last.clone(), value));
+    Ok(())
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, reason: &str) -> String {
+        let line = self.input[..self.pos].matches('\n').count() + 1;
+        format!("{} at line {}", reason, line)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => Err(self.error(&format!("expected {:?} but found {:?}", expected, c))),
+            None => Err(self.error(&format!("expected {:?} but found end of input", expected))),
+        }
+    }
+
+    /// Skip spaces and tabs
+    fn spaces(&mut self) {
+        while let Some(' ') | Some('\t') = self.peek() {
+            self.bump();
+        }
+    }
+
+    /// Skip whitespace, line ends and comments
+    fn blank(&mut self) {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+                Some('#') => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.bump();
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), String> {
+        self.spaces();
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.bump();
+            }
+        }
+        self.eat('\r');
+        match self.peek() {
+            None => Ok(()),
+            Some('\n') => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => Err(self.error(&format!("expected the end of the line but found {:?}", c))),
+        }
+    }
+
+    /// A key, of its dotted parts
+    fn key(&mut self) -> Result<Vec<String>, String> {
+        let mut path = Vec::new();
+        loop {
+            let part = match self.peek() {
+                Some('"') => self.basic()?,
+                Some('\'') => self.literal()?,
+                _ => {
+                    let start = self.pos;
+                    while let Some(c) = self.peek() {
+                        if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                            self.bump();
+                        } else {
+                            break;
+                        }
+                    }
+                    if start == self.pos {
+                        return Err(self.error("expected a key"));
+                    }
+                    String::from(&self.input[start..self.pos])
+                }
+            };
+            path.push(part);
+            self.spaces();
+            if !self.eat('.') {
+                return Ok(path);
+            }
+            self.spaces();
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.spaces();
+        match self.peek() {
+            Some('"') => self.basic().map(Value::Str),
+            Some('\'') => self.literal().map(Value::Str),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            Some('t') | Some('f') => {
+                let start = self.pos;
+                while let Some(c) = self.peek() {
+                    if c.is_ascii_alphabetic() {
+                        self.bump();
+                    } else {
+                        break;
+                    }
+                }
+                match &self.input[start..self.pos] {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    word => Err(self.error(&format!("unexpected {:?}", word))),
+                }
+            }
+            Some(c) if c == '+' || c == '-' || c.is_ascii_digit() => self.integer(),
+            Some(c) => Err(self.error(&format!("unexpected {:?}", c))),
+            None => Err(self.error("expected a value but found end of input")),
+        }
+    }
+
+    fn integer(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || "+-_.:".contains(c) {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        let text = &self.input[start..self.pos];
+        let (negative, digits) = match text.as_bytes()[0] {
+            b'-' => (true, &text[1..]),
+            b'+' => (false, &text[1..]),
+            _ => (false, text),
+        };
+        let digits = digits.replace('_', "");
+        let (radix, digits) = match digits.get(..2) {
+            Some("0x") => (16, &digits[2..]),
+            Some("0o") => (8, &digits[2..]),
+            Some("0b") => (2, &digits[2..]),
+            _ if digits.contains(|c| ".:eE".contains(c)) => {
+                return Err(self.error(&format!("only integers are supported, {:?}", text)))
+            }
+            _ => (10, digits.as_str()),
+        };
+        i64::from_str_radix(digits, radix)
+            .map(|value| Value::Int(if negative { -value } else { value }))
+            .map_err(|err| self.error(&format!("invalid integer {:?}, {}", text, err)))
+    }
+
+    fn basic(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        if self.input[self.pos..].starts_with("\"\"") {
+            return Err(self.error("multi-line strings are not supported"));
+        }
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(s),
+                Some('\\') => match self.bump() {
+                    Some('"') => s.push('"'),
+                    Some('\\') => s.push('\\'),
+                    Some('n') => s.push('\n'),
+                    Some('r') => s.push('\r'),
+                    Some('t') => s.push('\t'),
+                    Some('b') => s.push('\u{8}'),
+                    Some('f') => s.push('\u{c}'),
+                    Some(escape @ 'u') | Some(escape @ 'U') => {
+                        let digits = if escape == 'u' { 4 } else { 8 };
+                        let mut code = 0;
+                        for _ in 0..digits {
+                            let digit = self
+                                .bump()
+                                .and_then(|c| c.to_digit(16))
+                                .ok_or_else(|| self.error("invalid escape"))?;
+                            code = code * 16 + digit;
+                        }
+                        s.push(
+                            std::char::from_u32(code)
+                                .ok_or_else(|| self.error("invalid escape"))?,
+                        );
+                    }
+                    Some(c) => return Err(self.error(&format!("invalid escape {:?}", c))),
+                    None => return Err(self.error("unterminated string")),
+                },
+                Some('\n') | None => return Err(self.error("unterminated string")),
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn literal(&mut self) -> Result<String, String> {
+        self.expect('\'')?;
+        if self.input[self.pos..].starts_with("''") {
+            return Err(self.error("multi-line strings are not supported"));
+        }
+        let start = self.pos;
+        loop {
+            match self.bump() {
+                Some('\'') => return Ok(String::from(&self.input[start..self.pos - 1])),
+                Some('\n') | None => return Err(self.error("unterminated string")),
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        loop {
+            self.blank();
+            if self.eat(']') {
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.blank();
+            match self.bump() {
+                Some(',') => continue,
+                Some(']') => return Ok(Value::Array(items)),
+                Some(c) => return Err(self.error(&format!("unexpected {:?}", c))),
+                None => return Err(self.error("unterminated array")),
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut members = Vec::new();
+        self.spaces();
+        if self.eat('}') {
+            return Ok(Value::Object(members));
+        }
+        loop {
+            self.spaces();
+            let path = self.key()?;
+            self.spaces();
+            self.expect('=')?;
+            let value = self.value()?;
+            insert(&mut members, &path, value).map_err(|err| self.error(&err))?;
+            self.spaces();
+            match self.bump() {
+                Some(',') => continue,
+                Some('}') => return Ok(Value::Object(members)),
+                Some(c) => return Err(self.error(&format!("unexpected {:?}", c))),
+                None => return Err(self.error("unterminated inline table")),
+            }
+        }
+    }
+}
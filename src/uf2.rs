@@ -0,0 +1,46 @@
+//! UF2 export for drag and drop flashing
+//!
+//! A UF2 file is a sequence of 512 byte blocks, each carrying 256 bytes of
+//! an [`Image`](../image/struct.Image.html) and the address to write them
+//! to. The family ID tells the bootloader which chip the file is for.
+
+use crate::image::Image;
+
+/// UF2 family ID of the i.MX RT10xx parts
+pub const MIMXRT10XX_FAMILY_ID: u32 = 0x4FB2_D5BD;
+
+/// Image bytes carried by each block
+pub const PAYLOAD_SIZE: usize = 256;
+
+const BLOCK_SIZE: usize = 512;
+const MAGIC_START0: u32 = 0x0A32_4655;
+const MAGIC_START1: u32 = 0x9E5D_5157;
+const MAGIC_END: u32 = 0x0AB1_6F30;
+const FLAG_FAMILY_ID_PRESENT: u32 = 0x0000_2000;
+
+/// Render `image` as UF2 blocks tagged with `family_id`
+pub fn from_image(image: &Image, family_id: u32) -> Vec<u8> {
+    let chunks: Vec<&[u8]> = image.data.chunks(PAYLOAD_SIZE).collect();
+    let mut out = Vec::with_capacity(chunks.len() * BLOCK_SIZE);
+    for (idx, chunk) in chunks.iter().enumerate() {
+        let address = image.base + (idx * PAYLOAD_SIZE) as u64;
+        let header = [
+            MAGIC_START0,
+            MAGIC_START1,
+            FLAG_FAMILY_ID_PRESENT,
+            address as u32,
+            chunk.len() as u32,
+            idx as u32,
+            chunks.len() as u32,
+            family_id,
+        ];
+        for word in header.iter() {
+            out.extend_from_slice(&word.to_le_bytes());
+        }
+        let mut data = [0u8; 476];
+        data[..chunk.len()].copy_from_slice(chunk);
+        out.extend_from_slice(&data);
+        out.extend_from_slice(&MAGIC_END.to_le_bytes());
+    }
+    out
+}